@@ -5,30 +5,98 @@ ALTER TABLE api_keys ENABLE ROW LEVEL SECURITY;
 ALTER TABLE api_usage_logs ENABLE ROW LEVEL SECURITY;
 ALTER TABLE credit_transactions ENABLE ROW LEVEL SECURITY;
 ALTER TABLE mcp_tools ENABLE ROW LEVEL SECURITY;
+ALTER TABLE mcp_tool_circuits ENABLE ROW LEVEL SECURITY;
+ALTER TABLE mcp_resources ENABLE ROW LEVEL SECURITY;
+ALTER TABLE mcp_prompts ENABLE ROW LEVEL SECURITY;
+ALTER TABLE mcp_toolsets ENABLE ROW LEVEL SECURITY;
+ALTER TABLE downstream_auth_overrides ENABLE ROW LEVEL SECURITY;
+ALTER TABLE consumer_oauth_tokens ENABLE ROW LEVEL SECURITY;
+ALTER TABLE consumer_oauth_states ENABLE ROW LEVEL SECURITY;
+ALTER TABLE tenant_secrets ENABLE ROW LEVEL SECURITY;
+ALTER TABLE tenant_secret_versions ENABLE ROW LEVEL SECURITY;
+ALTER TABLE security_events ENABLE ROW LEVEL SECURITY;
 
 -- Global Bypass Policy (for administrative tasks)
 -- This allows access if 'app.bypass_rls' is set to 'true'.
+--
+-- This file runs on every startup: each policy is dropped and recreated so
+-- the batch never stops at an "already exists" error and new policies apply
+-- to existing databases.
 
 -- 1. Tenant Isolation Policy
+DROP POLICY IF EXISTS tenant_isolation ON tenants;
 CREATE POLICY tenant_isolation ON tenants
     USING (current_setting('app.bypass_rls', true) = 'true' OR id = current_setting('app.current_tenant_id', true));
 
 -- 2. API Key Isolation Policy
+DROP POLICY IF EXISTS api_key_isolation ON api_keys;
 CREATE POLICY api_key_isolation ON api_keys
     USING (current_setting('app.bypass_rls', true) = 'true' OR tenant_id = current_setting('app.current_tenant_id', true));
 
 -- 3. Usage Log Isolation Policy
+DROP POLICY IF EXISTS api_usage_log_isolation ON api_usage_logs;
 CREATE POLICY api_usage_log_isolation ON api_usage_logs
     USING (current_setting('app.bypass_rls', true) = 'true' OR tenant_id = current_setting('app.current_tenant_id', true));
 
 -- 4. Credit Transaction Isolation Policy
+DROP POLICY IF EXISTS credit_transaction_isolation ON credit_transactions;
 CREATE POLICY credit_transaction_isolation ON credit_transactions
     USING (current_setting('app.bypass_rls', true) = 'true' OR tenant_id = current_setting('app.current_tenant_id', true));
 
 -- 5. MCP Tool Isolation Policy
+DROP POLICY IF EXISTS mcp_tool_isolation ON mcp_tools;
 CREATE POLICY mcp_tool_isolation ON mcp_tools
     USING (current_setting('app.bypass_rls', true) = 'true' OR tenant_id = current_setting('app.current_tenant_id', true));
 
 -- 6. Tenant User Membership Isolation
+DROP POLICY IF EXISTS tenant_user_isolation ON tenant_users;
 CREATE POLICY tenant_user_isolation ON tenant_users
     USING (current_setting('app.bypass_rls', true) = 'true' OR tenant_id = current_setting('app.current_tenant_id', true));
+
+-- 7. MCP Tool Circuit Isolation
+DROP POLICY IF EXISTS mcp_tool_circuit_isolation ON mcp_tool_circuits;
+CREATE POLICY mcp_tool_circuit_isolation ON mcp_tool_circuits
+    USING (current_setting('app.bypass_rls', true) = 'true' OR tenant_id = current_setting('app.current_tenant_id', true));
+
+-- 8. MCP Resource Isolation
+DROP POLICY IF EXISTS mcp_resource_isolation ON mcp_resources;
+CREATE POLICY mcp_resource_isolation ON mcp_resources
+    USING (current_setting('app.bypass_rls', true) = 'true' OR tenant_id = current_setting('app.current_tenant_id', true));
+
+-- 9. MCP Prompt Isolation
+DROP POLICY IF EXISTS mcp_prompt_isolation ON mcp_prompts;
+CREATE POLICY mcp_prompt_isolation ON mcp_prompts
+    USING (current_setting('app.bypass_rls', true) = 'true' OR tenant_id = current_setting('app.current_tenant_id', true));
+
+-- 10. MCP Toolset Isolation
+DROP POLICY IF EXISTS mcp_toolset_isolation ON mcp_toolsets;
+CREATE POLICY mcp_toolset_isolation ON mcp_toolsets
+    USING (current_setting('app.bypass_rls', true) = 'true' OR tenant_id = current_setting('app.current_tenant_id', true));
+
+-- 11. Downstream Auth Override Isolation
+DROP POLICY IF EXISTS downstream_auth_override_isolation ON downstream_auth_overrides;
+CREATE POLICY downstream_auth_override_isolation ON downstream_auth_overrides
+    USING (current_setting('app.bypass_rls', true) = 'true' OR tenant_id = current_setting('app.current_tenant_id', true));
+
+-- 12. Consumer OAuth Token Isolation (owned by the provider tenant)
+DROP POLICY IF EXISTS consumer_oauth_token_isolation ON consumer_oauth_tokens;
+CREATE POLICY consumer_oauth_token_isolation ON consumer_oauth_tokens
+    USING (current_setting('app.bypass_rls', true) = 'true' OR provider_tenant_id = current_setting('app.current_tenant_id', true));
+
+DROP POLICY IF EXISTS consumer_oauth_state_isolation ON consumer_oauth_states;
+CREATE POLICY consumer_oauth_state_isolation ON consumer_oauth_states
+    USING (current_setting('app.bypass_rls', true) = 'true' OR provider_tenant_id = current_setting('app.current_tenant_id', true));
+
+-- 13. Tenant Secret Vault Isolation
+DROP POLICY IF EXISTS tenant_secret_isolation ON tenant_secrets;
+CREATE POLICY tenant_secret_isolation ON tenant_secrets
+    USING (current_setting('app.bypass_rls', true) = 'true' OR tenant_id = current_setting('app.current_tenant_id', true));
+
+DROP POLICY IF EXISTS tenant_secret_version_isolation ON tenant_secret_versions;
+CREATE POLICY tenant_secret_version_isolation ON tenant_secret_versions
+    USING (current_setting('app.bypass_rls', true) = 'true' OR tenant_id = current_setting('app.current_tenant_id', true));
+
+-- 14. Security Event Isolation (rows without a tenant are admin-only)
+DROP POLICY IF EXISTS security_event_isolation ON security_events;
+CREATE POLICY security_event_isolation ON security_events
+    USING (current_setting('app.bypass_rls', true) = 'true' OR tenant_id = current_setting('app.current_tenant_id', true));
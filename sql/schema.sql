@@ -260,11 +260,151 @@ BEGIN
     END IF;
 END $$;
 
+-- Declarative argument → REST request mapping (path/query/headers/body).
+-- NULL = default placement. See src/endpoint_store/request_mapping.rs.
+DO $$
+BEGIN
+    IF NOT EXISTS (
+        SELECT 1 FROM information_schema.columns
+        WHERE table_name = 'mcp_tools' AND column_name = 'request_mapping'
+    ) THEN
+        ALTER TABLE mcp_tools ADD COLUMN request_mapping JSONB DEFAULT NULL;
+    END IF;
+END $$;
+
+-- Per-tool resilience policy applied by the gateway (retries, concurrency,
+-- circuit breaker). See src/endpoint_store/tool_resilience.rs.
+DO $$
+BEGIN
+    IF NOT EXISTS (
+        SELECT 1 FROM information_schema.columns
+        WHERE table_name = 'mcp_tools' AND column_name = 'retry_max_attempts'
+    ) THEN
+        ALTER TABLE mcp_tools
+            ADD COLUMN retry_max_attempts        INTEGER   NOT NULL DEFAULT 0,
+            ADD COLUMN retry_backoff_ms          INTEGER   NOT NULL DEFAULT 200,
+            ADD COLUMN retry_max_backoff_ms      INTEGER   NOT NULL DEFAULT 5000,
+            ADD COLUMN retry_on_status           INTEGER[] NOT NULL DEFAULT '{502,503,504}',
+            ADD COLUMN max_concurrency           INTEGER   DEFAULT NULL,
+            ADD COLUMN circuit_failure_threshold INTEGER   NOT NULL DEFAULT 5,
+            ADD COLUMN circuit_cooldown_ms       INTEGER   NOT NULL DEFAULT 30000;
+    END IF;
+END $$;
+
+-- Toolset selectors: free-form tags and the api_group a tool came from.
+DO $$
+BEGIN
+    IF NOT EXISTS (
+        SELECT 1 FROM information_schema.columns
+        WHERE table_name = 'mcp_tools' AND column_name = 'tags'
+    ) THEN
+        ALTER TABLE mcp_tools
+            ADD COLUMN tags     TEXT[]  NOT NULL DEFAULT '{}',
+            ADD COLUMN group_id VARCHAR DEFAULT NULL;
+    END IF;
+END $$;
+
 CREATE INDEX IF NOT EXISTS idx_mcp_tools_lookup
     ON mcp_tools(tenant_id, tool_name, is_active);
 CREATE INDEX IF NOT EXISTS idx_mcp_tools_tenant
     ON mcp_tools(tenant_id);
 
+-- ── MCP tool circuits ────────────────────────────────────────────────────────
+-- Circuit-breaker state per (tenant, tool), fed by outcomes reported through
+-- log_api_usage. Rows also exist for virtual (endpoint-derived) tools, which
+-- have no mcp_tools row, so there is no FK to mcp_tools.
+-- state is 'closed' or 'open'; half-open is derived from opened_at + cooldown.
+-- probe_started_at is set while the single half-open probe is out.
+
+CREATE TABLE IF NOT EXISTS mcp_tool_circuits (
+    tenant_id            VARCHAR     NOT NULL REFERENCES tenants(id) ON DELETE CASCADE,
+    tool_name            VARCHAR     NOT NULL,
+    state                VARCHAR     NOT NULL DEFAULT 'closed',
+    consecutive_failures INTEGER     NOT NULL DEFAULT 0,
+    opened_at            TIMESTAMPTZ DEFAULT NULL,
+    probe_started_at     TIMESTAMPTZ DEFAULT NULL,
+    last_failure_at      TIMESTAMPTZ DEFAULT NULL,
+    last_success_at      TIMESTAMPTZ DEFAULT NULL,
+    updated_at           TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (tenant_id, tool_name)
+);
+
+DO $$
+BEGIN
+    IF NOT EXISTS (
+        SELECT 1 FROM information_schema.columns
+        WHERE table_name = 'mcp_tool_circuits' AND column_name = 'probe_started_at'
+    ) THEN
+        ALTER TABLE mcp_tool_circuits ADD COLUMN probe_started_at TIMESTAMPTZ DEFAULT NULL;
+    END IF;
+END $$;
+
+-- ── MCP toolsets ─────────────────────────────────────────────────────────────
+-- Named subsets of a tenant's tools. A tool belongs to a set if its name is in
+-- tool_names, one of its tags is in tags, or its group_id is in group_ids.
+-- Keys with rows in api_key_toolsets only see their sets' tools; keys without
+-- any binding see the full catalog.
+
+CREATE TABLE IF NOT EXISTS mcp_toolsets (
+    id          VARCHAR     PRIMARY KEY DEFAULT gen_random_uuid()::VARCHAR,
+    tenant_id   VARCHAR     NOT NULL REFERENCES tenants(id) ON DELETE CASCADE,
+    name        VARCHAR     NOT NULL,
+    description TEXT        NOT NULL DEFAULT '',
+    tool_names  TEXT[]      NOT NULL DEFAULT '{}',
+    tags        TEXT[]      NOT NULL DEFAULT '{}',
+    group_ids   TEXT[]      NOT NULL DEFAULT '{}',
+    created_at  TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at  TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    UNIQUE(tenant_id, name)
+);
+
+CREATE TABLE IF NOT EXISTS api_key_toolsets (
+    key_id     VARCHAR NOT NULL REFERENCES api_keys(id) ON DELETE CASCADE,
+    toolset_id VARCHAR NOT NULL REFERENCES mcp_toolsets(id) ON DELETE CASCADE,
+    PRIMARY KEY (key_id, toolset_id)
+);
+
+-- ── MCP resources & prompts ──────────────────────────────────────────────────
+-- Tenant-scoped registries served next to mcp_tools.
+-- A resource has either inline content or a fetch_url the gateway reads.
+
+CREATE TABLE IF NOT EXISTS mcp_resources (
+    id                VARCHAR     PRIMARY KEY DEFAULT gen_random_uuid()::VARCHAR,
+    tenant_id         VARCHAR     NOT NULL REFERENCES tenants(id) ON DELETE CASCADE,
+    uri               VARCHAR     NOT NULL,
+    name              VARCHAR     NOT NULL,
+    description       TEXT        NOT NULL DEFAULT '',
+    mime_type         VARCHAR     NOT NULL DEFAULT 'text/plain',
+    content           TEXT        DEFAULT NULL,
+    fetch_url         VARCHAR     DEFAULT NULL,
+    reference_data_id VARCHAR     DEFAULT NULL REFERENCES reference_data(id) ON DELETE SET NULL,
+    is_active         BOOLEAN     NOT NULL DEFAULT TRUE,
+    created_at        TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at        TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    UNIQUE(tenant_id, uri)
+);
+
+CREATE INDEX IF NOT EXISTS idx_mcp_resources_tenant
+    ON mcp_resources(tenant_id, is_active);
+
+-- arguments: [{ name, description, required }]
+-- messages:  [{ role, content }] — content may reference {{argument}}
+CREATE TABLE IF NOT EXISTS mcp_prompts (
+    id          VARCHAR     PRIMARY KEY DEFAULT gen_random_uuid()::VARCHAR,
+    tenant_id   VARCHAR     NOT NULL REFERENCES tenants(id) ON DELETE CASCADE,
+    name        VARCHAR     NOT NULL,
+    description TEXT        NOT NULL DEFAULT '',
+    arguments   JSONB       NOT NULL DEFAULT '[]',
+    messages    JSONB       NOT NULL DEFAULT '[]',
+    is_active   BOOLEAN     NOT NULL DEFAULT TRUE,
+    created_at  TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at  TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    UNIQUE(tenant_id, name)
+);
+
+CREATE INDEX IF NOT EXISTS idx_mcp_prompts_tenant
+    ON mcp_prompts(tenant_id, is_active);
+
 -- ── Tenant downstream auth ────────────────────────────────────────────────────
 -- One row per tenant — defines how the MCP gateway authenticates against the
 -- tenant's backend on every proxied call.
@@ -282,6 +422,156 @@ CREATE TABLE IF NOT EXISTS tenant_downstream_auth (
     updated_at           TIMESTAMPTZ NOT NULL DEFAULT NOW()
 );
 
+-- Per-group / per-tool overrides of the tenant config above.
+-- scope 'tool' → target = mcp tool_name; scope 'group' → target = api_groups.id.
+-- Resolution for a call: tool → group → tenant.
+CREATE TABLE IF NOT EXISTS downstream_auth_overrides (
+    tenant_id            VARCHAR NOT NULL REFERENCES tenants(id) ON DELETE CASCADE,
+    scope                VARCHAR NOT NULL,
+    target               VARCHAR NOT NULL,
+    auth_mode            VARCHAR NOT NULL DEFAULT 'none',
+    service_account_json TEXT    DEFAULT NULL,
+    target_audience      VARCHAR DEFAULT NULL,
+    bearer_token         VARCHAR DEFAULT NULL,
+    custom_headers       JSONB   DEFAULT NULL,
+    updated_at           TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (tenant_id, scope, target)
+);
+
+-- oauth2_client_credentials: the store fetches access tokens from the
+-- tenant's token endpoint and hands them to the gateway.
+DO $$
+DECLARE t TEXT;
+BEGIN
+    FOREACH t IN ARRAY ARRAY['tenant_downstream_auth', 'downstream_auth_overrides'] LOOP
+        IF NOT EXISTS (
+            SELECT 1 FROM information_schema.columns
+            WHERE table_name = t AND column_name = 'oauth_token_url'
+        ) THEN
+            EXECUTE format('ALTER TABLE %I
+                ADD COLUMN oauth_token_url     VARCHAR DEFAULT NULL,
+                ADD COLUMN oauth_client_id     VARCHAR DEFAULT NULL,
+                ADD COLUMN oauth_client_secret VARCHAR DEFAULT NULL,
+                ADD COLUMN oauth_scopes        VARCHAR DEFAULT NULL,
+                ADD COLUMN oauth_audience      VARCHAR DEFAULT NULL', t);
+        END IF;
+    END LOOP;
+END $$;
+
+-- oauth2_authorization_code: per-end-user tokens for consumer keys. The
+-- provider's OAuth app reuses the oauth_* columns above plus the
+-- authorization endpoint the end-user is sent to.
+DO $$
+DECLARE t TEXT;
+BEGIN
+    FOREACH t IN ARRAY ARRAY['tenant_downstream_auth', 'downstream_auth_overrides'] LOOP
+        IF NOT EXISTS (
+            SELECT 1 FROM information_schema.columns
+            WHERE table_name = t AND column_name = 'oauth_authorize_url'
+        ) THEN
+            EXECUTE format('ALTER TABLE %I ADD COLUMN oauth_authorize_url VARCHAR DEFAULT NULL', t);
+        END IF;
+    END LOOP;
+END $$;
+
+-- hmac_signature: the store signs outgoing requests with a shared secret.
+-- hmac_signed_headers lists request headers included in the signed string;
+-- hmac_signature_headers maps header names to templates such as
+-- "t={timestamp},v1={signature}".
+DO $$
+DECLARE t TEXT;
+BEGIN
+    FOREACH t IN ARRAY ARRAY['tenant_downstream_auth', 'downstream_auth_overrides'] LOOP
+        IF NOT EXISTS (
+            SELECT 1 FROM information_schema.columns
+            WHERE table_name = t AND column_name = 'hmac_secret'
+        ) THEN
+            EXECUTE format('ALTER TABLE %I
+                ADD COLUMN hmac_secret            VARCHAR DEFAULT NULL,
+                ADD COLUMN hmac_algorithm         VARCHAR DEFAULT NULL,
+                ADD COLUMN hmac_signed_headers    TEXT[]  DEFAULT NULL,
+                ADD COLUMN hmac_signature_headers JSONB   DEFAULT NULL', t);
+        END IF;
+    END LOOP;
+END $$;
+
+-- Connectivity test: health_check_url is probed instead of the tool's
+-- backend_url when set (absolute, or a path resolved against backend_url).
+-- verified_at is set by the first successful probe after a save.
+DO $$
+DECLARE t TEXT;
+BEGIN
+    FOREACH t IN ARRAY ARRAY['tenant_downstream_auth', 'downstream_auth_overrides'] LOOP
+        IF NOT EXISTS (
+            SELECT 1 FROM information_schema.columns
+            WHERE table_name = t AND column_name = 'health_check_url'
+        ) THEN
+            EXECUTE format('ALTER TABLE %I ADD COLUMN health_check_url VARCHAR DEFAULT NULL', t);
+        END IF;
+    END LOOP;
+    IF NOT EXISTS (
+        SELECT 1 FROM information_schema.columns
+        WHERE table_name = 'tenant_downstream_auth' AND column_name = 'verified_at'
+    ) THEN
+        ALTER TABLE tenant_downstream_auth ADD COLUMN verified_at TIMESTAMPTZ DEFAULT NULL;
+    END IF;
+END $$;
+
+-- Tokens obtained through the authorization-code flow, one per end-user of a
+-- provider. subject_kind 'key' → subject = api_keys.id of the consumer key;
+-- 'consumer' → subject = the provider's opaque consumer_id (X-Consumer-Id).
+CREATE TABLE IF NOT EXISTS consumer_oauth_tokens (
+    provider_tenant_id VARCHAR NOT NULL REFERENCES tenants(id) ON DELETE CASCADE,
+    subject_kind       VARCHAR NOT NULL,
+    subject            VARCHAR NOT NULL,
+    access_token       TEXT    NOT NULL,
+    refresh_token      TEXT    DEFAULT NULL,
+    token_type         VARCHAR NOT NULL DEFAULT 'Bearer',
+    scopes             VARCHAR DEFAULT NULL,
+    expires_at         TIMESTAMPTZ DEFAULT NULL,
+    created_at         TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at         TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (provider_tenant_id, subject_kind, subject)
+);
+
+-- Pending authorization requests (state + PKCE verifier), single use.
+CREATE TABLE IF NOT EXISTS consumer_oauth_states (
+    state              VARCHAR PRIMARY KEY,
+    provider_tenant_id VARCHAR NOT NULL REFERENCES tenants(id) ON DELETE CASCADE,
+    subject_kind       VARCHAR NOT NULL,
+    subject            VARCHAR NOT NULL,
+    tool_name          VARCHAR DEFAULT NULL,
+    redirect_uri       TEXT    NOT NULL,
+    code_verifier      VARCHAR NOT NULL,
+    created_at         TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    expires_at         TIMESTAMPTZ NOT NULL
+);
+
+-- Tenant secret vault: named, write-only, versioned secrets that auth configs
+-- and tools reference as {{secret:NAME}} (or {{secret:NAME@VERSION}}).
+-- Values are envelope-encrypted (see secret_crypto.rs).
+CREATE TABLE IF NOT EXISTS tenant_secrets (
+    tenant_id       VARCHAR NOT NULL REFERENCES tenants(id) ON DELETE CASCADE,
+    name            VARCHAR NOT NULL,
+    description     TEXT    DEFAULT NULL,
+    current_version INTEGER NOT NULL DEFAULT 1,
+    created_at      TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at      TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    last_used_at    TIMESTAMPTZ DEFAULT NULL,
+    PRIMARY KEY (tenant_id, name)
+);
+
+CREATE TABLE IF NOT EXISTS tenant_secret_versions (
+    tenant_id    VARCHAR NOT NULL,
+    name         VARCHAR NOT NULL,
+    version      INTEGER NOT NULL,
+    value        TEXT    NOT NULL,
+    created_at   TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    last_used_at TIMESTAMPTZ DEFAULT NULL,
+    PRIMARY KEY (tenant_id, name, version),
+    FOREIGN KEY (tenant_id, name) REFERENCES tenant_secrets(tenant_id, name) ON DELETE CASCADE
+);
+
 -- Per-provider OAuth client ID — allows each provider to have their own
 -- client_id (e.g. "cvenom-mcp") that resolves to their provider_tenant_id.
 DO $$
@@ -430,3 +720,171 @@ BEGIN
             WHERE expires_at IS NOT NULL;
     END IF;
 END $$;
+
+-- Key scopes (endpoint_store/key_scopes.rs): NULL = all scopes / unrestricted.
+DO $$
+BEGIN
+    IF NOT EXISTS (
+        SELECT 1 FROM information_schema.columns
+        WHERE table_name = 'api_keys' AND column_name = 'scopes'
+    ) THEN
+        ALTER TABLE api_keys ADD COLUMN scopes TEXT[];
+        ALTER TABLE api_keys ADD COLUMN allowed_group_ids TEXT[];
+        ALTER TABLE api_keys ADD COLUMN allowed_tools TEXT[];
+    END IF;
+END $$;
+
+-- Key rotation: a rotated key gets a new secret under the same id; the old
+-- hash keeps validating until previous_expires_at (grace window).
+DO $$
+BEGIN
+    IF NOT EXISTS (
+        SELECT 1 FROM information_schema.columns
+        WHERE table_name = 'api_keys' AND column_name = 'generation'
+    ) THEN
+        ALTER TABLE api_keys ADD COLUMN generation INT NOT NULL DEFAULT 1;
+        ALTER TABLE api_keys ADD COLUMN previous_key_hash VARCHAR;
+        ALTER TABLE api_keys ADD COLUMN previous_key_prefix VARCHAR;
+        ALTER TABLE api_keys ADD COLUMN previous_expires_at TIMESTAMP WITH TIME ZONE;
+        CREATE INDEX idx_api_keys_previous_hash ON api_keys(previous_key_hash)
+            WHERE previous_key_hash IS NOT NULL;
+    END IF;
+END $$;
+
+-- Key lifetime policy (endpoint_store/key_expiry.rs): NULL = unlimited.
+DO $$
+BEGIN
+    IF NOT EXISTS (
+        SELECT 1 FROM information_schema.columns
+        WHERE table_name = 'tenants' AND column_name = 'max_key_lifetime_days'
+    ) THEN
+        ALTER TABLE tenants ADD COLUMN max_key_lifetime_days INT;
+    END IF;
+END $$;
+
+-- Expiry warnings already sent: stage = days before expiry (14, 3, 1), 0 = expired.
+CREATE TABLE IF NOT EXISTS api_key_expiry_notices (
+    key_id  VARCHAR     NOT NULL REFERENCES api_keys(id) ON DELETE CASCADE,
+    stage   INT         NOT NULL,
+    sent_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (key_id, stage)
+);
+
+-- Rate limits and daily quotas (endpoint_store/key_quotas.rs).
+-- api_keys overrides; NULL falls back to the tenant default; all NULL = unlimited.
+DO $$
+BEGIN
+    IF NOT EXISTS (
+        SELECT 1 FROM information_schema.columns
+        WHERE table_name = 'api_keys' AND column_name = 'rate_limit_rpm'
+    ) THEN
+        ALTER TABLE api_keys ADD COLUMN rate_limit_rpm BIGINT;
+        ALTER TABLE api_keys ADD COLUMN daily_call_limit BIGINT;
+        ALTER TABLE api_keys ADD COLUMN daily_credit_limit BIGINT;
+    END IF;
+    IF NOT EXISTS (
+        SELECT 1 FROM information_schema.columns
+        WHERE table_name = 'tenants' AND column_name = 'default_rate_limit_rpm'
+    ) THEN
+        ALTER TABLE tenants ADD COLUMN default_rate_limit_rpm BIGINT;
+        ALTER TABLE tenants ADD COLUMN default_daily_call_limit BIGINT;
+        ALTER TABLE tenants ADD COLUMN default_daily_credit_limit BIGINT;
+    END IF;
+END $$;
+
+-- Current counter window per key: window_kind 'minute' or 'day'.
+CREATE TABLE IF NOT EXISTS api_key_quota_windows (
+    key_id       VARCHAR     NOT NULL REFERENCES api_keys(id) ON DELETE CASCADE,
+    window_kind  VARCHAR     NOT NULL,
+    window_start TIMESTAMPTZ NOT NULL,
+    calls        BIGINT      NOT NULL DEFAULT 0,
+    credits      BIGINT      NOT NULL DEFAULT 0,
+    PRIMARY KEY (key_id, window_kind)
+);
+
+-- Caller IP allowlist (endpoint_store/ip_allowlist.rs): normalised CIDRs, NULL = any address.
+DO $$
+BEGIN
+    IF NOT EXISTS (
+        SELECT 1 FROM information_schema.columns
+        WHERE table_name = 'api_keys' AND column_name = 'allowed_cidrs'
+    ) THEN
+        ALTER TABLE api_keys ADD COLUMN allowed_cidrs TEXT[];
+    END IF;
+END $$;
+
+-- Security log (endpoint_store/security_events.rs), e.g. 'ip_not_allowed'.
+-- tenant_id / key_id are NULL when the event cannot be tied to a key.
+CREATE TABLE IF NOT EXISTS security_events (
+    id         BIGSERIAL   PRIMARY KEY,
+    tenant_id  VARCHAR     REFERENCES tenants(id) ON DELETE CASCADE,
+    key_id     VARCHAR,
+    event_type VARCHAR     NOT NULL,
+    ip_address VARCHAR,
+    detail     JSONB       NOT NULL DEFAULT '{}'::jsonb,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+CREATE INDEX IF NOT EXISTS idx_security_events_tenant ON security_events (tenant_id, created_at DESC);
+CREATE INDEX IF NOT EXISTS idx_security_events_key ON security_events (key_id, created_at DESC);
+
+-- Key format (endpoint_store/api_key_management.rs): 'v1' = SHA-256 hash,
+-- 'v2' = HMAC-SHA256 under API0_KEY_PEPPER with an embedded checksum.
+DO $$
+BEGIN
+    IF NOT EXISTS (
+        SELECT 1 FROM information_schema.columns
+        WHERE table_name = 'api_keys' AND column_name = 'key_version'
+    ) THEN
+        ALTER TABLE api_keys ADD COLUMN key_version VARCHAR NOT NULL DEFAULT 'v1';
+    END IF;
+END $$;
+
+-- Brute-force protection for key validation (endpoint_store/auth_lockout.rs).
+-- kind is 'ip', 'key_prefix' or 'tenant'. One row per failed attempt, pruned hourly.
+CREATE TABLE IF NOT EXISTS auth_failures (
+    id         BIGSERIAL   PRIMARY KEY,
+    kind       VARCHAR     NOT NULL,
+    subject    VARCHAR     NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+CREATE INDEX IF NOT EXISTS idx_auth_failures_subject ON auth_failures (kind, subject, created_at);
+
+-- strikes drives escalation and resets once last_tripped_at is a day old.
+CREATE TABLE IF NOT EXISTS auth_lockouts (
+    kind            VARCHAR     NOT NULL,
+    subject         VARCHAR     NOT NULL,
+    strikes         INT         NOT NULL DEFAULT 1,
+    locked_until    TIMESTAMPTZ NOT NULL,
+    last_tripped_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (kind, subject)
+);
+
+-- Signing keys for access tokens exchanged from API keys (endpoint_store/access_tokens.rs).
+-- kid is the RFC 7638 thumbprint; private_key is PKCS#8 (base64) sealed with
+-- secret_crypto. Published in the JWKS until retires_at.
+CREATE TABLE IF NOT EXISTS token_signing_keys (
+    kid          VARCHAR     PRIMARY KEY,
+    algorithm    VARCHAR     NOT NULL DEFAULT 'EdDSA',
+    public_key   VARCHAR     NOT NULL,
+    private_key  TEXT        NOT NULL,
+    created_at   TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    activates_at TIMESTAMPTZ NOT NULL,
+    retires_at   TIMESTAMPTZ
+);
+
+-- Provider governance of consumer keys (endpoint_store/consumer_keys.rs).
+-- suspended_at: key fails validation until resumed. provider_*: caps set by
+-- the provider on top of the key's own limits; NULL = no cap.
+DO $$
+BEGIN
+    IF NOT EXISTS (
+        SELECT 1 FROM information_schema.columns
+        WHERE table_name = 'api_keys' AND column_name = 'suspended_at'
+    ) THEN
+        ALTER TABLE api_keys ADD COLUMN suspended_at TIMESTAMPTZ;
+        ALTER TABLE api_keys ADD COLUMN provider_rate_limit_rpm BIGINT;
+        ALTER TABLE api_keys ADD COLUMN provider_daily_call_limit BIGINT;
+        ALTER TABLE api_keys ADD COLUMN provider_daily_credit_limit BIGINT;
+    END IF;
+END $$;
+CREATE INDEX IF NOT EXISTS idx_api_keys_provider_email ON api_keys (provider_tenant_id, email);
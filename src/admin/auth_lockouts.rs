@@ -0,0 +1,104 @@
+// src/admin/auth_lockouts.rs
+//
+// Key validation lockouts (see endpoint_store/auth_lockout.rs).
+//
+// Admin (X-Internal-Secret):
+//   GET  /api/admin/auth-lockouts?kind=ip&active=true   — list lockouts and policies
+//   POST /api/admin/auth-lockouts/clear                  — lift one lockout
+//     Body: { "kind": "ip", "subject": "203.0.113.7" }
+
+use crate::app_log;
+use crate::endpoint_store::auth_lockout::{clear_lockout, list_lockouts, policy, POLICIES};
+use crate::endpoint_store::EndpointStore;
+use actix_web::{web, HttpRequest, HttpResponse, Responder};
+use serde::Deserialize;
+use std::sync::Arc;
+
+fn check_internal_secret(req: &HttpRequest) -> bool {
+    let expected = match std::env::var("API0_INTERNAL_SECRET") {
+        Ok(s) if !s.is_empty() => s,
+        _ => return false,
+    };
+    req.headers()
+        .get("X-Internal-Secret")
+        .and_then(|v| v.to_str().ok())
+        .map(|v| v == expected)
+        .unwrap_or(false)
+}
+
+#[derive(Debug, Deserialize)]
+pub struct LockoutQuery {
+    pub kind: Option<String>,
+    /// Only lockouts still in force (default true).
+    pub active: Option<bool>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ClearLockoutRequest {
+    pub kind: String,
+    pub subject: String,
+}
+
+pub async fn list_auth_lockouts(
+    req: HttpRequest,
+    store: web::Data<Arc<EndpointStore>>,
+    query: web::Query<LockoutQuery>,
+) -> impl Responder {
+    if !check_internal_secret(&req) {
+        return HttpResponse::Unauthorized()
+            .json(serde_json::json!({"success": false, "error": "Unauthorized"}));
+    }
+
+    let policies: Vec<_> = POLICIES
+        .iter()
+        .map(|p| {
+            serde_json::json!({
+                "kind": p.kind,
+                "threshold": p.threshold,
+                "window_secs": p.window_secs,
+                "base_lockout_secs": p.base_lockout_secs,
+                "max_lockout_secs": p.max_lockout_secs,
+            })
+        })
+        .collect();
+
+    match list_lockouts(&store, query.kind.as_deref(), query.active.unwrap_or(true)).await {
+        Ok(lockouts) => HttpResponse::Ok().json(serde_json::json!({
+            "success": true,
+            "policies": policies,
+            "lockouts": lockouts,
+        })),
+        Err(e) => {
+            app_log!(error, error = %e, "Failed to list auth lockouts");
+            HttpResponse::InternalServerError()
+                .json(serde_json::json!({"success": false, "error": "Failed to list lockouts"}))
+        }
+    }
+}
+
+pub async fn clear_auth_lockout(
+    req: HttpRequest,
+    store: web::Data<Arc<EndpointStore>>,
+    body: web::Json<ClearLockoutRequest>,
+) -> impl Responder {
+    if !check_internal_secret(&req) {
+        return HttpResponse::Unauthorized()
+            .json(serde_json::json!({"success": false, "error": "Unauthorized"}));
+    }
+    if policy(&body.kind).is_none() {
+        return HttpResponse::BadRequest()
+            .json(serde_json::json!({"success": false, "error": "kind must be ip, secret or tenant"}));
+    }
+
+    match clear_lockout(&store, &body.kind, &body.subject).await {
+        Ok(cleared) => {
+            app_log!(info, kind = %body.kind, subject = %body.subject, cleared = cleared, "Cleared auth lockout");
+            HttpResponse::Ok().json(serde_json::json!({"success": true, "cleared": cleared}))
+        }
+        Err(e) => {
+            app_log!(error, error = %e, "Failed to clear auth lockout");
+            HttpResponse::InternalServerError()
+                .json(serde_json::json!({"success": false, "error": "Failed to clear lockout"}))
+        }
+    }
+}
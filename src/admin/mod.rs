@@ -1,2 +1,4 @@
+pub mod auth_lockouts;
 pub mod model_config;
+pub mod token_keys;
 pub mod user_roles;
@@ -0,0 +1,74 @@
+// src/admin/token_keys.rs
+//
+// Access token signing keys (see endpoint_store/access_tokens.rs).
+//
+// Admin (X-Internal-Secret):
+//   GET  /api/admin/token-keys          — every key with its state
+//   POST /api/admin/token-keys/rotate   — start a new signing key
+//     Body: { "immediate": false }   true = old keys unpublished at once
+//                                    (suspected compromise)
+
+use crate::app_log;
+use crate::endpoint_store::access_tokens::{list_signing_keys, rotate_signing_key};
+use crate::endpoint_store::EndpointStore;
+use actix_web::{web, HttpRequest, HttpResponse, Responder};
+use serde::Deserialize;
+use std::sync::Arc;
+
+fn check_internal_secret(req: &HttpRequest) -> bool {
+    let expected = match std::env::var("API0_INTERNAL_SECRET") {
+        Ok(s) if !s.is_empty() => s,
+        _ => return false,
+    };
+    req.headers()
+        .get("X-Internal-Secret")
+        .and_then(|v| v.to_str().ok())
+        .map(|v| v == expected)
+        .unwrap_or(false)
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct RotateTokenKeyRequest {
+    #[serde(default)]
+    pub immediate: bool,
+}
+
+pub async fn list_token_keys(req: HttpRequest, store: web::Data<Arc<EndpointStore>>) -> impl Responder {
+    if !check_internal_secret(&req) {
+        return HttpResponse::Unauthorized()
+            .json(serde_json::json!({"success": false, "error": "Unauthorized"}));
+    }
+
+    match list_signing_keys(&store).await {
+        Ok(keys) => HttpResponse::Ok().json(serde_json::json!({"success": true, "keys": keys})),
+        Err(e) => {
+            app_log!(error, error = %e, "Failed to list token signing keys");
+            HttpResponse::InternalServerError()
+                .json(serde_json::json!({"success": false, "error": "Failed to list signing keys"}))
+        }
+    }
+}
+
+pub async fn rotate_token_key(
+    req: HttpRequest,
+    store: web::Data<Arc<EndpointStore>>,
+    body: Option<web::Json<RotateTokenKeyRequest>>,
+) -> impl Responder {
+    if !check_internal_secret(&req) {
+        return HttpResponse::Unauthorized()
+            .json(serde_json::json!({"success": false, "error": "Unauthorized"}));
+    }
+    let immediate = body.map(|b| b.immediate).unwrap_or(false);
+
+    match rotate_signing_key(&store, immediate).await {
+        Ok(kid) => {
+            app_log!(warn, kid = %kid, immediate = immediate, "Token signing key rotated by admin");
+            HttpResponse::Ok().json(serde_json::json!({"success": true, "kid": kid, "immediate": immediate}))
+        }
+        Err(e) => {
+            app_log!(error, error = %e, "Failed to rotate token signing key");
+            HttpResponse::InternalServerError()
+                .json(serde_json::json!({"success": false, "error": "Failed to rotate signing key"}))
+        }
+    }
+}
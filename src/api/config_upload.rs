@@ -66,7 +66,7 @@ fn decode_content(content: &str) -> Result<Vec<u8>, String> {
 
         // Try adding padding
         let mut padded_content = cleaned_content.clone();
-        while padded_content.len() % 4 != 0 {
+        while !padded_content.len().is_multiple_of(4) {
             padded_content.push('=');
         }
 
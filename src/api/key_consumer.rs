@@ -15,19 +15,27 @@
 //   {
 //     "provider_email":  "admin@cvenom.com",   -- identifies the provider tenant
 //     "consumer_email":  "alice@example.com",  -- the end-user who will use the key
-//     "key_name":        "Alice's MCP key"     -- label shown in dashboard
+//     "key_name":        "Alice's MCP key",    -- label shown in dashboard
+//     "ttl_days":        90                    -- optional; or "expires_at" (RFC 3339)
 //   }
 //
+// Keys expire after 365 days unless asked otherwise; the stricter of the
+// consumer's and provider's max_key_lifetime_days policy caps the lifetime.
+//
 // Response:
 //   { "success": true, "api_key": "sk_live_...", "key_prefix": "sk_live_xx",
-//     "key_id": "...", "consumer_email": "...", "provider_tenant_id": "..." }
+//     "key_id": "...", "consumer_email": "...", "provider_tenant_id": "...",
+//     "expires_at": "..." }
 //
 // The plain-text key is returned only once — the caller must store it.
 
 use crate::app_log;
 use crate::endpoint_store::api_key_management::{
-    extract_key_prefix, generate_secure_key, hash_api_key,
+    extract_key_prefix, generate_secure_key, hash_api_key, key_version,
 };
+use crate::endpoint_store::key_expiry::{resolve_new_key_expiry, KeyExpiry};
+use crate::endpoint_store::key_scopes::KeyScopes;
+use crate::endpoint_store::StoreError;
 use crate::endpoint_store::tenant_management::get_default_tenant;
 use crate::endpoint_store::EndpointStore;
 use actix_web::{web, HttpRequest, HttpResponse, Responder};
@@ -53,6 +61,12 @@ pub struct GenerateConsumerKeyRequest {
     pub provider_email: String,
     pub consumer_email: String,
     pub key_name: Option<String>,
+    /// Optional scopes / group and tool restrictions (key_scopes.rs).
+    #[serde(flatten)]
+    pub restrictions: KeyScopes,
+    /// `expires_at` or `ttl_days` (default 365 days, capped by tenant policy).
+    #[serde(flatten)]
+    pub expiry: KeyExpiry,
 }
 
 #[derive(Serialize)]
@@ -63,6 +77,7 @@ pub struct GenerateConsumerKeyResponse {
     pub key_id: String,
     pub consumer_email: String,
     pub provider_tenant_id: String,
+    pub expires_at: Option<String>,
 }
 
 pub async fn generate_consumer_key_handler(
@@ -82,6 +97,10 @@ pub async fn generate_consumer_key_handler(
         .as_deref()
         .unwrap_or("MCP Consumer Key")
         .to_string();
+    let mut restrictions = body.restrictions.clone();
+    if let Err(e) = restrictions.check() {
+        return HttpResponse::BadRequest().json(serde_json::json!({"success": false, "error": e}));
+    }
 
     // 1. Resolve provider tenant
     let provider_tenant = match get_default_tenant(&store, &provider_email).await {
@@ -108,6 +127,18 @@ pub async fn generate_consumer_key_handler(
         }
     };
 
+    let expires_at = match resolve_new_key_expiry(&store, &consumer_tenant.id, Some(&provider_tenant.id), &body.expiry).await {
+        Ok(at) => at,
+        Err(StoreError::Invalid(msg)) => {
+            return HttpResponse::BadRequest().json(serde_json::json!({"success": false, "error": msg}));
+        }
+        Err(e) => {
+            app_log!(error, error = %e, "Failed to resolve key expiry");
+            return HttpResponse::InternalServerError()
+                .json(serde_json::json!({"success":false,"error":"Database error"}));
+        }
+    };
+
     // 3. Generate the consumer key
     let new_key = generate_secure_key();
     let key_hash = hash_api_key(&new_key);
@@ -137,8 +168,9 @@ pub async fn generate_consumer_key_handler(
         .execute(
             "INSERT INTO api_keys
                 (id, email, key_hash, key_prefix, key_name,
-                 generated_at, usage_count, is_active, tenant_id, provider_tenant_id)
-             VALUES ($1, $2, $3, $4, $5, $6, 0, true, $7, $8)",
+                 generated_at, usage_count, is_active, tenant_id, provider_tenant_id,
+                 scopes, allowed_group_ids, allowed_tools, expires_at, key_version)
+             VALUES ($1, $2, $3, $4, $5, $6, 0, true, $7, $8, $9, $10, $11, $12, $13)",
             &[
                 &key_id as &(dyn tokio_postgres::types::ToSql + Sync),
                 &consumer_email as &(dyn tokio_postgres::types::ToSql + Sync),
@@ -148,6 +180,11 @@ pub async fn generate_consumer_key_handler(
                 &now as &(dyn tokio_postgres::types::ToSql + Sync),
                 &consumer_tenant.id as &(dyn tokio_postgres::types::ToSql + Sync),
                 &provider_tenant.id as &(dyn tokio_postgres::types::ToSql + Sync),
+                &restrictions.scopes as &(dyn tokio_postgres::types::ToSql + Sync),
+                &restrictions.allowed_group_ids as &(dyn tokio_postgres::types::ToSql + Sync),
+                &restrictions.allowed_tools as &(dyn tokio_postgres::types::ToSql + Sync),
+                &expires_at as &(dyn tokio_postgres::types::ToSql + Sync),
+                &key_version(&new_key) as &(dyn tokio_postgres::types::ToSql + Sync),
             ],
         )
         .await;
@@ -182,5 +219,6 @@ pub async fn generate_consumer_key_handler(
         key_id,
         consumer_email,
         provider_tenant_id: provider_tenant.id,
+        expires_at: expires_at.map(|at| at.to_rfc3339()),
     })
 }
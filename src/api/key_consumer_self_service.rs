@@ -3,13 +3,19 @@
 // Self-service consumer key API — authenticated with Firebase JWT.
 //
 // POST /api/consumer-keys/me
-//   Body:    { "provider_tenant_id": "...", "key_name": "optional label" }
+//   Body:    { "provider_tenant_id": "...", "key_name": "optional label",
+//              "ttl_days": 90 }   -- optional; or "expires_at"; default 365 days
 //   Creates a consumer key for the authenticated user linked to the given provider.
 //   Returns the plaintext key (shown once only) plus connection instructions.
 //
 // GET  /api/consumer-keys/me
 //   Returns all active consumer keys for the authenticated user, grouped by
 //   provider, WITHOUT exposing the plaintext key (it was shown once at creation).
+//   Each key carries its status: active, suspended (by the provider), expired
+//   or revoked.
+//
+// DELETE /api/consumer-keys/me/{key_id}
+//   Revokes one of the authenticated user's own consumer keys.
 //
 // Auth: Firebase JWT via `Authorization: Bearer <id_token>` (X-Firebase-Auth
 //       header is also accepted for dashboard compatibility).
@@ -19,8 +25,12 @@
 
 use crate::app_log;
 use crate::endpoint_store::api_key_management::{
-    extract_key_prefix, generate_secure_key, hash_api_key,
+    extract_key_prefix, generate_secure_key, hash_api_key, key_version,
 };
+use crate::endpoint_store::consumer_keys::revoke_own_consumer_key;
+use crate::endpoint_store::key_expiry::{resolve_new_key_expiry, KeyExpiry};
+use crate::endpoint_store::key_scopes::KeyScopes;
+use crate::endpoint_store::StoreError;
 use crate::endpoint_store::tenant_management::get_default_tenant;
 use crate::endpoint_store::EndpointStore;
 use crate::infra::auth::FirebaseUser;
@@ -36,6 +46,12 @@ use uuid::Uuid;
 pub struct GenerateSelfServiceKeyRequest {
     pub provider_tenant_id: String,
     pub key_name: Option<String>,
+    /// Optional scopes / group and tool restrictions (key_scopes.rs).
+    #[serde(flatten)]
+    pub restrictions: KeyScopes,
+    /// `expires_at` or `ttl_days` (default 365 days, capped by tenant policy).
+    #[serde(flatten)]
+    pub expiry: KeyExpiry,
 }
 
 // ── POST /api/consumer-keys/me ────────────────────────────────────────────────
@@ -52,6 +68,10 @@ pub async fn generate_self_service_key(
         .as_deref()
         .unwrap_or("My Claude MCP Key")
         .to_string();
+    let mut restrictions = body.restrictions.clone();
+    if let Err(e) = restrictions.check() {
+        return HttpResponse::BadRequest().json(serde_json::json!({"success": false, "error": e}));
+    }
 
     if provider_tenant_id.is_empty() {
         return HttpResponse::BadRequest().json(serde_json::json!({
@@ -102,6 +122,18 @@ pub async fn generate_self_service_key(
         }
     };
 
+    let expires_at = match resolve_new_key_expiry(&store, &consumer_tenant.id, Some(&provider_tenant_id), &body.expiry).await {
+        Ok(at) => at,
+        Err(StoreError::Invalid(msg)) => {
+            return HttpResponse::BadRequest().json(serde_json::json!({"success": false, "error": msg}));
+        }
+        Err(e) => {
+            app_log!(error, error = %e, "Failed to resolve key expiry");
+            return HttpResponse::InternalServerError()
+                .json(serde_json::json!({"success":false,"error":"Database error"}));
+        }
+    };
+
     // 3. Generate the consumer key
     let new_key    = generate_secure_key();
     let key_hash   = hash_api_key(&new_key);
@@ -131,8 +163,9 @@ pub async fn generate_self_service_key(
         .execute(
             "INSERT INTO api_keys
                 (id, email, key_hash, key_prefix, key_name,
-                 generated_at, usage_count, is_active, tenant_id, provider_tenant_id)
-             VALUES ($1, $2, $3, $4, $5, $6, 0, true, $7, $8)",
+                 generated_at, usage_count, is_active, tenant_id, provider_tenant_id,
+                 scopes, allowed_group_ids, allowed_tools, expires_at, key_version)
+             VALUES ($1, $2, $3, $4, $5, $6, 0, true, $7, $8, $9, $10, $11, $12, $13)",
             &[
                 &key_id as &(dyn tokio_postgres::types::ToSql + Sync),
                 &consumer_email as &(dyn tokio_postgres::types::ToSql + Sync),
@@ -142,6 +175,11 @@ pub async fn generate_self_service_key(
                 &now as &(dyn tokio_postgres::types::ToSql + Sync),
                 &consumer_tenant.id as &(dyn tokio_postgres::types::ToSql + Sync),
                 &provider_tenant_id as &(dyn tokio_postgres::types::ToSql + Sync),
+                &restrictions.scopes as &(dyn tokio_postgres::types::ToSql + Sync),
+                &restrictions.allowed_group_ids as &(dyn tokio_postgres::types::ToSql + Sync),
+                &restrictions.allowed_tools as &(dyn tokio_postgres::types::ToSql + Sync),
+                &expires_at as &(dyn tokio_postgres::types::ToSql + Sync),
+                &key_version(&new_key) as &(dyn tokio_postgres::types::ToSql + Sync),
             ],
         )
         .await;
@@ -172,6 +210,7 @@ pub async fn generate_self_service_key(
         "key_prefix": key_prefix,
         "key_id": key_id,
         "provider_tenant_id": provider_tenant_id,
+        "expires_at": expires_at.map(|at| at.to_rfc3339()),
         "message": "Key created. Copy it now — it will not be shown again.",
         "mcp_url": "https://api.api0.ai/mcp"
     }))
@@ -205,7 +244,8 @@ pub async fn list_self_service_keys(
         .query(
             "SELECT k.id, k.key_prefix, k.key_name, k.generated_at,
                     k.provider_tenant_id, t.name AS provider_name,
-                    k.is_active
+                    k.is_active, k.scopes, k.allowed_group_ids, k.allowed_tools,
+                    k.expires_at, k.suspended_at
              FROM api_keys k
              LEFT JOIN tenants t ON t.id = k.provider_tenant_id
              WHERE k.email = $1
@@ -228,6 +268,18 @@ pub async fn list_self_service_keys(
         .iter()
         .map(|row| {
             let ts: chrono::DateTime<chrono::Utc> = row.get(3);
+            let is_active: bool = row.get(6);
+            let expires_at: Option<chrono::DateTime<chrono::Utc>> = row.get(10);
+            let suspended_at: Option<chrono::DateTime<chrono::Utc>> = row.get(11);
+            let status = if !is_active {
+                "revoked"
+            } else if suspended_at.is_some() {
+                "suspended"
+            } else if expires_at.is_some_and(|at| at <= Utc::now()) {
+                "expired"
+            } else {
+                "active"
+            };
             serde_json::json!({
                 "id":                 row.get::<_, String>(0),
                 "key_prefix":         row.get::<_, String>(1),
@@ -235,7 +287,13 @@ pub async fn list_self_service_keys(
                 "generated_at":       ts.to_rfc3339(),
                 "provider_tenant_id": row.get::<_, Option<String>>(4),
                 "provider_name":      row.get::<_, Option<String>>(5),
-                "is_active":          row.get::<_, bool>(6),
+                "is_active":          is_active,
+                "status":             status,
+                "scopes":             KeyScopes::from_row(row, 7).effective_scopes(),
+                "allowed_group_ids":  row.get::<_, Option<Vec<String>>>(8),
+                "allowed_tools":      row.get::<_, Option<Vec<String>>>(9),
+                "expires_at":         expires_at.map(|at| at.to_rfc3339()),
+                "suspended_at":       suspended_at.map(|at| at.to_rfc3339()),
             })
         })
         .collect();
@@ -246,3 +304,27 @@ pub async fn list_self_service_keys(
         "mcp_url": "https://api.api0.ai/mcp"
     }))
 }
+
+pub async fn revoke_self_service_key(
+    user: FirebaseUser,
+    store: web::Data<Arc<EndpointStore>>,
+    key_id: web::Path<String>,
+) -> impl Responder {
+    let consumer_email = user.email.trim().to_lowercase();
+    let key_id = key_id.into_inner();
+
+    match revoke_own_consumer_key(&store, &consumer_email, &key_id).await {
+        Ok(true) => {
+            app_log!(info, consumer_email = %consumer_email, key_id = %key_id, "Consumer revoked own key");
+            HttpResponse::Ok().json(serde_json::json!({"success": true, "key_id": key_id}))
+        }
+        Ok(false) => HttpResponse::NotFound()
+            .json(serde_json::json!({"success": false, "error": "Consumer key not found"})),
+        Err(e) => {
+            app_log!(error, error = %e, consumer_email = %consumer_email, key_id = %key_id,
+                     "revoke_self_service_key failed");
+            HttpResponse::InternalServerError()
+                .json(serde_json::json!({"success": false, "error": "Failed to revoke key"}))
+        }
+    }
+}
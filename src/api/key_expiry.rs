@@ -0,0 +1,112 @@
+use crate::app_log;
+use crate::endpoint_store::key_expiry::{get_key_policy, list_expiring_keys, set_key_policy, KeyPolicy};
+use crate::endpoint_store::{EndpointStore, StoreError};
+use actix_web::{web, HttpResponse, Responder};
+use serde::Deserialize;
+use std::sync::Arc;
+
+// Key lifetime endpoints (see endpoint_store/key_expiry.rs):
+//   GET /api/user/keys/{tenant_id}/expiring?days=30   — active keys expiring soon
+//   GET /api/user/key-policy/{tenant_id}              — { max_key_lifetime_days }
+//   PUT /api/user/key-policy/{tenant_id}              — null = unlimited
+// {tenant_id} may also be an owner email, as for the other /user/keys routes.
+
+const DEFAULT_WINDOW_DAYS: i64 = 30;
+const MAX_WINDOW_DAYS: i64 = 366;
+
+#[derive(Debug, Deserialize)]
+pub struct ExpiringQuery {
+    pub days: Option<i64>,
+}
+
+async fn resolve_tenant(store: &EndpointStore, tenant_id: String) -> Result<String, HttpResponse> {
+    if !tenant_id.contains('@') {
+        return Ok(tenant_id);
+    }
+    use crate::endpoint_store::tenant_management;
+    match tenant_management::get_default_tenant(store, &tenant_id).await {
+        Ok(t) => Ok(t.id),
+        Err(e) => {
+            app_log!(error, email = %tenant_id, error = %e, "Failed to resolve tenant for key expiry lookup");
+            Err(HttpResponse::InternalServerError().json(serde_json::json!({
+                "success": false,
+                "message": "Account resolution failed"
+            })))
+        }
+    }
+}
+
+pub async fn list_expiring_keys_handler(
+    store: web::Data<Arc<EndpointStore>>,
+    tenant_id: web::Path<String>,
+    query: web::Query<ExpiringQuery>,
+) -> impl Responder {
+    let tenant_id = match resolve_tenant(&store, tenant_id.into_inner()).await {
+        Ok(t) => t,
+        Err(resp) => return resp,
+    };
+    let days = query.days.unwrap_or(DEFAULT_WINDOW_DAYS).clamp(1, MAX_WINDOW_DAYS);
+
+    match list_expiring_keys(&store, &tenant_id, days).await {
+        Ok(keys) => HttpResponse::Ok().json(serde_json::json!({
+            "success": true,
+            "within_days": days,
+            "keys": keys,
+        })),
+        Err(e) => {
+            app_log!(error, error = %e, tenant_id = %tenant_id, "Failed to list expiring API keys");
+            HttpResponse::InternalServerError().json(serde_json::json!({
+                "success": false,
+                "message": "Failed to list expiring keys",
+            }))
+        }
+    }
+}
+
+pub async fn get_key_policy_handler(
+    store: web::Data<Arc<EndpointStore>>,
+    tenant_id: web::Path<String>,
+) -> impl Responder {
+    let tenant_id = match resolve_tenant(&store, tenant_id.into_inner()).await {
+        Ok(t) => t,
+        Err(resp) => return resp,
+    };
+
+    match get_key_policy(&store, &tenant_id).await {
+        Ok(Some(policy)) => HttpResponse::Ok().json(serde_json::json!({ "success": true, "policy": policy })),
+        Ok(None) => HttpResponse::NotFound()
+            .json(serde_json::json!({ "success": false, "message": "Tenant not found" })),
+        Err(e) => {
+            app_log!(error, error = %e, tenant_id = %tenant_id, "Failed to load key policy");
+            HttpResponse::InternalServerError()
+                .json(serde_json::json!({ "success": false, "message": "Failed to load key policy" }))
+        }
+    }
+}
+
+pub async fn set_key_policy_handler(
+    store: web::Data<Arc<EndpointStore>>,
+    tenant_id: web::Path<String>,
+    body: web::Json<KeyPolicy>,
+) -> impl Responder {
+    let tenant_id = match resolve_tenant(&store, tenant_id.into_inner()).await {
+        Ok(t) => t,
+        Err(resp) => return resp,
+    };
+
+    match set_key_policy(&store, &tenant_id, &body).await {
+        Ok(true) => {
+            app_log!(info, tenant_id = %tenant_id, max_days = ?body.max_key_lifetime_days, "Updated key lifetime policy");
+            HttpResponse::Ok().json(serde_json::json!({ "success": true, "policy": body.into_inner() }))
+        }
+        Ok(false) => HttpResponse::NotFound()
+            .json(serde_json::json!({ "success": false, "message": "Tenant not found" })),
+        Err(StoreError::Invalid(msg)) => HttpResponse::BadRequest()
+            .json(serde_json::json!({ "success": false, "message": msg })),
+        Err(e) => {
+            app_log!(error, error = %e, tenant_id = %tenant_id, "Failed to update key policy");
+            HttpResponse::InternalServerError()
+                .json(serde_json::json!({ "success": false, "message": "Failed to update key policy" }))
+        }
+    }
+}
@@ -1,6 +1,6 @@
 use crate::email::{send_async, EmailKind};
 use crate::endpoint_store::api_key_management::generate_api_key_with_provider;
-use crate::endpoint_store::EndpointStore;
+use crate::endpoint_store::{EndpointStore, StoreError};
 use crate::endpoint_store::GenerateKeyRequest;
 
 use crate::app_log;
@@ -15,6 +15,13 @@ pub async fn generate_api_key(
     let key_name = &request.key_name;
     let provider_tenant_id = request.provider_tenant_id.as_deref();
     let explicit_tenant_id = request.tenant_id.as_deref();
+    let mut restrictions = request.restrictions.clone();
+    if let Err(e) = restrictions.check() {
+        return HttpResponse::BadRequest().json(serde_json::json!({
+            "success": false,
+            "message": e,
+        }));
+    }
 
     app_log!(info,
         email = %email,
@@ -24,8 +31,8 @@ pub async fn generate_api_key(
         "Received HTTP generate API key request"
     );
 
-    match generate_api_key_with_provider(&store, email, key_name, explicit_tenant_id, provider_tenant_id).await {
-        Ok((key, key_prefix, _)) => {
+    match generate_api_key_with_provider(&store, email, key_name, explicit_tenant_id, provider_tenant_id, &restrictions, &request.expiry).await {
+        Ok((key, key_prefix, _, expires_at)) => {
             app_log!(info, email = %email, key_prefix = %key_prefix, "Successfully generated API key");
 
             send_async(store.as_ref().clone(), email.clone(), EmailKind::KeyCreated {
@@ -68,8 +75,13 @@ pub async fn generate_api_key(
                 "message": "API key generated successfully",
                 "key": key,
                 "keyPrefix": key_prefix,
+                "expires_at": expires_at.map(|at| at.to_rfc3339()),
             }))
         }
+        Err(StoreError::Invalid(msg)) => HttpResponse::BadRequest().json(serde_json::json!({
+            "success": false,
+            "message": msg,
+        })),
         Err(e) => {
             app_log!(error,
                 error = %e,
@@ -0,0 +1,99 @@
+// src/api/key_ip_allowlist.rs
+//
+// Caller IP allowlists (see endpoint_store/ip_allowlist.rs) and the security log.
+//
+// PUT /api/user/keys/{tenant_id}/{key_id}/allowed-ips
+//   Body: { "allowed_cidrs": ["203.0.113.0/24", "2001:db8::1"] }   null = any address
+// GET /api/user/security-events/{tenant_id}?event_type=ip_not_allowed&limit=50
+
+use crate::app_log;
+use crate::endpoint_store::ip_allowlist::set_allowed_cidrs;
+use crate::endpoint_store::security_events::list_security_events;
+use crate::endpoint_store::{EndpointStore, StoreError};
+use actix_web::{web, HttpResponse, Responder};
+use serde::Deserialize;
+use std::sync::Arc;
+
+const DEFAULT_EVENT_LIMIT: i64 = 50;
+const MAX_EVENT_LIMIT: i64 = 500;
+
+#[derive(Debug, Deserialize)]
+pub struct AllowedIpsRequest {
+    pub allowed_cidrs: Option<Vec<String>>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SecurityEventsQuery {
+    pub event_type: Option<String>,
+    pub limit: Option<i64>,
+}
+
+async fn resolve_tenant(store: &EndpointStore, tenant_id: String) -> Result<String, HttpResponse> {
+    if !tenant_id.contains('@') {
+        return Ok(tenant_id);
+    }
+    use crate::endpoint_store::tenant_management;
+    match tenant_management::get_default_tenant(store, &tenant_id).await {
+        Ok(t) => Ok(t.id),
+        Err(e) => {
+            app_log!(error, email = %tenant_id, error = %e, "Failed to resolve tenant for IP allowlist");
+            Err(HttpResponse::InternalServerError().json(serde_json::json!({
+                "success": false,
+                "message": "Account resolution failed"
+            })))
+        }
+    }
+}
+
+pub async fn set_allowed_ips_handler(
+    store: web::Data<Arc<EndpointStore>>,
+    path_params: web::Path<(String, String)>,
+    body: web::Json<AllowedIpsRequest>,
+) -> impl Responder {
+    let (tenant_id, key_id) = path_params.into_inner();
+    let tenant_id = match resolve_tenant(&store, tenant_id).await {
+        Ok(t) => t,
+        Err(resp) => return resp,
+    };
+
+    match set_allowed_cidrs(&store, &tenant_id, &key_id, body.allowed_cidrs.as_deref()).await {
+        Ok(Some(cidrs)) => {
+            app_log!(info, tenant_id = %tenant_id, key_id = %key_id, allowed_cidrs = ?cidrs, "Updated API key IP allowlist");
+            HttpResponse::Ok().json(serde_json::json!({
+                "success": true,
+                "key_id": key_id,
+                "allowed_cidrs": cidrs,
+            }))
+        }
+        Ok(None) => HttpResponse::NotFound()
+            .json(serde_json::json!({ "success": false, "message": "API key not found" })),
+        Err(StoreError::Invalid(msg)) => HttpResponse::BadRequest()
+            .json(serde_json::json!({ "success": false, "message": msg })),
+        Err(e) => {
+            app_log!(error, error = %e, tenant_id = %tenant_id, "Failed to update API key IP allowlist");
+            HttpResponse::InternalServerError()
+                .json(serde_json::json!({ "success": false, "message": "Failed to update IP allowlist" }))
+        }
+    }
+}
+
+pub async fn list_security_events_handler(
+    store: web::Data<Arc<EndpointStore>>,
+    tenant_id: web::Path<String>,
+    query: web::Query<SecurityEventsQuery>,
+) -> impl Responder {
+    let tenant_id = match resolve_tenant(&store, tenant_id.into_inner()).await {
+        Ok(t) => t,
+        Err(resp) => return resp,
+    };
+    let limit = query.limit.unwrap_or(DEFAULT_EVENT_LIMIT).clamp(1, MAX_EVENT_LIMIT);
+
+    match list_security_events(&store, &tenant_id, query.event_type.as_deref(), limit).await {
+        Ok(events) => HttpResponse::Ok().json(serde_json::json!({ "success": true, "events": events })),
+        Err(e) => {
+            app_log!(error, error = %e, tenant_id = %tenant_id, "Failed to list security events");
+            HttpResponse::InternalServerError()
+                .json(serde_json::json!({ "success": false, "message": "Failed to list security events" }))
+        }
+    }
+}
@@ -0,0 +1,182 @@
+// src/api/key_quota.rs
+//
+// Rate limits and daily quotas (see endpoint_store/key_quotas.rs).
+//
+// POST /api/key/quota/consume                          — gateway, X-Internal-Secret
+//   Body: { "key_id": "...", "credits": 3, "dry_run": false }
+//   200 when allowed, 429 when a limit is exhausted; both carry
+//   X-RateLimit-Limit / -Remaining / -Reset (Unix seconds) for the tightest
+//   limit, Retry-After on 429, and every limit in the JSON body.
+//
+// PUT /api/user/keys/{tenant_id}/{key_id}/limits        — per-key overrides
+// GET /api/user/key-limits/{tenant_id}                  — tenant defaults
+// PUT /api/user/key-limits/{tenant_id}
+//   Body: { "requests_per_minute": 60, "calls_per_day": null, "credits_per_day": 500 }
+//   null = inherit (keys) / unlimited (tenant defaults).
+
+use crate::app_log;
+use crate::endpoint_store::key_quotas::{
+    consume_quota, get_tenant_default_limits, set_key_limits, set_tenant_default_limits, QuotaLimits,
+};
+use crate::endpoint_store::{EndpointStore, StoreError};
+use actix_web::{web, HttpRequest, HttpResponse, Responder};
+use serde::Deserialize;
+use std::sync::Arc;
+
+fn check_internal_secret(req: &HttpRequest) -> bool {
+    let expected = match std::env::var("API0_INTERNAL_SECRET") {
+        Ok(s) if !s.is_empty() => s,
+        _ => return false,
+    };
+    req.headers()
+        .get("X-Internal-Secret")
+        .and_then(|v| v.to_str().ok())
+        .map(|v| v == expected)
+        .unwrap_or(false)
+}
+
+async fn resolve_tenant(store: &EndpointStore, tenant_id: String) -> Result<String, HttpResponse> {
+    if !tenant_id.contains('@') {
+        return Ok(tenant_id);
+    }
+    use crate::endpoint_store::tenant_management;
+    match tenant_management::get_default_tenant(store, &tenant_id).await {
+        Ok(t) => Ok(t.id),
+        Err(e) => {
+            app_log!(error, email = %tenant_id, error = %e, "Failed to resolve tenant for key limits");
+            Err(HttpResponse::InternalServerError().json(serde_json::json!({
+                "success": false,
+                "message": "Account resolution failed"
+            })))
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ConsumeQuotaRequest {
+    pub key_id: String,
+    #[serde(default)]
+    pub credits: i64,
+    #[serde(default)]
+    pub dry_run: bool,
+}
+
+pub async fn consume_quota_handler(
+    req: HttpRequest,
+    store: web::Data<Arc<EndpointStore>>,
+    body: web::Json<ConsumeQuotaRequest>,
+) -> impl Responder {
+    if !check_internal_secret(&req) {
+        return HttpResponse::Unauthorized()
+            .json(serde_json::json!({"success": false, "error": "Unauthorized"}));
+    }
+    if body.credits < 0 {
+        return HttpResponse::BadRequest()
+            .json(serde_json::json!({"success": false, "error": "credits must not be negative"}));
+    }
+
+    let decision = match consume_quota(&store, &body.key_id, body.credits, body.dry_run).await {
+        Ok(Some(d)) => d,
+        Ok(None) => {
+            return HttpResponse::NotFound()
+                .json(serde_json::json!({"success": false, "error": "API key not found"}))
+        }
+        Err(e) => {
+            app_log!(error, error = %e, key_id = %body.key_id, "Quota check failed");
+            return HttpResponse::InternalServerError()
+                .json(serde_json::json!({"success": false, "error": "Quota check failed"}));
+        }
+    };
+
+    let mut resp = if decision.allowed {
+        HttpResponse::Ok()
+    } else {
+        app_log!(warn, key_id = %body.key_id, limited_by = ?decision.limited_by, "API key quota exhausted");
+        HttpResponse::TooManyRequests()
+    };
+    if let Some(h) = decision.headline() {
+        resp.insert_header(("X-RateLimit-Limit", h.limit.to_string()))
+            .insert_header(("X-RateLimit-Remaining", h.remaining.to_string()))
+            .insert_header(("X-RateLimit-Reset", h.reset.to_string()));
+        if !decision.allowed {
+            resp.insert_header(("Retry-After", h.reset_after_secs.max(1).to_string()));
+        }
+    }
+    resp.json(serde_json::json!({ "success": true, "quota": decision }))
+}
+
+pub async fn set_key_limits_handler(
+    store: web::Data<Arc<EndpointStore>>,
+    path_params: web::Path<(String, String)>,
+    body: web::Json<QuotaLimits>,
+) -> impl Responder {
+    let (tenant_id, key_id) = path_params.into_inner();
+    let tenant_id = match resolve_tenant(&store, tenant_id).await {
+        Ok(t) => t,
+        Err(resp) => return resp,
+    };
+
+    match set_key_limits(&store, &tenant_id, &key_id, &body).await {
+        Ok(true) => {
+            app_log!(info, tenant_id = %tenant_id, key_id = %key_id, limits = ?*body, "Updated API key limits");
+            HttpResponse::Ok().json(serde_json::json!({ "success": true, "key_id": key_id, "limits": *body }))
+        }
+        Ok(false) => HttpResponse::NotFound()
+            .json(serde_json::json!({ "success": false, "message": "API key not found" })),
+        Err(StoreError::Invalid(msg)) => HttpResponse::BadRequest()
+            .json(serde_json::json!({ "success": false, "message": msg })),
+        Err(e) => {
+            app_log!(error, error = %e, tenant_id = %tenant_id, "Failed to update API key limits");
+            HttpResponse::InternalServerError()
+                .json(serde_json::json!({ "success": false, "message": "Failed to update API key limits" }))
+        }
+    }
+}
+
+pub async fn get_tenant_limits_handler(
+    store: web::Data<Arc<EndpointStore>>,
+    tenant_id: web::Path<String>,
+) -> impl Responder {
+    let tenant_id = match resolve_tenant(&store, tenant_id.into_inner()).await {
+        Ok(t) => t,
+        Err(resp) => return resp,
+    };
+
+    match get_tenant_default_limits(&store, &tenant_id).await {
+        Ok(Some(limits)) => HttpResponse::Ok().json(serde_json::json!({ "success": true, "limits": limits })),
+        Ok(None) => HttpResponse::NotFound()
+            .json(serde_json::json!({ "success": false, "message": "Tenant not found" })),
+        Err(e) => {
+            app_log!(error, error = %e, tenant_id = %tenant_id, "Failed to load tenant key limits");
+            HttpResponse::InternalServerError()
+                .json(serde_json::json!({ "success": false, "message": "Failed to load key limits" }))
+        }
+    }
+}
+
+pub async fn set_tenant_limits_handler(
+    store: web::Data<Arc<EndpointStore>>,
+    tenant_id: web::Path<String>,
+    body: web::Json<QuotaLimits>,
+) -> impl Responder {
+    let tenant_id = match resolve_tenant(&store, tenant_id.into_inner()).await {
+        Ok(t) => t,
+        Err(resp) => return resp,
+    };
+
+    match set_tenant_default_limits(&store, &tenant_id, &body).await {
+        Ok(true) => {
+            app_log!(info, tenant_id = %tenant_id, limits = ?*body, "Updated tenant default key limits");
+            HttpResponse::Ok().json(serde_json::json!({ "success": true, "limits": *body }))
+        }
+        Ok(false) => HttpResponse::NotFound()
+            .json(serde_json::json!({ "success": false, "message": "Tenant not found" })),
+        Err(StoreError::Invalid(msg)) => HttpResponse::BadRequest()
+            .json(serde_json::json!({ "success": false, "message": msg })),
+        Err(e) => {
+            app_log!(error, error = %e, tenant_id = %tenant_id, "Failed to update tenant key limits");
+            HttpResponse::InternalServerError()
+                .json(serde_json::json!({ "success": false, "message": "Failed to update key limits" }))
+        }
+    }
+}
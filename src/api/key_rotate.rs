@@ -0,0 +1,106 @@
+use crate::app_log;
+use crate::email::{send_async, EmailKind};
+use crate::endpoint_store::api_key_management::key_version;
+use crate::endpoint_store::EndpointStore;
+use actix_web::{web, HttpResponse, Responder};
+use serde::Deserialize;
+use std::sync::Arc;
+
+/// Grace window when the request does not set one (override with
+/// API0_KEY_ROTATION_GRACE_SECS).
+const DEFAULT_GRACE_SECS: i64 = 24 * 3600;
+const MAX_GRACE_SECS: i64 = 30 * 24 * 3600;
+
+#[derive(Debug, Default, Deserialize)]
+pub struct RotateKeyRequest {
+    /// How long the old secret keeps validating; 0 = cut over immediately.
+    pub grace_period_secs: Option<i64>,
+}
+
+fn default_grace_secs() -> i64 {
+    std::env::var("API0_KEY_ROTATION_GRACE_SECS")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(DEFAULT_GRACE_SECS)
+}
+
+// Handler for rotating an API key's secret.
+// POST /api/user/keys/{tenant_id}/{key_id}/rotate   { "grace_period_secs": 86400 }
+// Returns the new plaintext secret once; the key id, name and scopes are kept.
+// The new secret uses the current key format, so rotating is also how a v1
+// key is migrated to v2.
+pub async fn rotate_api_key_handler(
+    store: web::Data<Arc<EndpointStore>>,
+    path_params: web::Path<(String, String)>,
+    body: Option<web::Json<RotateKeyRequest>>,
+) -> impl Responder {
+    let (mut tenant_id, key_id) = path_params.into_inner();
+    let grace_secs = body
+        .and_then(|b| b.grace_period_secs)
+        .unwrap_or_else(default_grace_secs);
+    app_log!(info, tenant_id = %tenant_id, key_id = %key_id, grace_secs = grace_secs,
+        "Received HTTP rotate API key request");
+
+    if !(0..=MAX_GRACE_SECS).contains(&grace_secs) {
+        return HttpResponse::BadRequest().json(serde_json::json!({
+            "success": false,
+            "message": format!("grace_period_secs must be between 0 and {}", MAX_GRACE_SECS),
+        }));
+    }
+
+    // If tenant_id looks like an email, resolve it to the actual tenant ID
+    if tenant_id.contains('@') {
+        use crate::endpoint_store::tenant_management;
+        match tenant_management::get_default_tenant(&store, &tenant_id).await {
+            Ok(t) => tenant_id = t.id,
+            Err(e) => {
+                app_log!(error, email = %tenant_id, error = %e, "Failed to resolve tenant for key rotation");
+                return HttpResponse::InternalServerError().json(serde_json::json!({
+                    "success": false,
+                    "message": "Account resolution failed"
+                }));
+            }
+        }
+    }
+
+    match store
+        .rotate_api_key(&tenant_id, &key_id, chrono::Duration::seconds(grace_secs))
+        .await
+    {
+        Ok(Some(rotated)) => {
+            app_log!(info, tenant_id = %tenant_id, key_id = %key_id, generation = rotated.generation,
+                "Rotated API key");
+
+            let old_expires_at = rotated.previous_expires_at.to_rfc3339();
+            send_async(store.as_ref().clone(), rotated.email.clone(), EmailKind::KeyRotated {
+                key_name: rotated.key_name.clone(),
+                key_prefix: rotated.key_prefix.clone(),
+                old_key_prefix: rotated.previous_key_prefix.clone(),
+                old_expires_at: rotated.previous_expires_at.format("%Y-%m-%d %H:%M UTC").to_string(),
+            });
+
+            HttpResponse::Ok().json(serde_json::json!({
+                "success": true,
+                "message": "API key rotated successfully",
+                "key": rotated.key,
+                "keyPrefix": rotated.key_prefix,
+                "key_id": key_id,
+                "generation": rotated.generation,
+                "key_version": key_version(&rotated.key),
+                "previous_key_prefix": rotated.previous_key_prefix,
+                "previous_expires_at": old_expires_at,
+            }))
+        }
+        Ok(None) => HttpResponse::NotFound().json(serde_json::json!({
+            "success": false,
+            "message": "API key not found",
+        })),
+        Err(e) => {
+            app_log!(error, error = %e, tenant_id = %tenant_id, "Failed to rotate API key");
+            HttpResponse::InternalServerError().json(serde_json::json!({
+                "success": false,
+                "message": "Failed to rotate API key",
+            }))
+        }
+    }
+}
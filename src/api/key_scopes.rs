@@ -0,0 +1,66 @@
+use crate::app_log;
+use crate::endpoint_store::key_scopes::KeyScopes;
+use crate::endpoint_store::EndpointStore;
+use actix_web::{web, HttpResponse, Responder};
+use std::sync::Arc;
+
+// Handler for replacing the scopes / group and tool restrictions of a key.
+// PUT /api/user/keys/{tenant_id}/{key_id}/scopes
+//   { "scopes": ["catalog:write"], "allowed_group_ids": null, "allowed_tools": null }
+// Omitted or null fields clear the restriction (full access).
+pub async fn set_api_key_scopes_handler(
+    store: web::Data<Arc<EndpointStore>>,
+    path_params: web::Path<(String, String)>,
+    body: web::Json<KeyScopes>,
+) -> impl Responder {
+    let (mut tenant_id, key_id) = path_params.into_inner();
+    app_log!(info, tenant_id = %tenant_id, key_id = %key_id, "Received HTTP set API key scopes request");
+
+    let mut restrictions = body.into_inner();
+    if let Err(e) = restrictions.check() {
+        return HttpResponse::BadRequest().json(serde_json::json!({
+            "success": false,
+            "message": e,
+        }));
+    }
+
+    // If tenant_id looks like an email, resolve it to the actual tenant ID
+    if tenant_id.contains('@') {
+        use crate::endpoint_store::tenant_management;
+        match tenant_management::get_default_tenant(&store, &tenant_id).await {
+            Ok(t) => tenant_id = t.id,
+            Err(e) => {
+                app_log!(error, email = %tenant_id, error = %e, "Failed to resolve tenant for scopes update");
+                return HttpResponse::InternalServerError().json(serde_json::json!({
+                    "success": false,
+                    "message": "Account resolution failed"
+                }));
+            }
+        }
+    }
+
+    match store.set_api_key_scopes(&tenant_id, &key_id, &restrictions).await {
+        Ok(true) => {
+            app_log!(info, tenant_id = %tenant_id, key_id = %key_id, scopes = ?restrictions.scopes,
+                "Updated API key scopes");
+            HttpResponse::Ok().json(serde_json::json!({
+                "success": true,
+                "key_id": key_id,
+                "scopes": restrictions.effective_scopes(),
+                "allowed_group_ids": restrictions.allowed_group_ids,
+                "allowed_tools": restrictions.allowed_tools,
+            }))
+        }
+        Ok(false) => HttpResponse::NotFound().json(serde_json::json!({
+            "success": false,
+            "message": "API key not found",
+        })),
+        Err(e) => {
+            app_log!(error, error = %e, tenant_id = %tenant_id, "Failed to update API key scopes");
+            HttpResponse::InternalServerError().json(serde_json::json!({
+                "success": false,
+                "message": "Failed to update API key scopes",
+            }))
+        }
+    }
+}
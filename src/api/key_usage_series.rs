@@ -0,0 +1,115 @@
+// src/api/key_usage_series.rs
+//
+// Per-key usage history (see endpoint_store/key_usage.rs).
+//
+// GET /api/user/keys/{tenant_id}/{key_id}/usage-series?granularity=day&days=30
+//   Calls and tokens per bucket, empty buckets included; hour buckets cover
+//   at most 14 days, day buckets at most 365.
+// GET /api/user/keys/{tenant_id}/stale?days=30
+//   Active keys unused for at least `days` days — candidates for revocation.
+// {tenant_id} may also be an owner email, as for the other /user/keys routes.
+
+use crate::app_log;
+use crate::endpoint_store::key_usage::{stale_keys, usage_series, Granularity};
+use crate::endpoint_store::EndpointStore;
+use actix_web::{web, HttpResponse, Responder};
+use serde::Deserialize;
+use std::sync::Arc;
+
+const DEFAULT_SERIES_DAYS: i64 = 30;
+const DEFAULT_STALE_DAYS: i64 = 30;
+const MAX_STALE_DAYS: i64 = 3650;
+
+#[derive(Debug, Deserialize)]
+pub struct UsageSeriesQuery {
+    pub granularity: Option<String>,
+    pub days: Option<i64>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct StaleKeysQuery {
+    pub days: Option<i64>,
+}
+
+async fn resolve_tenant(store: &EndpointStore, tenant_id: String) -> Result<String, HttpResponse> {
+    if !tenant_id.contains('@') {
+        return Ok(tenant_id);
+    }
+    use crate::endpoint_store::tenant_management;
+    match tenant_management::get_default_tenant(store, &tenant_id).await {
+        Ok(t) => Ok(t.id),
+        Err(e) => {
+            app_log!(error, email = %tenant_id, error = %e, "Failed to resolve tenant for key usage lookup");
+            Err(HttpResponse::InternalServerError().json(serde_json::json!({
+                "success": false,
+                "message": "Account resolution failed"
+            })))
+        }
+    }
+}
+
+pub async fn get_key_usage_series_handler(
+    store: web::Data<Arc<EndpointStore>>,
+    path_params: web::Path<(String, String)>,
+    query: web::Query<UsageSeriesQuery>,
+) -> impl Responder {
+    let (tenant_id, key_id) = path_params.into_inner();
+    let tenant_id = match resolve_tenant(&store, tenant_id).await {
+        Ok(t) => t,
+        Err(resp) => return resp,
+    };
+    let granularity = match Granularity::parse(query.granularity.as_deref().unwrap_or("day")) {
+        Some(g) => g,
+        None => {
+            return HttpResponse::BadRequest().json(serde_json::json!({
+                "success": false,
+                "message": "granularity must be hour or day",
+            }))
+        }
+    };
+    let days = query.days.unwrap_or(DEFAULT_SERIES_DAYS).clamp(1, granularity.max_days());
+
+    match usage_series(&store, &tenant_id, &key_id, granularity, days).await {
+        Ok(Some(series)) => HttpResponse::Ok().json(serde_json::json!({
+            "success": true,
+            "days": days,
+            "series": series,
+        })),
+        Ok(None) => HttpResponse::NotFound()
+            .json(serde_json::json!({ "success": false, "message": "API key not found" })),
+        Err(e) => {
+            app_log!(error, error = %e, tenant_id = %tenant_id, key_id = %key_id, "Failed to load API key usage series");
+            HttpResponse::InternalServerError().json(serde_json::json!({
+                "success": false,
+                "message": "Failed to load usage series",
+            }))
+        }
+    }
+}
+
+pub async fn list_stale_keys_handler(
+    store: web::Data<Arc<EndpointStore>>,
+    tenant_id: web::Path<String>,
+    query: web::Query<StaleKeysQuery>,
+) -> impl Responder {
+    let tenant_id = match resolve_tenant(&store, tenant_id.into_inner()).await {
+        Ok(t) => t,
+        Err(resp) => return resp,
+    };
+    let days = query.days.unwrap_or(DEFAULT_STALE_DAYS).clamp(1, MAX_STALE_DAYS);
+
+    match stale_keys(&store, &tenant_id, days).await {
+        Ok(keys) => HttpResponse::Ok().json(serde_json::json!({
+            "success": true,
+            "idle_days": days,
+            "keys": keys,
+        })),
+        Err(e) => {
+            app_log!(error, error = %e, tenant_id = %tenant_id, "Failed to list stale API keys");
+            HttpResponse::InternalServerError().json(serde_json::json!({
+                "success": false,
+                "message": "Failed to list stale keys",
+            }))
+        }
+    }
+}
@@ -1,66 +1,96 @@
 use crate::app_log;
 use crate::{
+    endpoint_store::api_key_management::{KeyValidation, ValidatedKey},
+    endpoint_store::auth_lockout::{self, Subject},
+    endpoint_store::ip_allowlist,
+    endpoint_store::key_scopes::ALL_SCOPES,
+    endpoint_store::security_events::{record_security_event, IP_NOT_ALLOWED},
     endpoint_store::EndpointStore,
     infra::models::{ValidateKeyRequest, ValidateKeyResponse},
 };
 use actix_web::{web, HttpRequest, HttpResponse, Responder};
-use std::collections::HashMap;
-use std::sync::{Arc, Mutex, OnceLock};
-use std::time::Instant;
-
-// ── Brute-force rate limiter ──────────────────────────────────────────────────
-// Tracks failed validation attempts per key-prefix (first 16 chars of the raw
-// API key submitted).  Exceeding MAX_FAILURES in WINDOW_SECS returns HTTP 429.
-// This is an in-process defence layer; a reverse-proxy limit should also exist.
-
-const MAX_FAILURES: u32 = 10;
-const WINDOW_SECS: u64 = 60;
-// Evict stale entries when the map exceeds this size to bound memory usage.
-const EVICT_THRESHOLD: usize = 5_000;
-
-struct FailureWindow {
-    count: u32,
-    started: Instant,
-}
+use ipnet::IpNet;
+use std::sync::Arc;
 
-static FAIL_MAP: OnceLock<Mutex<HashMap<String, FailureWindow>>> = OnceLock::new();
+// ── Brute-force protection ────────────────────────────────────────────────────
+// Failed validations count against the caller IP and a digest of the presented
+// secret, shared across replicas (endpoint_store/auth_lockout.rs). A locked-out
+// subject gets HTTP 429 before the key is even looked at.
+//
+// Nothing the caller asserts can pin a lockout on someone else:
+//   - The secret subject only matches the exact secret sent, so a key's
+//     public display prefix cannot be used to lock it out.
+//   - The caller IP is client_ip with X-Internal-Secret (the gateway), the
+//     last X-Forwarded-For entry behind a trusted proxy, else the TCP peer.
+//     The gateway or a proxy is never the caller, so they never share one
+//     IP subject between all their callers.
+//   - The tenant subject is the tenant of a real key presented for another
+//     tenant, never the expected_tenant_id of the request. A tenant lockout
+//     is checked once the lookup has attributed the key to that tenant.
 
-fn fail_map() -> &'static Mutex<HashMap<String, FailureWindow>> {
-    FAIL_MAP.get_or_init(|| Mutex::new(HashMap::new()))
+fn check_internal_secret(req: &HttpRequest) -> bool {
+    let expected = match std::env::var("API0_INTERNAL_SECRET") {
+        Ok(s) if !s.is_empty() => s,
+        _ => return false,
+    };
+    req.headers()
+        .get("X-Internal-Secret")
+        .and_then(|v| v.to_str().ok())
+        .map(|v| v == expected)
+        .unwrap_or(false)
 }
 
-/// Returns `true` if the caller is within limits (request allowed).
-fn rate_limit_check(limit_key: &str) -> bool {
-    let mut map = fail_map().lock().unwrap_or_else(|e| e.into_inner());
-    let now = Instant::now();
+/// Address of the caller, for IP allowlists and lockouts. None when it is
+/// not known (the gateway did not forward one, or a trusted proxy sent no
+/// X-Forwarded-For): a restricted key is then denied and no IP is blamed.
+pub(crate) fn caller_ip(client_ip: Option<&str>, http_req: &HttpRequest) -> Option<String> {
+    resolve_caller_ip(client_ip, http_req, ip_allowlist::trusted_proxies())
+}
 
-    // Periodic eviction of expired windows to cap memory.
-    if map.len() >= EVICT_THRESHOLD {
-        map.retain(|_, w| now.duration_since(w.started).as_secs() < WINDOW_SECS);
+fn resolve_caller_ip(client_ip: Option<&str>, http_req: &HttpRequest, proxies: &[IpNet]) -> Option<String> {
+    if check_internal_secret(http_req) {
+        return client_ip.map(str::trim).filter(|ip| !ip.is_empty()).map(str::to_string);
     }
-
-    match map.get(limit_key) {
-        None => true,
-        Some(w) => {
-            if now.duration_since(w.started).as_secs() >= WINDOW_SECS {
-                true // window has expired — reset on next record_failure call
-            } else {
-                w.count < MAX_FAILURES
-            }
-        }
+    let peer = http_req.peer_addr()?.ip();
+    if !proxies.iter().any(|net| net.contains(&peer)) {
+        return Some(peer.to_string());
     }
+    // Only the entry the proxy appended is its own observation.
+    http_req
+        .headers()
+        .get_all("X-Forwarded-For")
+        .last()
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.rsplit(',').next())
+        .and_then(ip_allowlist::parse_client_ip)
+        .map(|ip| ip.to_string())
 }
 
-/// Call after a failed validation to increment the counter.
-fn record_failure(limit_key: &str) {
-    let mut map = fail_map().lock().unwrap_or_else(|e| e.into_inner());
-    let now = Instant::now();
-    let entry = map.entry(limit_key.to_string()).or_insert(FailureWindow { count: 0, started: now });
-    if now.duration_since(entry.started).as_secs() >= WINDOW_SECS {
-        entry.count = 1;
-        entry.started = now;
-    } else {
-        entry.count = entry.count.saturating_add(1);
+/// Subjects a validation attempt is attributed to.
+pub(crate) fn lockout_subjects(api_key: &str, caller_ip: Option<&str>) -> Vec<Subject> {
+    let mut subjects = vec![Subject::secret(api_key)];
+    subjects.extend(caller_ip.and_then(Subject::ip));
+    subjects
+}
+
+/// Lockout of the tenant the lookup attributed the key to, if any.
+pub(crate) fn tenant_lockout(validation: &KeyValidation) -> Option<(Subject, chrono::DateTime<chrono::Utc>)> {
+    validation
+        .key()
+        .and_then(|key| auth_lockout::active_lockout(&[Subject::tenant(&key.tenant_id)]))
+}
+
+/// Strike for a real key presented for another tenant: it also counts
+/// against the key's own tenant.
+pub(crate) async fn record_wrong_tenant(store: &EndpointStore, subjects: &[Subject], key: &ValidatedKey) {
+    let mut subjects = subjects.to_vec();
+    subjects.push(Subject::tenant(&key.tenant_id));
+    record_validation_failure(store, &subjects).await;
+}
+
+pub(crate) async fn record_validation_failure(store: &EndpointStore, subjects: &[Subject]) {
+    if let Err(e) = auth_lockout::record_failure(store, subjects).await {
+        app_log!(error, error = %e, "Failed to record key validation failure");
     }
 }
 
@@ -94,79 +124,207 @@ pub async fn validate_api_key(
             warn,
             "No API key provided in request body or Authorization header"
         );
-        return HttpResponse::BadRequest().json(ValidateKeyResponse {
-            valid: false,
-            email: None,
-            key_id: None,
-            tenant_id: None,
-            provider_tenant_id: None,
-            message: "No API key provided".to_string(),
-        });
+        return HttpResponse::BadRequest().json(ValidateKeyResponse::rejected("No API key provided"));
+    }
+
+    let client_ip = caller_ip(req.client_ip.as_deref(), &http_req);
+    let client_ip = client_ip.as_deref();
+    let subjects = lockout_subjects(&api_key, client_ip);
+    if let Some((subject, until)) = auth_lockout::active_lockout(&subjects) {
+        let retry_after = (until - chrono::Utc::now()).num_seconds().max(1);
+        app_log!(warn, kind = subject.kind, subject = %subject.value, retry_after = retry_after,
+            "Key validation rejected: subject locked out");
+        return HttpResponse::TooManyRequests()
+            .insert_header(("Retry-After", retry_after.to_string()))
+            .json(ValidateKeyResponse {
+                reason: Some("locked_out".to_string()),
+                ..ValidateKeyResponse::rejected("Too many validation attempts — try again later")
+            });
     }
 
-    // Rate-limit by the first 16 chars of the submitted key so brute-force
-    // attempts against a known key prefix are throttled without leaking the key.
-    let limit_key = if api_key.len() >= 16 { &api_key[..16] } else { &api_key };
-    if !rate_limit_check(limit_key) {
-        app_log!(warn, limit_key = %limit_key, "Rate limit exceeded on key validation");
-        return HttpResponse::TooManyRequests().json(ValidateKeyResponse {
-            valid: false,
-            email: None,
-            key_id: None,
-            tenant_id: None,
-            provider_tenant_id: None,
-            message: "Too many validation attempts — try again later".to_string(),
-        });
+    if let Some(scope) = req.required_scope.as_deref() {
+        if !ALL_SCOPES.contains(&scope) {
+            return HttpResponse::BadRequest()
+                .json(ValidateKeyResponse::rejected(&format!("Unknown scope \"{}\"", scope)));
+        }
     }
 
-    app_log!(info, expected_tenant_id = ?req.expected_tenant_id, "Validating API key");
+    app_log!(info, expected_tenant_id = ?req.expected_tenant_id, required_scope = ?req.required_scope, "Validating API key");
 
-    match store.validate_api_key(&api_key, req.expected_tenant_id.as_deref()).await {
-        Ok(Some((email, key_id, tenant_id, provider_tenant_id))) => {
+    let validation = store
+        .validate_api_key(
+            &api_key,
+            req.expected_tenant_id.as_deref(),
+            req.required_scope.as_deref(),
+            client_ip,
+        )
+        .await;
+    if let Some((subject, until)) = validation.as_ref().ok().and_then(tenant_lockout) {
+        let retry_after = (until - chrono::Utc::now()).num_seconds().max(1);
+        app_log!(warn, tenant_id = %subject.value, retry_after = retry_after,
+            "Key validation rejected: tenant locked out");
+        return HttpResponse::TooManyRequests()
+            .insert_header(("Retry-After", retry_after.to_string()))
+            .json(ValidateKeyResponse {
+                reason: Some("locked_out".to_string()),
+                ..ValidateKeyResponse::rejected("Too many validation attempts — try again later")
+            });
+    }
+
+    match validation {
+        Ok(KeyValidation::Valid(key)) => {
             app_log!(info,
-                email = %email,
-                key_id = %key_id,
-                tenant_id = %tenant_id,
-                provider_tenant_id = ?provider_tenant_id,
+                email = %key.email,
+                key_id = %key.key_id,
+                tenant_id = %key.tenant_id,
+                provider_tenant_id = ?key.provider_tenant_id,
+                generation = key.generation,
                 "API key validation successful"
             );
+            if let Some(at) = key.previous_expires_at {
+                app_log!(info, key_id = %key.key_id, expires_at = %at, "Rotated-out API key secret used");
+            }
 
             HttpResponse::Ok().json(ValidateKeyResponse {
                 valid: true,
-                email: Some(email),
-                key_id: Some(key_id),
-                tenant_id: Some(tenant_id),
-                provider_tenant_id,
+                scopes: Some(key.restrictions.effective_scopes()),
+                allowed_group_ids: key.restrictions.allowed_group_ids,
+                allowed_tools: key.restrictions.allowed_tools,
+                email: Some(key.email),
+                key_id: Some(key.key_id),
+                tenant_id: Some(key.tenant_id),
+                provider_tenant_id: key.provider_tenant_id,
+                key_generation: Some(key.generation),
+                secret_expires_at: key.previous_expires_at.map(|at| at.to_rfc3339()),
+                reason: None,
                 message: "API key is valid".to_string(),
             })
         }
-        Ok(None) => {
-            record_failure(limit_key);
-            app_log!(warn, "Invalid API key provided");
+        Ok(KeyValidation::MissingScope(key)) => {
+            // A real key used for the wrong job — not a brute-force signal.
+            let scope = req.required_scope.as_deref().unwrap_or_default();
+            app_log!(warn, key_id = %key.key_id, required_scope = %scope, "API key lacks required scope");
+
+            HttpResponse::Ok().json(ValidateKeyResponse {
+                key_id: Some(key.key_id),
+                scopes: Some(key.restrictions.effective_scopes()),
+                reason: Some("missing_scope".to_string()),
+                ..ValidateKeyResponse::rejected(&format!("API key lacks the \"{}\" scope", scope))
+            })
+        }
+        Ok(KeyValidation::IpNotAllowed(key)) => {
+            // A real key, so not a brute-force signal either — but possibly a
+            // leaked one, hence the security event.
+            app_log!(warn, key_id = %key.key_id, client_ip = ?client_ip, "API key used from a disallowed address");
+            record_security_event(
+                &store,
+                Some(&key.tenant_id),
+                Some(&key.key_id),
+                IP_NOT_ALLOWED,
+                client_ip,
+                serde_json::json!({
+                    "source": "validate",
+                    "allowed_cidrs": key.allowed_cidrs,
+                }),
+            )
+            .await;
 
             HttpResponse::Ok().json(ValidateKeyResponse {
-                valid: false,
-                email: None,
-                key_id: None,
-                tenant_id: None,
-                provider_tenant_id: None,
-                message: "Invalid API key".to_string(),
+                key_id: Some(key.key_id),
+                reason: Some(IP_NOT_ALLOWED.to_string()),
+                ..ValidateKeyResponse::rejected("API key is not allowed from this address")
             })
         }
+        Ok(KeyValidation::Suspended(key)) => {
+            // Governed by the provider, not guessed — no lockout strike.
+            app_log!(warn, key_id = %key.key_id, provider_tenant_id = ?key.provider_tenant_id, "Suspended API key used");
+
+            HttpResponse::Ok().json(ValidateKeyResponse {
+                key_id: Some(key.key_id),
+                reason: Some("suspended".to_string()),
+                ..ValidateKeyResponse::rejected("API key is suspended")
+            })
+        }
+        Ok(KeyValidation::Malformed) => {
+            record_validation_failure(&store, &subjects).await;
+            app_log!(warn, "Malformed API key provided");
+
+            HttpResponse::Ok().json(ValidateKeyResponse {
+                reason: Some("malformed_key".to_string()),
+                ..ValidateKeyResponse::rejected("Malformed API key")
+            })
+        }
+        Ok(KeyValidation::WrongTenant(key)) => {
+            record_wrong_tenant(&store, &subjects, &key).await;
+            app_log!(warn, key_id = %key.key_id, expected_tenant_id = ?req.expected_tenant_id,
+                "API key presented for another tenant");
+
+            HttpResponse::Ok().json(ValidateKeyResponse::rejected("Invalid API key"))
+        }
+        Ok(KeyValidation::Invalid) => {
+            record_validation_failure(&store, &subjects).await;
+            app_log!(warn, "Invalid API key provided");
+
+            HttpResponse::Ok().json(ValidateKeyResponse::rejected("Invalid API key"))
+        }
         Err(e) => {
             app_log!(error,
                 error = %e,
                 "Database error during API key validation"
             );
 
-            HttpResponse::InternalServerError().json(ValidateKeyResponse {
-                valid: false,
-                email: None,
-                key_id: None,
-                tenant_id: None,
-                provider_tenant_id: None,
-                message: "Validation error".to_string(),
-            })
+            HttpResponse::InternalServerError().json(ValidateKeyResponse::rejected("Validation error"))
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::test::TestRequest;
+
+    const SECRET: &str = "key-validate-test-secret";
+
+    fn request(peer: &str, headers: &[(&str, &str)]) -> HttpRequest {
+        std::env::set_var("API0_INTERNAL_SECRET", SECRET);
+        let mut req = TestRequest::post().peer_addr(format!("{}:50000", peer).parse().unwrap());
+        for &header in headers {
+            req = req.append_header(header);
+        }
+        req.to_http_request()
+    }
+
+    #[test]
+    fn body_client_ip_needs_the_internal_secret() {
+        let claimed = Some("10.0.0.1");
+        assert_eq!(caller_ip(claimed, &request("198.51.100.4", &[])).as_deref(), Some("198.51.100.4"));
+        let wrong = request("198.51.100.4", &[("X-Internal-Secret", "wrong")]);
+        assert_eq!(caller_ip(claimed, &wrong).as_deref(), Some("198.51.100.4"));
+        let gateway = request("198.51.100.4", &[("X-Internal-Secret", SECRET)]);
+        assert_eq!(caller_ip(claimed, &gateway).as_deref(), Some("10.0.0.1"));
+        // Never the gateway's own address.
+        assert_eq!(caller_ip(None, &gateway), None);
+    }
+
+    #[test]
+    fn behind_a_trusted_proxy_the_last_forwarded_entry_is_the_caller() {
+        let proxies = ["192.0.2.0/24".parse::<IpNet>().unwrap()];
+        let spoofed = [("X-Forwarded-For", "10.0.0.1, 203.0.113.9")];
+        let via_proxy = request("192.0.2.10", &spoofed);
+        assert_eq!(resolve_caller_ip(None, &via_proxy, &proxies).as_deref(), Some("203.0.113.9"));
+        let repeated = request("192.0.2.10", &[("X-Forwarded-For", "10.0.0.1"), ("X-Forwarded-For", "203.0.113.9")]);
+        assert_eq!(resolve_caller_ip(None, &repeated, &proxies).as_deref(), Some("203.0.113.9"));
+        assert_eq!(resolve_caller_ip(None, &request("192.0.2.10", &[]), &proxies), None);
+        // Anyone else's X-Forwarded-For is ignored.
+        let direct = request("198.51.100.4", &spoofed);
+        assert_eq!(resolve_caller_ip(None, &direct, &proxies).as_deref(), Some("198.51.100.4"));
+    }
+
+    #[test]
+    fn display_prefix_is_not_a_subject() {
+        let real = lockout_subjects("sk_v2_abcdefREAL", None);
+        let guess = lockout_subjects("sk_v2_abcdefGUESS", None);
+        assert_ne!(real, guess);
+        assert!(real.iter().all(|s| s.kind != "key_prefix"));
+    }
+}
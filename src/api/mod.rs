@@ -9,9 +9,16 @@ pub mod reference_upload;
 pub mod key_generate;
 pub mod key_revoke;
 pub mod key_revoke_all;
+pub mod key_rotate;
+pub mod key_scopes;
 pub mod key_status;
 pub mod key_validate;
+pub mod token_exchange;
 pub mod key_consumer;
+pub mod key_expiry;
+pub mod key_quota;
+pub mod key_ip_allowlist;
+pub mod key_usage_series;
 pub mod usage_key;
 pub mod usage_log;
 pub mod usage_get_logs;
@@ -20,4 +27,5 @@ pub mod tenant_usage;
 pub mod tenant_name;
 pub mod providers;
 pub mod key_consumer_self_service;
+pub mod provider_consumer_keys;
 pub mod tenant_management;
@@ -0,0 +1,341 @@
+// src/api/provider_consumer_keys.rs
+//
+// Provider-side management of the consumer keys minted through
+// /api/consumer-keys (see endpoint_store/consumer_keys.rs).
+//
+// Auth: X-Internal-Secret, as for POST /api/consumer-keys.
+// {provider_tenant_id} may also be the provider's email.
+//
+// GET    /api/provider/{provider_tenant_id}/consumer-keys?status=active&consumer_email=
+//          status: active | suspended | expired | revoked | all (default: all but revoked)
+// GET    /api/provider/{provider_tenant_id}/consumer-usage?days=30&consumer_email=
+// POST   /api/provider/{provider_tenant_id}/consumer-keys/{key_id}/suspend
+// POST   /api/provider/{provider_tenant_id}/consumer-keys/{key_id}/resume
+// PUT    /api/provider/{provider_tenant_id}/consumer-keys/{key_id}/limits
+//          Body: { "requests_per_minute": 60, "calls_per_day": null, "credits_per_day": 500 }
+// DELETE /api/provider/{provider_tenant_id}/consumer-keys/{key_id}
+//
+// The same actions for every key of one consumer:
+// POST   /api/provider/{provider_tenant_id}/consumers/suspend   { "consumer_email": "..." }
+// POST   /api/provider/{provider_tenant_id}/consumers/resume    { "consumer_email": "..." }
+// PUT    /api/provider/{provider_tenant_id}/consumers/limits    { "consumer_email": "...", limits }
+// POST   /api/provider/{provider_tenant_id}/consumers/revoke    { "consumer_email": "..." }
+
+use crate::app_log;
+use crate::endpoint_store::consumer_keys::{
+    consumer_usage, list_consumer_keys, revoke_consumer_keys, set_provider_limits, set_suspended,
+    StatusFilter, Target,
+};
+use crate::endpoint_store::key_quotas::QuotaLimits;
+use crate::endpoint_store::{EndpointStore, StoreError};
+use actix_web::{web, HttpRequest, HttpResponse, Responder};
+use serde::Deserialize;
+use std::sync::Arc;
+
+const DEFAULT_USAGE_DAYS: i64 = 30;
+const MAX_USAGE_DAYS: i64 = 365;
+
+fn check_internal_secret(req: &HttpRequest) -> bool {
+    let expected = match std::env::var("API0_INTERNAL_SECRET") {
+        Ok(s) if !s.is_empty() => s,
+        _ => return false,
+    };
+    req.headers()
+        .get("X-Internal-Secret")
+        .and_then(|v| v.to_str().ok())
+        .map(|v| v == expected)
+        .unwrap_or(false)
+}
+
+async fn resolve_tenant(store: &EndpointStore, tenant_id: String) -> Result<String, HttpResponse> {
+    if !tenant_id.contains('@') {
+        return Ok(tenant_id);
+    }
+    use crate::endpoint_store::tenant_management;
+    match tenant_management::get_default_tenant(store, &tenant_id).await {
+        Ok(t) => Ok(t.id),
+        Err(e) => {
+            app_log!(error, email = %tenant_id, error = %e, "Failed to resolve provider tenant");
+            Err(HttpResponse::InternalServerError().json(serde_json::json!({
+                "success": false,
+                "message": "Account resolution failed"
+            })))
+        }
+    }
+}
+
+/// Secret check and provider resolution shared by every handler.
+async fn authorize(req: &HttpRequest, store: &EndpointStore, provider: String) -> Result<String, HttpResponse> {
+    if !check_internal_secret(req) {
+        return Err(HttpResponse::Unauthorized()
+            .json(serde_json::json!({"success": false, "message": "Unauthorized"})));
+    }
+    resolve_tenant(store, provider).await
+}
+
+fn normalize_email(email: &str) -> String {
+    email.trim().to_lowercase()
+}
+
+fn server_error(message: &str) -> HttpResponse {
+    HttpResponse::InternalServerError().json(serde_json::json!({"success": false, "message": message}))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ConsumerKeysQuery {
+    pub status: Option<String>,
+    pub consumer_email: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ConsumerUsageQuery {
+    pub days: Option<i64>,
+    pub consumer_email: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ConsumerRequest {
+    pub consumer_email: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ConsumerLimitsRequest {
+    pub consumer_email: String,
+    #[serde(flatten)]
+    pub limits: QuotaLimits,
+}
+
+pub async fn list_consumer_keys_handler(
+    req: HttpRequest,
+    store: web::Data<Arc<EndpointStore>>,
+    provider: web::Path<String>,
+    query: web::Query<ConsumerKeysQuery>,
+) -> impl Responder {
+    let provider = match authorize(&req, &store, provider.into_inner()).await {
+        Ok(p) => p,
+        Err(resp) => return resp,
+    };
+    let status = match query.status.as_deref().map(StatusFilter::parse) {
+        Some(None) => {
+            return HttpResponse::BadRequest().json(serde_json::json!({
+                "success": false,
+                "message": "status must be active, suspended, expired, revoked or all",
+            }))
+        }
+        Some(s) => s,
+        None => None,
+    };
+    let email = query.consumer_email.as_deref().map(normalize_email);
+
+    match list_consumer_keys(&store, &provider, status, email.as_deref()).await {
+        Ok(keys) => HttpResponse::Ok().json(serde_json::json!({"success": true, "keys": keys})),
+        Err(e) => {
+            app_log!(error, error = %e, provider_tenant_id = %provider, "Failed to list consumer keys");
+            server_error("Failed to list consumer keys")
+        }
+    }
+}
+
+pub async fn consumer_usage_handler(
+    req: HttpRequest,
+    store: web::Data<Arc<EndpointStore>>,
+    provider: web::Path<String>,
+    query: web::Query<ConsumerUsageQuery>,
+) -> impl Responder {
+    let provider = match authorize(&req, &store, provider.into_inner()).await {
+        Ok(p) => p,
+        Err(resp) => return resp,
+    };
+    let days = query.days.unwrap_or(DEFAULT_USAGE_DAYS).clamp(1, MAX_USAGE_DAYS);
+    let email = query.consumer_email.as_deref().map(normalize_email);
+
+    match consumer_usage(&store, &provider, days, email.as_deref()).await {
+        Ok(consumers) => HttpResponse::Ok().json(serde_json::json!({
+            "success": true,
+            "days": days,
+            "consumers": consumers,
+        })),
+        Err(e) => {
+            app_log!(error, error = %e, provider_tenant_id = %provider, "Failed to load consumer usage");
+            server_error("Failed to load consumer usage")
+        }
+    }
+}
+
+async fn suspend(
+    req: HttpRequest,
+    store: &EndpointStore,
+    provider: String,
+    target: Target<'_>,
+    suspended: bool,
+) -> HttpResponse {
+    let provider = match authorize(&req, store, provider).await {
+        Ok(p) => p,
+        Err(resp) => return resp,
+    };
+    match set_suspended(store, &provider, target, suspended).await {
+        Ok(ids) => {
+            app_log!(info, provider_tenant_id = %provider, target = ?target, suspended = suspended,
+                keys = ids.len(), "Updated consumer key suspension");
+            if ids.is_empty() && matches!(target, Target::Key(_)) {
+                return HttpResponse::NotFound().json(serde_json::json!({
+                    "success": false,
+                    "message": if suspended { "No active, unsuspended consumer key with this id" }
+                               else { "No suspended consumer key with this id" },
+                }));
+            }
+            HttpResponse::Ok().json(serde_json::json!({
+                "success": true,
+                "suspended": suspended,
+                "key_ids": ids,
+            }))
+        }
+        Err(e) => {
+            app_log!(error, error = %e, provider_tenant_id = %provider, "Failed to update consumer key suspension");
+            server_error("Failed to update consumer keys")
+        }
+    }
+}
+
+async fn revoke(req: HttpRequest, store: &EndpointStore, provider: String, target: Target<'_>) -> HttpResponse {
+    let provider = match authorize(&req, store, provider).await {
+        Ok(p) => p,
+        Err(resp) => return resp,
+    };
+    match revoke_consumer_keys(store, &provider, target).await {
+        Ok(ids) => {
+            app_log!(info, provider_tenant_id = %provider, target = ?target, keys = ids.len(), "Revoked consumer keys");
+            if ids.is_empty() && matches!(target, Target::Key(_)) {
+                return HttpResponse::NotFound()
+                    .json(serde_json::json!({"success": false, "message": "Consumer key not found"}));
+            }
+            HttpResponse::Ok().json(serde_json::json!({
+                "success": true,
+                "revoked": ids.len(),
+                "key_ids": ids,
+            }))
+        }
+        Err(e) => {
+            app_log!(error, error = %e, provider_tenant_id = %provider, "Failed to revoke consumer keys");
+            server_error("Failed to revoke consumer keys")
+        }
+    }
+}
+
+async fn limits(
+    req: HttpRequest,
+    store: &EndpointStore,
+    provider: String,
+    target: Target<'_>,
+    limits: &QuotaLimits,
+) -> HttpResponse {
+    let provider = match authorize(&req, store, provider).await {
+        Ok(p) => p,
+        Err(resp) => return resp,
+    };
+    match set_provider_limits(store, &provider, target, limits).await {
+        Ok(0) if matches!(target, Target::Key(_)) => HttpResponse::NotFound()
+            .json(serde_json::json!({"success": false, "message": "Consumer key not found"})),
+        Ok(n) => {
+            app_log!(info, provider_tenant_id = %provider, target = ?target, limits = ?limits, keys = n,
+                "Updated consumer key limits");
+            HttpResponse::Ok().json(serde_json::json!({
+                "success": true,
+                "updated": n,
+                "provider_limits": limits,
+            }))
+        }
+        Err(StoreError::Invalid(msg)) => {
+            HttpResponse::BadRequest().json(serde_json::json!({"success": false, "message": msg}))
+        }
+        Err(e) => {
+            app_log!(error, error = %e, provider_tenant_id = %provider, "Failed to update consumer key limits");
+            server_error("Failed to update consumer key limits")
+        }
+    }
+}
+
+// ── Single key ────────────────────────────────────────────────────────────────
+
+pub async fn suspend_consumer_key_handler(
+    req: HttpRequest,
+    store: web::Data<Arc<EndpointStore>>,
+    path: web::Path<(String, String)>,
+) -> impl Responder {
+    let (provider, key_id) = path.into_inner();
+    suspend(req, &store, provider, Target::Key(&key_id), true).await
+}
+
+pub async fn resume_consumer_key_handler(
+    req: HttpRequest,
+    store: web::Data<Arc<EndpointStore>>,
+    path: web::Path<(String, String)>,
+) -> impl Responder {
+    let (provider, key_id) = path.into_inner();
+    suspend(req, &store, provider, Target::Key(&key_id), false).await
+}
+
+pub async fn set_consumer_key_limits_handler(
+    req: HttpRequest,
+    store: web::Data<Arc<EndpointStore>>,
+    path: web::Path<(String, String)>,
+    body: web::Json<QuotaLimits>,
+) -> impl Responder {
+    let (provider, key_id) = path.into_inner();
+    limits(req, &store, provider, Target::Key(&key_id), &body).await
+}
+
+pub async fn revoke_consumer_key_handler(
+    req: HttpRequest,
+    store: web::Data<Arc<EndpointStore>>,
+    path: web::Path<(String, String)>,
+) -> impl Responder {
+    let (provider, key_id) = path.into_inner();
+    revoke(req, &store, provider, Target::Key(&key_id)).await
+}
+
+// ── Every key of one consumer ─────────────────────────────────────────────────
+
+pub async fn suspend_consumer_handler(
+    req: HttpRequest,
+    store: web::Data<Arc<EndpointStore>>,
+    provider: web::Path<String>,
+    body: web::Json<ConsumerRequest>,
+) -> impl Responder {
+    let email = normalize_email(&body.consumer_email);
+    suspend(req, &store, provider.into_inner(), Target::Consumer(&email), true).await
+}
+
+pub async fn resume_consumer_handler(
+    req: HttpRequest,
+    store: web::Data<Arc<EndpointStore>>,
+    provider: web::Path<String>,
+    body: web::Json<ConsumerRequest>,
+) -> impl Responder {
+    let email = normalize_email(&body.consumer_email);
+    suspend(req, &store, provider.into_inner(), Target::Consumer(&email), false).await
+}
+
+pub async fn set_consumer_limits_handler(
+    req: HttpRequest,
+    store: web::Data<Arc<EndpointStore>>,
+    provider: web::Path<String>,
+    body: web::Json<ConsumerLimitsRequest>,
+) -> impl Responder {
+    let email = normalize_email(&body.consumer_email);
+    limits(req, &store, provider.into_inner(), Target::Consumer(&email), &body.limits).await
+}
+
+pub async fn revoke_consumer_handler(
+    req: HttpRequest,
+    store: web::Data<Arc<EndpointStore>>,
+    provider: web::Path<String>,
+    body: web::Json<ConsumerRequest>,
+) -> impl Responder {
+    let email = normalize_email(&body.consumer_email);
+    if email.is_empty() {
+        return HttpResponse::BadRequest()
+            .json(serde_json::json!({"success": false, "message": "consumer_email is required"}));
+    }
+    revoke(req, &store, provider.into_inner(), Target::Consumer(&email)).await
+}
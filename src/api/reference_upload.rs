@@ -52,7 +52,7 @@ fn decode_content(content: &str) -> Result<Vec<u8>, String> {
         
         // Try padding
         let mut padded = cleaned_content.clone();
-        while padded.len() % 4 != 0 {
+        while !padded.len().is_multiple_of(4) {
             padded.push('=');
         }
         if let Ok(bytes) = general_purpose::STANDARD.decode(&padded) {
@@ -0,0 +1,260 @@
+// src/api/token_exchange.rs
+//
+// OAuth 2.0 token exchange (RFC 8693): API key or consumer key in, short-lived
+// signed JWT out (see endpoint_store/access_tokens.rs). The gateway exchanges
+// a key once, then verifies the JWT on each call against the JWKS instead of
+// calling /key/validate.
+//
+// POST /api/oauth/token      (application/x-www-form-urlencoded or JSON)
+//   grant_type=urn:ietf:params:oauth:grant-type:token-exchange
+//   subject_token=<API key>
+//   subject_token_type=urn:api0:params:oauth:token-type:api-key   (optional)
+//   scope="tools:list tools:call"   optional; narrows the key's scopes
+//   expires_in=300                  optional; seconds, at most 3600
+//   expected_tenant_id, client_ip   optional; as for /key/validate (client_ip
+//                                   only with X-Internal-Secret)
+// → { "access_token", "issued_token_type": "urn:ietf:params:oauth:token-type:jwt",
+//     "token_type": "Bearer", "expires_in", "scope" }
+//
+// GET /api/oauth/jwks        public keys for verifying the tokens (EdDSA)
+//
+// Failures use the RFC 6749 error body: { "error", "error_description" }.
+// Failed exchanges count towards the same lockouts as /key/validate.
+
+use crate::api::key_validate::{caller_ip, lockout_subjects, record_validation_failure, record_wrong_tenant, tenant_lockout};
+use crate::app_log;
+use crate::endpoint_store::access_tokens::{self, issue_access_token, DEFAULT_TTL_SECS, JWKS_MAX_AGE_SECS};
+use crate::endpoint_store::api_key_management::KeyValidation;
+use crate::endpoint_store::auth_lockout;
+use crate::endpoint_store::key_scopes::parse_scope_param;
+use crate::endpoint_store::security_events::{record_security_event, IP_NOT_ALLOWED};
+use crate::endpoint_store::EndpointStore;
+use actix_web::http::StatusCode;
+use actix_web::{web, Either, HttpRequest, HttpResponse, Responder};
+use serde::Deserialize;
+use std::sync::Arc;
+
+pub const TOKEN_EXCHANGE_GRANT: &str = "urn:ietf:params:oauth:grant-type:token-exchange";
+pub const API_KEY_TOKEN_TYPE: &str = "urn:api0:params:oauth:token-type:api-key";
+const JWT_TOKEN_TYPE: &str = "urn:ietf:params:oauth:token-type:jwt";
+const ACCESS_TOKEN_TYPE: &str = "urn:ietf:params:oauth:token-type:access_token";
+
+#[derive(Debug, Deserialize)]
+pub struct TokenRequest {
+    #[serde(default)]
+    pub grant_type: String,
+    #[serde(default)]
+    pub subject_token: String,
+    pub subject_token_type: Option<String>,
+    pub requested_token_type: Option<String>,
+    pub scope: Option<String>,
+    pub expires_in: Option<i64>,
+    pub expected_tenant_id: Option<String>,
+    pub client_ip: Option<String>,
+}
+
+fn oauth_error(status: StatusCode, error: &str, description: &str) -> HttpResponse {
+    HttpResponse::build(status)
+        .insert_header(("Cache-Control", "no-store"))
+        .json(serde_json::json!({ "error": error, "error_description": description }))
+}
+
+fn locked_out(retry_after: i64) -> HttpResponse {
+    HttpResponse::TooManyRequests()
+        .insert_header(("Retry-After", retry_after.to_string()))
+        .insert_header(("Cache-Control", "no-store"))
+        .json(serde_json::json!({
+            "error": "locked_out",
+            "error_description": "Too many failed attempts — try again later",
+        }))
+}
+
+pub async fn token_exchange_handler(
+    store: web::Data<Arc<EndpointStore>>,
+    body: Either<web::Form<TokenRequest>, web::Json<TokenRequest>>,
+    http_req: HttpRequest,
+) -> impl Responder {
+    let req = match body {
+        Either::Left(form) => form.into_inner(),
+        Either::Right(json) => json.into_inner(),
+    };
+
+    if req.grant_type != TOKEN_EXCHANGE_GRANT {
+        return oauth_error(
+            StatusCode::BAD_REQUEST,
+            "unsupported_grant_type",
+            &format!("grant_type must be {}", TOKEN_EXCHANGE_GRANT),
+        );
+    }
+    if req.subject_token.is_empty() {
+        return oauth_error(StatusCode::BAD_REQUEST, "invalid_request", "subject_token is required");
+    }
+    if req.subject_token_type.as_deref().is_some_and(|t| t != API_KEY_TOKEN_TYPE) {
+        return oauth_error(
+            StatusCode::BAD_REQUEST,
+            "invalid_request",
+            &format!("subject_token_type must be {}", API_KEY_TOKEN_TYPE),
+        );
+    }
+    if req.requested_token_type.as_deref().is_some_and(|t| t != JWT_TOKEN_TYPE && t != ACCESS_TOKEN_TYPE) {
+        return oauth_error(StatusCode::BAD_REQUEST, "invalid_request", "Only JWT access tokens can be issued");
+    }
+    let requested = match parse_scope_param(req.scope.as_deref()) {
+        Ok(s) => s,
+        Err(msg) => return oauth_error(StatusCode::BAD_REQUEST, "invalid_scope", &msg),
+    };
+
+    let client_ip = caller_ip(req.client_ip.as_deref(), &http_req);
+    let client_ip = client_ip.as_deref();
+    let subjects = lockout_subjects(&req.subject_token, client_ip);
+    if let Some((subject, until)) = auth_lockout::active_lockout(&subjects) {
+        let retry_after = (until - chrono::Utc::now()).num_seconds().max(1);
+        app_log!(warn, kind = subject.kind, subject = %subject.value, retry_after = retry_after,
+            "Token exchange rejected: subject locked out");
+        return locked_out(retry_after);
+    }
+
+    let validation = store
+        .validate_api_key(&req.subject_token, req.expected_tenant_id.as_deref(), None, client_ip)
+        .await;
+    if let Some((subject, until)) = validation.as_ref().ok().and_then(tenant_lockout) {
+        let retry_after = (until - chrono::Utc::now()).num_seconds().max(1);
+        app_log!(warn, tenant_id = %subject.value, retry_after = retry_after,
+            "Token exchange rejected: tenant locked out");
+        return locked_out(retry_after);
+    }
+
+    let key = match validation {
+        Ok(KeyValidation::Valid(key)) => key,
+        Ok(KeyValidation::IpNotAllowed(key)) => {
+            app_log!(warn, key_id = %key.key_id, client_ip = ?client_ip, "Token exchange from a disallowed address");
+            record_security_event(
+                &store,
+                Some(&key.tenant_id),
+                Some(&key.key_id),
+                IP_NOT_ALLOWED,
+                client_ip,
+                serde_json::json!({ "source": "token_exchange", "allowed_cidrs": key.allowed_cidrs }),
+            )
+            .await;
+            return oauth_error(StatusCode::BAD_REQUEST, "invalid_grant", "API key is not allowed from this address");
+        }
+        Ok(KeyValidation::Suspended(key)) => {
+            app_log!(warn, key_id = %key.key_id, "Token exchange with a suspended API key");
+            return oauth_error(StatusCode::BAD_REQUEST, "invalid_grant", "API key is suspended");
+        }
+        Ok(KeyValidation::WrongTenant(key)) => {
+            record_wrong_tenant(&store, &subjects, &key).await;
+            app_log!(warn, key_id = %key.key_id, "Token exchange with a key of another tenant");
+            return oauth_error(StatusCode::BAD_REQUEST, "invalid_grant", "Invalid API key");
+        }
+        // No scope is required at validation, so MissingScope cannot occur.
+        Ok(KeyValidation::Invalid | KeyValidation::Malformed | KeyValidation::MissingScope(_)) => {
+            record_validation_failure(&store, &subjects).await;
+            app_log!(warn, "Token exchange with an invalid API key");
+            return oauth_error(StatusCode::BAD_REQUEST, "invalid_grant", "Invalid API key");
+        }
+        Err(e) => {
+            app_log!(error, error = %e, "Database error during token exchange");
+            return oauth_error(StatusCode::INTERNAL_SERVER_ERROR, "server_error", "Token exchange failed");
+        }
+    };
+
+    let scopes = match requested {
+        Some(scopes) => {
+            if let Some(missing) = scopes.iter().find(|s| !key.restrictions.grants(s)) {
+                return oauth_error(
+                    StatusCode::BAD_REQUEST,
+                    "invalid_scope",
+                    &format!("API key lacks the \"{}\" scope", missing),
+                );
+            }
+            scopes
+        }
+        None => key.restrictions.effective_scopes(),
+    };
+
+    let ttl = req.expires_in.unwrap_or(DEFAULT_TTL_SECS);
+    match issue_access_token(&store, &key, scopes, ttl).await {
+        Ok(token) => {
+            app_log!(info, key_id = %key.key_id, tenant_id = %key.tenant_id, kid = %token.kid,
+                expires_in = token.expires_in, "Issued access token");
+            HttpResponse::Ok()
+                .insert_header(("Cache-Control", "no-store"))
+                .json(serde_json::json!({
+                    "access_token": token.access_token,
+                    "issued_token_type": JWT_TOKEN_TYPE,
+                    "token_type": "Bearer",
+                    "expires_in": token.expires_in,
+                    "scope": token.scopes.join(" "),
+                }))
+        }
+        Err(e) => {
+            app_log!(error, error = %e, key_id = %key.key_id, "Failed to issue access token");
+            oauth_error(StatusCode::INTERNAL_SERVER_ERROR, "server_error", "Token exchange failed")
+        }
+    }
+}
+
+pub async fn jwks_handler(store: web::Data<Arc<EndpointStore>>) -> impl Responder {
+    match access_tokens::jwks(&store).await {
+        Ok(jwks) => HttpResponse::Ok()
+            .insert_header(("Cache-Control", format!("public, max-age={}", JWKS_MAX_AGE_SECS)))
+            .json(jwks),
+        Err(e) => {
+            app_log!(error, error = %e, "Failed to load token signing keys");
+            HttpResponse::InternalServerError()
+                .json(serde_json::json!({ "success": false, "message": "Failed to load signing keys" }))
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::endpoint_store::api_key_management::generate_api_key;
+    use crate::endpoint_store::ip_allowlist::set_allowed_cidrs;
+    use actix_web::{test, App};
+
+    #[actix_web::test]
+    #[ignore = "requires live PostgreSQL (DATABASE_URL)"]
+    async fn exchange_from_a_disallowed_address_is_refused() {
+        let store = crate::endpoint_store::live_test_store().await;
+        let email = format!("exchange_ip_{}@example.com", uuid::Uuid::new_v4());
+        let (api_key, _, key_id, _) = generate_api_key(&store, &email, "ip-bound", None).await.unwrap();
+        let Ok(KeyValidation::Valid(key)) = store.validate_api_key(&api_key, None, None, None).await else {
+            panic!("new key validates");
+        };
+        let cidrs = vec!["10.0.0.0/8".to_string()];
+        set_allowed_cidrs(&store, &key.tenant_id, &key_id, Some(&cidrs)).await.unwrap().expect("key exists");
+
+        let app = test::init_service(
+            App::new()
+                .app_data(web::Data::new(Arc::new(store)))
+                .route("/api/oauth/token", web::post().to(token_exchange_handler)),
+        )
+        .await;
+        let exchange = |peer: &str| {
+            test::TestRequest::post()
+                .uri("/api/oauth/token")
+                .peer_addr(format!("{}:40000", peer).parse().unwrap())
+                .set_json(serde_json::json!({
+                    "grant_type": TOKEN_EXCHANGE_GRANT,
+                    "subject_token": api_key,
+                    // Claimed by the caller; only the gateway may forward it.
+                    "client_ip": "10.0.0.1",
+                }))
+                .to_request()
+        };
+
+        let resp = test::call_service(&app, exchange("198.51.100.4")).await;
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+        let body: serde_json::Value = test::read_body_json(resp).await;
+        assert_eq!(body["error"], "invalid_grant");
+        assert_eq!(body["error_description"], "API key is not allowed from this address");
+
+        let resp = test::call_service(&app, exchange("10.1.2.3")).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+    }
+}
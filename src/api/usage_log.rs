@@ -41,7 +41,7 @@ pub async fn log_api_usage(
                 // Minimum 1 credit if tokens > 0
                 let total_tokens = usage.total_tokens;
                 let cost = if total_tokens > 0 {
-                    (total_tokens as i64 / 50).max(1)
+                    (total_tokens / 50).max(1)
                 } else {
                     0
                 };
@@ -66,6 +66,17 @@ pub async fn log_api_usage(
                 }
             }
 
+            // Validation already enforces the allowlist; a disallowed address
+            // showing up here means a caller bypassed it, so flag it.
+            if log_request.ip_address.is_some() {
+                let store2 = store.as_ref().clone();
+                let key_id = log_request.key_id.clone();
+                let ip = log_request.ip_address.clone();
+                tokio::spawn(async move {
+                    audit_usage_ip(&store2, &key_id, ip.as_deref()).await;
+                });
+            }
+
             // First-call milestone: fire once per user (idempotent via first_call_at column).
             let store2 = store.as_ref().clone();
             let email2 = log_request.email.clone();
@@ -114,3 +125,30 @@ pub async fn log_api_usage(
         }
     }
 }
+
+async fn audit_usage_ip(store: &EndpointStore, key_id: &str, ip: Option<&str>) {
+    use crate::endpoint_store::ip_allowlist::{is_allowed, key_allowlist};
+    use crate::endpoint_store::security_events::{record_security_event, IP_NOT_ALLOWED};
+
+    let (tenant_id, cidrs) = match key_allowlist(store, key_id).await {
+        Ok(Some(found)) => found,
+        Ok(None) => return,
+        Err(e) => {
+            app_log!(error, error = %e, key_id = %key_id, "Failed to load key allowlist for usage audit");
+            return;
+        }
+    };
+    if is_allowed(&cidrs, ip) {
+        return;
+    }
+    app_log!(warn, key_id = %key_id, ip = ?ip, "Usage logged from an address outside the key allowlist");
+    record_security_event(
+        store,
+        Some(&tenant_id),
+        Some(key_id),
+        IP_NOT_ALLOWED,
+        ip,
+        serde_json::json!({ "source": "usage_log", "allowed_cidrs": cidrs }),
+    )
+    .await;
+}
@@ -2,6 +2,7 @@
 //
 // Centralised email system for api0.
 // SMTP config lives in system_config (email.smtp_*) or falls back to SMTP_* env vars.
+// email.smtp_password is envelope-encrypted at rest (endpoint_store/secret_crypto.rs).
 //
 // Public surface:
 //   send_async(store, to, kind)   — fire-and-forget, call from any handler
@@ -13,6 +14,7 @@
 //   PUT  /api/admin/smtp-config
 
 use crate::app_log;
+use crate::endpoint_store::secret_crypto::SecretSlot;
 use crate::endpoint_store::EndpointStore;
 use actix_web::{web, HttpRequest, HttpResponse, Responder};
 use lettre::message::header::ContentType;
@@ -44,6 +46,9 @@ pub enum EmailKind {
     LowCredits { balance: i64 },
     KeyCreated { key_prefix: String, key_name: String },
     KeyRevoked { key_prefix: String },
+    KeyRotated { key_name: String, key_prefix: String, old_key_prefix: String, old_expires_at: String },
+    KeyExpiring { key_name: String, key_prefix: String, expires_at: String, days_left: i32 },
+    KeyExpired { key_name: String, key_prefix: String },
     AccountDeleted,
     // ── Tier 2 — informational ───────────────────────────────────────────────
     CreditAdjustment { amount: i64, reason: String, new_balance: i64 },
@@ -64,6 +69,9 @@ impl EmailKind {
             Self::LowCredits { .. }        => "low_credits",
             Self::KeyCreated { .. }        => "key_created",
             Self::KeyRevoked { .. }        => "key_revoked",
+            Self::KeyRotated { .. }        => "key_rotated",
+            Self::KeyExpiring { .. }       => "key_expiring",
+            Self::KeyExpired { .. }        => "key_expired",
             Self::AccountDeleted           => "account_deleted",
             Self::CreditAdjustment { .. }  => "credit_adjustment",
             Self::FirstCallMilestone { .. }=> "first_call_milestone",
@@ -82,6 +90,12 @@ impl EmailKind {
             Self::LowCredits { balance }                     => format!("Low balance: {} credits remaining", balance),
             Self::KeyCreated { key_name, .. }                => format!("New API key created: {}", key_name),
             Self::KeyRevoked { key_prefix }                  => format!("API key {} revoked", key_prefix),
+            Self::KeyRotated { key_name, .. }                => format!("API key rotated: {}", key_name),
+            Self::KeyExpiring { key_name, days_left, .. }    => {
+                if *days_left == 1 { format!("API key {} expires tomorrow", key_name) }
+                else               { format!("API key {} expires in {} days", key_name, days_left) }
+            }
+            Self::KeyExpired { key_name, .. }                => format!("API key {} has expired", key_name),
             Self::AccountDeleted                             => "Your api0 account has been deleted".into(),
             Self::CreditAdjustment { amount, .. }            => {
                 if *amount >= 0 { format!("You received {} credits", amount) }
@@ -152,6 +166,39 @@ impl EmailKind {
 <p><a href="https://app.api0.ai" style="display:inline-block;padding:10px 20px;background:#6366F1;color:white;text-decoration:none;border-radius:6px">Manage Keys</a></p>"#
             ),
 
+            Self::KeyRotated { key_name, key_prefix, old_key_prefix, old_expires_at } => format!(
+                r#"<h1>API Key Rotated</h1>
+<p>The key <strong>{key_name}</strong> has a new secret. The previous secret keeps working until the end of its grace period, so you can update your clients without downtime.</p>
+<table style="border-collapse:collapse;margin:16px 0;background:#F8FAFC;border-radius:6px;overflow:hidden">
+  <tr><td style="padding:8px 16px;font-weight:bold;color:#475569">New prefix</td><td style="padding:8px 16px;font-family:monospace;color:#6366F1">{key_prefix}…</td></tr>
+  <tr><td style="padding:8px 16px;font-weight:bold;color:#475569">Old prefix</td><td style="padding:8px 16px;font-family:monospace">{old_key_prefix}…</td></tr>
+  <tr><td style="padding:8px 16px;font-weight:bold;color:#475569">Old secret stops working</td><td style="padding:8px 16px">{old_expires_at}</td></tr>
+</table>
+<p style="color:#64748B;font-size:13px">If you didn't rotate this key, revoke it immediately from the dashboard.</p>
+<p><a href="https://app.api0.ai" style="display:inline-block;padding:10px 20px;background:#6366F1;color:white;text-decoration:none;border-radius:6px">Manage Keys</a></p>"#
+            ),
+
+            Self::KeyExpiring { key_name, key_prefix, expires_at, days_left } => {
+                let when = if *days_left == 1 { "tomorrow".to_string() } else { format!("in {days_left} days") };
+                format!(
+                    r#"<h1>API Key Expiring {when}</h1>
+<p>The key <strong>{key_name}</strong> expires {when}. Requests using it will then receive 401 errors.</p>
+<table style="border-collapse:collapse;margin:16px 0;background:#F8FAFC;border-radius:6px;overflow:hidden">
+  <tr><td style="padding:8px 16px;font-weight:bold;color:#475569">Prefix</td><td style="padding:8px 16px;font-family:monospace;color:#6366F1">{key_prefix}…</td></tr>
+  <tr><td style="padding:8px 16px;font-weight:bold;color:#475569">Expires</td><td style="padding:8px 16px">{expires_at}</td></tr>
+</table>
+<p>Create a replacement key and update your clients before then.</p>
+<p><a href="https://app.api0.ai" style="display:inline-block;padding:10px 20px;background:#6366F1;color:white;text-decoration:none;border-radius:6px">Manage Keys</a></p>"#
+                )
+            }
+
+            Self::KeyExpired { key_name, key_prefix } => format!(
+                r#"<h1>API Key Expired</h1>
+<p>The key <strong>{key_name}</strong> (<code style="background:#F1F5F9;padding:2px 6px;border-radius:4px">{key_prefix}…</code>) has expired and no longer authenticates requests.</p>
+<p>Create a new key from the dashboard to restore access.</p>
+<p><a href="https://app.api0.ai" style="display:inline-block;padding:10px 20px;background:#6366F1;color:white;text-decoration:none;border-radius:6px">Manage Keys</a></p>"#
+            ),
+
             Self::AccountDeleted => r#"<h1>Account Deleted</h1>
 <p>Your api0 account and all associated data have been permanently removed.</p>
 <p>This includes your API keys, usage logs, and credit balance.</p>
@@ -282,13 +329,21 @@ fn wrap_layout(content: &str) -> String {
 pub fn send_async(store: Arc<EndpointStore>, to: impl Into<String>, kind: EmailKind) {
     let to = to.into();
     tokio::spawn(async move {
-        match deliver_internal(&store, &to, &kind).await {
-            Ok(()) => app_log!(info, to = %to, kind = %kind.name(), "Email sent"),
-            Err(e) => app_log!(error, to = %to, kind = %kind.name(), "Email failed: {}", e),
-        }
+        let _ = send(&store, &to, &kind).await;
     });
 }
 
+/// Send and wait for the outcome, for callers that must know whether the
+/// email went out.
+pub async fn send(store: &EndpointStore, to: &str, kind: &EmailKind) -> anyhow::Result<()> {
+    let sent = deliver_internal(store, to, kind).await;
+    match &sent {
+        Ok(()) => app_log!(info, to = %to, kind = %kind.name(), "Email sent"),
+        Err(e) => app_log!(error, to = %to, kind = %kind.name(), "Email failed: {}", e),
+    }
+    sent
+}
+
 // ── Delivery ──────────────────────────────────────────────────────────────────
 
 async fn deliver_internal(store: &EndpointStore, to: &str, kind: &EmailKind) -> anyhow::Result<()> {
@@ -316,6 +371,11 @@ async fn deliver_internal(store: &EndpointStore, to: &str, kind: &EmailKind) ->
 
 struct SmtpCfg { host: String, port: u16, user: String, password: String, from_addr: String }
 
+/// email.smtp_password is global, so bound to no tenant.
+fn smtp_password_slot() -> SecretSlot<'static> {
+    SecretSlot::new("system_config", "value", "")
+}
+
 async fn load_smtp_config(store: &EndpointStore) -> Option<SmtpCfg> {
     let client = store.get_admin_conn().await.ok()?;
     let rows = client
@@ -331,7 +391,13 @@ async fn load_smtp_config(store: &EndpointStore) -> Option<SmtpCfg> {
 
     let host     = map.get("email.smtp_host").cloned().or_else(|| std::env::var("SMTP_HOST").ok())?;
     let user     = map.get("email.smtp_user").cloned().or_else(|| std::env::var("SMTP_USER").ok())?;
-    let password = map.get("email.smtp_password").cloned().or_else(|| std::env::var("SMTP_PASSWORD").ok())?;
+    let password = match map.get("email.smtp_password") {
+        Some(stored) => match store.secrets().decrypt(smtp_password_slot(), stored) {
+            Ok(p) => p,
+            Err(e) => { app_log!(error, "Cannot decrypt email.smtp_password: {}", e); return None; }
+        },
+        None => std::env::var("SMTP_PASSWORD").ok()?,
+    };
     let port     = map.get("email.smtp_port").and_then(|v| v.parse().ok())
         .or_else(|| std::env::var("SMTP_PORT").ok().and_then(|v| v.parse().ok()))
         .unwrap_or(587);
@@ -459,7 +525,13 @@ pub async fn update_smtp_config_handler(
     if let Some(v) = &body.smtp_host     { save!("email.smtp_host", v); }
     if let Some(v) = body.smtp_port      { save!("email.smtp_port", &v.to_string()); }
     if let Some(v) = &body.smtp_user     { save!("email.smtp_user", v); }
-    if let Some(v) = &body.smtp_password { save!("email.smtp_password", v); }
+    if let Some(v) = &body.smtp_password {
+        let sealed = match store.secrets().encrypt(smtp_password_slot(), v) {
+            Ok(s) => s,
+            Err(e) => return HttpResponse::InternalServerError().json(serde_json::json!({"error":format!("{e}")})),
+        };
+        save!("email.smtp_password", &sealed);
+    }
     if let Some(v) = &body.email_from    { save!("email.from_addr", v); }
     app_log!(info, "Admin updated SMTP config");
     HttpResponse::Ok().json(serde_json::json!({"success":true}))
@@ -0,0 +1,522 @@
+// src/endpoint_store/access_tokens.rs
+//
+// Short-lived access tokens (EdDSA JWTs) exchanged for API keys, so the
+// gateway can authorise calls offline against the JWKS instead of calling
+// /key/validate on every request.
+//
+// Signing keys live in token_signing_keys, the private half sealed with
+// secret_crypto. A key's lifecycle is driven by three timestamps:
+//
+//   created_at    published in the JWKS from here on
+//   activates_at  signs new tokens from here on (the newest active key wins)
+//   retires_at    dropped from the JWKS; NULL while current
+//
+// Scheduled rotation (every API0_TOKEN_KEY_ROTATION_DAYS, default 30) creates
+// the next key PUBLISH_LEAD_SECS ahead of use, so gateways holding a cached
+// JWKS already know it, and keeps the old key published until every token
+// it signed has expired. An immediate rotation (suspected compromise) signs
+// with the new key at once and unpublishes the old ones.
+//
+// Every instance caches the key set for KEYSET_TTL_SECS; rotations also send
+// pg_notify on CHANNEL so the others reload right away (notify_listener.rs).
+
+use crate::app_log;
+use crate::endpoint_store::api_key_management::ValidatedKey;
+use crate::endpoint_store::db_helpers::ResultExt;
+use crate::endpoint_store::secret_crypto::SecretSlot;
+use crate::endpoint_store::{EndpointStore, StoreError};
+use base64::engine::general_purpose::{STANDARD, URL_SAFE_NO_PAD};
+use base64::Engine as _;
+use chrono::{DateTime, Duration, Utc};
+use jsonwebtoken::{Algorithm, EncodingKey, Header};
+use ring::signature::{Ed25519KeyPair, KeyPair};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::sync::{Mutex, OnceLock};
+use std::time::Instant;
+
+pub const CHANNEL: &str = "token_signing_keys";
+pub const ALGORITHM: &str = "EdDSA";
+pub const DEFAULT_TTL_SECS: i64 = 300;
+pub const MAX_TTL_SECS: i64 = 3600;
+/// Gateways may cache the JWKS this long (Cache-Control on the JWKS route).
+pub const JWKS_MAX_AGE_SECS: i64 = 300;
+/// A scheduled key is published this long before it signs anything.
+const PUBLISH_LEAD_SECS: i64 = 2 * JWKS_MAX_AGE_SECS;
+/// Allowance for gateway clocks when deciding a retired key is unused.
+const CLOCK_SKEW_SECS: i64 = 60;
+const KEYSET_TTL_SECS: u64 = 60;
+const DEFAULT_ROTATION_DAYS: i64 = 30;
+/// Retired keys are kept this long for the admin listing, then deleted.
+const RETIRED_RETENTION_DAYS: i64 = 7;
+
+fn env_or(var: &str, default: &str) -> String {
+    std::env::var(var).ok().filter(|v| !v.is_empty()).unwrap_or_else(|| default.to_string())
+}
+
+pub fn issuer() -> String {
+    env_or("API0_TOKEN_ISSUER", "api0")
+}
+
+pub fn audience() -> String {
+    env_or("API0_TOKEN_AUDIENCE", "api0-gateway")
+}
+
+fn rotation_days() -> i64 {
+    std::env::var("API0_TOKEN_KEY_ROTATION_DAYS")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .filter(|d| *d > 0)
+        .unwrap_or(DEFAULT_ROTATION_DAYS)
+}
+
+// ── Claims ────────────────────────────────────────────────────────────────────
+
+/// What a verified token tells the gateway. Restriction lists are omitted
+/// when the key has none, exactly as /key/validate reports them.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AccessClaims {
+    pub iss: String,
+    pub aud: String,
+    /// The key id, repeated as `key_id` for readers that do not map `sub`.
+    pub sub: String,
+    pub iat: i64,
+    pub exp: i64,
+    pub jti: String,
+    pub tenant_id: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub provider_tenant_id: Option<String>,
+    pub key_id: String,
+    pub scopes: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub allowed_group_ids: Option<Vec<String>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub allowed_tools: Option<Vec<String>>,
+    /// The key's IP allowlist; the gateway applies it to each call.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub allowed_cidrs: Option<Vec<String>>,
+}
+
+impl AccessClaims {
+    pub fn new(key: &ValidatedKey, scopes: Vec<String>, now: DateTime<Utc>, ttl_secs: i64) -> Self {
+        AccessClaims {
+            iss: issuer(),
+            aud: audience(),
+            sub: key.key_id.clone(),
+            iat: now.timestamp(),
+            exp: now.timestamp() + ttl_secs,
+            jti: uuid::Uuid::new_v4().to_string(),
+            tenant_id: key.tenant_id.clone(),
+            provider_tenant_id: key.provider_tenant_id.clone(),
+            key_id: key.key_id.clone(),
+            scopes,
+            allowed_group_ids: key.restrictions.allowed_group_ids.clone(),
+            allowed_tools: key.restrictions.allowed_tools.clone(),
+            allowed_cidrs: key.allowed_cidrs.clone(),
+        }
+    }
+}
+
+// ── Signing keys ──────────────────────────────────────────────────────────────
+
+#[derive(Clone)]
+struct SigningKey {
+    kid: String,
+    /// Raw Ed25519 public key, base64url (the JWK "x").
+    public_x: String,
+    encoding: EncodingKey,
+    activates_at: DateTime<Utc>,
+}
+
+impl SigningKey {
+    fn from_pkcs8(kid: String, pkcs8: &[u8], activates_at: DateTime<Utc>) -> Result<Self, StoreError> {
+        let pair = Ed25519KeyPair::from_pkcs8(pkcs8)
+            .map_err(|e| StoreError::Crypto(format!("invalid token signing key {}: {}", kid, e)))?;
+        Ok(SigningKey {
+            kid,
+            public_x: URL_SAFE_NO_PAD.encode(pair.public_key().as_ref()),
+            encoding: EncodingKey::from_ed_der(pkcs8),
+            activates_at,
+        })
+    }
+
+    fn jwk(&self) -> serde_json::Value {
+        serde_json::json!({
+            "kty": "OKP",
+            "crv": "Ed25519",
+            "x": self.public_x,
+            "kid": self.kid,
+            "use": "sig",
+            "alg": ALGORITHM,
+        })
+    }
+}
+
+/// RFC 7638 thumbprint of an Ed25519 public key, used as its `kid`.
+fn thumbprint(public_x: &str) -> String {
+    let canonical = format!(r#"{{"crv":"Ed25519","kty":"OKP","x":"{}"}}"#, public_x);
+    URL_SAFE_NO_PAD.encode(Sha256::digest(canonical.as_bytes()))
+}
+
+/// A fresh key pair: (kid, PKCS#8 document, public x).
+fn generate_key_pair() -> Result<(String, Vec<u8>, String), StoreError> {
+    let rng = ring::rand::SystemRandom::new();
+    let pkcs8 = Ed25519KeyPair::generate_pkcs8(&rng)
+        .map_err(|_| StoreError::Crypto("failed to generate token signing key".to_string()))?;
+    let pair = Ed25519KeyPair::from_pkcs8(pkcs8.as_ref())
+        .map_err(|e| StoreError::Crypto(e.to_string()))?;
+    let public_x = URL_SAFE_NO_PAD.encode(pair.public_key().as_ref());
+    Ok((thumbprint(&public_x), pkcs8.as_ref().to_vec(), public_x))
+}
+
+/// The newest key whose activation time has passed.
+fn pick_signer(keys: &[SigningKey], now: DateTime<Utc>) -> Option<&SigningKey> {
+    keys.iter().filter(|k| k.activates_at <= now).max_by_key(|k| k.activates_at)
+}
+
+struct KeySet {
+    keys: Vec<SigningKey>,
+    loaded_at: Instant,
+}
+
+static KEYSET: OnceLock<Mutex<Option<KeySet>>> = OnceLock::new();
+
+fn keyset() -> &'static Mutex<Option<KeySet>> {
+    KEYSET.get_or_init(|| Mutex::new(None))
+}
+
+fn cached_keys() -> Option<Vec<SigningKey>> {
+    let guard = keyset().lock().unwrap_or_else(|e| e.into_inner());
+    guard
+        .as_ref()
+        .filter(|s| s.loaded_at.elapsed().as_secs() < KEYSET_TTL_SECS)
+        .map(|s| s.keys.clone())
+}
+
+/// Forget the cached key set; the next use reloads it.
+pub fn invalidate() {
+    *keyset().lock().unwrap_or_else(|e| e.into_inner()) = None;
+}
+
+/// Apply a notification received on CHANNEL (see notify_listener.rs).
+pub fn handle_notification(_payload: &str) {
+    invalidate();
+}
+
+/// Signing keys belong to no tenant.
+fn private_key_slot() -> SecretSlot<'static> {
+    SecretSlot::new("token_signing_keys", "private_key", "")
+}
+
+/// Published keys (not yet retired), decrypted.
+async fn load_keys(store: &EndpointStore) -> Result<Vec<SigningKey>, StoreError> {
+    let client = store.get_admin_conn().await?;
+    let rows = client
+        .query(
+            "SELECT kid, private_key, activates_at FROM token_signing_keys
+             WHERE retires_at IS NULL OR retires_at > NOW()",
+            &[],
+        )
+        .await
+        .to_store_error()?;
+
+    let mut keys = Vec::with_capacity(rows.len());
+    for row in &rows {
+        let kid: String = row.get(0);
+        let sealed: String = row.get(1);
+        let pkcs8 = STANDARD
+            .decode(store.secrets().decrypt(private_key_slot(), &sealed)?)
+            .map_err(|e| StoreError::Crypto(format!("invalid token signing key {}: {}", kid, e)))?;
+        keys.push(SigningKey::from_pkcs8(kid, &pkcs8, row.get(2))?);
+    }
+
+    *keyset().lock().unwrap_or_else(|e| e.into_inner()) =
+        Some(KeySet { keys: keys.clone(), loaded_at: Instant::now() });
+    Ok(keys)
+}
+
+async fn published_keys(store: &EndpointStore) -> Result<Vec<SigningKey>, StoreError> {
+    match cached_keys() {
+        Some(keys) => Ok(keys),
+        None => load_keys(store).await,
+    }
+}
+
+/// When create_key() actually creates a key; checked under the lock.
+enum When {
+    Always,
+    /// No key can sign yet (first start, or all retired).
+    NoSigner,
+    /// The newest published key is older than this many days.
+    OlderThanDays(i64),
+}
+
+/// Create a signing key and retire the current ones, serialised across
+/// instances with an advisory lock. Returns the new kid, or None when `when`
+/// did not hold.
+async fn create_key(store: &EndpointStore, immediate: bool, when: When) -> Result<Option<String>, StoreError> {
+    let mut client = store.get_admin_conn().await?;
+    let tx = client.transaction().await.to_store_error()?;
+    tx.execute("SELECT pg_advisory_xact_lock(hashtext($1))", &[&CHANNEL])
+        .await
+        .to_store_error()?;
+
+    let due: bool = match when {
+        When::Always => true,
+        When::NoSigner => tx
+            .query_one(
+                "SELECT NOT EXISTS (SELECT 1 FROM token_signing_keys
+                                    WHERE activates_at <= NOW() AND (retires_at IS NULL OR retires_at > NOW()))",
+                &[],
+            )
+            .await
+            .to_store_error()?
+            .get(0),
+        When::OlderThanDays(days) => tx
+            .query_one(
+                "SELECT COALESCE(MAX(created_at) < NOW() - make_interval(days => $1::INT), false)
+                 FROM token_signing_keys WHERE retires_at IS NULL OR retires_at > NOW()",
+                &[&(days as i32)],
+            )
+            .await
+            .to_store_error()?
+            .get(0),
+    };
+    if !due {
+        return Ok(None);
+    }
+
+    let (kid, pkcs8, public_x) = generate_key_pair()?;
+    let sealed = store.secrets().encrypt(private_key_slot(), &STANDARD.encode(&pkcs8))?;
+    let now = Utc::now();
+    let activates_at = if immediate { now } else { now + Duration::seconds(PUBLISH_LEAD_SECS) };
+    // Tokens signed by a superseded key stay verifiable until they expire;
+    // after a compromise they must not.
+    let retires_at = if immediate {
+        now
+    } else {
+        activates_at + Duration::seconds(MAX_TTL_SECS + CLOCK_SKEW_SECS)
+    };
+
+    tx.execute(
+        "UPDATE token_signing_keys SET retires_at = $1
+         WHERE retires_at IS NULL OR retires_at > $1",
+        &[&retires_at],
+    )
+    .await
+    .to_store_error()?;
+    tx.execute(
+        "INSERT INTO token_signing_keys (kid, algorithm, public_key, private_key, activates_at)
+         VALUES ($1, $2, $3, $4, $5)",
+        &[&kid, &ALGORITHM, &public_x, &sealed, &activates_at],
+    )
+    .await
+    .to_store_error()?;
+    tx.execute("SELECT pg_notify($1, 'reload')", &[&CHANNEL]).await.to_store_error()?;
+    tx.commit().await.to_store_error()?;
+
+    invalidate();
+    app_log!(info, kid = %kid, activates_at = %activates_at, immediate = immediate, "Created token signing key");
+    Ok(Some(kid))
+}
+
+/// Start a new signing key. `immediate` is for a suspected compromise: tokens
+/// signed by the old keys stop verifying as soon as gateways refresh.
+pub async fn rotate_signing_key(store: &EndpointStore, immediate: bool) -> Result<String, StoreError> {
+    create_key(store, immediate, When::Always)
+        .await?
+        .ok_or_else(|| StoreError::Crypto("token signing key was not created".to_string()))
+}
+
+/// Scheduled upkeep: create the first key, rotate on age, delete long-retired
+/// keys. Safe to run on every instance.
+pub async fn maintain_signing_keys(store: &EndpointStore) -> Result<(), StoreError> {
+    if create_key(store, true, When::NoSigner).await?.is_none() {
+        create_key(store, false, When::OlderThanDays(rotation_days())).await?;
+    }
+
+    let client = store.get_admin_conn().await?;
+    let pruned = client
+        .execute(
+            "DELETE FROM token_signing_keys WHERE retires_at < NOW() - make_interval(days => $1::INT)",
+            &[&(RETIRED_RETENTION_DAYS as i32)],
+        )
+        .await
+        .to_store_error()?;
+    if pruned > 0 {
+        app_log!(info, pruned = pruned, "Deleted retired token signing keys");
+    }
+    Ok(())
+}
+
+// ── Issuing and publishing ────────────────────────────────────────────────────
+
+#[derive(Debug, Clone, Serialize)]
+pub struct IssuedToken {
+    pub access_token: String,
+    pub expires_in: i64,
+    pub scopes: Vec<String>,
+    pub kid: String,
+}
+
+fn sign(key: &SigningKey, claims: &AccessClaims) -> Result<String, StoreError> {
+    let mut header = Header::new(Algorithm::EdDSA);
+    header.kid = Some(key.kid.clone());
+    jsonwebtoken::encode(&header, claims, &key.encoding).map_err(|e| StoreError::Crypto(e.to_string()))
+}
+
+/// Sign an access token for a validated key. `scopes` must already be a
+/// subset of what the key grants; `ttl_secs` is clamped to MAX_TTL_SECS.
+pub async fn issue_access_token(
+    store: &EndpointStore,
+    key: &ValidatedKey,
+    scopes: Vec<String>,
+    ttl_secs: i64,
+) -> Result<IssuedToken, StoreError> {
+    let now = Utc::now();
+    let mut ttl_secs = ttl_secs.clamp(1, MAX_TTL_SECS);
+    // A token must not outlive the secret it was exchanged for.
+    if let Some(at) = key.valid_until() {
+        ttl_secs = ttl_secs.min((at - now).num_seconds().max(1));
+    }
+
+    let mut keys = published_keys(store).await?;
+    if pick_signer(&keys, now).is_none() {
+        create_key(store, true, When::NoSigner).await?;
+        keys = load_keys(store).await?;
+    }
+    let signer = pick_signer(&keys, now)
+        .ok_or_else(|| StoreError::Crypto("no token signing key available".to_string()))?;
+
+    let claims = AccessClaims::new(key, scopes, now, ttl_secs);
+    Ok(IssuedToken {
+        access_token: sign(signer, &claims)?,
+        expires_in: ttl_secs,
+        scopes: claims.scopes,
+        kid: signer.kid.clone(),
+    })
+}
+
+/// Public keys of every published signing key, as a JWK Set.
+pub async fn jwks(store: &EndpointStore) -> Result<serde_json::Value, StoreError> {
+    let mut keys = published_keys(store).await?;
+    keys.sort_by_key(|k| std::cmp::Reverse(k.activates_at));
+    Ok(serde_json::json!({ "keys": keys.iter().map(SigningKey::jwk).collect::<Vec<_>>() }))
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SigningKeyInfo {
+    pub kid: String,
+    pub algorithm: String,
+    pub created_at: String,
+    pub activates_at: String,
+    pub retires_at: Option<String>,
+    /// "pending", "signing", "verifying" (superseded but published) or "retired".
+    pub state: &'static str,
+}
+
+pub async fn list_signing_keys(store: &EndpointStore) -> Result<Vec<SigningKeyInfo>, StoreError> {
+    let client = store.get_admin_conn().await?;
+    let rows = client
+        .query(
+            "SELECT kid, algorithm, created_at, activates_at, retires_at
+             FROM token_signing_keys ORDER BY activates_at DESC",
+            &[],
+        )
+        .await
+        .to_store_error()?;
+
+    let now = Utc::now();
+    let mut signer_seen = false;
+    Ok(rows
+        .iter()
+        .map(|r| {
+            let activates_at: DateTime<Utc> = r.get(3);
+            let retires_at: Option<DateTime<Utc>> = r.get(4);
+            let state = if retires_at.is_some_and(|at| at <= now) {
+                "retired"
+            } else if activates_at > now {
+                "pending"
+            } else if !signer_seen {
+                signer_seen = true;
+                "signing"
+            } else {
+                "verifying"
+            };
+            SigningKeyInfo {
+                kid: r.get(0),
+                algorithm: r.get(1),
+                created_at: r.get::<_, DateTime<Utc>>(2).to_rfc3339(),
+                activates_at: activates_at.to_rfc3339(),
+                retires_at: retires_at.map(|t| t.to_rfc3339()),
+                state,
+            }
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use jsonwebtoken::jwk::Jwk;
+    use jsonwebtoken::{decode, decode_header, DecodingKey, Validation};
+
+    fn signing_key(activates_at: DateTime<Utc>) -> SigningKey {
+        let (kid, pkcs8, _) = generate_key_pair().unwrap();
+        SigningKey::from_pkcs8(kid, &pkcs8, activates_at).unwrap()
+    }
+
+    fn validated_key() -> ValidatedKey {
+        ValidatedKey {
+            email: "a@b.c".into(),
+            key_id: "k1".into(),
+            tenant_id: "t1".into(),
+            provider_tenant_id: Some("p1".into()),
+            restrictions: Default::default(),
+            generation: 1,
+            previous_expires_at: None,
+            allowed_cidrs: None,
+            expires_at: None,
+            suspended: false,
+        }
+    }
+
+    #[test]
+    fn token_verifies_against_published_jwk() {
+        let key = signing_key(Utc::now());
+        let claims = AccessClaims::new(&validated_key(), vec!["tools:call".into()], Utc::now(), 300);
+        let token = sign(&key, &claims).unwrap();
+
+        let jwk: Jwk = serde_json::from_value(key.jwk()).unwrap();
+        assert_eq!(decode_header(&token).unwrap().kid.as_deref(), Some(key.kid.as_str()));
+        let mut validation = Validation::new(Algorithm::EdDSA);
+        validation.set_audience(&[audience()]);
+        validation.set_issuer(&[issuer()]);
+        let decoded = decode::<AccessClaims>(&token, &DecodingKey::from_jwk(&jwk).unwrap(), &validation).unwrap();
+        assert_eq!(decoded.claims, claims);
+
+        let other = signing_key(Utc::now());
+        let jwk: Jwk = serde_json::from_value(other.jwk()).unwrap();
+        assert!(decode::<AccessClaims>(&token, &DecodingKey::from_jwk(&jwk).unwrap(), &validation).is_err());
+    }
+
+    #[test]
+    fn newest_active_key_signs() {
+        let now = Utc::now();
+        let old = signing_key(now - Duration::days(30));
+        let current = signing_key(now - Duration::hours(1));
+        let pending = signing_key(now + Duration::minutes(10));
+        let keys = vec![old, current.clone(), pending];
+        assert_eq!(pick_signer(&keys, now).unwrap().kid, current.kid);
+        assert!(pick_signer(&keys[2..], now).is_none());
+    }
+
+    #[test]
+    fn kid_is_rfc7638_thumbprint() {
+        // Example key from RFC 8037, appendix A.3.
+        assert_eq!(
+            thumbprint("11qYAYKxCrfVS_7TyWQHOg7hcvPapiMlrwIaaPcHURo"),
+            "kPrK_qmxVWaYVA9wwBF6Iuo3vVzz7TxHCTwXBygrS4k"
+        );
+    }
+}
@@ -1,37 +1,128 @@
 use crate::app_log;
 use crate::endpoint_store::db_helpers::ResultExt;
+use crate::endpoint_store::ip_allowlist;
+use crate::endpoint_store::key_cache::{self, Invalidation};
+use crate::endpoint_store::key_expiry::{self, KeyExpiry};
+use crate::endpoint_store::key_quotas::QuotaLimits;
+use crate::endpoint_store::key_scopes::KeyScopes;
 use crate::endpoint_store::models::{ApiKeyInfo, KeyPreference};
 use crate::endpoint_store::{EndpointStore, StoreError};
 use crate::infra::db::PgConnection;
-use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine as _};
+use base64::{engine::general_purpose::{STANDARD, URL_SAFE_NO_PAD}, Engine as _};
+use hmac::{Hmac, Mac};
 use chrono::Utc;
 use rand::{rng, RngCore};
 use sha2::{Digest, Sha256};
+use std::sync::OnceLock;
 use uuid::Uuid;
 
-/// Algorithm version embedded in every generated key.
+/// Algorithm versions embedded in generated keys.
 ///
-/// Increment to v2, v3 … whenever the key format or generation algorithm
-/// changes. Validators can inspect `parts[1]` and dispatch to the matching
-/// verification path, keeping old keys valid during a migration window.
-const KEY_VERSION: &str = "v1";
-
-/// Generate a secure API key.
+/// Validators inspect the version segment and dispatch to the matching
+/// verification path, so old keys stay valid during a migration window.
+///
+/// - `v1`: `sk_v1_<43 base64url chars>`, stored as bare SHA-256.
+/// - `v2`: `sk_v2_<43 base64url chars><6 char checksum>`, stored as
+///   HMAC-SHA256 under the server pepper (API0_KEY_PEPPER). The checksum
+///   rejects typo'd or truncated keys before any lookup.
 ///
-/// Format:  `sk_v1_<43 base64url chars>`  (50 chars total)
-/// Entropy: 32 bytes = 256 bits from the OS CSPRNG.
+/// New keys are v2 once a pepper is configured. Existing v1 keys keep
+/// working; owners move a key to v2 by rotating it (key_rotate.rs).
+const KEY_VERSION_V1: &str = "v1";
+const KEY_VERSION_V2: &str = "v2";
+const V2_PREFIX: &str = "sk_v2_";
+const V2_PAYLOAD_LEN: usize = 43;
+const V2_CHECKSUM_LEN: usize = 6;
+/// Pattern for secret scanners (GitHub push protection, gitleaks, …).
+/// Matches v2 keys only; the checksum lets scanners drop false positives.
+pub const V2_KEY_PATTERN: &str = r"sk_v2_[A-Za-z0-9_-]{49}";
+/// Minimum pepper length in bytes.
+const MIN_PEPPER_LEN: usize = 32;
+
+static KEY_PEPPER: OnceLock<Option<Vec<u8>>> = OnceLock::new();
+
+/// Load API0_KEY_PEPPER (base64, at least 32 bytes). Called once at startup;
+/// returns whether v2 keys are enabled. The pepper cannot be rotated without
+/// reissuing every v2 key, so keep it with the other long-lived secrets.
+pub fn init_key_pepper() -> Result<bool, String> {
+    let pepper = match std::env::var("API0_KEY_PEPPER") {
+        Ok(b64) if !b64.trim().is_empty() => {
+            let bytes = STANDARD
+                .decode(b64.trim())
+                .map_err(|e| format!("API0_KEY_PEPPER: invalid base64: {}", e))?;
+            if bytes.len() < MIN_PEPPER_LEN {
+                return Err(format!("API0_KEY_PEPPER must be at least {} bytes", MIN_PEPPER_LEN));
+            }
+            Some(bytes)
+        }
+        _ => None,
+    };
+    let enabled = pepper.is_some();
+    let _ = KEY_PEPPER.set(pepper);
+    Ok(enabled)
+}
+
+fn key_pepper() -> Option<&'static [u8]> {
+    KEY_PEPPER.get().and_then(|p| p.as_deref())
+}
+
+/// Checksum over `sk_v2_<payload>`: the first 4 bytes of its SHA-256.
+fn v2_checksum(body: &str) -> String {
+    URL_SAFE_NO_PAD.encode(&Sha256::digest(body.as_bytes())[..4])
+}
+
+/// Generate a secure API key: v2 when a pepper is configured, else v1.
 ///
-/// The version segment lets us change the algorithm later without ambiguity —
-/// a future `sk_v2_…` key will be recognisably distinct from a `sk_v1_…` key,
-/// so validators can reject unsupported versions or apply per-version logic.
+/// Entropy: 32 bytes = 256 bits from the OS CSPRNG in both formats.
 pub fn generate_secure_key() -> String {
     let mut bytes = [0u8; 32]; // 256 bits
     rng().fill_bytes(&mut bytes);
-    format!("sk_{KEY_VERSION}_{}", URL_SAFE_NO_PAD.encode(bytes))
+    let payload = URL_SAFE_NO_PAD.encode(bytes);
+    if key_pepper().is_none() {
+        return format!("sk_{KEY_VERSION_V1}_{payload}");
+    }
+    let body = format!("{V2_PREFIX}{payload}");
+    let checksum = v2_checksum(&body);
+    format!("{body}{checksum}")
+}
+
+/// Format version of a key ("v2" for sk_v2_ keys, "v1" for everything else).
+pub fn key_version(key: &str) -> &'static str {
+    if key.starts_with(V2_PREFIX) {
+        KEY_VERSION_V2
+    } else {
+        KEY_VERSION_V1
+    }
+}
+
+/// Offline check of a v2 key's length, alphabet and checksum.
+/// Other formats carry no checksum and always pass.
+pub fn is_well_formed(key: &str) -> bool {
+    let Some(rest) = key.strip_prefix(V2_PREFIX) else {
+        return true;
+    };
+    if rest.len() != V2_PAYLOAD_LEN + V2_CHECKSUM_LEN
+        || !rest.bytes().all(|b| b.is_ascii_alphanumeric() || b == b'_' || b == b'-')
+    {
+        return false;
+    }
+    let (body, checksum) = key.split_at(V2_PREFIX.len() + V2_PAYLOAD_LEN);
+    v2_checksum(body) == checksum
 }
 
-/// Hash the API key for secure storage.
+/// HMAC-SHA256 of a v2 key under `pepper`.
+fn hash_v2(pepper: &[u8], key: &str) -> String {
+    let mut mac = Hmac::<Sha256>::new_from_slice(pepper).expect("HMAC accepts any key length");
+    mac.update(key.as_bytes());
+    URL_SAFE_NO_PAD.encode(mac.finalize().into_bytes())
+}
+
+/// Hash the API key for secure storage: peppered HMAC for v2, SHA-256 otherwise.
+/// A v2 key hashed without a pepper falls back to SHA-256 and matches nothing.
 pub fn hash_api_key(key: &str) -> String {
+    if let (KEY_VERSION_V2, Some(pepper)) = (key_version(key), key_pepper()) {
+        return hash_v2(pepper, key);
+    }
     let mut hasher = Sha256::new();
     hasher.update(key.as_bytes());
     let result = hasher.finalize();
@@ -115,7 +206,11 @@ pub async fn get_api_keys_status(
     // 3. Get key details
     let rows = client
         .query(
-            "SELECT id, key_prefix, key_name, generated_at, last_used, usage_count
+            "SELECT id, key_prefix, key_name, generated_at, last_used, usage_count,
+                    scopes, allowed_group_ids, allowed_tools, generation,
+                    previous_key_prefix, previous_expires_at, expires_at,
+                    rate_limit_rpm, daily_call_limit, daily_credit_limit,
+                    allowed_cidrs, key_version
             FROM api_keys
             WHERE tenant_id = $1 AND is_active = true
             ORDER BY generated_at DESC",
@@ -124,19 +219,7 @@ pub async fn get_api_keys_status(
         .await
         .to_store_error()?;
 
-    let mut keys = Vec::new();
-    for row in rows {
-        keys.push(ApiKeyInfo {
-            id: row.get(0),
-            key_prefix: row.get(1),
-            key_name: row.get(2),
-            generated_at: row.get::<_, chrono::DateTime<chrono::Utc>>(3).to_rfc3339(),
-            last_used: row
-                .get::<_, Option<chrono::DateTime<chrono::Utc>>>(4)
-                .map(|dt| dt.to_rfc3339()),
-            usage_count: row.get::<_, i64>(5),
-        });
-    }
+    let keys = rows.iter().map(row_to_key_info).collect();
 
     Ok(KeyPreference {
         has_keys: true,
@@ -175,6 +258,7 @@ pub async fn revoke_api_key(
         )
         .await
         .to_store_error()?;
+    key_cache::notify_invalidation(&client, Invalidation::Key(key_id.to_string())).await?;
 
     Ok(true)
 }
@@ -193,74 +277,252 @@ pub async fn revoke_all_api_keys(
         )
         .await
         .to_store_error()?;
+    key_cache::notify_invalidation(&client, Invalidation::Tenant(tenant_id.to_string())).await?;
 
     Ok(count)
 }
 
-/// Record API key usage
-#[allow(dead_code)]
-pub async fn record_api_key_usage(store: &EndpointStore, key_id: &str) -> Result<(), StoreError> {
-    let client = store.get_admin_conn().await?;
-    let now = Utc::now();
+/// A key that passed validation.
+#[derive(Debug, Clone)]
+pub struct ValidatedKey {
+    pub email: String,
+    pub key_id: String,
+    pub tenant_id: String,
+    pub provider_tenant_id: Option<String>,
+    pub restrictions: KeyScopes,
+    /// Generation of the secret that matched.
+    pub generation: i32,
+    /// Set when the pre-rotation secret matched: when it stops working.
+    pub previous_expires_at: Option<chrono::DateTime<Utc>>,
+    /// Caller IP allowlist; None = any address (see ip_allowlist.rs).
+    pub allowed_cidrs: Option<Vec<String>>,
+    /// When the key itself expires; None = never.
+    pub expires_at: Option<chrono::DateTime<Utc>>,
+    /// Suspended by the provider of a consumer key (consumer_keys.rs).
+    pub suspended: bool,
+}
 
-    client
-        .execute(
-            "UPDATE api_keys SET
-             last_used = $1,
-             usage_count = usage_count + 1
-             WHERE id = $2 AND is_active = true",
-            &[&now, &key_id],
-        )
-        .await
-        .to_store_error()?;
+impl ValidatedKey {
+    /// When the matched secret stops working: key expiry or the end of the
+    /// rotation grace window, whichever comes first.
+    pub fn valid_until(&self) -> Option<chrono::DateTime<Utc>> {
+        match (self.expires_at, self.previous_expires_at) {
+            (Some(a), Some(b)) => Some(a.min(b)),
+            (a, b) => a.or(b),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum KeyValidation {
+    Valid(ValidatedKey),
+    /// Unknown, revoked or expired.
+    Invalid,
+    /// The key exists but belongs to another tenant than the caller expected.
+    /// Reported to the caller like Invalid.
+    WrongTenant(ValidatedKey),
+    /// A v2 key whose checksum or shape is wrong; rejected without a lookup.
+    Malformed,
+    /// The key is valid but does not carry the required scope.
+    MissingScope(ValidatedKey),
+    /// The key is valid but the caller's address is outside its allowlist.
+    IpNotAllowed(ValidatedKey),
+    /// The key exists but its provider has suspended it.
+    Suspended(ValidatedKey),
+}
 
-    Ok(())
+impl KeyValidation {
+    /// The key the lookup found, whatever the outcome.
+    pub fn key(&self) -> Option<&ValidatedKey> {
+        match self {
+            KeyValidation::Valid(k)
+            | KeyValidation::WrongTenant(k)
+            | KeyValidation::MissingScope(k)
+            | KeyValidation::IpNotAllowed(k)
+            | KeyValidation::Suspended(k) => Some(k),
+            KeyValidation::Invalid | KeyValidation::Malformed => None,
+        }
+    }
 }
 
-/// Validate an API key and return the key_id and email if valid.
+/// Validate an API key.
 /// If expected_tenant_id is provided, the key MUST belong to that tenant.
-/// Returns (email, key_id, tenant_id, provider_tenant_id) for a valid key.
+/// If required_scope is provided, the key must grant it (see key_scopes.rs).
+/// Keys with an IP allowlist only validate for a `client_ip` inside it.
+/// v2 keys are checksum-verified first; the lookup itself is served from
+/// key_cache.rs when possible.
 pub async fn validate_api_key(
     store: &EndpointStore,
     key: &str,
     expected_tenant_id: Option<&str>,
-) -> Result<Option<(String, String, String, Option<String>)>, StoreError> {
-    let client = store.get_admin_conn().await?;
+    required_scope: Option<&str>,
+    client_ip: Option<&str>,
+) -> Result<KeyValidation, StoreError> {
+    if !is_well_formed(key) {
+        return Ok(KeyValidation::Malformed);
+    }
+    if key_version(key) == KEY_VERSION_V2 && key_pepper().is_none() {
+        app_log!(error, "v2 API key presented but API0_KEY_PEPPER is not configured");
+        return Ok(KeyValidation::Invalid);
+    }
     let key_hash = hash_api_key(key);
+    let found = match key_cache::get(&key_hash) {
+        Some(cached) => cached,
+        None => lookup_active_key(store, &key_hash).await?,
+    };
 
-    let row = if let Some(expected_id) = expected_tenant_id {
-        client
-            .query_opt(
-                "SELECT id, email, tenant_id, provider_tenant_id
-                 FROM api_keys
-                 WHERE key_hash = $1
-                   AND tenant_id = $2
-                   AND is_active = true
-                   AND (expires_at IS NULL OR expires_at > NOW())",
-                &[&key_hash, &expected_id],
-            )
-            .await
-            .to_store_error()?
-    } else {
-        client
-            .query_opt(
-                "SELECT id, email, tenant_id, provider_tenant_id
-                 FROM api_keys
-                 WHERE key_hash = $1
-                   AND is_active = true
-                   AND (expires_at IS NULL OR expires_at > NOW())",
-                &[&key_hash],
-            )
-            .await
-            .to_store_error()?
+    let Some(key) = found else {
+        return Ok(KeyValidation::Invalid);
+    };
+    if expected_tenant_id.is_some_and(|t| t != key.tenant_id) {
+        return Ok(KeyValidation::WrongTenant(key));
+    }
+    if key.suspended {
+        return Ok(KeyValidation::Suspended(key));
+    }
+    if let Some(cidrs) = &key.allowed_cidrs {
+        if !ip_allowlist::is_allowed(cidrs, client_ip) {
+            return Ok(KeyValidation::IpNotAllowed(key));
+        }
+    }
+    match required_scope {
+        Some(scope) if !key.restrictions.grants(scope) => Ok(KeyValidation::MissingScope(key)),
+        _ => Ok(KeyValidation::Valid(key)),
+    }
+}
+
+/// Active, unexpired key whose current (or still-in-grace previous) secret
+/// hashes to `key_hash`. The result is cached either way.
+async fn lookup_active_key(store: &EndpointStore, key_hash: &str) -> Result<Option<ValidatedKey>, StoreError> {
+    let client = store.get_admin_conn().await?;
+    let row = client
+        .query_opt(
+            "SELECT id, email, tenant_id, provider_tenant_id,
+                    scopes, allowed_group_ids, allowed_tools,
+                    generation, key_hash = $1 AS is_current, previous_expires_at,
+                    allowed_cidrs, expires_at, suspended_at IS NOT NULL
+             FROM api_keys
+             WHERE (key_hash = $1
+                    OR (previous_key_hash = $1 AND previous_expires_at > NOW()))
+               AND is_active = true
+               AND (expires_at IS NULL OR expires_at > NOW())",
+            &[&key_hash],
+        )
+        .await
+        .to_store_error()?;
+
+    let Some(r) = row else {
+        key_cache::put(key_hash, None, None);
+        return Ok(None);
     };
+    let generation: i32 = r.get(7);
+    let is_current: bool = r.get(8);
+    let expires_at: Option<chrono::DateTime<Utc>> = r.get(11);
+    let key = ValidatedKey {
+        email: r.get(1),
+        key_id: r.get(0),
+        tenant_id: r.get::<_, Option<String>>(2).unwrap_or_default(),
+        provider_tenant_id: r.get(3),
+        restrictions: KeyScopes::from_row(&r, 4),
+        generation: if is_current { generation } else { generation - 1 },
+        previous_expires_at: if is_current { None } else { r.get(9) },
+        allowed_cidrs: r.get(10),
+        expires_at,
+        suspended: r.get(12),
+    };
+    let valid_until = key.valid_until();
+    key_cache::put(key_hash, Some(key.clone()), valid_until);
+    Ok(Some(key))
+}
+
+/// Result of rotating a key's secret.
+#[derive(Debug)]
+pub struct RotatedKey {
+    pub key: String,
+    pub key_prefix: String,
+    pub key_name: String,
+    pub email: String,
+    pub generation: i32,
+    pub previous_key_prefix: String,
+    pub previous_expires_at: chrono::DateTime<Utc>,
+}
+
+/// Issue a new secret for an active key, keeping id, name, scopes and expiry.
+/// The current secret keeps validating for `grace`; a secret still in the
+/// grace window from an earlier rotation stops working immediately.
+/// Returns None when the key does not exist for this tenant.
+pub async fn rotate_api_key(
+    store: &EndpointStore,
+    tenant_id: &str,
+    key_id: &str,
+    grace: chrono::Duration,
+) -> Result<Option<RotatedKey>, StoreError> {
+    let client = store.get_conn(Some(tenant_id)).await?;
+
+    let new_key = generate_secure_key();
+    let key_hash = hash_api_key(&new_key);
+    let key_prefix = extract_key_prefix(&new_key);
+    let previous_expires_at = Utc::now() + grace;
+
+    let row = client
+        .query_opt(
+            "UPDATE api_keys SET
+                previous_key_hash = key_hash,
+                previous_key_prefix = key_prefix,
+                previous_expires_at = $3,
+                key_hash = $4,
+                key_prefix = $5,
+                key_version = $6,
+                generation = generation + 1
+             WHERE id = $1 AND tenant_id = $2 AND is_active = true
+             RETURNING key_name, email, generation, previous_key_prefix",
+            &[&key_id, &tenant_id, &previous_expires_at, &key_hash, &key_prefix, &key_version(&new_key)],
+        )
+        .await
+        .to_store_error()?;
+    if row.is_some() {
+        // The old secret is cached as current; it must pick up its grace window.
+        key_cache::notify_invalidation(&client, Invalidation::Key(key_id.to_string())).await?;
+    }
 
-    Ok(row.map(|r| (
-        r.get::<_, String>(1),                        // email
-        r.get::<_, String>(0),                        // key_id
-        r.get::<_, Option<String>>(2).unwrap_or_default(), // tenant_id
-        r.get::<_, Option<String>>(3),                // provider_tenant_id
-    )))
+    Ok(row.map(|r| RotatedKey {
+        key: new_key,
+        key_prefix,
+        key_name: r.get(0),
+        email: r.get(1),
+        generation: r.get(2),
+        previous_key_prefix: r.get(3),
+        previous_expires_at,
+    }))
+}
+
+/// Replace the scopes and group/tool restrictions of an active key.
+/// Returns false when the key does not exist for this tenant.
+pub async fn set_api_key_scopes(
+    store: &EndpointStore,
+    tenant_id: &str,
+    key_id: &str,
+    restrictions: &KeyScopes,
+) -> Result<bool, StoreError> {
+    let client = store.get_conn(Some(tenant_id)).await?;
+    let n = client
+        .execute(
+            "UPDATE api_keys SET scopes = $3, allowed_group_ids = $4, allowed_tools = $5
+             WHERE id = $1 AND tenant_id = $2 AND is_active = true",
+            &[
+                &key_id,
+                &tenant_id,
+                &restrictions.scopes,
+                &restrictions.allowed_group_ids,
+                &restrictions.allowed_tools,
+            ],
+        )
+        .await
+        .to_store_error()?;
+    if n > 0 {
+        key_cache::notify_invalidation(&client, Invalidation::Key(key_id.to_string())).await?;
+    }
+    Ok(n > 0)
 }
 
 /// Get usage statistics for a specific API key
@@ -273,7 +535,11 @@ pub async fn get_api_key_usage(
 
     let row = client
         .query_opt(
-            "SELECT id, key_prefix, key_name, generated_at, last_used, usage_count
+            "SELECT id, key_prefix, key_name, generated_at, last_used, usage_count,
+                    scopes, allowed_group_ids, allowed_tools, generation,
+                    previous_key_prefix, previous_expires_at, expires_at,
+                    rate_limit_rpm, daily_call_limit, daily_credit_limit,
+                    allowed_cidrs, key_version
              FROM api_keys
              WHERE id = $1 AND tenant_id = $2 AND is_active = true",
             &[&key_id, &tenant_id],
@@ -281,7 +547,18 @@ pub async fn get_api_key_usage(
         .await
         .to_store_error()?;
 
-    Ok(row.map(|r| ApiKeyInfo {
+    Ok(row.as_ref().map(row_to_key_info))
+}
+
+/// Columns: id, key_prefix, key_name, generated_at, last_used, usage_count,
+/// scopes, allowed_group_ids, allowed_tools, generation, previous_key_prefix,
+/// previous_expires_at, expires_at, rate_limit_rpm, daily_call_limit,
+/// daily_credit_limit, allowed_cidrs, key_version.
+fn row_to_key_info(r: &tokio_postgres::Row) -> ApiKeyInfo {
+    let previous_expires_at = r
+        .get::<_, Option<chrono::DateTime<chrono::Utc>>>(11)
+        .filter(|at| *at > Utc::now());
+    ApiKeyInfo {
         id: r.get(0),
         key_prefix: r.get(1),
         key_name: r.get(2),
@@ -290,7 +567,21 @@ pub async fn get_api_key_usage(
             .get::<_, Option<chrono::DateTime<chrono::Utc>>>(4)
             .map(|dt| dt.to_rfc3339()),
         usage_count: r.get::<_, i64>(5),
-    }))
+        restrictions: KeyScopes::from_row(r, 6),
+        generation: r.get(9),
+        previous_key_prefix: previous_expires_at.and(r.get::<_, Option<String>>(10)),
+        previous_expires_at: previous_expires_at.map(|at| at.to_rfc3339()),
+        expires_at: r
+            .get::<_, Option<chrono::DateTime<chrono::Utc>>>(12)
+            .map(|dt| dt.to_rfc3339()),
+        limits: QuotaLimits {
+            requests_per_minute: r.get(13),
+            calls_per_day: r.get(14),
+            credits_per_day: r.get(15),
+        },
+        allowed_cidrs: r.get(16),
+        key_version: r.get(17),
+    }
 }
 
 #[allow(dead_code)]
@@ -408,8 +699,8 @@ pub async fn generate_api_key(
     email: &str,
     key_name: &str,
     tenant_id: Option<&str>,
-) -> Result<(String, String, String), StoreError> {
-    generate_api_key_with_provider(store, email, key_name, tenant_id, None).await
+) -> Result<(String, String, String, Option<chrono::DateTime<Utc>>), StoreError> {
+    generate_api_key_with_provider(store, email, key_name, tenant_id, None, &KeyScopes::default(), &KeyExpiry::default()).await
 }
 
 /// Generate a new API key, optionally scoped to a provider tenant.
@@ -419,7 +710,9 @@ pub async fn generate_api_key_with_provider(
     key_name: &str,
     explicit_tenant_id: Option<&str>,
     provider_tenant_id: Option<&str>,
-) -> Result<(String, String, String), StoreError> {
+    restrictions: &KeyScopes,
+    expiry: &KeyExpiry,
+) -> Result<(String, String, String, Option<chrono::DateTime<Utc>>), StoreError> {
     use crate::endpoint_store::tenant_management;
 
     // 1. Resolve billing tenant_id
@@ -430,6 +723,11 @@ pub async fn generate_api_key_with_provider(
         tenant.id
     };
 
+    // Consumer keys default to a year, other keys to no expiry; the tenant
+    // policy (key_expiry.rs) caps both.
+    let expires_at =
+        key_expiry::resolve_new_key_expiry(store, &tenant_id, provider_tenant_id, expiry).await?;
+
     let mut client = store.get_conn(Some(&tenant_id)).await?;
     let tx = client.transaction().await.to_store_error()?;
 
@@ -439,19 +737,12 @@ pub async fn generate_api_key_with_provider(
     let now = Utc::now();
     let key_id = Uuid::new_v4().to_string();
 
-    // Consumer keys (provider_tenant_id set) expire after 1 year by default;
-    // admin/tenant keys never expire (NULL) unless an explicit expires_at is desired.
-    let expires_at: Option<chrono::DateTime<chrono::Utc>> = if provider_tenant_id.is_some() {
-        Some(now + chrono::Duration::days(365))
-    } else {
-        None
-    };
-
     tx.execute(
         "INSERT INTO api_keys (
             id, email, key_hash, key_prefix, key_name,
-            generated_at, usage_count, is_active, tenant_id, provider_tenant_id, expires_at
-        ) VALUES ($1, $2, $3, $4, $5, $6, 0, true, $7, $8, $9)",
+            generated_at, usage_count, is_active, tenant_id, provider_tenant_id, expires_at,
+            scopes, allowed_group_ids, allowed_tools, key_version
+        ) VALUES ($1, $2, $3, $4, $5, $6, 0, true, $7, $8, $9, $10, $11, $12, $13)",
         &[
             &key_id as &(dyn tokio_postgres::types::ToSql + Sync),
             &email as &(dyn tokio_postgres::types::ToSql + Sync),
@@ -462,6 +753,10 @@ pub async fn generate_api_key_with_provider(
             &tenant_id as &(dyn tokio_postgres::types::ToSql + Sync),
             &provider_tenant_id as &(dyn tokio_postgres::types::ToSql + Sync),
             &expires_at as &(dyn tokio_postgres::types::ToSql + Sync),
+            &restrictions.scopes as &(dyn tokio_postgres::types::ToSql + Sync),
+            &restrictions.allowed_group_ids as &(dyn tokio_postgres::types::ToSql + Sync),
+            &restrictions.allowed_tools as &(dyn tokio_postgres::types::ToSql + Sync),
+            &key_version(&new_key) as &(dyn tokio_postgres::types::ToSql + Sync),
         ],
     )
     .await
@@ -469,5 +764,86 @@ pub async fn generate_api_key_with_provider(
 
     tx.commit().await.to_store_error()?;
 
-    Ok((new_key, key_prefix, key_id))
+    Ok((new_key, key_prefix, key_id, expires_at))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn v2_key(payload: &str) -> String {
+        let body = format!("{V2_PREFIX}{payload}");
+        let checksum = v2_checksum(&body);
+        format!("{body}{checksum}")
+    }
+
+    #[test]
+    fn v2_checksum_catches_typos() {
+        let key = v2_key(&URL_SAFE_NO_PAD.encode([7u8; 32]));
+        assert_eq!(key.len(), V2_PREFIX.len() + V2_PAYLOAD_LEN + V2_CHECKSUM_LEN);
+        assert!(is_well_formed(&key));
+        assert_eq!(key_version(&key), "v2");
+
+        let mut typo = key.clone().into_bytes();
+        typo[10] = if typo[10] == b'A' { b'B' } else { b'A' };
+        assert!(!is_well_formed(&String::from_utf8(typo).unwrap()));
+        assert!(!is_well_formed(&key[..key.len() - 1]));
+        assert!(!is_well_formed(&format!("{key}x")));
+        assert!(!is_well_formed(&format!("{}+{}", &key[..20], &key[21..])));
+
+        // v1 and legacy keys carry no checksum.
+        assert!(is_well_formed("sk_v1_anything"));
+        assert_eq!(key_version("sk_v1_anything"), "v1");
+    }
+
+    #[test]
+    fn v2_hash_depends_on_pepper() {
+        let key = v2_key(&URL_SAFE_NO_PAD.encode([1u8; 32]));
+        let a = hash_v2(&[1u8; 32], &key);
+        assert_eq!(a, hash_v2(&[1u8; 32], &key));
+        assert_ne!(a, hash_v2(&[2u8; 32], &key));
+        assert_ne!(a, URL_SAFE_NO_PAD.encode(Sha256::digest(key.as_bytes())));
+    }
+
+    #[test]
+    fn scanner_pattern_describes_v2_keys() {
+        let key = v2_key(&URL_SAFE_NO_PAD.encode([9u8; 32]));
+        let (prefix, class) = V2_KEY_PATTERN.split_once('[').unwrap();
+        assert!(key.starts_with(prefix));
+        assert!(class.ends_with(&format!("{{{}}}", key.len() - prefix.len())));
+    }
+
+    async fn validated(store: &EndpointStore, key: &str) -> Option<ValidatedKey> {
+        match validate_api_key(store, key, None, None, None).await.unwrap() {
+            KeyValidation::Valid(k) => Some(k),
+            _ => None,
+        }
+    }
+
+    #[tokio::test]
+    #[ignore = "requires live PostgreSQL (DATABASE_URL)"]
+    async fn rotation_keeps_old_secret_for_the_grace_window() {
+        let store = crate::endpoint_store::live_test_store().await;
+        let email = format!("rotate_{}@example.com", Uuid::new_v4());
+        let (old_key, _, key_id, _) = generate_api_key(&store, &email, "rotating", None).await.unwrap();
+        let before = validated(&store, &old_key).await.expect("new key validates");
+        assert!(key_cache::get(&hash_api_key(&old_key)).is_some(), "validation is cached");
+
+        let rotated = rotate_api_key(&store, &before.tenant_id, &key_id, chrono::Duration::seconds(2))
+            .await
+            .unwrap()
+            .expect("key exists");
+        assert_eq!(rotated.generation, before.generation + 1);
+        assert!(key_cache::get(&hash_api_key(&old_key)).is_none(), "rotation drops the cached entry");
+
+        let current = validated(&store, &rotated.key).await.expect("new secret validates");
+        assert_eq!(current.generation, rotated.generation);
+        let previous = validated(&store, &old_key).await.expect("old secret validates in grace");
+        assert_eq!(previous.generation, before.generation);
+        assert!(previous.previous_expires_at.is_some());
+
+        tokio::time::sleep(std::time::Duration::from_millis(2_500)).await;
+        assert!(validated(&store, &old_key).await.is_none(), "old secret fails after grace");
+        assert!(validated(&store, &rotated.key).await.is_some());
+    }
 }
@@ -0,0 +1,402 @@
+// src/endpoint_store/argument_validation.rs
+//
+// Validates a `tools/call` argument object against the tool's stored
+// `input_schema` before the gateway forwards the call to the tenant backend.
+//
+// Besides reporting errors, validation normalises the arguments:
+//   - string values are coerced to number / integer / boolean when the schema
+//     asks for that type and does not also accept a string ("42" → 42, "true" → true)
+//   - missing properties that declare a `default` are filled in
+//
+// Supported JSON Schema keywords (anything else is ignored):
+//   type (single or list), properties, required, additionalProperties (bool or schema),
+//   items, enum, const, default, minimum, maximum, exclusiveMinimum, exclusiveMaximum,
+//   minLength, maxLength, minItems, maxItems
+//
+// Error paths are JSON Pointers into the argument object ("" is the root,
+// "/address/zip" a nested property, "/tags/0" an array element).
+
+use serde::Serialize;
+use serde_json::{Map, Number, Value};
+
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct ArgumentError {
+    pub path: String,
+    pub message: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ArgumentValidation {
+    pub valid: bool,
+    /// Arguments after coercion and default filling — forward these, not the originals.
+    pub arguments: Value,
+    pub errors: Vec<ArgumentError>,
+}
+
+/// Validate (and normalise) `arguments` against `schema`.
+/// A `null` argument object is treated as `{}`, matching MCP clients that omit it.
+pub fn validate_arguments(schema: &Value, arguments: &Value) -> ArgumentValidation {
+    let mut errors = Vec::new();
+    let input = if arguments.is_null() {
+        Value::Object(Map::new())
+    } else {
+        arguments.clone()
+    };
+
+    let arguments = validate_value(schema, input, "", &mut errors);
+
+    ArgumentValidation {
+        valid: errors.is_empty(),
+        arguments,
+        errors,
+    }
+}
+
+fn validate_value(schema: &Value, value: Value, path: &str, errors: &mut Vec<ArgumentError>) -> Value {
+    let schema = match schema.as_object() {
+        Some(s) => s,
+        // `true`, `{}` or a non-object schema accept anything.
+        None => return value,
+    };
+
+    let allowed = allowed_types(schema);
+    let value = coerce(value, &allowed);
+
+    if !allowed.is_empty() && !allowed.iter().any(|t| matches_type(&value, t)) {
+        errors.push(ArgumentError {
+            path: path.to_string(),
+            message: format!("expected {}, got {}", allowed.join(" or "), type_name(&value)),
+        });
+        return value;
+    }
+
+    if let Some(options) = schema.get("enum").and_then(|e| e.as_array()) {
+        if !options.contains(&value) {
+            errors.push(ArgumentError {
+                path: path.to_string(),
+                message: format!("must be one of {}", Value::Array(options.clone())),
+            });
+        }
+    }
+
+    if let Some(expected) = schema.get("const") {
+        if expected != &value {
+            errors.push(ArgumentError {
+                path: path.to_string(),
+                message: format!("must equal {}", expected),
+            });
+        }
+    }
+
+    match value {
+        Value::Object(map) => Value::Object(validate_object(schema, map, path, errors)),
+        Value::Array(items) => Value::Array(validate_array(schema, items, path, errors)),
+        Value::Number(ref n) => {
+            check_number_bounds(schema, n, path, errors);
+            value
+        }
+        Value::String(ref s) => {
+            check_string_bounds(schema, s, path, errors);
+            value
+        }
+        other => other,
+    }
+}
+
+fn validate_object(
+    schema: &Map<String, Value>,
+    mut map: Map<String, Value>,
+    path: &str,
+    errors: &mut Vec<ArgumentError>,
+) -> Map<String, Value> {
+    let empty = Map::new();
+    let properties = schema
+        .get("properties")
+        .and_then(|p| p.as_object())
+        .unwrap_or(&empty);
+
+    // Fill defaults before the required check so a defaulted property satisfies it.
+    for (name, prop_schema) in properties {
+        if !map.contains_key(name) {
+            if let Some(default) = prop_schema.get("default") {
+                map.insert(name.clone(), default.clone());
+            }
+        }
+    }
+
+    if let Some(required) = schema.get("required").and_then(|r| r.as_array()) {
+        for name in required.iter().filter_map(|n| n.as_str()) {
+            if !map.contains_key(name) {
+                errors.push(ArgumentError {
+                    path: child_path(path, name),
+                    message: "is required".to_string(),
+                });
+            }
+        }
+    }
+
+    let additional = schema.get("additionalProperties");
+    let mut out = Map::with_capacity(map.len());
+    for (name, value) in map {
+        let child = child_path(path, &name);
+        let value = match properties.get(&name) {
+            Some(prop_schema) => validate_value(prop_schema, value, &child, errors),
+            None => match additional {
+                Some(Value::Bool(false)) => {
+                    errors.push(ArgumentError {
+                        path: child,
+                        message: "is not an allowed property".to_string(),
+                    });
+                    value
+                }
+                Some(extra_schema @ Value::Object(_)) => {
+                    validate_value(extra_schema, value, &child, errors)
+                }
+                _ => value,
+            },
+        };
+        out.insert(name, value);
+    }
+    out
+}
+
+fn validate_array(
+    schema: &Map<String, Value>,
+    items: Vec<Value>,
+    path: &str,
+    errors: &mut Vec<ArgumentError>,
+) -> Vec<Value> {
+    if let Some(min) = schema.get("minItems").and_then(|v| v.as_u64()) {
+        if (items.len() as u64) < min {
+            errors.push(ArgumentError {
+                path: path.to_string(),
+                message: format!("must contain at least {} items", min),
+            });
+        }
+    }
+    if let Some(max) = schema.get("maxItems").and_then(|v| v.as_u64()) {
+        if (items.len() as u64) > max {
+            errors.push(ArgumentError {
+                path: path.to_string(),
+                message: format!("must contain at most {} items", max),
+            });
+        }
+    }
+
+    match schema.get("items") {
+        Some(item_schema) => items
+            .into_iter()
+            .enumerate()
+            .map(|(i, item)| validate_value(item_schema, item, &child_path(path, &i.to_string()), errors))
+            .collect(),
+        None => items,
+    }
+}
+
+fn check_number_bounds(schema: &Map<String, Value>, n: &Number, path: &str, errors: &mut Vec<ArgumentError>) {
+    let v = match n.as_f64() {
+        Some(v) => v,
+        None => return,
+    };
+    let bound = |key: &str| schema.get(key).and_then(|b| b.as_f64());
+
+    if let Some(min) = bound("minimum") {
+        if v < min {
+            errors.push(ArgumentError { path: path.to_string(), message: format!("must be >= {}", min) });
+        }
+    }
+    if let Some(max) = bound("maximum") {
+        if v > max {
+            errors.push(ArgumentError { path: path.to_string(), message: format!("must be <= {}", max) });
+        }
+    }
+    if let Some(min) = bound("exclusiveMinimum") {
+        if v <= min {
+            errors.push(ArgumentError { path: path.to_string(), message: format!("must be > {}", min) });
+        }
+    }
+    if let Some(max) = bound("exclusiveMaximum") {
+        if v >= max {
+            errors.push(ArgumentError { path: path.to_string(), message: format!("must be < {}", max) });
+        }
+    }
+}
+
+fn check_string_bounds(schema: &Map<String, Value>, s: &str, path: &str, errors: &mut Vec<ArgumentError>) {
+    let len = s.chars().count() as u64;
+    if let Some(min) = schema.get("minLength").and_then(|v| v.as_u64()) {
+        if len < min {
+            errors.push(ArgumentError {
+                path: path.to_string(),
+                message: format!("must be at least {} characters", min),
+            });
+        }
+    }
+    if let Some(max) = schema.get("maxLength").and_then(|v| v.as_u64()) {
+        if len > max {
+            errors.push(ArgumentError {
+                path: path.to_string(),
+                message: format!("must be at most {} characters", max),
+            });
+        }
+    }
+}
+
+// ── helpers ───────────────────────────────────────────────────────────────────
+
+fn allowed_types(schema: &Map<String, Value>) -> Vec<String> {
+    match schema.get("type") {
+        Some(Value::String(t)) => vec![t.clone()],
+        Some(Value::Array(ts)) => ts.iter().filter_map(|t| t.as_str().map(String::from)).collect(),
+        _ => Vec::new(),
+    }
+}
+
+/// Coerce string values the schema would otherwise reject.
+/// Strings are left untouched whenever the schema also accepts a string.
+fn coerce(value: Value, allowed: &[String]) -> Value {
+    let s = match &value {
+        Value::String(s) if !allowed.is_empty() && !allowed.iter().any(|t| t == "string") => s.trim(),
+        _ => return value,
+    };
+
+    for t in allowed {
+        match t.as_str() {
+            "integer" => {
+                if let Ok(i) = s.parse::<i64>() {
+                    return Value::Number(i.into());
+                }
+            }
+            "number" => {
+                if let Ok(i) = s.parse::<i64>() {
+                    return Value::Number(i.into());
+                }
+                if let Some(n) = s.parse::<f64>().ok().and_then(Number::from_f64) {
+                    return Value::Number(n);
+                }
+            }
+            "boolean" => match s.to_ascii_lowercase().as_str() {
+                "true" => return Value::Bool(true),
+                "false" => return Value::Bool(false),
+                _ => {}
+            },
+            _ => {}
+        }
+    }
+    value
+}
+
+fn matches_type(value: &Value, t: &str) -> bool {
+    match t {
+        "string" => value.is_string(),
+        "boolean" => value.is_boolean(),
+        "object" => value.is_object(),
+        "array" => value.is_array(),
+        "null" => value.is_null(),
+        "number" => value.is_number(),
+        "integer" => match value {
+            Value::Number(n) => n.is_i64() || n.is_u64() || n.as_f64().map(|f| f.fract() == 0.0).unwrap_or(false),
+            _ => false,
+        },
+        // Unknown type names are not ours to reject.
+        _ => true,
+    }
+}
+
+fn type_name(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "boolean",
+        Value::Number(_) => "number",
+        Value::String(_) => "string",
+        Value::Array(_) => "array",
+        Value::Object(_) => "object",
+    }
+}
+
+/// Append a JSON Pointer segment, escaping `~` and `/` per RFC 6901.
+fn child_path(parent: &str, segment: &str) -> String {
+    format!("{}/{}", parent, segment.replace('~', "~0").replace('/', "~1"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn schema() -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "query":   { "type": "string", "minLength": 1 },
+                "limit":   { "type": "integer", "minimum": 1, "maximum": 100, "default": 10 },
+                "exact":   { "type": "boolean" },
+                "sort":    { "type": "string", "enum": ["asc", "desc"] },
+                "address": {
+                    "type": "object",
+                    "properties": { "zip": { "type": "string", "maxLength": 5 } },
+                    "required": ["zip"]
+                },
+                "tags":    { "type": "array", "items": { "type": "number" } }
+            },
+            "required": ["query"],
+            "additionalProperties": false
+        })
+    }
+
+    #[test]
+    fn valid_arguments_get_defaults() {
+        let result = validate_arguments(&schema(), &json!({ "query": "rust" }));
+        assert!(result.valid, "{:?}", result.errors);
+        assert_eq!(result.arguments, json!({ "query": "rust", "limit": 10 }));
+    }
+
+    #[test]
+    fn string_scalars_are_coerced() {
+        let result = validate_arguments(
+            &schema(),
+            &json!({ "query": "rust", "limit": "25", "exact": "TRUE", "tags": ["1.5", 2] }),
+        );
+        assert!(result.valid, "{:?}", result.errors);
+        assert_eq!(result.arguments["limit"], json!(25));
+        assert_eq!(result.arguments["exact"], json!(true));
+        assert_eq!(result.arguments["tags"], json!([1.5, 2]));
+    }
+
+    #[test]
+    fn errors_carry_json_pointer_paths() {
+        let result = validate_arguments(
+            &schema(),
+            &json!({
+                "limit": "lots",
+                "sort": "sideways",
+                "address": { "zip": "1234567" },
+                "tags": [1, "x"],
+                "extra": 1
+            }),
+        );
+        assert!(!result.valid);
+        let paths: Vec<&str> = result.errors.iter().map(|e| e.path.as_str()).collect();
+        assert!(paths.contains(&"/query"));
+        assert!(paths.contains(&"/limit"));
+        assert!(paths.contains(&"/sort"));
+        assert!(paths.contains(&"/address/zip"));
+        assert!(paths.contains(&"/tags/1"));
+        assert!(paths.contains(&"/extra"));
+    }
+
+    #[test]
+    fn numbers_are_not_coerced_when_string_is_allowed() {
+        let schema = json!({ "type": "object", "properties": { "id": { "type": ["string", "integer"] } } });
+        let result = validate_arguments(&schema, &json!({ "id": "007" }));
+        assert!(result.valid);
+        assert_eq!(result.arguments["id"], json!("007"));
+    }
+
+    #[test]
+    fn null_arguments_are_treated_as_empty_object() {
+        let result = validate_arguments(&json!({ "type": "object", "properties": {} }), &Value::Null);
+        assert!(result.valid);
+        assert_eq!(result.arguments, json!({}));
+    }
+}
@@ -0,0 +1,422 @@
+// src/endpoint_store/auth_lockout.rs
+//
+// Brute-force protection for API key validation, shared by all replicas.
+//
+//   - Every failed validation is recorded against the caller IP (IPv6
+//     grouped by /64) and a digest of the presented secret; a real key
+//     presented for another tenant also counts against its own tenant. Each
+//     kind has its own POLICY.
+//   - Nothing public identifies a subject: the display prefix of a key is
+//     not one, so knowing it is not enough to lock the key out.
+//   - Failures are counted over a sliding window (auth_failures). Reaching
+//     the threshold locks the subject out (auth_lockouts); each further
+//     lockout within STRIKE_DECAY_SECS doubles the duration, up to the cap.
+//   - Lockouts are mirrored in memory so the hot path never queries for
+//     them; tripping or clearing one is broadcast on CHANNEL and picked up
+//     by notify_listener.rs on every replica.
+
+use crate::app_log;
+use crate::endpoint_store::db_helpers::ResultExt;
+use crate::endpoint_store::ip_allowlist::parse_client_ip;
+use crate::endpoint_store::security_events::{record_security_event, AUTH_LOCKOUT};
+use crate::endpoint_store::{EndpointStore, StoreError};
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use chrono::{DateTime, Duration, Utc};
+use ipnet::Ipv6Net;
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::net::IpAddr;
+use std::sync::{Mutex, OnceLock};
+use tokio_postgres::Client;
+
+pub const CHANNEL: &str = "auth_lockouts";
+/// A lockout older than this no longer counts towards escalation.
+const STRIKE_DECAY_SECS: i64 = 24 * 3600;
+/// Failures older than this are pruned (longer than any window).
+const FAILURE_RETENTION_SECS: i64 = 3600;
+
+#[derive(Debug, Clone, Copy)]
+pub struct Policy {
+    pub kind: &'static str,
+    /// Failures within `window_secs` that trip a lockout.
+    pub threshold: i64,
+    pub window_secs: i64,
+    /// First lockout; doubled per strike up to `max_lockout_secs`.
+    pub base_lockout_secs: i64,
+    pub max_lockout_secs: i64,
+}
+
+/// The tenant limit is a backstop against a tenant's real keys being replayed
+/// against other tenants; it locks out every caller of that tenant, so it is
+/// high and capped low.
+pub const POLICIES: [Policy; 3] = [
+    Policy { kind: "ip", threshold: 20, window_secs: 60, base_lockout_secs: 60, max_lockout_secs: 24 * 3600 },
+    Policy { kind: "secret", threshold: 10, window_secs: 60, base_lockout_secs: 60, max_lockout_secs: 24 * 3600 },
+    Policy { kind: "tenant", threshold: 200, window_secs: 60, base_lockout_secs: 30, max_lockout_secs: 15 * 60 },
+];
+
+pub fn policy(kind: &str) -> Option<&'static Policy> {
+    POLICIES.iter().find(|p| p.kind == kind)
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Subject {
+    pub kind: &'static str,
+    pub value: String,
+}
+
+impl Subject {
+    /// IPv4 callers by address, IPv6 callers by /64 (one host's allocation).
+    pub fn ip(raw: &str) -> Option<Self> {
+        let value = match parse_client_ip(raw)? {
+            IpAddr::V4(v4) => v4.to_string(),
+            IpAddr::V6(v6) => Ipv6Net::new(v6, 64).ok()?.trunc().to_string(),
+        };
+        Some(Subject { kind: "ip", value })
+    }
+
+    /// A presented secret, by digest: only the same wrong secret sent again
+    /// accumulates, never the real one.
+    pub fn secret(presented: &str) -> Self {
+        let digest = Sha256::digest(format!("auth_lockout:{}", presented).as_bytes());
+        Subject { kind: "secret", value: URL_SAFE_NO_PAD.encode(&digest[..16]) }
+    }
+
+    pub fn tenant(tenant_id: &str) -> Self {
+        Subject { kind: "tenant", value: tenant_id.to_string() }
+    }
+
+    fn policy(&self) -> &'static Policy {
+        policy(self.kind).expect("subjects are built with known kinds")
+    }
+}
+
+/// Strike count for a lockout tripped now, given the previous one.
+pub fn next_strikes(previous: Option<(i32, DateTime<Utc>)>, now: DateTime<Utc>) -> i32 {
+    match previous {
+        Some((strikes, last)) if now - last < Duration::seconds(STRIKE_DECAY_SECS) => strikes + 1,
+        _ => 1,
+    }
+}
+
+/// Lockout length for the given strike (1 = first lockout).
+pub fn lockout_secs(policy: &Policy, strikes: i32) -> i64 {
+    let doublings = (strikes.max(1) - 1).min(30) as u32;
+    policy.base_lockout_secs.saturating_mul(1i64 << doublings).min(policy.max_lockout_secs)
+}
+
+// ── In-memory mirror ──────────────────────────────────────────────────────────
+
+/// (kind, subject) → locked_until
+type LockMap = HashMap<(String, String), DateTime<Utc>>;
+
+static LOCKS: OnceLock<Mutex<LockMap>> = OnceLock::new();
+
+fn locks() -> &'static Mutex<LockMap> {
+    LOCKS.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+/// The latest-ending active lockout among `subjects`, if any.
+pub fn active_lockout(subjects: &[Subject]) -> Option<(Subject, DateTime<Utc>)> {
+    let now = Utc::now();
+    let map = locks().lock().unwrap_or_else(|e| e.into_inner());
+    subjects
+        .iter()
+        .filter_map(|s| {
+            map.get(&(s.kind.to_string(), s.value.clone()))
+                .filter(|until| **until > now)
+                .map(|until| (s.clone(), *until))
+        })
+        .max_by_key(|(_, until)| *until)
+}
+
+fn set_local(kind: &str, subject: &str, until: Option<DateTime<Utc>>) {
+    let mut map = locks().lock().unwrap_or_else(|e| e.into_inner());
+    let now = Utc::now();
+    map.retain(|_, t| *t > now);
+    match until {
+        Some(t) => map.insert((kind.to_string(), subject.to_string()), t),
+        None => map.remove(&(kind.to_string(), subject.to_string())),
+    };
+}
+
+/// Payloads: `lock:<kind>:<unix seconds>:<subject>` and `clear:<kind>:<subject>`.
+/// The subject goes last because IPv6 subjects contain ':'.
+pub fn handle_notification(payload: &str) {
+    let mut parts = payload.splitn(3, ':');
+    match (parts.next(), parts.next(), parts.next()) {
+        (Some("lock"), Some(kind), Some(rest)) => {
+            let until = rest
+                .split_once(':')
+                .and_then(|(ts, subject)| Some((DateTime::from_timestamp(ts.parse().ok()?, 0)?, subject)));
+            match until {
+                Some((until, subject)) => set_local(kind, subject, Some(until)),
+                None => app_log!(warn, payload = %payload, "Ignoring malformed auth lockout notification"),
+            }
+        }
+        (Some("clear"), Some(kind), Some(subject)) => set_local(kind, subject, None),
+        _ => app_log!(warn, payload = %payload, "Ignoring unknown auth lockout notification"),
+    }
+}
+
+/// Replace the mirror with the lockouts active in the database.
+pub async fn reload(client: &Client) -> Result<(), StoreError> {
+    let rows = client
+        .query(
+            "SELECT kind, subject, locked_until FROM auth_lockouts WHERE locked_until > NOW()",
+            &[],
+        )
+        .await
+        .to_store_error()?;
+    let mut map = locks().lock().unwrap_or_else(|e| e.into_inner());
+    map.clear();
+    for r in &rows {
+        map.insert((r.get(0), r.get(1)), r.get(2));
+    }
+    Ok(())
+}
+
+// ── Recording failures ────────────────────────────────────────────────────────
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Lockout {
+    pub kind: String,
+    pub subject: String,
+    pub strikes: i32,
+    pub locked_until: String,
+}
+
+/// Record one failed validation against each subject and trip any lockout
+/// whose threshold is reached. Returns the lockouts tripped by this call.
+pub async fn record_failure(store: &EndpointStore, subjects: &[Subject]) -> Result<Vec<Lockout>, StoreError> {
+    let ip_address = subjects.iter().find(|s| s.kind == "ip").map(|s| s.value.as_str());
+    let mut client = store.get_admin_conn().await?;
+    let mut tripped = Vec::new();
+
+    for subject in subjects {
+        let policy = subject.policy();
+        // The inserted row is not visible to the count in the same statement.
+        let failures: i64 = client
+            .query_one(
+                "WITH ins AS (INSERT INTO auth_failures (kind, subject) VALUES ($1, $2))
+                 SELECT COUNT(*) + 1 FROM auth_failures
+                 WHERE kind = $1 AND subject = $2
+                   AND created_at > NOW() - make_interval(secs => $3::BIGINT)",
+                &[&subject.kind, &subject.value, &policy.window_secs],
+            )
+            .await
+            .to_store_error()?
+            .get(0);
+        if failures < policy.threshold {
+            continue;
+        }
+
+        let tx = client.transaction().await.to_store_error()?;
+        let previous = tx
+            .query_opt(
+                "SELECT strikes, last_tripped_at, locked_until > NOW() FROM auth_lockouts
+                 WHERE kind = $1 AND subject = $2 FOR UPDATE",
+                &[&subject.kind, &subject.value],
+            )
+            .await
+            .to_store_error()?;
+        if previous.as_ref().is_some_and(|r| r.get::<_, bool>(2)) {
+            continue; // another replica tripped it first
+        }
+        let now = Utc::now();
+        let strikes = next_strikes(previous.map(|r| (r.get(0), r.get(1))), now);
+        let locked_until = now + Duration::seconds(lockout_secs(policy, strikes));
+        let won = tx
+            .execute(
+                "INSERT INTO auth_lockouts (kind, subject, strikes, locked_until, last_tripped_at)
+                 VALUES ($1, $2, $3, $4, $5)
+                 ON CONFLICT (kind, subject) DO UPDATE SET
+                     strikes = EXCLUDED.strikes,
+                     locked_until = EXCLUDED.locked_until,
+                     last_tripped_at = EXCLUDED.last_tripped_at
+                 WHERE auth_lockouts.locked_until <= NOW()",
+                &[&subject.kind, &subject.value, &strikes, &locked_until, &now],
+            )
+            .await
+            .to_store_error()?;
+        if won == 0 {
+            continue;
+        }
+        tx.execute(
+            "DELETE FROM auth_failures WHERE kind = $1 AND subject = $2",
+            &[&subject.kind, &subject.value],
+        )
+        .await
+        .to_store_error()?;
+        tx.execute(
+            "SELECT pg_notify($1, $2)",
+            &[&CHANNEL, &format!("lock:{}:{}:{}", subject.kind, locked_until.timestamp(), subject.value)],
+        )
+        .await
+        .to_store_error()?;
+        tx.commit().await.to_store_error()?;
+        set_local(subject.kind, &subject.value, Some(locked_until));
+
+        app_log!(warn, kind = subject.kind, subject = %subject.value, strikes = strikes,
+            locked_until = %locked_until, "Key validation lockout tripped");
+        let tenant_id = (subject.kind == "tenant").then_some(subject.value.as_str());
+        record_security_event(
+            store,
+            tenant_id,
+            None,
+            AUTH_LOCKOUT,
+            ip_address,
+            serde_json::json!({
+                "kind": subject.kind,
+                "subject": subject.value,
+                "failures": failures,
+                "window_secs": policy.window_secs,
+                "strikes": strikes,
+                "locked_until": locked_until.to_rfc3339(),
+            }),
+        )
+        .await;
+        tripped.push(Lockout {
+            kind: subject.kind.to_string(),
+            subject: subject.value.clone(),
+            strikes,
+            locked_until: locked_until.to_rfc3339(),
+        });
+    }
+    Ok(tripped)
+}
+
+// ── Admin ─────────────────────────────────────────────────────────────────────
+
+#[derive(Debug, Clone, Serialize)]
+pub struct LockoutInfo {
+    pub kind: String,
+    pub subject: String,
+    pub strikes: i32,
+    pub locked_until: String,
+    pub last_tripped_at: String,
+    pub active: bool,
+}
+
+/// Lockouts, latest first. Inactive rows are kept until their strikes decay.
+pub async fn list_lockouts(
+    store: &EndpointStore,
+    kind: Option<&str>,
+    active_only: bool,
+) -> Result<Vec<LockoutInfo>, StoreError> {
+    let client = store.get_admin_conn().await?;
+    let rows = client
+        .query(
+            "SELECT kind, subject, strikes, locked_until, last_tripped_at, locked_until > NOW()
+             FROM auth_lockouts
+             WHERE ($1::VARCHAR IS NULL OR kind = $1)
+               AND (NOT $2 OR locked_until > NOW())
+             ORDER BY last_tripped_at DESC
+             LIMIT 500",
+            &[&kind, &active_only],
+        )
+        .await
+        .to_store_error()?;
+    Ok(rows
+        .iter()
+        .map(|r| LockoutInfo {
+            kind: r.get(0),
+            subject: r.get(1),
+            strikes: r.get(2),
+            locked_until: r.get::<_, DateTime<Utc>>(3).to_rfc3339(),
+            last_tripped_at: r.get::<_, DateTime<Utc>>(4).to_rfc3339(),
+            active: r.get(5),
+        })
+        .collect())
+}
+
+/// Lift a lockout and forget its strikes and recent failures on every
+/// replica. Returns false when there was nothing to clear.
+pub async fn clear_lockout(store: &EndpointStore, kind: &str, subject: &str) -> Result<bool, StoreError> {
+    let mut client = store.get_admin_conn().await?;
+    let tx = client.transaction().await.to_store_error()?;
+    let n = tx
+        .execute("DELETE FROM auth_lockouts WHERE kind = $1 AND subject = $2", &[&kind, &subject])
+        .await
+        .to_store_error()?;
+    tx.execute("DELETE FROM auth_failures WHERE kind = $1 AND subject = $2", &[&kind, &subject])
+        .await
+        .to_store_error()?;
+    tx.execute("SELECT pg_notify($1, $2)", &[&CHANNEL, &format!("clear:{}:{}", kind, subject)])
+        .await
+        .to_store_error()?;
+    tx.commit().await.to_store_error()?;
+    set_local(kind, subject, None);
+    Ok(n > 0)
+}
+
+/// Drop failures past every window and lockouts whose strikes have decayed.
+pub async fn prune(store: &EndpointStore) -> Result<(u64, u64), StoreError> {
+    let client = store.get_admin_conn().await?;
+    let failures = client
+        .execute(
+            "DELETE FROM auth_failures WHERE created_at < NOW() - make_interval(secs => $1::BIGINT)",
+            &[&FAILURE_RETENTION_SECS],
+        )
+        .await
+        .to_store_error()?;
+    let lockouts = client
+        .execute(
+            "DELETE FROM auth_lockouts
+             WHERE locked_until < NOW() AND last_tripped_at < NOW() - make_interval(secs => $1::BIGINT)",
+            &[&STRIKE_DECAY_SECS],
+        )
+        .await
+        .to_store_error()?;
+    Ok((failures, lockouts))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn escalation() {
+        let ip = policy("ip").unwrap();
+        assert_eq!(lockout_secs(ip, 1), 60);
+        assert_eq!(lockout_secs(ip, 2), 120);
+        assert_eq!(lockout_secs(ip, 5), 960);
+        assert_eq!(lockout_secs(ip, 40), ip.max_lockout_secs);
+        assert_eq!(lockout_secs(policy("tenant").unwrap(), 10), 15 * 60);
+
+        let now = Utc::now();
+        assert_eq!(next_strikes(None, now), 1);
+        assert_eq!(next_strikes(Some((3, now - Duration::hours(1))), now), 4);
+        assert_eq!(next_strikes(Some((3, now - Duration::days(2))), now), 1);
+    }
+
+    #[test]
+    fn ip_subjects() {
+        assert_eq!(Subject::ip("203.0.113.7").unwrap().value, "203.0.113.7");
+        assert_eq!(Subject::ip("::ffff:203.0.113.7").unwrap().value, "203.0.113.7");
+        assert_eq!(Subject::ip("2001:db8:1:2:3:4:5:6").unwrap().value, "2001:db8:1:2::/64");
+        assert!(Subject::ip("nope").is_none());
+    }
+
+    #[test]
+    fn secret_subjects_do_not_share_a_prefix() {
+        let real = Subject::secret("sk_v2_abcdefREALSECRET");
+        assert_eq!(real, Subject::secret("sk_v2_abcdefREALSECRET"));
+        assert_ne!(real, Subject::secret("sk_v2_abcdefGUESS"));
+        assert!(!real.value.contains("abcdef"));
+    }
+
+    #[test]
+    fn mirror_follows_notifications() {
+        let subject = Subject::ip("2001:db8:9::1").unwrap();
+        let until = Utc::now() + Duration::minutes(5);
+        handle_notification(&format!("lock:ip:{}:{}", until.timestamp(), subject.value));
+        let (hit, at) = active_lockout(&[Subject::secret("sk_v1_zzzzzz"), subject.clone()]).unwrap();
+        assert_eq!(hit, subject);
+        assert_eq!(at.timestamp(), until.timestamp());
+
+        handle_notification(&format!("clear:ip:{}", subject.value));
+        assert!(active_lockout(&[subject]).is_none());
+    }
+}
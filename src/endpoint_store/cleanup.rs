@@ -1,4 +1,5 @@
 use crate::endpoint_store::db_helpers::ResultExt;
+use crate::endpoint_store::key_cache::{self, Invalidation};
 use crate::endpoint_store::EndpointStore;
 use crate::endpoint_store::StoreError;
 
@@ -155,6 +156,8 @@ pub async fn force_clean_user_data(store: &EndpointStore, email: &str) -> Result
     tx.execute("DELETE FROM user_preferences WHERE email = $1", &[&email]).await.to_store_error()?;
 
     tx.commit().await.to_store_error()?;
+    // Deleted keys may be cached on any instance; this is rare enough to drop everything.
+    key_cache::notify_invalidation(&client, Invalidation::All).await?;
     Ok(())
 }
 
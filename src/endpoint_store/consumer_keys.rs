@@ -0,0 +1,334 @@
+// src/endpoint_store/consumer_keys.rs
+//
+// Provider-side governance of consumer keys: api_keys rows whose
+// provider_tenant_id is the provider's tenant (the row itself belongs to the
+// consumer's tenant, so these queries filter on provider_tenant_id with an
+// admin connection rather than relying on RLS).
+//
+//   - suspend / resume   suspended_at; a suspended key fails validation with
+//                        its own reason and can be resumed later
+//   - revoke             is_active = false, as for the owner's own revoke
+//   - limits             provider_* caps on top of the key's own limits;
+//                        the consumer cannot raise them (key_quotas.rs)
+//
+// Every change targets one key or all of one consumer's keys (Target), and
+// invalidates the validation cache for the keys it touched.
+
+use crate::endpoint_store::db_helpers::ResultExt;
+use crate::endpoint_store::key_cache::{self, Invalidation};
+use crate::endpoint_store::key_quotas::QuotaLimits;
+use crate::endpoint_store::{EndpointStore, StoreError};
+use chrono::{DateTime, Duration, Utc};
+use serde::Serialize;
+use tokio_postgres::Client;
+
+/// Above this many keys one change clears the whole validation cache
+/// instead of notifying key by key.
+const MAX_KEY_INVALIDATIONS: usize = 50;
+
+/// Which of a provider's consumer keys a change applies to.
+#[derive(Debug, Clone, Copy)]
+pub enum Target<'a> {
+    Key(&'a str),
+    /// Every key of this consumer email (lowercase).
+    Consumer(&'a str),
+}
+
+impl<'a> Target<'a> {
+    /// ($key_id, $email) for `($n::VARCHAR IS NULL OR id = $n)` filters.
+    fn params(self) -> (Option<&'a str>, Option<&'a str>) {
+        match self {
+            Target::Key(id) => (Some(id), None),
+            Target::Consumer(email) => (None, Some(email)),
+        }
+    }
+}
+
+/// Filter on the key's state; None lists everything except revoked keys.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StatusFilter {
+    Active,
+    Suspended,
+    Expired,
+    Revoked,
+    All,
+}
+
+impl StatusFilter {
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "active" => Some(StatusFilter::Active),
+            "suspended" => Some(StatusFilter::Suspended),
+            "expired" => Some(StatusFilter::Expired),
+            "revoked" => Some(StatusFilter::Revoked),
+            "all" => Some(StatusFilter::All),
+            _ => None,
+        }
+    }
+
+    fn as_str(self) -> &'static str {
+        match self {
+            StatusFilter::Active => "active",
+            StatusFilter::Suspended => "suspended",
+            StatusFilter::Expired => "expired",
+            StatusFilter::Revoked => "revoked",
+            StatusFilter::All => "all",
+        }
+    }
+}
+
+/// SQL for a key's status; revoked wins over suspended wins over expired.
+const STATUS_SQL: &str = "CASE WHEN NOT k.is_active THEN 'revoked'
+                               WHEN k.suspended_at IS NOT NULL THEN 'suspended'
+                               WHEN k.expires_at IS NOT NULL AND k.expires_at <= NOW() THEN 'expired'
+                               ELSE 'active' END";
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ConsumerKey {
+    pub key_id: String,
+    pub key_name: String,
+    pub key_prefix: String,
+    pub consumer_email: String,
+    pub consumer_tenant_id: Option<String>,
+    /// "active", "suspended", "expired" or "revoked".
+    pub status: String,
+    pub generated_at: String,
+    pub last_used: Option<String>,
+    pub usage_count: i64,
+    pub expires_at: Option<String>,
+    pub suspended_at: Option<String>,
+    /// Caps set by the provider; None = no cap.
+    pub provider_limits: QuotaLimits,
+}
+
+fn rfc3339(t: Option<DateTime<Utc>>) -> Option<String> {
+    t.map(|t| t.to_rfc3339())
+}
+
+pub async fn list_consumer_keys(
+    store: &EndpointStore,
+    provider_tenant_id: &str,
+    status: Option<StatusFilter>,
+    consumer_email: Option<&str>,
+) -> Result<Vec<ConsumerKey>, StoreError> {
+    let client = store.get_admin_conn().await?;
+    let status = status.map(StatusFilter::as_str);
+    let rows = client
+        .query(
+            &format!(
+                "SELECT * FROM (
+                    SELECT k.id, k.key_name, k.key_prefix, k.email, k.tenant_id, {STATUS_SQL} AS status,
+                           k.generated_at, k.last_used, k.usage_count, k.expires_at, k.suspended_at,
+                           k.provider_rate_limit_rpm, k.provider_daily_call_limit, k.provider_daily_credit_limit
+                    FROM api_keys k
+                    WHERE k.provider_tenant_id = $1 AND ($2::VARCHAR IS NULL OR k.email = $2)
+                 ) s
+                 WHERE CASE COALESCE($3::VARCHAR, '') WHEN '' THEN s.status <> 'revoked'
+                                                      WHEN 'all' THEN true
+                                                      ELSE s.status = $3 END
+                 ORDER BY s.email, s.generated_at DESC"
+            ),
+            &[&provider_tenant_id, &consumer_email, &status],
+        )
+        .await
+        .to_store_error()?;
+
+    Ok(rows
+        .iter()
+        .map(|r| ConsumerKey {
+            key_id: r.get(0),
+            key_name: r.get(1),
+            key_prefix: r.get(2),
+            consumer_email: r.get(3),
+            consumer_tenant_id: r.get(4),
+            status: r.get(5),
+            generated_at: r.get::<_, DateTime<Utc>>(6).to_rfc3339(),
+            last_used: rfc3339(r.get(7)),
+            usage_count: r.get(8),
+            expires_at: rfc3339(r.get(9)),
+            suspended_at: rfc3339(r.get(10)),
+            provider_limits: QuotaLimits {
+                requests_per_minute: r.get(11),
+                calls_per_day: r.get(12),
+                credits_per_day: r.get(13),
+            },
+        })
+        .collect())
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ConsumerUsage {
+    pub consumer_email: String,
+    pub active_keys: i64,
+    pub calls: i64,
+    pub total_tokens: i64,
+    pub last_call_at: Option<String>,
+}
+
+/// Calls and tokens per consumer over the last `days` days, busiest first.
+/// Consumers without calls in the window are included with zeros.
+pub async fn consumer_usage(
+    store: &EndpointStore,
+    provider_tenant_id: &str,
+    days: i64,
+    consumer_email: Option<&str>,
+) -> Result<Vec<ConsumerUsage>, StoreError> {
+    let client = store.get_admin_conn().await?;
+    let since = Utc::now() - Duration::days(days);
+    let rows = client
+        .query(
+            "SELECT k.email,
+                    COUNT(DISTINCT k.id) FILTER (WHERE k.is_active),
+                    COUNT(l.id),
+                    COALESCE(SUM(l.total_tokens), 0)::BIGINT,
+                    MAX(l.timestamp)
+             FROM api_keys k
+             LEFT JOIN api_usage_logs l ON l.key_id = k.id AND l.timestamp >= $2
+             WHERE k.provider_tenant_id = $1 AND ($3::VARCHAR IS NULL OR k.email = $3)
+             GROUP BY k.email
+             ORDER BY 3 DESC, k.email",
+            &[&provider_tenant_id, &since, &consumer_email],
+        )
+        .await
+        .to_store_error()?;
+
+    Ok(rows
+        .iter()
+        .map(|r| ConsumerUsage {
+            consumer_email: r.get(0),
+            active_keys: r.get(1),
+            calls: r.get(2),
+            total_tokens: r.get(3),
+            last_call_at: rfc3339(r.get(4)),
+        })
+        .collect())
+}
+
+async fn invalidate_keys(client: &Client, key_ids: &[String]) -> Result<(), StoreError> {
+    if key_ids.len() > MAX_KEY_INVALIDATIONS {
+        return key_cache::notify_invalidation(client, Invalidation::All).await;
+    }
+    for id in key_ids {
+        key_cache::notify_invalidation(client, Invalidation::Key(id.clone())).await?;
+    }
+    Ok(())
+}
+
+/// Suspend (or resume) active keys. Returns the ids that changed state.
+pub async fn set_suspended(
+    store: &EndpointStore,
+    provider_tenant_id: &str,
+    target: Target<'_>,
+    suspended: bool,
+) -> Result<Vec<String>, StoreError> {
+    let (key_id, email) = target.params();
+    let client = store.get_admin_conn().await?;
+    let rows = client
+        .query(
+            "UPDATE api_keys SET suspended_at = CASE WHEN $4 THEN NOW() END
+             WHERE provider_tenant_id = $1 AND is_active = true
+               AND ($2::VARCHAR IS NULL OR id = $2) AND ($3::VARCHAR IS NULL OR email = $3)
+               AND (suspended_at IS NULL) = $4
+             RETURNING id",
+            &[&provider_tenant_id, &key_id, &email, &suspended],
+        )
+        .await
+        .to_store_error()?;
+    let ids: Vec<String> = rows.iter().map(|r| r.get(0)).collect();
+    invalidate_keys(&client, &ids).await?;
+    Ok(ids)
+}
+
+/// Revoke active keys. Returns the ids revoked.
+pub async fn revoke_consumer_keys(
+    store: &EndpointStore,
+    provider_tenant_id: &str,
+    target: Target<'_>,
+) -> Result<Vec<String>, StoreError> {
+    let (key_id, email) = target.params();
+    let client = store.get_admin_conn().await?;
+    let rows = client
+        .query(
+            "UPDATE api_keys SET is_active = false
+             WHERE provider_tenant_id = $1 AND is_active = true
+               AND ($2::VARCHAR IS NULL OR id = $2) AND ($3::VARCHAR IS NULL OR email = $3)
+             RETURNING id",
+            &[&provider_tenant_id, &key_id, &email],
+        )
+        .await
+        .to_store_error()?;
+    let ids: Vec<String> = rows.iter().map(|r| r.get(0)).collect();
+    invalidate_keys(&client, &ids).await?;
+    Ok(ids)
+}
+
+/// Set the provider's caps (None = no cap) on active keys. Returns how many
+/// keys were updated.
+pub async fn set_provider_limits(
+    store: &EndpointStore,
+    provider_tenant_id: &str,
+    target: Target<'_>,
+    limits: &QuotaLimits,
+) -> Result<u64, StoreError> {
+    limits.check().map_err(StoreError::Invalid)?;
+    let (key_id, email) = target.params();
+    let client = store.get_admin_conn().await?;
+    client
+        .execute(
+            "UPDATE api_keys SET provider_rate_limit_rpm = $4, provider_daily_call_limit = $5,
+                                 provider_daily_credit_limit = $6
+             WHERE provider_tenant_id = $1 AND is_active = true
+               AND ($2::VARCHAR IS NULL OR id = $2) AND ($3::VARCHAR IS NULL OR email = $3)",
+            &[
+                &provider_tenant_id,
+                &key_id,
+                &email,
+                &limits.requests_per_minute,
+                &limits.calls_per_day,
+                &limits.credits_per_day,
+            ],
+        )
+        .await
+        .to_store_error()
+}
+
+/// A consumer revoking one of their own consumer keys. Returns false when
+/// no such active key exists for this email.
+pub async fn revoke_own_consumer_key(
+    store: &EndpointStore,
+    consumer_email: &str,
+    key_id: &str,
+) -> Result<bool, StoreError> {
+    let client = store.get_admin_conn().await?;
+    let n = client
+        .execute(
+            "UPDATE api_keys SET is_active = false
+             WHERE id = $1 AND email = $2 AND provider_tenant_id IS NOT NULL AND is_active = true",
+            &[&key_id, &consumer_email],
+        )
+        .await
+        .to_store_error()?;
+    if n > 0 {
+        key_cache::notify_invalidation(&client, Invalidation::Key(key_id.to_string())).await?;
+    }
+    Ok(n > 0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn status_filter_round_trip() {
+        for s in ["active", "suspended", "expired", "revoked", "all"] {
+            assert_eq!(StatusFilter::parse(s).map(StatusFilter::as_str), Some(s));
+        }
+        assert_eq!(StatusFilter::parse("deleted"), None);
+    }
+
+    #[test]
+    fn target_params() {
+        assert_eq!(Target::Key("k1").params(), (Some("k1"), None));
+        assert_eq!(Target::Consumer("a@b.c").params(), (None, Some("a@b.c")));
+    }
+}
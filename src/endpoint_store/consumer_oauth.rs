@@ -0,0 +1,729 @@
+// src/endpoint_store/consumer_oauth.rs
+//
+// Delegated per-end-user OAuth tokens for consumer keys.
+//
+// A provider tenant whose downstream auth mode is oauth2_authorization_code
+// lets each of its consumers connect their own account at the backend:
+//
+//   start    — store a single-use state + PKCE verifier, return the
+//              authorization URL the end-user is sent to
+//   complete — exchange the returned code for access/refresh tokens and store
+//              them against the consumer key (or the provider's consumer_id)
+//   refresh  — refresh_token grant; also done automatically when the gateway
+//              asks for a token that is about to expire
+//
+// The gateway resolves the end-user's token through downstream_access_token
+// by passing the consumer subject along with the tenant / tool.
+//
+// Tokens and PKCE verifiers are envelope-encrypted (secret_crypto.rs).
+
+use crate::app_log;
+use crate::endpoint_store::db_helpers::ResultExt;
+use crate::endpoint_store::downstream_auth_management::TenantDownstreamAuth;
+use crate::endpoint_store::downstream_tokens::{request_token, DownstreamTokenError, IssuedToken};
+use crate::endpoint_store::secret_crypto::SecretSlot;
+use crate::endpoint_store::{EndpointStore, StoreError};
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine as _};
+use chrono::{DateTime, Utc};
+use rand::{rng, RngCore};
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+
+/// How long an authorization request stays valid.
+const STATE_TTL_MINUTES: i64 = 10;
+/// Refresh stored tokens this long before they expire.
+const REFRESH_MARGIN_SECS: i64 = 60;
+
+pub const AUTHORIZATION_CODE_MODE: &str = "oauth2_authorization_code";
+
+/// Who a delegated token belongs to, within one provider tenant.
+#[derive(Debug, Clone)]
+pub enum ConsumerSubject {
+    /// A consumer key (api_keys.id with provider_tenant_id set).
+    Key(String),
+    /// The provider's opaque end-user id (X-Consumer-Id).
+    Consumer(String),
+}
+
+impl ConsumerSubject {
+    /// From the `key_id` / `consumer_id` pair used by the HTTP API; exactly
+    /// one must be set.
+    pub fn from_parts(key_id: Option<&str>, consumer_id: Option<&str>) -> Result<Self, String> {
+        let key_id = key_id.map(str::trim).filter(|s| !s.is_empty());
+        let consumer_id = consumer_id.map(str::trim).filter(|s| !s.is_empty());
+        match (key_id, consumer_id) {
+            (Some(k), None) => Ok(ConsumerSubject::Key(k.to_string())),
+            (None, Some(c)) => Ok(ConsumerSubject::Consumer(c.to_string())),
+            _ => Err("exactly one of key_id or consumer_id is required".to_string()),
+        }
+    }
+
+    fn kind(&self) -> &'static str {
+        match self {
+            ConsumerSubject::Key(_) => "key",
+            ConsumerSubject::Consumer(_) => "consumer",
+        }
+    }
+
+    fn id(&self) -> &str {
+        match self {
+            ConsumerSubject::Key(id) | ConsumerSubject::Consumer(id) => id,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ConsumerOAuthStart {
+    pub authorization_url: String,
+    pub state: String,
+    pub expires_at: String,
+}
+
+/// Stored connection, without the token values.
+#[derive(Debug, Clone, Serialize)]
+pub struct ConsumerOAuthConnection {
+    pub provider_tenant_id: String,
+    pub subject_kind: String,
+    pub subject: String,
+    pub scopes: Option<String>,
+    pub expires_at: Option<String>,
+    pub has_refresh_token: bool,
+    pub updated_at: String,
+}
+
+struct StoredToken {
+    access_token: String,
+    refresh_token: Option<String>,
+    token_type: String,
+    scopes: Option<String>,
+    expires_at: Option<DateTime<Utc>>,
+}
+
+/// Begin the authorization-code flow for one end-user of `provider_tenant_id`.
+/// `tool_name` selects a per-tool / per-group OAuth app when overrides exist.
+pub async fn start_consumer_oauth(
+    store: &EndpointStore,
+    provider_tenant_id: &str,
+    subject: &ConsumerSubject,
+    redirect_uri: &str,
+    tool_name: Option<&str>,
+) -> Result<ConsumerOAuthStart, DownstreamTokenError> {
+    let auth = authorization_code_config(store, provider_tenant_id, tool_name).await?;
+    let authorize_url = auth
+        .oauth_authorize_url
+        .as_deref()
+        .ok_or_else(|| DownstreamTokenError::NotConfigured("missing oauth_authorize_url".to_string()))?;
+    let client_id = auth
+        .oauth_client_id
+        .as_deref()
+        .ok_or_else(|| DownstreamTokenError::NotConfigured("missing oauth_client_id".to_string()))?;
+
+    ensure_subject_usable(store, provider_tenant_id, subject).await?;
+
+    let state = random_token();
+    let code_verifier = random_token();
+    let code_challenge = URL_SAFE_NO_PAD.encode(Sha256::digest(code_verifier.as_bytes()));
+    let expires_at = Utc::now() + chrono::Duration::minutes(STATE_TTL_MINUTES);
+    let sealed_verifier = store.secrets().encrypt(verifier_slot(provider_tenant_id), &code_verifier)?;
+
+    let mut params: Vec<(&str, &str)> = vec![
+        ("response_type", "code"),
+        ("client_id", client_id),
+        ("redirect_uri", redirect_uri),
+        ("state", &state),
+        ("code_challenge", &code_challenge),
+        ("code_challenge_method", "S256"),
+    ];
+    if let Some(scopes) = auth.oauth_scopes.as_deref().filter(|s| !s.is_empty()) {
+        params.push(("scope", scopes));
+    }
+    let authorization_url = reqwest::Url::parse_with_params(authorize_url, &params)
+        .map_err(|e| DownstreamTokenError::NotConfigured(format!("invalid oauth_authorize_url: {}", e)))?
+        .to_string();
+
+    let client = store.get_conn(Some(provider_tenant_id)).await?;
+    client
+        .execute(
+            "DELETE FROM consumer_oauth_states WHERE expires_at < NOW()",
+            &[],
+        )
+        .await
+        .to_store_error()?;
+    client
+        .execute(
+            "INSERT INTO consumer_oauth_states
+                (state, provider_tenant_id, subject_kind, subject, tool_name,
+                 redirect_uri, code_verifier, expires_at)
+             VALUES ($1, $2, $3, $4, $5, $6, $7, $8)",
+            &[
+                &state as &(dyn tokio_postgres::types::ToSql + Sync),
+                &provider_tenant_id as &(dyn tokio_postgres::types::ToSql + Sync),
+                &subject.kind() as &(dyn tokio_postgres::types::ToSql + Sync),
+                &subject.id() as &(dyn tokio_postgres::types::ToSql + Sync),
+                &tool_name as &(dyn tokio_postgres::types::ToSql + Sync),
+                &redirect_uri as &(dyn tokio_postgres::types::ToSql + Sync),
+                &sealed_verifier as &(dyn tokio_postgres::types::ToSql + Sync),
+                &expires_at as &(dyn tokio_postgres::types::ToSql + Sync),
+            ],
+        )
+        .await
+        .to_store_error()?;
+
+    app_log!(info, provider_tenant_id = %provider_tenant_id, subject_kind = subject.kind(),
+        "Started consumer OAuth authorization");
+
+    Ok(ConsumerOAuthStart {
+        authorization_url,
+        state,
+        expires_at: expires_at.to_rfc3339(),
+    })
+}
+
+/// Finish the flow: consume the state, exchange the code, store the tokens.
+pub async fn complete_consumer_oauth(
+    store: &EndpointStore,
+    state: &str,
+    code: &str,
+) -> Result<ConsumerOAuthConnection, DownstreamTokenError> {
+    let client = store.get_admin_conn().await?;
+    let row = client
+        .query_opt(
+            "DELETE FROM consumer_oauth_states
+             WHERE state = $1 AND expires_at > NOW()
+             RETURNING provider_tenant_id, subject_kind, subject, tool_name,
+                       redirect_uri, code_verifier",
+            &[&state],
+        )
+        .await
+        .to_store_error()?
+        .ok_or(DownstreamTokenError::InvalidState)?;
+    drop(client);
+
+    let provider_tenant_id: String = row.get(0);
+    let subject = match row.get::<_, String>(1).as_str() {
+        "key" => ConsumerSubject::Key(row.get(2)),
+        _ => ConsumerSubject::Consumer(row.get(2)),
+    };
+    let tool_name: Option<String> = row.get(3);
+    let redirect_uri: String = row.get(4);
+    let code_verifier = store.secrets().decrypt(verifier_slot(&provider_tenant_id), row.get(5))?;
+
+    let auth = authorization_code_config(store, &provider_tenant_id, tool_name.as_deref()).await?;
+    let (token_url, client_id) = token_endpoint(&auth)?;
+
+    let mut form: Vec<(&str, &str)> = vec![
+        ("grant_type", "authorization_code"),
+        ("code", code),
+        ("redirect_uri", &redirect_uri),
+        ("client_id", client_id),
+        ("code_verifier", &code_verifier),
+    ];
+    if let Some(secret) = auth.oauth_client_secret.as_deref().filter(|s| !s.is_empty()) {
+        form.push(("client_secret", secret));
+    }
+
+    let grant = request_token(token_url, &form).await?;
+    let connection = save_token(
+        store,
+        &provider_tenant_id,
+        &subject,
+        &grant.token,
+        grant.refresh_token.as_deref(),
+        grant.scope.as_deref().or(auth.oauth_scopes.as_deref()),
+    )
+    .await?;
+
+    app_log!(info, provider_tenant_id = %provider_tenant_id, subject_kind = subject.kind(),
+        "Completed consumer OAuth authorization");
+
+    Ok(connection)
+}
+
+/// refresh_token grant for a stored connection.
+pub async fn refresh_consumer_oauth(
+    store: &EndpointStore,
+    provider_tenant_id: &str,
+    subject: &ConsumerSubject,
+    tool_name: Option<&str>,
+) -> Result<ConsumerOAuthConnection, DownstreamTokenError> {
+    let auth = authorization_code_config(store, provider_tenant_id, tool_name).await?;
+    ensure_subject_usable(store, provider_tenant_id, subject).await?;
+    let stored = load_token(store, provider_tenant_id, subject)
+        .await?
+        .ok_or_else(|| DownstreamTokenError::ConsentRequired("no stored token".to_string()))?;
+    refresh_stored(store, provider_tenant_id, subject, &auth, stored).await?;
+    get_consumer_oauth_connection(store, provider_tenant_id, subject)
+        .await?
+        .ok_or_else(|| DownstreamTokenError::ConsentRequired("no stored token".to_string()))
+}
+
+/// The end-user's current access token, refreshed first when it is about to
+/// expire or `force_refresh` is set. `auth` is the resolved provider config.
+/// A consumer key that has since been revoked, suspended or has expired gets
+/// nothing, though its tokens stay stored until disconnected.
+pub async fn consumer_access_token(
+    store: &EndpointStore,
+    provider_tenant_id: &str,
+    subject: &ConsumerSubject,
+    auth: &TenantDownstreamAuth,
+    force_refresh: bool,
+) -> Result<IssuedToken, DownstreamTokenError> {
+    ensure_subject_usable(store, provider_tenant_id, subject).await?;
+    let stored = load_token(store, provider_tenant_id, subject)
+        .await?
+        .ok_or_else(|| DownstreamTokenError::ConsentRequired("end-user has not connected".to_string()))?;
+
+    let stale = stored
+        .expires_at
+        .map(|exp| exp - chrono::Duration::seconds(REFRESH_MARGIN_SECS) <= Utc::now())
+        .unwrap_or(false);
+    if !stale && !force_refresh {
+        return Ok(IssuedToken {
+            access_token: stored.access_token,
+            token_type: stored.token_type,
+            expires_at: stored.expires_at,
+        });
+    }
+
+    refresh_stored(store, provider_tenant_id, subject, auth, stored).await
+}
+
+pub async fn get_consumer_oauth_connection(
+    store: &EndpointStore,
+    provider_tenant_id: &str,
+    subject: &ConsumerSubject,
+) -> Result<Option<ConsumerOAuthConnection>, StoreError> {
+    let client = store.get_conn(Some(provider_tenant_id)).await?;
+    let row = client
+        .query_opt(
+            "SELECT provider_tenant_id, subject_kind, subject, scopes, expires_at,
+                    refresh_token IS NOT NULL, updated_at
+             FROM consumer_oauth_tokens
+             WHERE provider_tenant_id = $1 AND subject_kind = $2 AND subject = $3",
+            &[&provider_tenant_id, &subject.kind(), &subject.id()],
+        )
+        .await
+        .to_store_error()?;
+    Ok(row.map(|r| row_to_connection(&r)))
+}
+
+/// Disconnect: forget the end-user's tokens.
+pub async fn delete_consumer_oauth(
+    store: &EndpointStore,
+    provider_tenant_id: &str,
+    subject: &ConsumerSubject,
+) -> Result<bool, StoreError> {
+    let client = store.get_conn(Some(provider_tenant_id)).await?;
+    let n = client
+        .execute(
+            "DELETE FROM consumer_oauth_tokens
+             WHERE provider_tenant_id = $1 AND subject_kind = $2 AND subject = $3",
+            &[&provider_tenant_id, &subject.kind(), &subject.id()],
+        )
+        .await
+        .to_store_error()?;
+    Ok(n > 0)
+}
+
+// ── helpers ───────────────────────────────────────────────────────────────────
+
+async fn authorization_code_config(
+    store: &EndpointStore,
+    provider_tenant_id: &str,
+    tool_name: Option<&str>,
+) -> Result<TenantDownstreamAuth, DownstreamTokenError> {
+    let resolved = store.resolve_downstream_auth(provider_tenant_id, tool_name).await?;
+    match resolved.auth {
+        Some(auth) if auth.auth_mode == AUTHORIZATION_CODE_MODE => Ok(auth),
+        Some(auth) => Err(DownstreamTokenError::NotConfigured(format!(
+            "auth_mode is {}, not {}",
+            auth.auth_mode, AUTHORIZATION_CODE_MODE
+        ))),
+        None => Err(DownstreamTokenError::NotConfigured("auth_mode is none".to_string())),
+    }
+}
+
+fn token_endpoint(auth: &TenantDownstreamAuth) -> Result<(&str, &str), DownstreamTokenError> {
+    match (auth.oauth_token_url.as_deref(), auth.oauth_client_id.as_deref()) {
+        (Some(url), Some(id)) => Ok((url, id)),
+        _ => Err(DownstreamTokenError::NotConfigured(
+            "missing oauth_token_url or oauth_client_id".to_string(),
+        )),
+    }
+}
+
+/// A key subject must be an active, unsuspended and unexpired consumer key
+/// issued for this provider. Consumer ids are the provider's own business.
+async fn ensure_subject_usable(
+    store: &EndpointStore,
+    provider_tenant_id: &str,
+    subject: &ConsumerSubject,
+) -> Result<(), DownstreamTokenError> {
+    let ConsumerSubject::Key(key_id) = subject else {
+        return Ok(());
+    };
+    let client = store.get_admin_conn().await?;
+    let found = client
+        .query_opt(
+            "SELECT 1 FROM api_keys
+             WHERE id = $1 AND provider_tenant_id = $2 AND is_active = true
+               AND suspended_at IS NULL AND (expires_at IS NULL OR expires_at > NOW())",
+            &[&key_id, &provider_tenant_id],
+        )
+        .await
+        .to_store_error()?;
+    if found.is_none() {
+        return Err(StoreError::NotFound(format!(
+            "Consumer key {} for provider {}",
+            key_id, provider_tenant_id
+        ))
+        .into());
+    }
+    Ok(())
+}
+
+async fn refresh_stored(
+    store: &EndpointStore,
+    provider_tenant_id: &str,
+    subject: &ConsumerSubject,
+    auth: &TenantDownstreamAuth,
+    stored: StoredToken,
+) -> Result<IssuedToken, DownstreamTokenError> {
+    let refresh_token = stored.refresh_token.ok_or_else(|| {
+        DownstreamTokenError::ConsentRequired("token expired and no refresh_token is stored".to_string())
+    })?;
+    let (token_url, client_id) = token_endpoint(auth)?;
+
+    let mut form: Vec<(&str, &str)> = vec![
+        ("grant_type", "refresh_token"),
+        ("refresh_token", &refresh_token),
+        ("client_id", client_id),
+    ];
+    if let Some(secret) = auth.oauth_client_secret.as_deref().filter(|s| !s.is_empty()) {
+        form.push(("client_secret", secret));
+    }
+
+    let grant = match request_token(token_url, &form).await {
+        Ok(g) => g,
+        // invalid_grant & co: the end-user revoked access or the refresh token
+        // expired — only a new consent helps.
+        Err(DownstreamTokenError::Rejected { status: 400 | 401, body }) => {
+            app_log!(warn, provider_tenant_id = %provider_tenant_id, subject_kind = subject.kind(),
+                "Consumer refresh token rejected");
+            return Err(DownstreamTokenError::ConsentRequired(format!("refresh rejected: {}", body)));
+        }
+        Err(e) => return Err(e),
+    };
+
+    // Providers that do not rotate refresh tokens omit it, and most omit the
+    // scope on refresh — keep what was granted before.
+    let next_refresh = grant.refresh_token.as_deref().unwrap_or(&refresh_token);
+    save_token(
+        store,
+        provider_tenant_id,
+        subject,
+        &grant.token,
+        Some(next_refresh),
+        grant.scope.as_deref().or(stored.scopes.as_deref()),
+    )
+    .await?;
+
+    app_log!(info, provider_tenant_id = %provider_tenant_id, subject_kind = subject.kind(),
+        "Refreshed consumer OAuth token");
+
+    Ok(grant.token)
+}
+
+async fn load_token(
+    store: &EndpointStore,
+    provider_tenant_id: &str,
+    subject: &ConsumerSubject,
+) -> Result<Option<StoredToken>, StoreError> {
+    let client = store.get_conn(Some(provider_tenant_id)).await?;
+    let row = client
+        .query_opt(
+            "SELECT access_token, refresh_token, token_type, scopes, expires_at
+             FROM consumer_oauth_tokens
+             WHERE provider_tenant_id = $1 AND subject_kind = $2 AND subject = $3",
+            &[&provider_tenant_id, &subject.kind(), &subject.id()],
+        )
+        .await
+        .to_store_error()?;
+    let Some(r) = row else {
+        return Ok(None);
+    };
+    let secrets = store.secrets();
+    Ok(Some(StoredToken {
+        access_token: secrets.decrypt(token_slot("access_token", provider_tenant_id), r.get(0))?,
+        refresh_token: secrets.decrypt_opt(token_slot("refresh_token", provider_tenant_id), r.get(1))?,
+        token_type: r.get(2),
+        scopes: r.get(3),
+        expires_at: r.get(4),
+    }))
+}
+
+async fn save_token(
+    store: &EndpointStore,
+    provider_tenant_id: &str,
+    subject: &ConsumerSubject,
+    token: &IssuedToken,
+    refresh_token: Option<&str>,
+    scopes: Option<&str>,
+) -> Result<ConsumerOAuthConnection, StoreError> {
+    let secrets = store.secrets();
+    let access_token = secrets.encrypt(token_slot("access_token", provider_tenant_id), &token.access_token)?;
+    let refresh_token =
+        secrets.encrypt_opt(token_slot("refresh_token", provider_tenant_id), &refresh_token.map(str::to_string))?;
+    let client = store.get_conn(Some(provider_tenant_id)).await?;
+    let now = Utc::now();
+
+    let row = client
+        .query_one(
+            "INSERT INTO consumer_oauth_tokens
+                (provider_tenant_id, subject_kind, subject, access_token, refresh_token,
+                 token_type, scopes, expires_at, created_at, updated_at)
+             VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $9)
+             ON CONFLICT (provider_tenant_id, subject_kind, subject) DO UPDATE SET
+                access_token  = EXCLUDED.access_token,
+                refresh_token = EXCLUDED.refresh_token,
+                token_type    = EXCLUDED.token_type,
+                scopes        = EXCLUDED.scopes,
+                expires_at    = EXCLUDED.expires_at,
+                updated_at    = EXCLUDED.updated_at
+             RETURNING provider_tenant_id, subject_kind, subject, scopes, expires_at,
+                       refresh_token IS NOT NULL, updated_at",
+            &[
+                &provider_tenant_id as &(dyn tokio_postgres::types::ToSql + Sync),
+                &subject.kind() as &(dyn tokio_postgres::types::ToSql + Sync),
+                &subject.id() as &(dyn tokio_postgres::types::ToSql + Sync),
+                &access_token as &(dyn tokio_postgres::types::ToSql + Sync),
+                &refresh_token as &(dyn tokio_postgres::types::ToSql + Sync),
+                &token.token_type as &(dyn tokio_postgres::types::ToSql + Sync),
+                &scopes as &(dyn tokio_postgres::types::ToSql + Sync),
+                &token.expires_at as &(dyn tokio_postgres::types::ToSql + Sync),
+                &now as &(dyn tokio_postgres::types::ToSql + Sync),
+            ],
+        )
+        .await
+        .to_store_error()?;
+
+    Ok(row_to_connection(&row))
+}
+
+fn row_to_connection(row: &tokio_postgres::Row) -> ConsumerOAuthConnection {
+    ConsumerOAuthConnection {
+        provider_tenant_id: row.get(0),
+        subject_kind:       row.get(1),
+        subject:            row.get(2),
+        scopes:             row.get(3),
+        expires_at:         row.get::<_, Option<DateTime<Utc>>>(4).map(|t| t.to_rfc3339()),
+        has_refresh_token:  row.get(5),
+        updated_at:         row.get::<_, DateTime<Utc>>(6).to_rfc3339(),
+    }
+}
+
+fn token_slot<'a>(column: &'a str, provider_tenant_id: &'a str) -> SecretSlot<'a> {
+    SecretSlot::new("consumer_oauth_tokens", column, provider_tenant_id)
+}
+
+fn verifier_slot(provider_tenant_id: &str) -> SecretSlot<'_> {
+    SecretSlot::new("consumer_oauth_states", "code_verifier", provider_tenant_id)
+}
+
+fn random_token() -> String {
+    let mut bytes = [0u8; 32];
+    rng().fill_bytes(&mut bytes);
+    URL_SAFE_NO_PAD.encode(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::endpoint_store::api_key_management::generate_api_key_with_provider;
+    use crate::endpoint_store::consumer_keys::{set_suspended, Target};
+    use crate::endpoint_store::downstream_auth_management::{save_downstream_auth, SaveDownstreamAuthRequest};
+    use crate::endpoint_store::key_expiry::KeyExpiry;
+    use crate::endpoint_store::key_scopes::KeyScopes;
+    use crate::endpoint_store::tenant_management::get_default_tenant;
+    use std::collections::HashMap;
+    use std::sync::{Arc, Mutex};
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+
+    type Forms = Arc<Mutex<Vec<HashMap<String, String>>>>;
+
+    /// Stand-in token endpoint: answers with `bodies` in turn (the last one
+    /// repeats) and records each posted form.
+    async fn token_endpoint(bodies: Vec<&'static str>) -> (String, Forms) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/oauth/token", listener.local_addr().unwrap());
+        let forms: Forms = Arc::default();
+        let seen = forms.clone();
+        tokio::spawn(async move {
+            loop {
+                let (mut sock, _) = match listener.accept().await {
+                    Ok(s) => s,
+                    Err(_) => return,
+                };
+                let mut req = Vec::new();
+                let mut buf = [0u8; 4096];
+                let form = loop {
+                    match sock.read(&mut buf).await {
+                        Ok(0) | Err(_) => break None,
+                        Ok(n) => req.extend_from_slice(&buf[..n]),
+                    }
+                    let text = String::from_utf8_lossy(&req);
+                    let Some((head, body)) = text.split_once("\r\n\r\n") else { continue };
+                    let len = head
+                        .lines()
+                        .find_map(|l| l.to_ascii_lowercase().strip_prefix("content-length:").map(|v| v.trim().to_string()))
+                        .and_then(|v| v.parse::<usize>().ok())
+                        .unwrap_or(0);
+                    if body.len() >= len {
+                        let form = reqwest::Url::parse(&format!("http://form/?{}", body)).unwrap();
+                        break Some(form.query_pairs().into_owned().collect());
+                    }
+                };
+                let Some(form) = form else { continue };
+                let body = {
+                    let mut forms = seen.lock().unwrap();
+                    forms.push(form);
+                    bodies[(forms.len() - 1).min(bodies.len() - 1)]
+                };
+                let resp = format!(
+                    "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                    body.len(),
+                    body
+                );
+                let _ = sock.write_all(resp.as_bytes()).await;
+            }
+        });
+        (url, forms)
+    }
+
+    /// A provider set up for the authorization-code flow against `token_url`,
+    /// and one of its consumer keys.
+    async fn provider_with_consumer_key(store: &EndpointStore, token_url: &str) -> (String, ConsumerSubject) {
+        let provider = format!("provider_{}@example.com", uuid::Uuid::new_v4());
+        let provider_tenant_id = get_default_tenant(store, &provider).await.unwrap().id;
+        let auth: SaveDownstreamAuthRequest = serde_json::from_value(serde_json::json!({
+            "auth_mode": AUTHORIZATION_CODE_MODE,
+            "oauth_authorize_url": "https://auth.example.com/authorize",
+            "oauth_token_url": token_url,
+            "oauth_client_id": "cid",
+            "oauth_scopes": "read",
+        }))
+        .unwrap();
+        save_downstream_auth(store, &provider_tenant_id, &auth).await.unwrap();
+
+        let consumer = format!("consumer_{}@example.com", uuid::Uuid::new_v4());
+        let (_, _, key_id, _) = generate_api_key_with_provider(
+            store,
+            &consumer,
+            "delegated",
+            None,
+            Some(&provider_tenant_id),
+            &KeyScopes::default(),
+            &KeyExpiry::default(),
+        )
+        .await
+        .unwrap();
+        (provider_tenant_id, ConsumerSubject::Key(key_id))
+    }
+
+    async fn connect(store: &EndpointStore, provider_tenant_id: &str, subject: &ConsumerSubject) -> ConsumerOAuthStart {
+        let started = start_consumer_oauth(store, provider_tenant_id, subject, "https://app.example.com/cb", None)
+            .await
+            .unwrap();
+        complete_consumer_oauth(store, &started.state, "code-1").await.unwrap();
+        started
+    }
+
+    #[tokio::test]
+    #[ignore = "requires live PostgreSQL (DATABASE_URL)"]
+    async fn state_is_single_use_and_pkce_verifier_round_trips() {
+        let store = crate::endpoint_store::live_test_store().await;
+        let (url, forms) =
+            token_endpoint(vec![r#"{"access_token":"tok-1","refresh_token":"ref-1","expires_in":3600}"#]).await;
+        let (provider_tenant_id, subject) = provider_with_consumer_key(&store, &url).await;
+
+        let started = connect(&store, &provider_tenant_id, &subject).await;
+        let authorize = reqwest::Url::parse(&started.authorization_url).unwrap();
+        let query: HashMap<_, _> = authorize.query_pairs().into_owned().collect();
+        assert_eq!(query["code_challenge_method"], "S256");
+
+        let exchanged = forms.lock().unwrap()[0].clone();
+        assert_eq!(exchanged["grant_type"], "authorization_code");
+        assert_eq!(exchanged["code"], "code-1");
+        let verifier = &exchanged["code_verifier"];
+        assert_eq!(URL_SAFE_NO_PAD.encode(Sha256::digest(verifier.as_bytes())), query["code_challenge"]);
+
+        assert!(matches!(
+            complete_consumer_oauth(&store, &started.state, "code-1").await,
+            Err(DownstreamTokenError::InvalidState)
+        ));
+        assert_eq!(forms.lock().unwrap().len(), 1, "a replayed state never reaches the token endpoint");
+    }
+
+    #[tokio::test]
+    #[ignore = "requires live PostgreSQL (DATABASE_URL)"]
+    async fn refreshes_tokens_about_to_expire() {
+        let store = crate::endpoint_store::live_test_store().await;
+        // Inside REFRESH_MARGIN_SECS, so stale as soon as it is stored.
+        let (url, forms) = token_endpoint(vec![
+            r#"{"access_token":"tok-1","refresh_token":"ref-1","expires_in":30}"#,
+            r#"{"access_token":"tok-2","expires_in":3600}"#,
+        ])
+        .await;
+        let (provider_tenant_id, subject) = provider_with_consumer_key(&store, &url).await;
+        connect(&store, &provider_tenant_id, &subject).await;
+        let auth = authorization_code_config(&store, &provider_tenant_id, None).await.unwrap();
+
+        let token = consumer_access_token(&store, &provider_tenant_id, &subject, &auth, false).await.unwrap();
+        assert_eq!(token.access_token, "tok-2");
+        let refreshed = forms.lock().unwrap()[1].clone();
+        assert_eq!(refreshed["grant_type"], "refresh_token");
+        assert_eq!(refreshed["refresh_token"], "ref-1");
+
+        let again = consumer_access_token(&store, &provider_tenant_id, &subject, &auth, false).await.unwrap();
+        assert_eq!(again.access_token, "tok-2");
+        assert_eq!(forms.lock().unwrap().len(), 2, "a fresh token is served from storage");
+        let connection = get_consumer_oauth_connection(&store, &provider_tenant_id, &subject).await.unwrap().unwrap();
+        assert!(connection.has_refresh_token, "the refresh token is kept when not rotated");
+    }
+
+    #[tokio::test]
+    #[ignore = "requires live PostgreSQL (DATABASE_URL)"]
+    async fn suspended_consumer_key_is_not_served() {
+        let store = crate::endpoint_store::live_test_store().await;
+        let (url, forms) =
+            token_endpoint(vec![r#"{"access_token":"tok-1","refresh_token":"ref-1","expires_in":3600}"#]).await;
+        let (provider_tenant_id, subject) = provider_with_consumer_key(&store, &url).await;
+        connect(&store, &provider_tenant_id, &subject).await;
+        let auth = authorization_code_config(&store, &provider_tenant_id, None).await.unwrap();
+        assert!(consumer_access_token(&store, &provider_tenant_id, &subject, &auth, false).await.is_ok());
+
+        let ConsumerSubject::Key(key_id) = &subject else { unreachable!() };
+        set_suspended(&store, &provider_tenant_id, Target::Key(key_id), true).await.unwrap();
+        for force_refresh in [false, true] {
+            assert!(matches!(
+                consumer_access_token(&store, &provider_tenant_id, &subject, &auth, force_refresh).await,
+                Err(DownstreamTokenError::Store(StoreError::NotFound(_)))
+            ));
+        }
+        assert!(refresh_consumer_oauth(&store, &provider_tenant_id, &subject, None).await.is_err());
+        assert_eq!(forms.lock().unwrap().len(), 1, "no refresh for a suspended key");
+    }
+
+    #[test]
+    fn subject_requires_exactly_one_id() {
+        assert!(matches!(ConsumerSubject::from_parts(Some("k1"), None), Ok(ConsumerSubject::Key(k)) if k == "k1"));
+        assert!(matches!(
+            ConsumerSubject::from_parts(None, Some(" u-9 ")),
+            Ok(ConsumerSubject::Consumer(c)) if c == "u-9"
+        ));
+        assert!(ConsumerSubject::from_parts(None, None).is_err());
+        assert!(ConsumerSubject::from_parts(Some("k1"), Some("u-9")).is_err());
+        assert!(ConsumerSubject::from_parts(Some(" "), None).is_err());
+    }
+}
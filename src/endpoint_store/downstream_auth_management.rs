@@ -1,8 +1,18 @@
 // src/endpoint_store/downstream_auth_management.rs
-// CRUD for tenant_downstream_auth table.
+// CRUD for tenant_downstream_auth and downstream_auth_overrides tables.
+//
+// A tenant has one default config; individual API groups and MCP tools can
+// override it. Resolution order for a call is tool → group → tenant.
+//
+// service_account_json, bearer_token, oauth_client_secret and hmac_secret are
+// envelope-encrypted at rest (secret_crypto.rs); the row helpers decrypt, so
+// callers only ever see plaintext.
 
 use crate::app_log;
 use crate::endpoint_store::db_helpers::ResultExt;
+use crate::endpoint_store::request_signing;
+use crate::endpoint_store::secret_crypto::SecretSlot;
+use crate::endpoint_store::secret_vault;
 use crate::endpoint_store::{EndpointStore, StoreError};
 use chrono::Utc;
 use serde::{Deserialize, Serialize};
@@ -11,12 +21,29 @@ use serde_json::Value;
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TenantDownstreamAuth {
     pub tenant_id: String,
-    pub auth_mode: String, // "none" | "google_sa" | "static_bearer" | "header_injection"
+    pub auth_mode: String, // "none" | "google_sa" | "static_bearer" | "header_injection" | "oauth2_client_credentials" | "oauth2_authorization_code" | "hmac_signature"
     pub service_account_json: Option<String>,
     pub target_audience: Option<String>,
     pub bearer_token: Option<String>,
     pub custom_headers: Option<Value>, // JSONB: {"Header-Name": "value"}
     pub updated_at: String,
+    // oauth2_client_credentials — the store fetches tokens, see downstream_tokens.rs
+    pub oauth_token_url: Option<String>,
+    pub oauth_client_id: Option<String>,
+    pub oauth_client_secret: Option<String>,
+    pub oauth_scopes: Option<String>,
+    pub oauth_audience: Option<String>,
+    // oauth2_authorization_code — per end-user tokens, see consumer_oauth.rs
+    pub oauth_authorize_url: Option<String>,
+    // hmac_signature — see request_signing.rs
+    pub hmac_secret: Option<String>,
+    pub hmac_algorithm: Option<String>, // "sha256" (default) | "sha1" | "sha512"
+    pub hmac_signed_headers: Option<Vec<String>>,
+    pub hmac_signature_headers: Option<Value>, // {"X-Signature": "t={timestamp},v1={signature}"}
+    // connectivity test — see downstream_probe.rs
+    pub health_check_url: Option<String>,
+    /// Last successful probe since the config was saved (tenant level only).
+    pub verified_at: Option<String>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -26,6 +53,107 @@ pub struct SaveDownstreamAuthRequest {
     pub target_audience: Option<String>,
     pub bearer_token: Option<String>,
     pub custom_headers: Option<Value>,
+    #[serde(default)]
+    pub oauth_token_url: Option<String>,
+    #[serde(default)]
+    pub oauth_client_id: Option<String>,
+    #[serde(default)]
+    pub oauth_client_secret: Option<String>,
+    #[serde(default)]
+    pub oauth_scopes: Option<String>,
+    #[serde(default)]
+    pub oauth_audience: Option<String>,
+    #[serde(default)]
+    pub oauth_authorize_url: Option<String>,
+    #[serde(default)]
+    pub hmac_secret: Option<String>,
+    #[serde(default)]
+    pub hmac_algorithm: Option<String>,
+    #[serde(default)]
+    pub hmac_signed_headers: Option<Vec<String>>,
+    #[serde(default)]
+    pub hmac_signature_headers: Option<Value>,
+    #[serde(default)]
+    pub health_check_url: Option<String>,
+}
+
+impl SaveDownstreamAuthRequest {
+    /// Secrets are write-only: an omitted secret (or the masked value handed
+    /// out by `redacted()`) keeps what is stored, an empty string clears it.
+    /// custom_headers is kept when omitted; header values sent back masked
+    /// keep their stored value.
+    pub fn keep_stored_secrets(&mut self, stored: Option<&TenantDownstreamAuth>) {
+        let stored_str = |f: fn(&TenantDownstreamAuth) -> &Option<String>| stored.and_then(|s| f(s).as_deref());
+        self.service_account_json = keep_secret(self.service_account_json.take(), stored_str(|s| &s.service_account_json));
+        self.bearer_token = keep_secret(self.bearer_token.take(), stored_str(|s| &s.bearer_token));
+        self.oauth_client_secret = keep_secret(self.oauth_client_secret.take(), stored_str(|s| &s.oauth_client_secret));
+        self.hmac_secret = keep_secret(self.hmac_secret.take(), stored_str(|s| &s.hmac_secret));
+
+        let stored_headers = stored.and_then(|s| s.custom_headers.as_ref());
+        self.custom_headers = match self.custom_headers.take() {
+            None => stored_headers.cloned(),
+            Some(Value::Object(mut sent)) => {
+                let stored_map = stored_headers.and_then(|v| v.as_object());
+                for (name, value) in sent.iter_mut() {
+                    let previous = stored_map.and_then(|m| m.get(name)).and_then(|v| v.as_str());
+                    if let (Some(prev), Some(v)) = (previous, value.as_str()) {
+                        if v == mask_secret(prev) {
+                            *value = Value::String(prev.to_string());
+                        }
+                    }
+                }
+                Some(Value::Object(sent))
+            }
+            Some(other) => Some(other),
+        };
+    }
+
+    pub fn check(&self) -> Result<(), String> {
+        if let Some(url) = self.health_check_url.as_deref().filter(|u| !u.is_empty()) {
+            if !url.starts_with('/') && !url.starts_with("https://") && !url.starts_with("http://") {
+                return Err("health_check_url must be an http(s) URL or a path starting with /".to_string());
+            }
+        }
+        let filled = |v: &Option<String>| v.as_deref().is_some_and(|s| !s.trim().is_empty());
+        let http_url = |v: &Option<String>| {
+            v.as_deref().is_some_and(|u| u.starts_with("https://") || u.starts_with("http://"))
+        };
+        match self.auth_mode.as_str() {
+            "oauth2_client_credentials" => {
+                if !filled(&self.oauth_token_url) || !filled(&self.oauth_client_id) || !filled(&self.oauth_client_secret) {
+                    return Err(
+                        "oauth2_client_credentials requires oauth_token_url, oauth_client_id and oauth_client_secret"
+                            .to_string(),
+                    );
+                }
+                if !http_url(&self.oauth_token_url) {
+                    return Err("oauth_token_url must be an http(s) URL".to_string());
+                }
+            }
+            "oauth2_authorization_code" => {
+                if !filled(&self.oauth_authorize_url) || !filled(&self.oauth_token_url) || !filled(&self.oauth_client_id) {
+                    return Err(
+                        "oauth2_authorization_code requires oauth_authorize_url, oauth_token_url and oauth_client_id"
+                            .to_string(),
+                    );
+                }
+                if !http_url(&self.oauth_authorize_url) || !http_url(&self.oauth_token_url) {
+                    return Err("oauth_authorize_url and oauth_token_url must be http(s) URLs".to_string());
+                }
+            }
+            "hmac_signature" => {
+                if !filled(&self.hmac_secret) {
+                    return Err("hmac_signature requires hmac_secret".to_string());
+                }
+                request_signing::check_config(
+                    self.hmac_algorithm.as_deref(),
+                    self.hmac_signature_headers.as_ref(),
+                )?;
+            }
+            _ => {}
+        }
+        Ok(())
+    }
 }
 
 pub async fn get_downstream_auth(
@@ -37,22 +165,18 @@ pub async fn get_downstream_auth(
     let row = client
         .query_opt(
             "SELECT tenant_id, auth_mode, service_account_json, target_audience,
-                    bearer_token, custom_headers, updated_at
+                    bearer_token, custom_headers, updated_at,
+                    oauth_token_url, oauth_client_id, oauth_client_secret,
+                    oauth_scopes, oauth_audience, oauth_authorize_url,
+                    hmac_secret, hmac_algorithm, hmac_signed_headers, hmac_signature_headers,
+                    health_check_url, verified_at
              FROM tenant_downstream_auth WHERE tenant_id = $1",
             &[&tenant_id],
         )
         .await
         .to_store_error()?;
 
-    Ok(row.map(|r| TenantDownstreamAuth {
-        tenant_id:            r.get(0),
-        auth_mode:            r.get(1),
-        service_account_json: r.get(2),
-        target_audience:      r.get(3),
-        bearer_token:         r.get(4),
-        custom_headers:       r.get(5),
-        updated_at:           r.get::<_, chrono::DateTime<Utc>>(6).to_rfc3339(),
-    }))
+    row.map(|r| row_to_auth(store, &r)).transpose()
 }
 
 pub async fn save_downstream_auth(
@@ -62,30 +186,62 @@ pub async fn save_downstream_auth(
 ) -> Result<TenantDownstreamAuth, StoreError> {
     let client = store.get_conn(Some(tenant_id)).await?;
     let now = Utc::now();
+    let sealed = SealedSecrets::new(store, "tenant_downstream_auth", tenant_id, req)?;
 
     let row = client
         .query_one(
             "INSERT INTO tenant_downstream_auth
                 (tenant_id, auth_mode, service_account_json, target_audience,
-                 bearer_token, custom_headers, updated_at)
-             VALUES ($1, $2, $3, $4, $5, $6, $7)
+                 bearer_token, custom_headers, updated_at,
+                 oauth_token_url, oauth_client_id, oauth_client_secret,
+                 oauth_scopes, oauth_audience, oauth_authorize_url,
+                 hmac_secret, hmac_algorithm, hmac_signed_headers, hmac_signature_headers,
+                 health_check_url, verified_at)
+             VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15, $16, $17, $18, NULL)
              ON CONFLICT (tenant_id) DO UPDATE SET
                 auth_mode            = EXCLUDED.auth_mode,
                 service_account_json = EXCLUDED.service_account_json,
                 target_audience      = EXCLUDED.target_audience,
                 bearer_token         = EXCLUDED.bearer_token,
                 custom_headers       = EXCLUDED.custom_headers,
-                updated_at           = EXCLUDED.updated_at
+                updated_at           = EXCLUDED.updated_at,
+                oauth_token_url      = EXCLUDED.oauth_token_url,
+                oauth_client_id      = EXCLUDED.oauth_client_id,
+                oauth_client_secret  = EXCLUDED.oauth_client_secret,
+                oauth_scopes         = EXCLUDED.oauth_scopes,
+                oauth_audience       = EXCLUDED.oauth_audience,
+                oauth_authorize_url  = EXCLUDED.oauth_authorize_url,
+                hmac_secret            = EXCLUDED.hmac_secret,
+                hmac_algorithm         = EXCLUDED.hmac_algorithm,
+                hmac_signed_headers    = EXCLUDED.hmac_signed_headers,
+                hmac_signature_headers = EXCLUDED.hmac_signature_headers,
+                health_check_url       = EXCLUDED.health_check_url,
+                verified_at            = NULL
              RETURNING tenant_id, auth_mode, service_account_json, target_audience,
-                       bearer_token, custom_headers, updated_at",
+                       bearer_token, custom_headers, updated_at,
+                       oauth_token_url, oauth_client_id, oauth_client_secret,
+                       oauth_scopes, oauth_audience, oauth_authorize_url,
+                       hmac_secret, hmac_algorithm, hmac_signed_headers, hmac_signature_headers,
+                       health_check_url, verified_at",
             &[
                 &tenant_id as &(dyn tokio_postgres::types::ToSql + Sync),
                 &req.auth_mode as &(dyn tokio_postgres::types::ToSql + Sync),
-                &req.service_account_json as &(dyn tokio_postgres::types::ToSql + Sync),
+                &sealed.service_account_json as &(dyn tokio_postgres::types::ToSql + Sync),
                 &req.target_audience as &(dyn tokio_postgres::types::ToSql + Sync),
-                &req.bearer_token as &(dyn tokio_postgres::types::ToSql + Sync),
+                &sealed.bearer_token as &(dyn tokio_postgres::types::ToSql + Sync),
                 &req.custom_headers as &(dyn tokio_postgres::types::ToSql + Sync),
                 &now as &(dyn tokio_postgres::types::ToSql + Sync),
+                &req.oauth_token_url as &(dyn tokio_postgres::types::ToSql + Sync),
+                &req.oauth_client_id as &(dyn tokio_postgres::types::ToSql + Sync),
+                &sealed.oauth_client_secret as &(dyn tokio_postgres::types::ToSql + Sync),
+                &req.oauth_scopes as &(dyn tokio_postgres::types::ToSql + Sync),
+                &req.oauth_audience as &(dyn tokio_postgres::types::ToSql + Sync),
+                &req.oauth_authorize_url as &(dyn tokio_postgres::types::ToSql + Sync),
+                &sealed.hmac_secret as &(dyn tokio_postgres::types::ToSql + Sync),
+                &req.hmac_algorithm as &(dyn tokio_postgres::types::ToSql + Sync),
+                &req.hmac_signed_headers as &(dyn tokio_postgres::types::ToSql + Sync),
+                &req.hmac_signature_headers as &(dyn tokio_postgres::types::ToSql + Sync),
+                &req.health_check_url as &(dyn tokio_postgres::types::ToSql + Sync),
             ],
         )
         .await
@@ -93,13 +249,642 @@ pub async fn save_downstream_auth(
 
     app_log!(info, tenant_id = %tenant_id, mode = %req.auth_mode, "Saved downstream auth config");
 
+    row_to_auth(store, &row)
+}
+
+/// Record a successful connectivity probe of the tenant config. Returns true
+/// for the first success since the config was last saved.
+pub async fn mark_downstream_auth_verified(
+    store: &EndpointStore,
+    tenant_id: &str,
+) -> Result<bool, StoreError> {
+    let client = store.get_conn(Some(tenant_id)).await?;
+    let n = client
+        .execute(
+            "UPDATE tenant_downstream_auth SET verified_at = NOW()
+             WHERE tenant_id = $1 AND verified_at IS NULL",
+            &[&tenant_id],
+        )
+        .await
+        .to_store_error()?;
+    Ok(n > 0)
+}
+
+/// Encrypted forms of the secret columns of a save request.
+struct SealedSecrets {
+    service_account_json: Option<String>,
+    bearer_token: Option<String>,
+    oauth_client_secret: Option<String>,
+    hmac_secret: Option<String>,
+}
+
+impl SealedSecrets {
+    /// `table` is tenant_downstream_auth or downstream_auth_overrides.
+    fn new(store: &EndpointStore, table: &str, tenant_id: &str, req: &SaveDownstreamAuthRequest) -> Result<Self, StoreError> {
+        let secrets = store.secrets();
+        let slot = |column| SecretSlot::new(table, column, tenant_id);
+        Ok(SealedSecrets {
+            service_account_json: secrets.encrypt_opt(slot("service_account_json"), &req.service_account_json)?,
+            bearer_token:         secrets.encrypt_opt(slot("bearer_token"), &req.bearer_token)?,
+            oauth_client_secret:  secrets.encrypt_opt(slot("oauth_client_secret"), &req.oauth_client_secret)?,
+            hmac_secret:          secrets.encrypt_opt(slot("hmac_secret"), &req.hmac_secret)?,
+        })
+    }
+}
+
+fn row_to_auth(store: &EndpointStore, row: &tokio_postgres::Row) -> Result<TenantDownstreamAuth, StoreError> {
+    let secrets = store.secrets();
+    let tenant_id: &str = row.get(0);
+    let slot = |column| SecretSlot::new("tenant_downstream_auth", column, tenant_id);
     Ok(TenantDownstreamAuth {
         tenant_id:            row.get(0),
         auth_mode:            row.get(1),
-        service_account_json: row.get(2),
+        service_account_json: secrets.decrypt_opt(slot("service_account_json"), row.get(2))?,
         target_audience:      row.get(3),
-        bearer_token:         row.get(4),
+        bearer_token:         secrets.decrypt_opt(slot("bearer_token"), row.get(4))?,
         custom_headers:       row.get(5),
         updated_at:           row.get::<_, chrono::DateTime<Utc>>(6).to_rfc3339(),
+        oauth_token_url:      row.get(7),
+        oauth_client_id:      row.get(8),
+        oauth_client_secret:  secrets.decrypt_opt(slot("oauth_client_secret"), row.get(9))?,
+        oauth_scopes:         row.get(10),
+        oauth_audience:       row.get(11),
+        oauth_authorize_url:  row.get(12),
+        hmac_secret:            secrets.decrypt_opt(slot("hmac_secret"), row.get(13))?,
+        hmac_algorithm:         row.get(14),
+        hmac_signed_headers:    row.get(15),
+        hmac_signature_headers: row.get(16),
+        health_check_url:       row.get(17),
+        verified_at:            row
+            .get::<_, Option<chrono::DateTime<Utc>>>(18)
+            .map(|t| t.to_rfc3339()),
     })
 }
+
+// ── Redaction ─────────────────────────────────────────────────────────────────
+//
+// The dashboard only ever sees DownstreamAuthView: secrets are reduced to
+// their last 4 characters plus a presence flag, and the service-account key
+// to its client_email. The full config is served to the gateway alone
+// (GET /api/tenant/downstream-auth/{tenant_id}, X-Internal-Secret).
+
+const MASK: &str = "****";
+
+/// "****" + last 4 characters; secrets of 8 characters or fewer are fully
+/// masked. Values referencing the vault ({{secret:NAME}}) are shown as-is.
+pub fn mask_secret(secret: &str) -> String {
+    if secret_vault::has_references(secret) {
+        return secret.to_string();
+    }
+    let chars: Vec<char> = secret.chars().collect();
+    if chars.len() <= 8 {
+        return MASK.to_string();
+    }
+    let tail: String = chars[chars.len() - 4..].iter().collect();
+    format!("{}{}", MASK, tail)
+}
+
+fn keep_secret(sent: Option<String>, stored: Option<&str>) -> Option<String> {
+    match sent {
+        None => stored.map(str::to_string),
+        Some(v) if v.is_empty() => None,
+        Some(v) if stored.is_some_and(|s| mask_secret(s) == v) => stored.map(str::to_string),
+        Some(v) => Some(v),
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DownstreamAuthView {
+    pub tenant_id: String,
+    pub auth_mode: String,
+    pub service_account_email: Option<String>,
+    pub has_service_account_json: bool,
+    pub target_audience: Option<String>,
+    pub bearer_token: Option<String>, // masked
+    pub has_bearer_token: bool,
+    pub custom_headers: Option<Value>, // values masked
+    pub has_custom_headers: bool,
+    pub updated_at: String,
+    pub oauth_token_url: Option<String>,
+    pub oauth_client_id: Option<String>,
+    pub oauth_client_secret: Option<String>, // masked
+    pub has_oauth_client_secret: bool,
+    pub oauth_scopes: Option<String>,
+    pub oauth_audience: Option<String>,
+    pub oauth_authorize_url: Option<String>,
+    pub hmac_secret: Option<String>, // masked
+    pub has_hmac_secret: bool,
+    pub hmac_algorithm: Option<String>,
+    pub hmac_signed_headers: Option<Vec<String>>,
+    pub hmac_signature_headers: Option<Value>,
+    pub health_check_url: Option<String>,
+    pub verified_at: Option<String>,
+}
+
+impl TenantDownstreamAuth {
+    /// The config as handed to the gateway: secrets the store uses itself
+    /// (the service-account key, the OAuth client secret and the HMAC secret)
+    /// are dropped, since the store mints the tokens and signs the requests.
+    pub fn for_gateway(mut self) -> Self {
+        self.service_account_json = None;
+        self.oauth_client_secret = None;
+        self.hmac_secret = None;
+        self
+    }
+
+    pub fn redacted(&self) -> DownstreamAuthView {
+        let masked = |v: &Option<String>| v.as_deref().filter(|s| !s.is_empty()).map(mask_secret);
+        let present = |v: &Option<String>| v.as_deref().is_some_and(|s| !s.is_empty());
+        let service_account_email = self
+            .service_account_json
+            .as_deref()
+            .and_then(|j| serde_json::from_str::<Value>(j).ok())
+            .and_then(|v| v.get("client_email").and_then(|e| e.as_str()).map(str::to_string));
+        let custom_headers = self.custom_headers.as_ref().map(|headers| match headers.as_object() {
+            Some(map) => Value::Object(
+                map.iter()
+                    .map(|(k, v)| (k.clone(), Value::String(v.as_str().map(mask_secret).unwrap_or_else(|| MASK.to_string()))))
+                    .collect(),
+            ),
+            None => Value::String(MASK.to_string()),
+        });
+
+        DownstreamAuthView {
+            tenant_id:                self.tenant_id.clone(),
+            auth_mode:                self.auth_mode.clone(),
+            service_account_email,
+            has_service_account_json: present(&self.service_account_json),
+            target_audience:          self.target_audience.clone(),
+            bearer_token:             masked(&self.bearer_token),
+            has_bearer_token:         present(&self.bearer_token),
+            has_custom_headers:       self.custom_headers.as_ref().is_some_and(|v| !v.is_null()),
+            custom_headers,
+            updated_at:               self.updated_at.clone(),
+            oauth_token_url:          self.oauth_token_url.clone(),
+            oauth_client_id:          self.oauth_client_id.clone(),
+            oauth_client_secret:      masked(&self.oauth_client_secret),
+            has_oauth_client_secret:  present(&self.oauth_client_secret),
+            oauth_scopes:             self.oauth_scopes.clone(),
+            oauth_audience:           self.oauth_audience.clone(),
+            oauth_authorize_url:      self.oauth_authorize_url.clone(),
+            hmac_secret:              masked(&self.hmac_secret),
+            has_hmac_secret:          present(&self.hmac_secret),
+            hmac_algorithm:           self.hmac_algorithm.clone(),
+            hmac_signed_headers:      self.hmac_signed_headers.clone(),
+            hmac_signature_headers:   self.hmac_signature_headers.clone(),
+            health_check_url:         self.health_check_url.clone(),
+            verified_at:              self.verified_at.clone(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DownstreamAuthOverrideView {
+    pub scope: String,
+    pub target: String,
+    #[serde(flatten)]
+    pub auth: DownstreamAuthView,
+}
+
+impl DownstreamAuthOverride {
+    pub fn redacted(&self) -> DownstreamAuthOverrideView {
+        DownstreamAuthOverrideView {
+            scope: self.scope.clone(),
+            target: self.target.clone(),
+            auth: self.clone().into_auth().redacted(),
+        }
+    }
+}
+
+// ── Overrides ─────────────────────────────────────────────────────────────────
+
+pub const OVERRIDE_SCOPES: [&str; 2] = ["tool", "group"];
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DownstreamAuthOverride {
+    pub tenant_id: String,
+    /// "tool" (target = tool_name) or "group" (target = api_groups.id)
+    pub scope: String,
+    pub target: String,
+    pub auth_mode: String,
+    pub service_account_json: Option<String>,
+    pub target_audience: Option<String>,
+    pub bearer_token: Option<String>,
+    pub custom_headers: Option<Value>,
+    pub updated_at: String,
+    pub oauth_token_url: Option<String>,
+    pub oauth_client_id: Option<String>,
+    pub oauth_client_secret: Option<String>,
+    pub oauth_scopes: Option<String>,
+    pub oauth_audience: Option<String>,
+    pub oauth_authorize_url: Option<String>,
+    pub hmac_secret: Option<String>,
+    pub hmac_algorithm: Option<String>,
+    pub hmac_signed_headers: Option<Vec<String>>,
+    pub hmac_signature_headers: Option<Value>,
+    pub health_check_url: Option<String>,
+}
+
+impl DownstreamAuthOverride {
+    pub fn into_auth(self) -> TenantDownstreamAuth {
+        TenantDownstreamAuth {
+            tenant_id:            self.tenant_id,
+            auth_mode:            self.auth_mode,
+            service_account_json: self.service_account_json,
+            target_audience:      self.target_audience,
+            bearer_token:         self.bearer_token,
+            custom_headers:       self.custom_headers,
+            updated_at:           self.updated_at,
+            oauth_token_url:      self.oauth_token_url,
+            oauth_client_id:      self.oauth_client_id,
+            oauth_client_secret:  self.oauth_client_secret,
+            oauth_scopes:         self.oauth_scopes,
+            oauth_audience:       self.oauth_audience,
+            oauth_authorize_url:  self.oauth_authorize_url,
+            hmac_secret:            self.hmac_secret,
+            hmac_algorithm:         self.hmac_algorithm,
+            hmac_signed_headers:    self.hmac_signed_headers,
+            hmac_signature_headers: self.hmac_signature_headers,
+            health_check_url:       self.health_check_url,
+            verified_at:            None,
+        }
+    }
+}
+
+/// Effective config for a call plus where it came from:
+/// "tool", "group", "tenant" or "none" (nothing configured).
+#[derive(Debug, Clone, Serialize)]
+pub struct ResolvedDownstreamAuth {
+    pub source: String,
+    pub auth: Option<TenantDownstreamAuth>,
+}
+
+pub async fn save_downstream_auth_override(
+    store: &EndpointStore,
+    tenant_id: &str,
+    scope: &str,
+    target: &str,
+    req: &SaveDownstreamAuthRequest,
+) -> Result<DownstreamAuthOverride, StoreError> {
+    let client = store.get_conn(Some(tenant_id)).await?;
+    let now = Utc::now();
+    let sealed = SealedSecrets::new(store, "downstream_auth_overrides", tenant_id, req)?;
+
+    let row = client
+        .query_one(
+            "INSERT INTO downstream_auth_overrides
+                (tenant_id, scope, target, auth_mode, service_account_json, target_audience,
+                 bearer_token, custom_headers, updated_at,
+                 oauth_token_url, oauth_client_id, oauth_client_secret,
+                 oauth_scopes, oauth_audience, oauth_authorize_url,
+                 hmac_secret, hmac_algorithm, hmac_signed_headers, hmac_signature_headers,
+                 health_check_url)
+             VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15, $16, $17, $18, $19, $20)
+             ON CONFLICT (tenant_id, scope, target) DO UPDATE SET
+                auth_mode            = EXCLUDED.auth_mode,
+                service_account_json = EXCLUDED.service_account_json,
+                target_audience      = EXCLUDED.target_audience,
+                bearer_token         = EXCLUDED.bearer_token,
+                custom_headers       = EXCLUDED.custom_headers,
+                updated_at           = EXCLUDED.updated_at,
+                oauth_token_url      = EXCLUDED.oauth_token_url,
+                oauth_client_id      = EXCLUDED.oauth_client_id,
+                oauth_client_secret  = EXCLUDED.oauth_client_secret,
+                oauth_scopes         = EXCLUDED.oauth_scopes,
+                oauth_audience       = EXCLUDED.oauth_audience,
+                oauth_authorize_url  = EXCLUDED.oauth_authorize_url,
+                hmac_secret            = EXCLUDED.hmac_secret,
+                hmac_algorithm         = EXCLUDED.hmac_algorithm,
+                hmac_signed_headers    = EXCLUDED.hmac_signed_headers,
+                hmac_signature_headers = EXCLUDED.hmac_signature_headers,
+                health_check_url       = EXCLUDED.health_check_url
+             RETURNING tenant_id, scope, target, auth_mode, service_account_json,
+                       target_audience, bearer_token, custom_headers, updated_at,
+                       oauth_token_url, oauth_client_id, oauth_client_secret,
+                       oauth_scopes, oauth_audience, oauth_authorize_url,
+                       hmac_secret, hmac_algorithm, hmac_signed_headers, hmac_signature_headers,
+                       health_check_url",
+            &[
+                &tenant_id as &(dyn tokio_postgres::types::ToSql + Sync),
+                &scope as &(dyn tokio_postgres::types::ToSql + Sync),
+                &target as &(dyn tokio_postgres::types::ToSql + Sync),
+                &req.auth_mode as &(dyn tokio_postgres::types::ToSql + Sync),
+                &sealed.service_account_json as &(dyn tokio_postgres::types::ToSql + Sync),
+                &req.target_audience as &(dyn tokio_postgres::types::ToSql + Sync),
+                &sealed.bearer_token as &(dyn tokio_postgres::types::ToSql + Sync),
+                &req.custom_headers as &(dyn tokio_postgres::types::ToSql + Sync),
+                &now as &(dyn tokio_postgres::types::ToSql + Sync),
+                &req.oauth_token_url as &(dyn tokio_postgres::types::ToSql + Sync),
+                &req.oauth_client_id as &(dyn tokio_postgres::types::ToSql + Sync),
+                &sealed.oauth_client_secret as &(dyn tokio_postgres::types::ToSql + Sync),
+                &req.oauth_scopes as &(dyn tokio_postgres::types::ToSql + Sync),
+                &req.oauth_audience as &(dyn tokio_postgres::types::ToSql + Sync),
+                &req.oauth_authorize_url as &(dyn tokio_postgres::types::ToSql + Sync),
+                &sealed.hmac_secret as &(dyn tokio_postgres::types::ToSql + Sync),
+                &req.hmac_algorithm as &(dyn tokio_postgres::types::ToSql + Sync),
+                &req.hmac_signed_headers as &(dyn tokio_postgres::types::ToSql + Sync),
+                &req.hmac_signature_headers as &(dyn tokio_postgres::types::ToSql + Sync),
+                &req.health_check_url as &(dyn tokio_postgres::types::ToSql + Sync),
+            ],
+        )
+        .await
+        .to_store_error()?;
+
+    app_log!(info, tenant_id = %tenant_id, scope = %scope, target = %target, mode = %req.auth_mode,
+        "Saved downstream auth override");
+
+    row_to_override(store, &row)
+}
+
+pub async fn list_downstream_auth_overrides(
+    store: &EndpointStore,
+    tenant_id: &str,
+) -> Result<Vec<DownstreamAuthOverride>, StoreError> {
+    let client = store.get_conn(Some(tenant_id)).await?;
+
+    let rows = client
+        .query(
+            "SELECT tenant_id, scope, target, auth_mode, service_account_json,
+                    target_audience, bearer_token, custom_headers, updated_at,
+                    oauth_token_url, oauth_client_id, oauth_client_secret,
+                    oauth_scopes, oauth_audience, oauth_authorize_url,
+                    hmac_secret, hmac_algorithm, hmac_signed_headers, hmac_signature_headers,
+                    health_check_url
+             FROM downstream_auth_overrides
+             WHERE tenant_id = $1
+             ORDER BY scope, target",
+            &[&tenant_id],
+        )
+        .await
+        .to_store_error()?;
+
+    rows.iter().map(|r| row_to_override(store, r)).collect()
+}
+
+pub async fn get_downstream_auth_override(
+    store: &EndpointStore,
+    tenant_id: &str,
+    scope: &str,
+    target: &str,
+) -> Result<Option<DownstreamAuthOverride>, StoreError> {
+    let client = store.get_conn(Some(tenant_id)).await?;
+
+    let row = client
+        .query_opt(
+            "SELECT tenant_id, scope, target, auth_mode, service_account_json,
+                    target_audience, bearer_token, custom_headers, updated_at,
+                    oauth_token_url, oauth_client_id, oauth_client_secret,
+                    oauth_scopes, oauth_audience, oauth_authorize_url,
+                    hmac_secret, hmac_algorithm, hmac_signed_headers, hmac_signature_headers,
+                    health_check_url
+             FROM downstream_auth_overrides
+             WHERE tenant_id = $1 AND scope = $2 AND target = $3",
+            &[&tenant_id, &scope, &target],
+        )
+        .await
+        .to_store_error()?;
+
+    row.as_ref().map(|r| row_to_override(store, r)).transpose()
+}
+
+pub async fn delete_downstream_auth_override(
+    store: &EndpointStore,
+    tenant_id: &str,
+    scope: &str,
+    target: &str,
+) -> Result<bool, StoreError> {
+    let client = store.get_conn(Some(tenant_id)).await?;
+
+    let n = client
+        .execute(
+            "DELETE FROM downstream_auth_overrides
+             WHERE tenant_id = $1 AND scope = $2 AND target = $3",
+            &[&tenant_id, &scope, &target],
+        )
+        .await
+        .to_store_error()?;
+
+    Ok(n > 0)
+}
+
+/// Resolve the config the gateway must use for a call to `tool_name`
+/// (tool → group → tenant). Without a tool name only the tenant default applies.
+/// Vault references ({{secret:NAME}}) are substituted.
+pub async fn resolve_downstream_auth(
+    store: &EndpointStore,
+    tenant_id: &str,
+    tool_name: Option<&str>,
+) -> Result<ResolvedDownstreamAuth, StoreError> {
+    let mut resolved = find_downstream_auth(store, tenant_id, tool_name).await?;
+    if let Some(auth) = resolved.auth.as_mut() {
+        secret_vault::resolve_auth_secrets(store, tenant_id, auth).await?;
+    }
+    Ok(resolved)
+}
+
+async fn find_downstream_auth(
+    store: &EndpointStore,
+    tenant_id: &str,
+    tool_name: Option<&str>,
+) -> Result<ResolvedDownstreamAuth, StoreError> {
+    if let Some(tool_name) = tool_name {
+        let client = store.get_conn(Some(tenant_id)).await?;
+        let group_id = store
+            .get_mcp_tool(tenant_id, tool_name, None, None)
+            .await?
+            .and_then(|t| t.group_id);
+
+        let rows = client
+            .query(
+                "SELECT tenant_id, scope, target, auth_mode, service_account_json,
+                        target_audience, bearer_token, custom_headers, updated_at,
+                        oauth_token_url, oauth_client_id, oauth_client_secret,
+                        oauth_scopes, oauth_audience, oauth_authorize_url,
+                        hmac_secret, hmac_algorithm, hmac_signed_headers, hmac_signature_headers,
+                        health_check_url
+                 FROM downstream_auth_overrides
+                 WHERE tenant_id = $1
+                   AND ((scope = 'tool' AND target = $2) OR (scope = 'group' AND target = $3))",
+                &[&tenant_id, &tool_name, &group_id.as_deref().unwrap_or("")],
+            )
+            .await
+            .to_store_error()?;
+
+        let overrides = rows
+            .iter()
+            .map(|r| row_to_override(store, r))
+            .collect::<Result<Vec<_>, _>>()?;
+        if let Some(o) = most_specific(overrides) {
+            return Ok(ResolvedDownstreamAuth { source: o.scope.clone(), auth: Some(o.into_auth()) });
+        }
+    }
+
+    Ok(match get_downstream_auth(store, tenant_id).await? {
+        Some(auth) => ResolvedDownstreamAuth { source: "tenant".to_string(), auth: Some(auth) },
+        None => ResolvedDownstreamAuth { source: "none".to_string(), auth: None },
+    })
+}
+
+/// The override that wins: tool before group (OVERRIDE_SCOPES order).
+fn most_specific(overrides: Vec<DownstreamAuthOverride>) -> Option<DownstreamAuthOverride> {
+    overrides
+        .into_iter()
+        .min_by_key(|o| OVERRIDE_SCOPES.iter().position(|s| *s == o.scope))
+}
+
+fn row_to_override(store: &EndpointStore, row: &tokio_postgres::Row) -> Result<DownstreamAuthOverride, StoreError> {
+    let secrets = store.secrets();
+    let tenant_id: &str = row.get(0);
+    let slot = |column| SecretSlot::new("downstream_auth_overrides", column, tenant_id);
+    Ok(DownstreamAuthOverride {
+        tenant_id:            row.get(0),
+        scope:                row.get(1),
+        target:               row.get(2),
+        auth_mode:            row.get(3),
+        service_account_json: secrets.decrypt_opt(slot("service_account_json"), row.get(4))?,
+        target_audience:      row.get(5),
+        bearer_token:         secrets.decrypt_opt(slot("bearer_token"), row.get(6))?,
+        custom_headers:       row.get(7),
+        updated_at:           row.get::<_, chrono::DateTime<Utc>>(8).to_rfc3339(),
+        oauth_token_url:      row.get(9),
+        oauth_client_id:      row.get(10),
+        oauth_client_secret:  secrets.decrypt_opt(slot("oauth_client_secret"), row.get(11))?,
+        oauth_scopes:         row.get(12),
+        oauth_audience:       row.get(13),
+        oauth_authorize_url:  row.get(14),
+        hmac_secret:            secrets.decrypt_opt(slot("hmac_secret"), row.get(15))?,
+        hmac_algorithm:         row.get(16),
+        hmac_signed_headers:    row.get(17),
+        hmac_signature_headers: row.get(18),
+        health_check_url:       row.get(19),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::endpoint_store::live_test_store;
+    use crate::endpoint_store::mcp_tools_management::{upsert_mcp_tool, UpsertMcpToolRequest};
+    use crate::endpoint_store::tenant_management::get_default_tenant;
+    use serde_json::json;
+
+    fn stored() -> TenantDownstreamAuth {
+        serde_json::from_value(json!({
+            "tenant_id": "t1",
+            "auth_mode": "static_bearer",
+            "service_account_json": "{\"client_email\":\"sa@proj.iam.gserviceaccount.com\"}",
+            "target_audience": null,
+            "bearer_token": "sk_live_0123456789abcd",
+            "custom_headers": {"X-Api-Key": "key-0123456789wxyz", "X-Short": "abc"},
+            "updated_at": "",
+            "oauth_token_url": null,
+            "oauth_client_id": null,
+            "oauth_client_secret": null,
+            "oauth_scopes": null,
+            "oauth_audience": null,
+            "oauth_authorize_url": null,
+            "hmac_secret": null,
+            "hmac_algorithm": null,
+            "hmac_signed_headers": null,
+            "hmac_signature_headers": null,
+            "health_check_url": null,
+            "verified_at": null,
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn redacted_view_masks_secrets() {
+        let view = stored().redacted();
+        assert_eq!(view.bearer_token.as_deref(), Some("****abcd"));
+        assert!(view.has_bearer_token);
+        assert_eq!(view.service_account_email.as_deref(), Some("sa@proj.iam.gserviceaccount.com"));
+        assert_eq!(view.custom_headers, Some(json!({"X-Api-Key": "****wxyz", "X-Short": "****"})));
+        assert!(!view.has_hmac_secret && view.hmac_secret.is_none());
+    }
+
+    #[test]
+    fn gateway_view_drops_store_held_secrets() {
+        let mut auth = stored();
+        auth.oauth_client_secret = Some("cs".to_string());
+        auth.hmac_secret = Some("whsec".to_string());
+        let auth = auth.for_gateway();
+        assert!(auth.service_account_json.is_none());
+        assert!(auth.oauth_client_secret.is_none() && auth.hmac_secret.is_none());
+        assert_eq!(auth.bearer_token.as_deref(), Some("sk_live_0123456789abcd"));
+    }
+
+    #[test]
+    fn omitted_or_masked_secrets_keep_stored_values() {
+        let mut req: SaveDownstreamAuthRequest = serde_json::from_value(json!({
+            "auth_mode": "static_bearer",
+            "service_account_json": "",
+            "custom_headers": {"X-Api-Key": "****wxyz", "X-New": "v"},
+            "oauth_client_secret": "new-secret",
+        }))
+        .unwrap();
+        req.keep_stored_secrets(Some(&stored()));
+
+        assert_eq!(req.bearer_token.as_deref(), Some("sk_live_0123456789abcd"));
+        assert_eq!(req.service_account_json, None);
+        assert_eq!(req.oauth_client_secret.as_deref(), Some("new-secret"));
+        assert_eq!(req.custom_headers, Some(json!({"X-Api-Key": "key-0123456789wxyz", "X-New": "v"})));
+    }
+
+    fn bearer(token: &str) -> SaveDownstreamAuthRequest {
+        serde_json::from_value(json!({ "auth_mode": "static_bearer", "bearer_token": token })).unwrap()
+    }
+
+    fn override_for(scope: &str) -> DownstreamAuthOverride {
+        let mut auth: serde_json::Map<String, Value> = serde_json::from_value(json!(stored())).unwrap();
+        auth.insert("scope".into(), json!(scope));
+        auth.insert("target".into(), json!(format!("{}-target", scope)));
+        serde_json::from_value(Value::Object(auth)).unwrap()
+    }
+
+    #[test]
+    fn tool_override_beats_group_override() {
+        let picked = most_specific(vec![override_for("group"), override_for("tool")]).unwrap();
+        assert_eq!(picked.scope, "tool");
+        let picked = most_specific(vec![override_for("group")]).unwrap();
+        assert_eq!(picked.scope, "group");
+        assert!(most_specific(Vec::new()).is_none());
+    }
+
+    async fn resolved_token(store: &EndpointStore, tenant_id: &str, tool: &str) -> (String, Option<String>) {
+        let r = find_downstream_auth(store, tenant_id, Some(tool)).await.unwrap();
+        (r.source, r.auth.and_then(|a| a.bearer_token))
+    }
+
+    #[tokio::test]
+    #[ignore = "requires live PostgreSQL (DATABASE_URL)"]
+    async fn resolves_tool_then_group_then_tenant() {
+        let store = live_test_store().await;
+        let email = format!("dsauth_{}@example.com", uuid::Uuid::new_v4());
+        let tenant_id = get_default_tenant(&store, &email).await.unwrap().id;
+        let group_id = uuid::Uuid::new_v4().to_string();
+        for (name, group) in [("grouped", Some(group_id.clone())), ("ungrouped", None)] {
+            let tool: UpsertMcpToolRequest = serde_json::from_value(json!({
+                "tool_name": name,
+                "backend_url": "https://backend.example.com",
+                "group_id": group,
+            }))
+            .unwrap();
+            upsert_mcp_tool(&store, &tenant_id, &tool).await.unwrap();
+        }
+
+        save_downstream_auth(&store, &tenant_id, &bearer("tenant-token")).await.unwrap();
+        save_downstream_auth_override(&store, &tenant_id, "group", &group_id, &bearer("group-token")).await.unwrap();
+        save_downstream_auth_override(&store, &tenant_id, "tool", "grouped", &bearer("tool-token")).await.unwrap();
+
+        let tenant = ("tenant".to_string(), Some("tenant-token".to_string()));
+        assert_eq!(resolved_token(&store, &tenant_id, "grouped").await, ("tool".into(), Some("tool-token".into())));
+        assert_eq!(resolved_token(&store, &tenant_id, "ungrouped").await, tenant);
+
+        delete_downstream_auth_override(&store, &tenant_id, "tool", "grouped").await.unwrap();
+        assert_eq!(resolved_token(&store, &tenant_id, "grouped").await, ("group".into(), Some("group-token".into())));
+
+        delete_downstream_auth_override(&store, &tenant_id, "group", &group_id).await.unwrap();
+        assert_eq!(resolved_token(&store, &tenant_id, "grouped").await, tenant);
+    }
+}
@@ -0,0 +1,326 @@
+// src/endpoint_store/downstream_probe.rs
+//
+// Connectivity test for downstream auth configs.
+//
+// Resolves the effective auth for the tenant (or one tool), builds the
+// credentials the gateway would send, and probes the backend with a GET:
+//
+//   target — health_check_url (absolute, or a path resolved against the
+//            tool's backend_url) > the tool's backend_url. There is no
+//            caller-supplied URL: the probe carries real credentials.
+//   steps  — DNS resolution, TLS (https only), credential acquisition,
+//            HTTP status; each with latency and the error it hit
+//
+// The probe passes when the backend answers below 500 and does not reject
+// the credentials (401 / 403). 404 / 405 count as reachable: many backends
+// only serve the tool's verb on the tool's path.
+//
+// Vault references in the target are resolved for the request only; the
+// report shows the URL as configured and errors are stripped of URLs.
+
+use crate::endpoint_store::downstream_auth_management::TenantDownstreamAuth;
+use crate::endpoint_store::request_signing::SignRequest;
+use crate::endpoint_store::secret_vault;
+use crate::endpoint_store::{EndpointStore, StoreError};
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::time::{Duration, Instant};
+
+const PROBE_TIMEOUT_SECS: u64 = 10;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ProbeStep {
+    pub ok: bool,
+    pub latency_ms: Option<u64>,
+    pub detail: Option<String>,
+}
+
+impl ProbeStep {
+    fn pass(latency_ms: Option<u64>, detail: Option<String>) -> Self {
+        ProbeStep { ok: true, latency_ms, detail }
+    }
+
+    fn fail(latency_ms: Option<u64>, detail: String) -> Self {
+        ProbeStep { ok: false, latency_ms, detail: Some(detail) }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ProbeReport {
+    pub ok: bool,
+    pub url: String,
+    pub auth_mode: String,
+    /// Which config level applied: "tool", "group", "tenant" or "none".
+    pub source: String,
+    pub dns: Option<ProbeStep>,
+    /// None for plain http targets.
+    pub tls: Option<ProbeStep>,
+    pub auth: ProbeStep,
+    pub http: Option<ProbeStep>,
+    pub status: Option<u16>,
+    /// The backend answered 401 / 403 to the probe.
+    pub auth_rejected: bool,
+    pub latency_ms: u64,
+    /// First failing step, human-readable.
+    pub error: Option<String>,
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum ProbeError {
+    #[error("{0}")]
+    NoTarget(String),
+    #[error(transparent)]
+    Store(#[from] StoreError),
+}
+
+pub async fn probe_downstream(
+    store: &EndpointStore,
+    tenant_id: &str,
+    tool_name: Option<&str>,
+) -> Result<ProbeReport, ProbeError> {
+    let started = Instant::now();
+    let resolved = store.resolve_downstream_auth(tenant_id, tool_name).await?;
+    let auth_mode = resolved
+        .auth
+        .as_ref()
+        .map(|a| a.auth_mode.clone())
+        .unwrap_or_else(|| "none".to_string());
+
+    let backend_url = match tool_name {
+        Some(name) => Some(
+            store
+                .get_mcp_tool(tenant_id, name, None, None)
+                .await?
+                .ok_or_else(|| StoreError::NotFound(format!("MCP tool {}", name)))?
+                .backend_url,
+        ),
+        None => None,
+    };
+    let health_check_url = resolved.auth.as_ref().and_then(|a| a.health_check_url.as_deref());
+    let target = probe_target(health_check_url, backend_url.as_deref())?;
+    let parsed = reqwest::Url::parse(&secret_vault::resolve_text(store, tenant_id, &target).await?)
+        .map_err(|e| ProbeError::NoTarget(format!("invalid probe url {}: {}", target, e)))?;
+
+    let mut report = ProbeReport {
+        ok: false,
+        url: target.clone(),
+        auth_mode,
+        source: resolved.source.clone(),
+        dns: None,
+        tls: None,
+        auth: ProbeStep::pass(None, None),
+        http: None,
+        status: None,
+        auth_rejected: false,
+        latency_ms: 0,
+        error: None,
+    };
+
+    // 1. DNS
+    let host = parsed.host_str().unwrap_or_default().to_string();
+    let port = parsed.port_or_known_default().unwrap_or(443);
+    let dns_started = Instant::now();
+    match tokio::net::lookup_host((host.as_str(), port)).await {
+        Ok(addrs) => {
+            let addrs: Vec<String> = addrs.map(|a| a.ip().to_string()).collect();
+            report.dns = Some(ProbeStep::pass(Some(elapsed_ms(dns_started)), Some(addrs.join(", "))));
+        }
+        Err(e) => {
+            report.dns = Some(ProbeStep::fail(Some(elapsed_ms(dns_started)), e.to_string()));
+            return Ok(finish(report, started, format!("DNS lookup for {} failed: {}", host, e)));
+        }
+    }
+
+    // 2. Credentials
+    let auth_started = Instant::now();
+    let headers = match probe_headers(store, tenant_id, tool_name, resolved.auth.as_ref(), &parsed).await {
+        Ok((headers, note)) => {
+            report.auth = ProbeStep::pass(Some(elapsed_ms(auth_started)), note);
+            headers
+        }
+        Err(e) => {
+            report.auth = ProbeStep::fail(Some(elapsed_ms(auth_started)), e.clone());
+            return Ok(finish(report, started, format!("Could not build credentials: {}", e)));
+        }
+    };
+
+    // 3. TLS + HTTP
+    let https = parsed.scheme() == "https";
+    let http = match reqwest::Client::builder()
+        .timeout(Duration::from_secs(PROBE_TIMEOUT_SECS))
+        .redirect(reqwest::redirect::Policy::none())
+        .build()
+    {
+        Ok(c) => c,
+        Err(e) => return Ok(finish(report, started, e.to_string())),
+    };
+    let mut request = http.get(parsed.clone());
+    for (name, value) in &headers {
+        request = request.header(name, value);
+    }
+
+    let http_started = Instant::now();
+    match request.send().await {
+        Ok(resp) => {
+            let latency = elapsed_ms(http_started);
+            if https {
+                report.tls = Some(ProbeStep::pass(None, None));
+            }
+            let status = resp.status().as_u16();
+            report.status = Some(status);
+            report.auth_rejected = status == 401 || status == 403;
+            if report.auth_rejected {
+                report.http = Some(ProbeStep::fail(Some(latency), format!("HTTP {}", status)));
+                return Ok(finish(report, started, format!("Backend rejected the credentials (HTTP {})", status)));
+            }
+            if status >= 500 {
+                report.http = Some(ProbeStep::fail(Some(latency), format!("HTTP {}", status)));
+                return Ok(finish(report, started, format!("Backend error (HTTP {})", status)));
+            }
+            report.http = Some(ProbeStep::pass(Some(latency), Some(format!("HTTP {}", status))));
+            report.ok = true;
+            report.latency_ms = elapsed_ms(started);
+            Ok(report)
+        }
+        Err(e) => {
+            let e = e.without_url();
+            let latency = elapsed_ms(http_started);
+            let chain = error_chain(&e);
+            let tls_failure = https && is_tls_error(&chain);
+            // A connect error before any handshake leaves TLS untested.
+            if tls_failure {
+                report.tls = Some(ProbeStep::fail(Some(latency), chain.clone()));
+            } else if https && !e.is_connect() {
+                report.tls = Some(ProbeStep::pass(None, None));
+            }
+            report.http = Some(ProbeStep::fail(Some(latency), chain.clone()));
+            let summary = if tls_failure {
+                format!("TLS handshake failed: {}", chain)
+            } else if e.is_timeout() {
+                format!("Timed out after {}s", PROBE_TIMEOUT_SECS)
+            } else if e.is_connect() {
+                format!("Connection failed: {}", chain)
+            } else {
+                chain
+            };
+            Ok(finish(report, started, summary))
+        }
+    }
+}
+
+/// Headers the gateway would attach for this auth config, plus a note for
+/// modes that cannot be fully exercised by a probe.
+async fn probe_headers(
+    store: &EndpointStore,
+    tenant_id: &str,
+    tool_name: Option<&str>,
+    auth: Option<&TenantDownstreamAuth>,
+    url: &reqwest::Url,
+) -> Result<(BTreeMap<String, String>, Option<String>), String> {
+    let mut headers = BTreeMap::new();
+    let Some(auth) = auth else {
+        return Ok((headers, Some("no downstream auth configured".to_string())));
+    };
+
+    match auth.auth_mode.as_str() {
+        "none" => {}
+        "header_injection" => {
+            for (name, value) in auth.custom_headers.iter().flat_map(|v| v.as_object()).flatten() {
+                if let Some(v) = value.as_str() {
+                    headers.insert(name.clone(), v.to_string());
+                }
+            }
+        }
+        "hmac_signature" => {
+            let path = match url.query() {
+                Some(q) => format!("{}?{}", url.path(), q),
+                None => url.path().to_string(),
+            };
+            let req = SignRequest {
+                method: "GET".to_string(),
+                path,
+                body: None,
+                body_sha256: None,
+                headers: BTreeMap::new(),
+                timestamp: None,
+            };
+            let signed = store
+                .sign_downstream_request(tenant_id, tool_name, &req)
+                .await
+                .map_err(|e| e.to_string())?;
+            headers.extend(signed.headers);
+        }
+        "oauth2_authorization_code" => {
+            return Ok((
+                headers,
+                Some("per end-user tokens are not probed; request sent without credentials".to_string()),
+            ));
+        }
+        _ => {
+            let token = store
+                .downstream_access_token(tenant_id, tool_name, None, false)
+                .await
+                .map_err(|e| e.to_string())?;
+            headers.insert("Authorization".to_string(), format!("Bearer {}", token.token.access_token));
+        }
+    }
+    Ok((headers, None))
+}
+
+fn probe_target(health_check_url: Option<&str>, backend_url: Option<&str>) -> Result<String, ProbeError> {
+    let is_absolute = |u: &str| u.starts_with("https://") || u.starts_with("http://");
+    match (health_check_url.filter(|u| !u.is_empty()), backend_url) {
+        (Some(h), _) if is_absolute(h) => Ok(h.to_string()),
+        (Some(path), Some(base)) => reqwest::Url::parse(base)
+            .and_then(|b| b.join(path))
+            .map(|u| u.to_string())
+            .map_err(|e| ProbeError::NoTarget(format!("cannot resolve {} against {}: {}", path, base, e))),
+        (None, Some(base)) => Ok(base.to_string()),
+        _ => Err(ProbeError::NoTarget(
+            "nothing to probe: pass tool_name or configure an absolute health_check_url".to_string(),
+        )),
+    }
+}
+
+fn finish(mut report: ProbeReport, started: Instant, error: String) -> ProbeReport {
+    report.ok = false;
+    report.latency_ms = elapsed_ms(started);
+    report.error = Some(error);
+    report
+}
+
+fn elapsed_ms(since: Instant) -> u64 {
+    since.elapsed().as_millis() as u64
+}
+
+fn error_chain(e: &dyn std::error::Error) -> String {
+    let mut parts = vec![e.to_string()];
+    let mut source = e.source();
+    while let Some(s) = source {
+        parts.push(s.to_string());
+        source = s.source();
+    }
+    parts.join(": ")
+}
+
+fn is_tls_error(chain: &str) -> bool {
+    let lower = chain.to_lowercase();
+    ["certificate", "tls", "ssl", "handshake"].iter().any(|k| lower.contains(k))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn picks_probe_target_in_order() {
+        let base = Some("https://api.example.com/v1/tool");
+        assert_eq!(
+            probe_target(Some("https://status.example.com"), base).unwrap(),
+            "https://status.example.com"
+        );
+        assert_eq!(probe_target(Some("/health"), base).unwrap(), "https://api.example.com/health");
+        assert_eq!(probe_target(None, base).unwrap(), "https://api.example.com/v1/tool");
+        assert!(probe_target(Some("/health"), None).is_err());
+    }
+}
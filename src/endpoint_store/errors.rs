@@ -6,6 +6,10 @@ pub enum StoreError {
     Pool(String),
     #[error("Resource not found: {0}")]
     NotFound(String),
+    #[error("Secret encryption error: {0}")]
+    Crypto(String),
+    #[error("Invalid request: {0}")]
+    Invalid(String),
 }
 
 impl From<tokio_postgres::Error> for StoreError {
@@ -0,0 +1,187 @@
+// src/endpoint_store/ip_allowlist.rs
+//
+// Caller IP allowlists on API keys.
+//
+//   - api_keys.allowed_cidrs holds IPv4/IPv6 networks in canonical form
+//     ("10.0.0.0/8", "2001:db8::/32"); NULL = any address.
+//   - A bare address is stored as a single-host network (/32 or /128).
+//   - IPv4-mapped IPv6 callers (::ffff:a.b.c.d) match IPv4 entries, since
+//     dual-stack proxies report them that way.
+//   - A restricted key used without a known caller IP is denied. The caller
+//     IP is one address; forwarded chains are not accepted.
+//   - Load balancers listed in API0_TRUSTED_PROXIES are not callers: behind
+//     one, the caller is the X-Forwarded-For entry it appended (the last).
+
+use crate::endpoint_store::db_helpers::ResultExt;
+use crate::endpoint_store::key_cache::{self, Invalidation};
+use crate::endpoint_store::{EndpointStore, StoreError};
+use ipnet::IpNet;
+use std::net::IpAddr;
+use std::sync::OnceLock;
+
+/// Upper bound on entries per key.
+pub const MAX_CIDRS: usize = 64;
+
+static TRUSTED_PROXIES: OnceLock<Vec<IpNet>> = OnceLock::new();
+
+/// Parse one entry: a CIDR or a bare address.
+fn parse_entry(entry: &str) -> Result<IpNet, String> {
+    let entry = entry.trim();
+    if let Ok(net) = entry.parse::<IpNet>() {
+        return Ok(net.trunc());
+    }
+    entry
+        .parse::<IpAddr>()
+        .map(IpNet::from)
+        .map_err(|_| format!("\"{}\" is not an IP address or CIDR", entry))
+}
+
+/// Validate and canonicalise an allowlist: host bits are cleared, duplicates
+/// dropped and entries sorted. `None` (no restriction) passes through; an
+/// empty list is rejected because it would lock the key out entirely.
+pub fn normalize(cidrs: Option<&[String]>) -> Result<Option<Vec<String>>, String> {
+    let Some(cidrs) = cidrs else {
+        return Ok(None);
+    };
+    if cidrs.is_empty() {
+        return Err("allowed_cidrs must not be empty; use null to allow any address".into());
+    }
+    if cidrs.len() > MAX_CIDRS {
+        return Err(format!("allowed_cidrs accepts at most {} entries", MAX_CIDRS));
+    }
+    let mut nets = cidrs.iter().map(|c| parse_entry(c)).collect::<Result<Vec<_>, _>>()?;
+    nets.sort();
+    nets.dedup();
+    Ok(Some(nets.iter().map(|n| n.to_string()).collect()))
+}
+
+/// Parse a single caller address. Lists such as an X-Forwarded-For chain are
+/// rejected: their leading entries are whatever the client sent.
+pub fn parse_client_ip(raw: &str) -> Option<IpAddr> {
+    let ip: IpAddr = raw.trim().parse().ok()?;
+    Some(match ip {
+        IpAddr::V6(v6) => v6.to_ipv4_mapped().map(IpAddr::V4).unwrap_or(ip),
+        v4 => v4,
+    })
+}
+
+/// Parse API0_TRUSTED_PROXIES: comma-separated CIDRs or addresses.
+pub fn parse_trusted_proxies(spec: &str) -> Result<Vec<IpNet>, String> {
+    spec.split(',')
+        .filter(|e| !e.trim().is_empty())
+        .map(|e| parse_entry(e).map_err(|msg| format!("API0_TRUSTED_PROXIES: {}", msg)))
+        .collect()
+}
+
+/// Load API0_TRUSTED_PROXIES. Called once at startup; returns the number of
+/// trusted networks.
+pub fn init_trusted_proxies() -> Result<usize, String> {
+    let nets = match std::env::var("API0_TRUSTED_PROXIES") {
+        Ok(spec) => parse_trusted_proxies(&spec)?,
+        Err(_) => Vec::new(),
+    };
+    let n = nets.len();
+    let _ = TRUSTED_PROXIES.set(nets);
+    Ok(n)
+}
+
+pub fn trusted_proxies() -> &'static [IpNet] {
+    TRUSTED_PROXIES.get().map(Vec::as_slice).unwrap_or_default()
+}
+
+/// Whether `client_ip` may use a key restricted to `allowed`. Stored entries
+/// that fail to parse never match.
+pub fn is_allowed(allowed: &[String], client_ip: Option<&str>) -> bool {
+    let Some(ip) = client_ip.and_then(parse_client_ip) else {
+        return false;
+    };
+    allowed
+        .iter()
+        .filter_map(|c| c.parse::<IpNet>().ok())
+        .any(|net| net.contains(&ip))
+}
+
+/// Replace the allowlist of an active key. Returns the stored (normalised)
+/// list, or None when the key does not exist for this tenant.
+pub async fn set_allowed_cidrs(
+    store: &EndpointStore,
+    tenant_id: &str,
+    key_id: &str,
+    cidrs: Option<&[String]>,
+) -> Result<Option<Option<Vec<String>>>, StoreError> {
+    let cidrs = normalize(cidrs).map_err(StoreError::Invalid)?;
+    let client = store.get_conn(Some(tenant_id)).await?;
+    let n = client
+        .execute(
+            "UPDATE api_keys SET allowed_cidrs = $3
+             WHERE id = $1 AND tenant_id = $2 AND is_active = true",
+            &[&key_id, &tenant_id, &cidrs],
+        )
+        .await
+        .to_store_error()?;
+    if n > 0 {
+        key_cache::notify_invalidation(&client, Invalidation::Key(key_id.to_string())).await?;
+    }
+    Ok((n > 0).then_some(cidrs))
+}
+
+/// Owning tenant and allowlist of a key, when it has one. Used to audit the
+/// caller address reported with usage logs.
+pub async fn key_allowlist(
+    store: &EndpointStore,
+    key_id: &str,
+) -> Result<Option<(String, Vec<String>)>, StoreError> {
+    let client = store.get_admin_conn().await?;
+    let row = client
+        .query_opt(
+            "SELECT tenant_id, allowed_cidrs FROM api_keys
+             WHERE id = $1 AND allowed_cidrs IS NOT NULL",
+            &[&key_id],
+        )
+        .await
+        .to_store_error()?;
+    Ok(row.map(|r| (r.get(0), r.get(1))))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn list(v: &[&str]) -> Vec<String> {
+        v.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn normalize_canonicalises_entries() {
+        let input = list(&["10.1.2.3/8", "192.168.0.7", "2001:db8::1/32", "10.0.0.0/8"]);
+        assert_eq!(
+            normalize(Some(&input)).unwrap(),
+            Some(list(&["10.0.0.0/8", "192.168.0.7/32", "2001:db8::/32"]))
+        );
+        assert_eq!(normalize(None).unwrap(), None);
+        assert!(normalize(Some(&[])).is_err());
+        assert!(normalize(Some(&list(&["10.0.0.0/33"]))).is_err());
+        assert!(normalize(Some(&list(&["example.com"]))).is_err());
+    }
+
+    #[test]
+    fn allowlist_matching() {
+        let allowed = list(&["10.0.0.0/8", "2001:db8::/32"]);
+        assert!(is_allowed(&allowed, Some("10.20.30.40")));
+        assert!(is_allowed(&allowed, Some("2001:db8:1::5")));
+        assert!(is_allowed(&allowed, Some("::ffff:10.0.0.1")));
+        assert!(!is_allowed(&allowed, Some("10.0.0.1, 203.0.113.9")), "spoofed leading entry");
+        assert!(!is_allowed(&allowed, Some("11.0.0.1")));
+        assert!(!is_allowed(&allowed, Some("2001:db9::1")));
+        assert!(!is_allowed(&allowed, None));
+        assert!(!is_allowed(&allowed, Some("not-an-ip")));
+    }
+
+    #[test]
+    fn trusted_proxy_spec() {
+        let nets = parse_trusted_proxies(" 10.0.0.0/8, 192.0.2.7 ,").unwrap();
+        assert_eq!(nets, vec!["10.0.0.0/8".parse::<IpNet>().unwrap(), "192.0.2.7/32".parse().unwrap()]);
+        assert!(parse_trusted_proxies("").unwrap().is_empty());
+        assert!(parse_trusted_proxies("10.0.0.0/8, lb.internal").is_err());
+    }
+}
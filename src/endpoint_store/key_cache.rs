@@ -0,0 +1,197 @@
+// src/endpoint_store/key_cache.rs
+//
+// In-process cache for API key validation, keyed by key hash.
+//
+//   - Hits are kept for API0_KEY_CACHE_TTL_SECS (default 30), misses for
+//     API0_KEY_CACHE_NEGATIVE_TTL_SECS (default 5); 0 disables either.
+//   - A hit never outlives the key's expires_at, or the grace window when a
+//     rotated-out secret matched, so expiry takes effect on time without any
+//     message.
+//   - Changes that revoke or alter a key call notify_invalidation(), which
+//     drops local entries at once and sends pg_notify on CHANNEL so every
+//     other instance drops them too (see notify_listener.rs).
+//   - Per-request checks (expected tenant, scope, caller IP) run on the
+//     cached record, so only the key lookup itself is cached.
+
+use crate::app_log;
+use crate::endpoint_store::api_key_management::ValidatedKey;
+use crate::endpoint_store::db_helpers::ResultExt;
+use crate::endpoint_store::StoreError;
+use chrono::{DateTime, Utc};
+use std::collections::HashMap;
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
+use tokio_postgres::Client;
+
+pub const CHANNEL: &str = "api_key_cache";
+const DEFAULT_TTL_SECS: u64 = 30;
+const DEFAULT_NEGATIVE_TTL_SECS: u64 = 5;
+/// Above this size expired entries are swept; if that is not enough the
+/// cache is cleared.
+const MAX_ENTRIES: usize = 100_000;
+
+/// What gets invalidated.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Invalidation {
+    Key(String),
+    Tenant(String),
+    All,
+}
+
+impl Invalidation {
+    fn to_payload(&self) -> String {
+        match self {
+            Invalidation::Key(id) => format!("key:{}", id),
+            Invalidation::Tenant(id) => format!("tenant:{}", id),
+            Invalidation::All => "all".to_string(),
+        }
+    }
+
+    fn from_payload(payload: &str) -> Option<Self> {
+        match payload.split_once(':') {
+            Some(("key", id)) => Some(Invalidation::Key(id.to_string())),
+            Some(("tenant", id)) => Some(Invalidation::Tenant(id.to_string())),
+            None if payload == "all" => Some(Invalidation::All),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Clone)]
+struct Entry {
+    /// None = no active key has this hash.
+    key: Option<ValidatedKey>,
+    fresh_until: Instant,
+}
+
+static CACHE: OnceLock<Mutex<HashMap<String, Entry>>> = OnceLock::new();
+
+fn cache() -> &'static Mutex<HashMap<String, Entry>> {
+    CACHE.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+fn ttl_from_env(var: &str, default: u64) -> Duration {
+    Duration::from_secs(std::env::var(var).ok().and_then(|v| v.parse().ok()).unwrap_or(default))
+}
+
+fn positive_ttl() -> Duration {
+    static TTL: OnceLock<Duration> = OnceLock::new();
+    *TTL.get_or_init(|| ttl_from_env("API0_KEY_CACHE_TTL_SECS", DEFAULT_TTL_SECS))
+}
+
+fn negative_ttl() -> Duration {
+    static TTL: OnceLock<Duration> = OnceLock::new();
+    *TTL.get_or_init(|| ttl_from_env("API0_KEY_CACHE_NEGATIVE_TTL_SECS", DEFAULT_NEGATIVE_TTL_SECS))
+}
+
+/// Cached lookup result: Some(None) is a cached miss, None means "ask the DB".
+pub fn get(key_hash: &str) -> Option<Option<ValidatedKey>> {
+    let map = cache().lock().unwrap_or_else(|e| e.into_inner());
+    map.get(key_hash)
+        .filter(|e| Instant::now() < e.fresh_until)
+        .map(|e| e.key.clone())
+}
+
+/// Remember a lookup result. `valid_until` is when the matched secret stops
+/// working (key expiry or end of the rotation grace window).
+pub fn put(key_hash: &str, key: Option<ValidatedKey>, valid_until: Option<DateTime<Utc>>) {
+    let ttl = if key.is_some() { positive_ttl() } else { negative_ttl() };
+    let ttl = match valid_until {
+        Some(at) => ttl.min((at - Utc::now()).to_std().unwrap_or_default()),
+        None => ttl,
+    };
+    if ttl.is_zero() {
+        return;
+    }
+
+    let now = Instant::now();
+    let mut map = cache().lock().unwrap_or_else(|e| e.into_inner());
+    if map.len() >= MAX_ENTRIES {
+        map.retain(|_, e| now < e.fresh_until);
+        if map.len() >= MAX_ENTRIES {
+            map.clear();
+        }
+    }
+    map.insert(key_hash.to_string(), Entry { key, fresh_until: now + ttl });
+}
+
+fn apply(inv: &Invalidation) {
+    let mut map = cache().lock().unwrap_or_else(|e| e.into_inner());
+    match inv {
+        Invalidation::All => map.clear(),
+        // Misses stay: a revoked or changed key cannot turn an unknown hash valid.
+        Invalidation::Key(id) => map.retain(|_, e| e.key.as_ref().is_none_or(|k| &k.key_id != id)),
+        Invalidation::Tenant(id) => map.retain(|_, e| e.key.as_ref().is_none_or(|k| &k.tenant_id != id)),
+    }
+}
+
+/// Drop cached entries here and, through pg_notify, on every other instance.
+pub async fn notify_invalidation(client: &Client, inv: Invalidation) -> Result<(), StoreError> {
+    apply(&inv);
+    client
+        .execute("SELECT pg_notify($1, $2)", &[&CHANNEL, &inv.to_payload()])
+        .await
+        .to_store_error()?;
+    Ok(())
+}
+
+/// Apply a notification received on CHANNEL (see notify_listener.rs).
+pub fn handle_notification(payload: &str) {
+    match Invalidation::from_payload(payload) {
+        Some(inv) => apply(&inv),
+        None => app_log!(warn, payload = %payload, "Ignoring unknown API key cache notification"),
+    }
+}
+
+/// Drop everything; used when notifications may have been missed.
+pub fn clear() {
+    apply(&Invalidation::All);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(id: &str, tenant: &str) -> ValidatedKey {
+        ValidatedKey {
+            email: "a@b.c".into(),
+            key_id: id.into(),
+            tenant_id: tenant.into(),
+            provider_tenant_id: None,
+            restrictions: Default::default(),
+            generation: 1,
+            previous_expires_at: None,
+            allowed_cidrs: None,
+            expires_at: None,
+            suspended: false,
+        }
+    }
+
+    #[test]
+    fn payload_round_trip() {
+        for inv in [Invalidation::Key("k1".into()), Invalidation::Tenant("t:1".into()), Invalidation::All] {
+            assert_eq!(Invalidation::from_payload(&inv.to_payload()), Some(inv));
+        }
+        assert_eq!(Invalidation::from_payload("bogus"), None);
+    }
+
+    #[test]
+    fn invalidation_and_expiry() {
+        put("h-key", Some(key("cache-k1", "cache-t1")), None);
+        put("h-tenant", Some(key("cache-k2", "cache-t2")), None);
+        put("h-miss", None, None);
+        put("h-expired", Some(key("cache-k3", "cache-t1")), Some(Utc::now() - chrono::Duration::seconds(1)));
+
+        assert!(get("h-key").unwrap().is_some());
+        assert!(get("h-miss").unwrap().is_none());
+        assert!(get("h-expired").is_none());
+
+        apply(&Invalidation::Key("cache-k1".into()));
+        assert!(get("h-key").is_none());
+        assert!(get("h-tenant").is_some());
+        assert!(get("h-miss").is_some());
+
+        apply(&Invalidation::Tenant("cache-t2".into()));
+        assert!(get("h-tenant").is_none());
+    }
+}
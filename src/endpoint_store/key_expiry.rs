@@ -0,0 +1,347 @@
+// src/endpoint_store/key_expiry.rs
+//
+// API key lifetimes.
+//
+//   - Callers pick `expires_at` or `ttl_days` when creating a key; consumer
+//     keys default to CONSUMER_KEY_DEFAULT_DAYS, other keys to no expiry.
+//   - tenants.max_key_lifetime_days caps both. For consumer keys the stricter
+//     of the consumer's and the provider's policy applies; when a cap exists
+//     and the caller asks for nothing, the key gets the cap.
+//   - due_expiry_notices() claims the warnings the scheduler should send:
+//     14, 3 and 1 days ahead, plus one notice once the key has expired.
+//     Claims live in api_key_expiry_notices so each stage goes out once;
+//     the scheduler releases a claim whose email could not be delivered so
+//     the next run tries again.
+
+use crate::endpoint_store::db_helpers::ResultExt;
+use crate::endpoint_store::{EndpointStore, StoreError};
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+
+pub const CONSUMER_KEY_DEFAULT_DAYS: i64 = 365;
+/// Upper bound for any policy value (10 years).
+pub const MAX_POLICY_DAYS: i32 = 3650;
+/// Warning stages in days before expiry; 0 = "has expired".
+pub const NOTICE_STAGES: [i32; 4] = [14, 3, 1, 0];
+
+/// Requested lifetime for a new key. At most one of the two may be set.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct KeyExpiry {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expires_at: Option<DateTime<Utc>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ttl_days: Option<i64>,
+}
+
+impl KeyExpiry {
+    /// Resolve the expiry for a key created at `now`.
+    /// `default_days` applies when nothing was requested; `max_days` is the
+    /// tenant policy and rejects anything longer (including "never").
+    pub fn resolve(
+        &self,
+        now: DateTime<Utc>,
+        default_days: Option<i64>,
+        max_days: Option<i32>,
+    ) -> Result<Option<DateTime<Utc>>, String> {
+        let requested = match (self.expires_at, self.ttl_days) {
+            (Some(_), Some(_)) => return Err("set either expires_at or ttl_days, not both".into()),
+            (Some(at), None) => Some(at),
+            (None, Some(days)) if days <= 0 => return Err("ttl_days must be positive".into()),
+            (None, Some(days)) if days > MAX_POLICY_DAYS as i64 => {
+                return Err(format!("ttl_days must be at most {}", MAX_POLICY_DAYS))
+            }
+            (None, Some(days)) => Some(now + Duration::days(days)),
+            (None, None) => None,
+        };
+        if let Some(at) = requested {
+            if at <= now {
+                return Err("expires_at must be in the future".into());
+            }
+        }
+
+        let Some(max) = max_days else {
+            return Ok(requested.or_else(|| default_days.map(|d| now + Duration::days(d))));
+        };
+        let limit = now + Duration::days(max as i64);
+        match requested {
+            Some(at) if at > limit => Err(format!(
+                "key lifetime exceeds the tenant policy of {} days",
+                max
+            )),
+            Some(at) => Ok(Some(at)),
+            None => Ok(Some(
+                default_days.map(|d| now + Duration::days(d)).map_or(limit, |at| at.min(limit)),
+            )),
+        }
+    }
+}
+
+/// The warning stage a key with `remaining` lifetime is in, if any.
+pub fn notice_stage(remaining: Duration) -> Option<i32> {
+    if remaining <= Duration::zero() {
+        return Some(0);
+    }
+    NOTICE_STAGES
+        .iter()
+        .rev()
+        .copied()
+        .find(|&days| days > 0 && remaining <= Duration::days(days as i64))
+}
+
+// ── Tenant policy ─────────────────────────────────────────────────────────────
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct KeyPolicy {
+    /// Maximum key lifetime in days; None = unlimited.
+    pub max_key_lifetime_days: Option<i32>,
+}
+
+pub async fn get_key_policy(store: &EndpointStore, tenant_id: &str) -> Result<Option<KeyPolicy>, StoreError> {
+    let client = store.get_conn(Some(tenant_id)).await?;
+    let row = client
+        .query_opt("SELECT max_key_lifetime_days FROM tenants WHERE id = $1", &[&tenant_id])
+        .await
+        .to_store_error()?;
+    Ok(row.map(|r| KeyPolicy { max_key_lifetime_days: r.get(0) }))
+}
+
+/// Returns false when the tenant does not exist. Existing keys keep their
+/// expiry; the policy applies to keys created afterwards.
+pub async fn set_key_policy(store: &EndpointStore, tenant_id: &str, policy: &KeyPolicy) -> Result<bool, StoreError> {
+    if let Some(days) = policy.max_key_lifetime_days {
+        if !(1..=MAX_POLICY_DAYS).contains(&days) {
+            return Err(StoreError::Invalid(format!(
+                "max_key_lifetime_days must be between 1 and {}",
+                MAX_POLICY_DAYS
+            )));
+        }
+    }
+    let client = store.get_conn(Some(tenant_id)).await?;
+    let n = client
+        .execute(
+            "UPDATE tenants SET max_key_lifetime_days = $2 WHERE id = $1",
+            &[&tenant_id, &policy.max_key_lifetime_days],
+        )
+        .await
+        .to_store_error()?;
+    Ok(n > 0)
+}
+
+/// Expiry for a new key owned by `tenant_id` (and, for consumer keys, giving
+/// access to `provider_tenant_id`). Policy violations are StoreError::Invalid.
+pub async fn resolve_new_key_expiry(
+    store: &EndpointStore,
+    tenant_id: &str,
+    provider_tenant_id: Option<&str>,
+    expiry: &KeyExpiry,
+) -> Result<Option<DateTime<Utc>>, StoreError> {
+    let tenant_ids: Vec<&str> = std::iter::once(tenant_id).chain(provider_tenant_id).collect();
+    let client = store.get_admin_conn().await?;
+    let row = client
+        .query_one(
+            "SELECT MIN(max_key_lifetime_days) FROM tenants WHERE id = ANY($1)",
+            &[&tenant_ids],
+        )
+        .await
+        .to_store_error()?;
+    let max_days: Option<i32> = row.get(0);
+    let default_days = provider_tenant_id.map(|_| CONSUMER_KEY_DEFAULT_DAYS);
+
+    expiry
+        .resolve(Utc::now(), default_days, max_days)
+        .map_err(StoreError::Invalid)
+}
+
+// ── Upcoming expirations ──────────────────────────────────────────────────────
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ExpiringKey {
+    pub id: String,
+    pub key_prefix: String,
+    pub key_name: String,
+    pub email: String,
+    pub provider_tenant_id: Option<String>,
+    pub expires_at: String,
+}
+
+/// Active keys of a tenant that expire within `within_days`, soonest first.
+pub async fn list_expiring_keys(
+    store: &EndpointStore,
+    tenant_id: &str,
+    within_days: i64,
+) -> Result<Vec<ExpiringKey>, StoreError> {
+    let client = store.get_conn(Some(tenant_id)).await?;
+    let until = Utc::now() + Duration::days(within_days);
+    let rows = client
+        .query(
+            "SELECT id, key_prefix, key_name, email, provider_tenant_id, expires_at
+             FROM api_keys
+             WHERE tenant_id = $1 AND is_active = true
+               AND expires_at IS NOT NULL AND expires_at > NOW() AND expires_at <= $2
+             ORDER BY expires_at",
+            &[&tenant_id, &until],
+        )
+        .await
+        .to_store_error()?;
+
+    Ok(rows
+        .iter()
+        .map(|r| ExpiringKey {
+            id: r.get(0),
+            key_prefix: r.get(1),
+            key_name: r.get(2),
+            email: r.get(3),
+            provider_tenant_id: r.get(4),
+            expires_at: r.get::<_, DateTime<Utc>>(5).to_rfc3339(),
+        })
+        .collect())
+}
+
+// ── Expiry notices ────────────────────────────────────────────────────────────
+
+#[derive(Debug, Clone)]
+pub struct ExpiryNotice {
+    pub key_id: String,
+    pub email: String,
+    pub key_name: String,
+    pub key_prefix: String,
+    pub expires_at: DateTime<Utc>,
+    /// Days-before stage (14, 3, 1) or 0 once expired.
+    pub stage: i32,
+    /// Whole days left, rounded up (a key created with a short TTL can be
+    /// in the 14-day stage with only a few days left).
+    pub days_left: i32,
+}
+
+/// Claim every notice that is due now. Each (key, stage) is returned once
+/// across all replicas; a key created with a short TTL skips the stages it
+/// was already past. Expired keys only get a notice within a day of expiry.
+pub async fn due_expiry_notices(store: &EndpointStore) -> Result<Vec<ExpiryNotice>, StoreError> {
+    let client = store.get_admin_conn().await?;
+    let horizon = Utc::now() + Duration::days(NOTICE_STAGES[0] as i64);
+    let rows = client
+        .query(
+            "SELECT id, email, key_name, key_prefix, expires_at
+             FROM api_keys
+             WHERE is_active = true
+               AND expires_at IS NOT NULL
+               AND expires_at > NOW() - INTERVAL '1 day'
+               AND expires_at <= $1",
+            &[&horizon],
+        )
+        .await
+        .to_store_error()?;
+
+    let now = Utc::now();
+    let mut notices = Vec::new();
+    for r in &rows {
+        let key_id: String = r.get(0);
+        let expires_at: DateTime<Utc> = r.get(4);
+        let remaining = expires_at - now;
+        let Some(stage) = notice_stage(remaining) else {
+            continue;
+        };
+        let claimed = client
+            .execute(
+                "INSERT INTO api_key_expiry_notices (key_id, stage) VALUES ($1, $2)
+                 ON CONFLICT DO NOTHING",
+                &[&key_id, &stage],
+            )
+            .await
+            .to_store_error()?;
+        if claimed == 0 {
+            continue;
+        }
+        notices.push(ExpiryNotice {
+            key_id,
+            email: r.get(1),
+            key_name: r.get(2),
+            key_prefix: r.get(3),
+            expires_at,
+            stage,
+            days_left: ((remaining.num_hours() + 23) / 24).max(0) as i32,
+        });
+    }
+    Ok(notices)
+}
+
+/// Give back the claim on a notice that was not delivered.
+pub async fn release_expiry_notice(store: &EndpointStore, key_id: &str, stage: i32) -> Result<(), StoreError> {
+    let client = store.get_admin_conn().await?;
+    client
+        .execute(
+            "DELETE FROM api_key_expiry_notices WHERE key_id = $1 AND stage = $2",
+            &[&key_id, &stage],
+        )
+        .await
+        .to_store_error()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn now() -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339("2026-01-01T00:00:00Z").unwrap().with_timezone(&Utc)
+    }
+
+    #[test]
+    fn defaults_and_requests_without_policy() {
+        let none = KeyExpiry::default();
+        assert_eq!(none.resolve(now(), None, None).unwrap(), None);
+        assert_eq!(none.resolve(now(), Some(365), None).unwrap(), Some(now() + Duration::days(365)));
+
+        let ttl = KeyExpiry { ttl_days: Some(30), ..Default::default() };
+        assert_eq!(ttl.resolve(now(), Some(365), None).unwrap(), Some(now() + Duration::days(30)));
+
+        let both = KeyExpiry { expires_at: Some(now()), ttl_days: Some(1) };
+        assert!(both.resolve(now(), None, None).is_err());
+        let past = KeyExpiry { expires_at: Some(now() - Duration::days(1)), ..Default::default() };
+        assert!(past.resolve(now(), None, None).is_err());
+    }
+
+    #[test]
+    fn policy_caps_lifetime() {
+        let none = KeyExpiry::default();
+        assert_eq!(none.resolve(now(), None, Some(90)).unwrap(), Some(now() + Duration::days(90)));
+        assert_eq!(none.resolve(now(), Some(365), Some(90)).unwrap(), Some(now() + Duration::days(90)));
+        assert_eq!(none.resolve(now(), Some(30), Some(90)).unwrap(), Some(now() + Duration::days(30)));
+
+        let long = KeyExpiry { ttl_days: Some(91), ..Default::default() };
+        assert!(long.resolve(now(), None, Some(90)).is_err());
+    }
+
+    #[tokio::test]
+    #[ignore = "requires live PostgreSQL (DATABASE_URL)"]
+    async fn released_notice_is_claimed_again() {
+        let store = crate::endpoint_store::live_test_store().await;
+        let email = format!("expiry_{}@example.com", uuid::Uuid::new_v4());
+        let (_, _, key_id, _) =
+            crate::endpoint_store::api_key_management::generate_api_key(&store, &email, "k", None).await.unwrap();
+        let client = store.get_admin_conn().await.unwrap();
+        client
+            .execute("UPDATE api_keys SET expires_at = NOW() + INTERVAL '2 days' WHERE id = $1", &[&key_id])
+            .await
+            .unwrap();
+        let stages_for = |notices: Vec<ExpiryNotice>| {
+            notices.into_iter().filter(|n| n.key_id == key_id).map(|n| n.stage).collect::<Vec<_>>()
+        };
+
+        assert_eq!(stages_for(due_expiry_notices(&store).await.unwrap()), vec![3]);
+        assert_eq!(stages_for(due_expiry_notices(&store).await.unwrap()), Vec::<i32>::new());
+
+        release_expiry_notice(&store, &key_id, 3).await.unwrap();
+        assert_eq!(stages_for(due_expiry_notices(&store).await.unwrap()), vec![3]);
+    }
+
+    #[test]
+    fn notice_stages() {
+        assert_eq!(notice_stage(Duration::days(20)), None);
+        assert_eq!(notice_stage(Duration::days(14)), Some(14));
+        assert_eq!(notice_stage(Duration::days(5)), Some(14));
+        assert_eq!(notice_stage(Duration::hours(60)), Some(3));
+        assert_eq!(notice_stage(Duration::hours(2)), Some(1));
+        assert_eq!(notice_stage(Duration::seconds(-5)), Some(0));
+    }
+}
@@ -0,0 +1,371 @@
+// src/endpoint_store/key_quotas.rs
+//
+// Per-key rate limits and daily quotas, enforced by the gateway through
+// consume_quota() before each tool call.
+//
+//   requests_per_minute   fixed one-minute windows
+//   calls_per_day         UTC calendar day
+//   credits_per_day       UTC calendar day, in credits charged by the call
+//
+// api_keys.* overrides; NULL falls back to the tenant default
+// (tenants.default_*). A consumer key gets the stricter of its own tenant's
+// and the provider's defaults, further capped by api_keys.provider_* (set
+// by the provider, see consumer_keys.rs). All NULL = unlimited, and nothing
+// is written.
+//
+// Counters live in api_key_quota_windows, one row per (key, window kind).
+// consume_quota locks those rows inside one transaction, so concurrent
+// gateway replicas cannot overshoot a limit.
+
+use crate::endpoint_store::db_helpers::ResultExt;
+use crate::endpoint_store::{EndpointStore, StoreError};
+use chrono::{DateTime, Duration, DurationRound, Utc};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct QuotaLimits {
+    #[serde(default)]
+    pub requests_per_minute: Option<i64>,
+    #[serde(default)]
+    pub calls_per_day: Option<i64>,
+    #[serde(default)]
+    pub credits_per_day: Option<i64>,
+}
+
+impl QuotaLimits {
+    pub fn check(&self) -> Result<(), String> {
+        for (name, value) in [
+            ("requests_per_minute", self.requests_per_minute),
+            ("calls_per_day", self.calls_per_day),
+            ("credits_per_day", self.credits_per_day),
+        ] {
+            if matches!(value, Some(v) if v < 0) {
+                return Err(format!("{} must not be negative", name));
+            }
+        }
+        Ok(())
+    }
+
+    pub fn is_unlimited(&self) -> bool {
+        self.requests_per_minute.is_none() && self.calls_per_day.is_none() && self.credits_per_day.is_none()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Window {
+    Minute,
+    Day,
+}
+
+impl Window {
+    fn kind(self) -> &'static str {
+        match self {
+            Window::Minute => "minute",
+            Window::Day => "day",
+        }
+    }
+
+    fn length(self) -> Duration {
+        match self {
+            Window::Minute => Duration::minutes(1),
+            Window::Day => Duration::days(1),
+        }
+    }
+
+    fn start(self, now: DateTime<Utc>) -> DateTime<Utc> {
+        now.duration_trunc(self.length()).unwrap_or(now)
+    }
+}
+
+/// One limit as seen by the caller, shaped after the X-RateLimit-* headers.
+#[derive(Debug, Clone, Serialize)]
+pub struct QuotaStatus {
+    /// "requests_per_minute", "calls_per_day" or "credits_per_day".
+    pub name: &'static str,
+    pub limit: i64,
+    pub remaining: i64,
+    /// Unix seconds when the window resets.
+    pub reset: i64,
+    pub reset_after_secs: i64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct QuotaDecision {
+    pub allowed: bool,
+    /// The limit that rejected the call.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub limited_by: Option<&'static str>,
+    pub limits: Vec<QuotaStatus>,
+}
+
+impl QuotaDecision {
+    /// The limit to report in X-RateLimit-* headers: the one that rejected
+    /// the call, else the one closest to running out.
+    pub fn headline(&self) -> Option<&QuotaStatus> {
+        match self.limited_by {
+            Some(name) => self.limits.iter().find(|l| l.name == name),
+            None => self
+                .limits
+                .iter()
+                .min_by_key(|l| (l.remaining * 1000).checked_div(l.limit).unwrap_or(0)),
+        }
+    }
+}
+
+/// Apply one call costing `credits` to window counters already used
+/// (`minute_calls`, `day_calls`, `day_credits`). Counters are post-call when
+/// allowed and unchanged when rejected.
+fn decide(
+    limits: &QuotaLimits,
+    now: DateTime<Utc>,
+    minute_calls: i64,
+    day_calls: i64,
+    day_credits: i64,
+    credits: i64,
+) -> QuotaDecision {
+    let checks = [
+        ("requests_per_minute", limits.requests_per_minute, Window::Minute, minute_calls, 1),
+        ("calls_per_day", limits.calls_per_day, Window::Day, day_calls, 1),
+        ("credits_per_day", limits.credits_per_day, Window::Day, day_credits, credits),
+    ];
+    let limited_by = checks
+        .iter()
+        .find(|(_, limit, _, used, cost)| matches!(limit, Some(l) if used + cost > *l))
+        .map(|(name, ..)| *name);
+    let allowed = limited_by.is_none();
+
+    let limits = checks
+        .iter()
+        .filter_map(|&(name, limit, window, used, cost)| {
+            let limit = limit?;
+            let used = if allowed { used + cost } else { used };
+            let reset = window.start(now) + window.length();
+            Some(QuotaStatus {
+                name,
+                limit,
+                remaining: (limit - used).max(0),
+                reset: reset.timestamp(),
+                reset_after_secs: (reset - now).num_seconds().max(0),
+            })
+        })
+        .collect();
+
+    QuotaDecision { allowed, limited_by, limits }
+}
+
+/// Effective limits for a key, or None when the key is not active, suspended
+/// or expired.
+pub async fn effective_limits(store: &EndpointStore, key_id: &str) -> Result<Option<QuotaLimits>, StoreError> {
+    let client = store.get_admin_conn().await?;
+    let row = client
+        .query_opt(
+            "SELECT LEAST(COALESCE(k.rate_limit_rpm,     LEAST(t.default_rate_limit_rpm,     p.default_rate_limit_rpm)),
+                          k.provider_rate_limit_rpm),
+                    LEAST(COALESCE(k.daily_call_limit,   LEAST(t.default_daily_call_limit,   p.default_daily_call_limit)),
+                          k.provider_daily_call_limit),
+                    LEAST(COALESCE(k.daily_credit_limit, LEAST(t.default_daily_credit_limit, p.default_daily_credit_limit)),
+                          k.provider_daily_credit_limit)
+             FROM api_keys k
+             LEFT JOIN tenants t ON t.id = k.tenant_id
+             LEFT JOIN tenants p ON p.id = k.provider_tenant_id
+             WHERE k.id = $1 AND k.is_active = true AND k.suspended_at IS NULL
+               AND (k.expires_at IS NULL OR k.expires_at > NOW())",
+            &[&key_id],
+        )
+        .await
+        .to_store_error()?;
+    Ok(row.map(|r| QuotaLimits {
+        requests_per_minute: r.get(0),
+        calls_per_day: r.get(1),
+        credits_per_day: r.get(2),
+    }))
+}
+
+/// Check the key's limits for one call costing `credits` and, unless
+/// `dry_run`, count it. Returns None when the key is not active.
+pub async fn consume_quota(
+    store: &EndpointStore,
+    key_id: &str,
+    credits: i64,
+    dry_run: bool,
+) -> Result<Option<QuotaDecision>, StoreError> {
+    let Some(limits) = effective_limits(store, key_id).await? else {
+        return Ok(None);
+    };
+    let now = Utc::now();
+    if limits.is_unlimited() {
+        return Ok(Some(decide(&limits, now, 0, 0, 0, credits)));
+    }
+
+    let mut client = store.get_admin_conn().await?;
+    let tx = client.transaction().await.to_store_error()?;
+
+    // Lock (and roll over) each window row; day before minute so concurrent
+    // callers always lock in the same order.
+    let mut windows = Vec::new();
+    if limits.calls_per_day.is_some() || limits.credits_per_day.is_some() {
+        windows.push(Window::Day);
+    }
+    if limits.requests_per_minute.is_some() {
+        windows.push(Window::Minute);
+    }
+    let (mut minute_calls, mut day_calls, mut day_credits) = (0, 0, 0);
+    for &window in &windows {
+        let row = tx
+            .query_one(
+                "INSERT INTO api_key_quota_windows (key_id, window_kind, window_start, calls, credits)
+                 VALUES ($1, $2, $3, 0, 0)
+                 ON CONFLICT (key_id, window_kind) DO UPDATE SET
+                     calls   = CASE WHEN api_key_quota_windows.window_start = EXCLUDED.window_start
+                                    THEN api_key_quota_windows.calls ELSE 0 END,
+                     credits = CASE WHEN api_key_quota_windows.window_start = EXCLUDED.window_start
+                                    THEN api_key_quota_windows.credits ELSE 0 END,
+                     window_start = EXCLUDED.window_start
+                 RETURNING calls, credits",
+                &[&key_id, &window.kind(), &window.start(now)],
+            )
+            .await
+            .to_store_error()?;
+        match window {
+            Window::Minute => minute_calls = row.get(0),
+            Window::Day => (day_calls, day_credits) = (row.get(0), row.get(1)),
+        }
+    }
+
+    let decision = decide(&limits, now, minute_calls, day_calls, day_credits, credits);
+    if decision.allowed && !dry_run {
+        for &window in &windows {
+            let cost = if window == Window::Day { credits } else { 0 };
+            tx.execute(
+                "UPDATE api_key_quota_windows SET calls = calls + 1, credits = credits + $3
+                 WHERE key_id = $1 AND window_kind = $2",
+                &[&key_id, &window.kind(), &cost],
+            )
+            .await
+            .to_store_error()?;
+        }
+    }
+    tx.commit().await.to_store_error()?;
+    Ok(Some(decision))
+}
+
+/// Per-key overrides (NULL = tenant default). Returns false when the key
+/// does not exist for this tenant.
+pub async fn set_key_limits(
+    store: &EndpointStore,
+    tenant_id: &str,
+    key_id: &str,
+    limits: &QuotaLimits,
+) -> Result<bool, StoreError> {
+    limits.check().map_err(StoreError::Invalid)?;
+    let client = store.get_conn(Some(tenant_id)).await?;
+    let n = client
+        .execute(
+            "UPDATE api_keys SET rate_limit_rpm = $3, daily_call_limit = $4, daily_credit_limit = $5
+             WHERE id = $1 AND tenant_id = $2 AND is_active = true",
+            &[&key_id, &tenant_id, &limits.requests_per_minute, &limits.calls_per_day, &limits.credits_per_day],
+        )
+        .await
+        .to_store_error()?;
+    Ok(n > 0)
+}
+
+pub async fn get_tenant_default_limits(
+    store: &EndpointStore,
+    tenant_id: &str,
+) -> Result<Option<QuotaLimits>, StoreError> {
+    let client = store.get_conn(Some(tenant_id)).await?;
+    let row = client
+        .query_opt(
+            "SELECT default_rate_limit_rpm, default_daily_call_limit, default_daily_credit_limit
+             FROM tenants WHERE id = $1",
+            &[&tenant_id],
+        )
+        .await
+        .to_store_error()?;
+    Ok(row.map(|r| QuotaLimits {
+        requests_per_minute: r.get(0),
+        calls_per_day: r.get(1),
+        credits_per_day: r.get(2),
+    }))
+}
+
+/// Returns false when the tenant does not exist.
+pub async fn set_tenant_default_limits(
+    store: &EndpointStore,
+    tenant_id: &str,
+    limits: &QuotaLimits,
+) -> Result<bool, StoreError> {
+    limits.check().map_err(StoreError::Invalid)?;
+    let client = store.get_conn(Some(tenant_id)).await?;
+    let n = client
+        .execute(
+            "UPDATE tenants SET default_rate_limit_rpm = $2, default_daily_call_limit = $3,
+                                default_daily_credit_limit = $4
+             WHERE id = $1",
+            &[&tenant_id, &limits.requests_per_minute, &limits.calls_per_day, &limits.credits_per_day],
+        )
+        .await
+        .to_store_error()?;
+    Ok(n > 0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(s: &str) -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339(s).unwrap().with_timezone(&Utc)
+    }
+
+    #[tokio::test]
+    #[ignore = "requires live PostgreSQL (DATABASE_URL)"]
+    async fn expired_key_has_no_limits() {
+        let store = crate::endpoint_store::live_test_store().await;
+        let email = format!("quota_{}@example.com", uuid::Uuid::new_v4());
+        let (_, _, key_id, _) =
+            crate::endpoint_store::api_key_management::generate_api_key(&store, &email, "k", None).await.unwrap();
+        assert!(effective_limits(&store, &key_id).await.unwrap().is_some());
+
+        let client = store.get_admin_conn().await.unwrap();
+        client
+            .execute("UPDATE api_keys SET expires_at = NOW() - INTERVAL '1 minute' WHERE id = $1", &[&key_id])
+            .await
+            .unwrap();
+        assert!(effective_limits(&store, &key_id).await.unwrap().is_none());
+        assert!(consume_quota(&store, &key_id, 1, false).await.unwrap().is_none());
+    }
+
+    #[test]
+    fn allows_and_counts_down() {
+        let limits = QuotaLimits { requests_per_minute: Some(10), credits_per_day: Some(100), ..Default::default() };
+        let d = decide(&limits, at("2026-03-01T12:00:30Z"), 3, 0, 40, 5);
+        assert!(d.allowed);
+        assert_eq!(d.limits.len(), 2);
+        assert_eq!((d.limits[0].name, d.limits[0].remaining, d.limits[0].reset_after_secs), ("requests_per_minute", 6, 30));
+        assert_eq!((d.limits[1].name, d.limits[1].remaining), ("credits_per_day", 55));
+        assert_eq!(d.limits[1].reset, at("2026-03-02T00:00:00Z").timestamp());
+        assert_eq!(d.headline().unwrap().name, "credits_per_day");
+    }
+
+    #[test]
+    fn rejects_on_first_exhausted_limit() {
+        let limits = QuotaLimits { requests_per_minute: Some(10), calls_per_day: Some(50), credits_per_day: Some(100) };
+        let d = decide(&limits, at("2026-03-01T12:00:00Z"), 2, 50, 0, 1);
+        assert!(!d.allowed);
+        assert_eq!(d.limited_by, Some("calls_per_day"));
+        assert_eq!(d.headline().unwrap().remaining, 0);
+
+        // A call that would overshoot the credit budget is refused outright.
+        let d = decide(&limits, at("2026-03-01T12:00:00Z"), 0, 0, 98, 5);
+        assert_eq!(d.limited_by, Some("credits_per_day"));
+        assert_eq!(d.limits[2].remaining, 2);
+    }
+
+    #[test]
+    fn unlimited_reports_nothing() {
+        let d = decide(&QuotaLimits::default(), Utc::now(), 0, 0, 0, 1);
+        assert!(d.allowed && d.limits.is_empty() && d.headline().is_none());
+        assert!(QuotaLimits { calls_per_day: Some(-1), ..Default::default() }.check().is_err());
+    }
+}
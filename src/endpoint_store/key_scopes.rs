@@ -0,0 +1,148 @@
+// src/endpoint_store/key_scopes.rs
+//
+// Permission scopes on api_keys rows.
+//
+//   tools:list     discover tools (tools/list, catalog reads)
+//   tools:call     invoke tools — spends credits
+//   catalog:write  upload / edit tools, groups and toolsets
+//   usage:read     usage logs and per-key statistics
+//   billing:read   credit balance and transactions
+//
+// `scopes` NULL means every scope (keys created before scopes existed).
+// `allowed_group_ids` / `allowed_tools` NULL means no restriction; when set,
+// the gateway only exposes the listed groups / tool names to the key.
+
+use serde::{Deserialize, Serialize};
+
+pub const ALL_SCOPES: [&str; 5] = [
+    "tools:list",
+    "tools:call",
+    "catalog:write",
+    "usage:read",
+    "billing:read",
+];
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct KeyScopes {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub scopes: Option<Vec<String>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub allowed_group_ids: Option<Vec<String>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub allowed_tools: Option<Vec<String>>,
+}
+
+impl KeyScopes {
+    pub fn from_row(row: &tokio_postgres::Row, first_col: usize) -> Self {
+        KeyScopes {
+            scopes: row.get(first_col),
+            allowed_group_ids: row.get(first_col + 1),
+            allowed_tools: row.get(first_col + 2),
+        }
+    }
+
+    /// Reject unknown scopes and empty names; dedupe the lists.
+    pub fn check(&mut self) -> Result<(), String> {
+        if let Some(scopes) = &self.scopes {
+            if let Some(bad) = scopes.iter().find(|s| !ALL_SCOPES.contains(&s.as_str())) {
+                return Err(format!(
+                    "unknown scope \"{}\" (expected one of {})",
+                    bad,
+                    ALL_SCOPES.join(", ")
+                ));
+            }
+        }
+        for (field, list) in [
+            ("scopes", &mut self.scopes),
+            ("allowed_group_ids", &mut self.allowed_group_ids),
+            ("allowed_tools", &mut self.allowed_tools),
+        ] {
+            if let Some(items) = list {
+                if items.iter().any(|i| i.trim().is_empty()) {
+                    return Err(format!("{} must not contain empty values", field));
+                }
+                items.sort();
+                items.dedup();
+            }
+        }
+        Ok(())
+    }
+
+    pub fn grants(&self, scope: &str) -> bool {
+        match &self.scopes {
+            None => true,
+            Some(scopes) => scopes.iter().any(|s| s == scope),
+        }
+    }
+
+    /// The scopes as the caller should see them (NULL expanded to all).
+    pub fn effective_scopes(&self) -> Vec<String> {
+        match &self.scopes {
+            Some(scopes) => scopes.clone(),
+            None => ALL_SCOPES.iter().map(|s| s.to_string()).collect(),
+        }
+    }
+}
+
+/// An OAuth `scope` parameter (space-separated), sorted and deduped; None
+/// when empty or absent.
+pub fn parse_scope_param(scope: Option<&str>) -> Result<Option<Vec<String>>, String> {
+    let Some(scope) = scope.map(str::trim).filter(|s| !s.is_empty()) else {
+        return Ok(None);
+    };
+    let mut scopes: Vec<String> = scope.split_whitespace().map(str::to_string).collect();
+    if let Some(bad) = scopes.iter().find(|s| !ALL_SCOPES.contains(&s.as_str())) {
+        return Err(format!("Unknown scope \"{}\"", bad));
+    }
+    scopes.sort();
+    scopes.dedup();
+    Ok(Some(scopes))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn scopes(list: &[&str]) -> KeyScopes {
+        KeyScopes { scopes: Some(list.iter().map(|s| s.to_string()).collect()), ..Default::default() }
+    }
+
+    #[test]
+    fn null_scopes_grant_everything() {
+        let legacy = KeyScopes::default();
+        assert!(ALL_SCOPES.iter().all(|s| legacy.grants(s)));
+        assert_eq!(legacy.effective_scopes().len(), ALL_SCOPES.len());
+    }
+
+    #[test]
+    fn listed_scopes_only() {
+        let ci = scopes(&["catalog:write"]);
+        assert!(ci.grants("catalog:write"));
+        assert!(!ci.grants("tools:call"));
+        assert!(!scopes(&[]).grants("tools:list"));
+    }
+
+    #[test]
+    fn check_rejects_unknown_and_dedupes() {
+        assert!(scopes(&["tools:delete"]).check().is_err());
+
+        let mut s = KeyScopes { allowed_tools: Some(vec!["b".into(), "a".into(), "b".into()]), ..scopes(&["tools:call", "tools:call"]) };
+        s.check().unwrap();
+        assert_eq!(s.scopes, Some(vec!["tools:call".to_string()]));
+        assert_eq!(s.allowed_tools, Some(vec!["a".to_string(), "b".to_string()]));
+
+        let mut empty = KeyScopes { allowed_group_ids: Some(vec![" ".into()]), ..Default::default() };
+        assert!(empty.check().is_err());
+    }
+
+    #[test]
+    fn scope_param_parsing() {
+        assert_eq!(parse_scope_param(None), Ok(None));
+        assert_eq!(parse_scope_param(Some("  ")), Ok(None));
+        assert_eq!(
+            parse_scope_param(Some("tools:call tools:list tools:call")),
+            Ok(Some(vec!["tools:call".to_string(), "tools:list".to_string()]))
+        );
+        assert!(parse_scope_param(Some("tools:call admin")).is_err());
+    }
+}
@@ -0,0 +1,309 @@
+// src/endpoint_store/key_usage.rs
+//
+// Per-key usage counters (api_keys.usage_count / last_used) and usage series.
+//
+//   - Every logged call is counted in memory by record(); flush() writes all
+//     pending counts in one UPDATE. main.rs runs it every
+//     API0_KEY_USAGE_FLUSH_SECS (default 10) and once more on shutdown, so
+//     the columns lag real usage by at most one interval.
+//   - A failed flush puts its counts back, to be retried with the next one.
+//   - Usage series are read from api_usage_logs, so they are exact and do not
+//     depend on the counters.
+
+use crate::app_log;
+use crate::endpoint_store::db_helpers::ResultExt;
+use crate::endpoint_store::{EndpointStore, StoreError};
+use chrono::{DateTime, Duration, Utc};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::{Mutex, OnceLock};
+
+const DEFAULT_FLUSH_SECS: u64 = 10;
+
+/// Calls counted since the last flush.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Pending {
+    calls: i64,
+    last_used: DateTime<Utc>,
+}
+
+impl Pending {
+    fn merge(&mut self, other: Pending) {
+        self.calls += other.calls;
+        self.last_used = self.last_used.max(other.last_used);
+    }
+}
+
+type PendingMap = HashMap<String, Pending>;
+
+static PENDING: OnceLock<Mutex<PendingMap>> = OnceLock::new();
+
+fn pending() -> &'static Mutex<PendingMap> {
+    PENDING.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+fn merge_into(map: &mut PendingMap, key_id: &str, p: Pending) {
+    match map.get_mut(key_id) {
+        Some(existing) => existing.merge(p),
+        None => {
+            map.insert(key_id.to_string(), p);
+        }
+    }
+}
+
+pub fn flush_interval() -> std::time::Duration {
+    let secs = std::env::var("API0_KEY_USAGE_FLUSH_SECS")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .filter(|s| *s > 0)
+        .unwrap_or(DEFAULT_FLUSH_SECS);
+    std::time::Duration::from_secs(secs)
+}
+
+/// Count one call of `key_id` made at `at`.
+pub fn record(key_id: &str, at: DateTime<Utc>) {
+    let mut map = pending().lock().unwrap_or_else(|e| e.into_inner());
+    merge_into(&mut map, key_id, Pending { calls: 1, last_used: at });
+}
+
+fn take_pending() -> PendingMap {
+    std::mem::take(&mut *pending().lock().unwrap_or_else(|e| e.into_inner()))
+}
+
+fn restore_pending(batch: PendingMap) {
+    let mut map = pending().lock().unwrap_or_else(|e| e.into_inner());
+    for (key_id, p) in batch {
+        merge_into(&mut map, &key_id, p);
+    }
+}
+
+/// Write pending counts to api_keys. Returns the number of keys updated.
+pub async fn flush(store: &EndpointStore) -> Result<u64, StoreError> {
+    let batch = take_pending();
+    if batch.is_empty() {
+        return Ok(0);
+    }
+
+    let mut ids = Vec::with_capacity(batch.len());
+    let mut calls = Vec::with_capacity(batch.len());
+    let mut last_used = Vec::with_capacity(batch.len());
+    for (key_id, p) in &batch {
+        ids.push(key_id.clone());
+        calls.push(p.calls);
+        last_used.push(p.last_used);
+    }
+
+    let result = async {
+        let client = store.get_admin_conn().await?;
+        client
+            .execute(
+                "UPDATE api_keys k SET
+                    usage_count = k.usage_count + u.calls,
+                    last_used = GREATEST(k.last_used, u.last_used)
+                 FROM unnest($1::VARCHAR[], $2::BIGINT[], $3::TIMESTAMPTZ[]) AS u(id, calls, last_used)
+                 WHERE k.id = u.id",
+                &[&ids, &calls, &last_used],
+            )
+            .await
+            .to_store_error()
+    }
+    .await;
+
+    if result.is_err() {
+        restore_pending(batch);
+    }
+    result
+}
+
+/// Flush and log the outcome; used by the scheduler and at shutdown.
+pub async fn flush_logged(store: &EndpointStore) {
+    match flush(store).await {
+        Ok(0) => {}
+        Ok(n) => app_log!(debug, keys = n, "Flushed API key usage counters"),
+        Err(e) => app_log!(error, error = %e, "Failed to flush API key usage counters; will retry"),
+    }
+}
+
+/// Bucket size of a usage series.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Granularity {
+    Hour,
+    Day,
+}
+
+impl Granularity {
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "hour" => Some(Granularity::Hour),
+            "day" => Some(Granularity::Day),
+            _ => None,
+        }
+    }
+
+    fn as_str(self) -> &'static str {
+        match self {
+            Granularity::Hour => "hour",
+            Granularity::Day => "day",
+        }
+    }
+
+    /// Longest range served at this granularity.
+    pub fn max_days(self) -> i64 {
+        match self {
+            Granularity::Hour => 14,
+            Granularity::Day => 365,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct UsagePoint {
+    pub bucket: String,
+    pub calls: i64,
+    pub total_tokens: i64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct KeyUsageSeries {
+    pub key_id: String,
+    pub key_name: String,
+    pub is_active: bool,
+    pub generated_at: String,
+    pub last_used: Option<String>,
+    pub usage_count: i64,
+    pub granularity: &'static str,
+    pub points: Vec<UsagePoint>,
+}
+
+/// Calls and tokens per hour/day over the last `days` days, oldest first,
+/// with empty buckets included. None if the key is not in the tenant.
+pub async fn usage_series(
+    store: &EndpointStore,
+    tenant_id: &str,
+    key_id: &str,
+    granularity: Granularity,
+    days: i64,
+) -> Result<Option<KeyUsageSeries>, StoreError> {
+    let client = store.get_conn(Some(tenant_id)).await?;
+
+    // Revoked keys are included: their history is what shows they were idle.
+    let key = client
+        .query_opt(
+            "SELECT key_name, is_active, generated_at, last_used, usage_count
+             FROM api_keys WHERE id = $1 AND tenant_id = $2",
+            &[&key_id, &tenant_id],
+        )
+        .await
+        .to_store_error()?;
+    let Some(key) = key else {
+        return Ok(None);
+    };
+
+    let unit = granularity.as_str();
+    let since = Utc::now() - Duration::days(days);
+    let rows = client
+        .query(
+            "WITH counts AS (
+                SELECT date_trunc($2, timestamp) AS bucket,
+                       COUNT(*) AS calls,
+                       COALESCE(SUM(total_tokens), 0)::BIGINT AS tokens
+                FROM api_usage_logs
+                WHERE key_id = $1 AND timestamp >= $3
+                GROUP BY 1
+             )
+             SELECT s.bucket, COALESCE(c.calls, 0), COALESCE(c.tokens, 0)
+             FROM generate_series(date_trunc($2, $3), date_trunc($2, NOW()), ('1 ' || $2)::INTERVAL) AS s(bucket)
+             LEFT JOIN counts c ON c.bucket = s.bucket
+             ORDER BY s.bucket",
+            &[&key_id, &unit, &since],
+        )
+        .await
+        .to_store_error()?;
+
+    Ok(Some(KeyUsageSeries {
+        key_id: key_id.to_string(),
+        key_name: key.get(0),
+        is_active: key.get(1),
+        generated_at: key.get::<_, DateTime<Utc>>(2).to_rfc3339(),
+        last_used: key.get::<_, Option<DateTime<Utc>>>(3).map(|t| t.to_rfc3339()),
+        usage_count: key.get(4),
+        granularity: unit,
+        points: rows
+            .iter()
+            .map(|r| UsagePoint {
+                bucket: r.get::<_, DateTime<Utc>>(0).to_rfc3339(),
+                calls: r.get(1),
+                total_tokens: r.get(2),
+            })
+            .collect(),
+    }))
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct StaleKey {
+    pub key_id: String,
+    pub key_name: String,
+    pub key_prefix: String,
+    pub generated_at: String,
+    /// None = never used.
+    pub last_used: Option<String>,
+    pub usage_count: i64,
+    pub idle_days: i64,
+}
+
+/// Active keys not used for at least `days` days (never-used keys count from
+/// creation), most idle first.
+pub async fn stale_keys(store: &EndpointStore, tenant_id: &str, days: i64) -> Result<Vec<StaleKey>, StoreError> {
+    let client = store.get_conn(Some(tenant_id)).await?;
+    let cutoff = Utc::now() - Duration::days(days);
+    let rows = client
+        .query(
+            "SELECT id, key_name, key_prefix, generated_at, last_used, usage_count,
+                    EXTRACT(DAY FROM NOW() - COALESCE(last_used, generated_at))::BIGINT
+             FROM api_keys
+             WHERE tenant_id = $1 AND is_active = true
+               AND COALESCE(last_used, generated_at) < $2
+             ORDER BY COALESCE(last_used, generated_at)",
+            &[&tenant_id, &cutoff],
+        )
+        .await
+        .to_store_error()?;
+
+    Ok(rows
+        .iter()
+        .map(|r| StaleKey {
+            key_id: r.get(0),
+            key_name: r.get(1),
+            key_prefix: r.get(2),
+            generated_at: r.get::<_, DateTime<Utc>>(3).to_rfc3339(),
+            last_used: r.get::<_, Option<DateTime<Utc>>>(4).map(|t| t.to_rfc3339()),
+            usage_count: r.get(5),
+            idle_days: r.get(6),
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn merge_adds_calls_and_keeps_latest_use() {
+        let t0 = Utc::now();
+        let t1 = t0 + Duration::seconds(5);
+        let mut map = PendingMap::new();
+        merge_into(&mut map, "k1", Pending { calls: 1, last_used: t1 });
+        merge_into(&mut map, "k1", Pending { calls: 3, last_used: t0 });
+        merge_into(&mut map, "k2", Pending { calls: 1, last_used: t0 });
+
+        assert_eq!(map["k1"], Pending { calls: 4, last_used: t1 });
+        assert_eq!(map["k2"], Pending { calls: 1, last_used: t0 });
+    }
+
+    #[test]
+    fn granularity_parse() {
+        assert_eq!(Granularity::parse("hour"), Some(Granularity::Hour));
+        assert_eq!(Granularity::parse("day"), Some(Granularity::Day));
+        assert_eq!(Granularity::parse("week"), None);
+    }
+}
@@ -0,0 +1,281 @@
+// src/endpoint_store/mcp_prompts_management.rs
+//
+// CRUD for the mcp_prompts table — canned MCP prompts (workflows) a tenant
+// ships to AI clients, plus rendering for `prompts/get`.
+//
+// Message templates reference arguments as `{{name}}`.
+
+use crate::app_log;
+use crate::endpoint_store::db_helpers::ResultExt;
+use crate::endpoint_store::{EndpointStore, StoreError};
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct PromptArgument {
+    pub name: String,
+    #[serde(default)]
+    pub description: String,
+    #[serde(default)]
+    pub required: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct PromptMessage {
+    /// "user" or "assistant"
+    pub role: String,
+    /// Text template; `{{arg}}` is replaced with the argument value.
+    pub content: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct McpPrompt {
+    pub id: String,
+    pub tenant_id: String,
+    pub name: String,
+    pub description: String,
+    pub arguments: Vec<PromptArgument>,
+    pub messages: Vec<PromptMessage>,
+    pub is_active: bool,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+impl McpPrompt {
+    /// Entry for an MCP `prompts/list` result.
+    pub fn to_mcp_listing(&self) -> serde_json::Value {
+        serde_json::json!({
+            "name": self.name,
+            "description": self.description,
+            "arguments": self.arguments,
+        })
+    }
+
+    /// Body of an MCP `prompts/get` result with arguments substituted.
+    /// Fails when a required argument is missing.
+    pub fn render(
+        &self,
+        arguments: &serde_json::Map<String, serde_json::Value>,
+    ) -> Result<serde_json::Value, String> {
+        let missing: Vec<&str> = self
+            .arguments
+            .iter()
+            .filter(|a| a.required && !arguments.contains_key(&a.name))
+            .map(|a| a.name.as_str())
+            .collect();
+        if !missing.is_empty() {
+            return Err(format!("missing required arguments: {}", missing.join(", ")));
+        }
+
+        let messages: Vec<serde_json::Value> = self
+            .messages
+            .iter()
+            .map(|m| {
+                serde_json::json!({
+                    "role": m.role,
+                    "content": { "type": "text", "text": fill_template(&m.content, arguments) },
+                })
+            })
+            .collect();
+
+        Ok(serde_json::json!({ "description": self.description, "messages": messages }))
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct UpsertMcpPromptRequest {
+    pub name: String,
+    pub description: Option<String>,
+    #[serde(default)]
+    pub arguments: Vec<PromptArgument>,
+    pub messages: Vec<PromptMessage>,
+}
+
+impl UpsertMcpPromptRequest {
+    pub fn check(&self) -> Result<(), String> {
+        if self.name.trim().is_empty() {
+            return Err("name is required".to_string());
+        }
+        if self.messages.is_empty() {
+            return Err("at least one message is required".to_string());
+        }
+        if let Some(m) = self.messages.iter().find(|m| m.role != "user" && m.role != "assistant") {
+            return Err(format!("invalid message role \"{}\" (user or assistant)", m.role));
+        }
+        Ok(())
+    }
+}
+
+pub async fn upsert_mcp_prompt(
+    store: &EndpointStore,
+    tenant_id: &str,
+    req: &UpsertMcpPromptRequest,
+) -> Result<McpPrompt, StoreError> {
+    let client = store.get_conn(Some(tenant_id)).await?;
+    let id = Uuid::new_v4().to_string();
+    let now = Utc::now();
+    let description = req.description.as_deref().unwrap_or("");
+    let arguments = serde_json::to_value(&req.arguments).unwrap_or_default();
+    let messages = serde_json::to_value(&req.messages).unwrap_or_default();
+
+    let row = client
+        .query_one(
+            "INSERT INTO mcp_prompts
+                (id, tenant_id, name, description, arguments, messages,
+                 is_active, created_at, updated_at)
+             VALUES ($1, $2, $3, $4, $5, $6, true, $7, $7)
+             ON CONFLICT (tenant_id, name) DO UPDATE SET
+                description = EXCLUDED.description,
+                arguments   = EXCLUDED.arguments,
+                messages    = EXCLUDED.messages,
+                is_active   = true,
+                updated_at  = EXCLUDED.updated_at
+             RETURNING id, tenant_id, name, description, arguments, messages,
+                       is_active, created_at, updated_at",
+            &[
+                &id as &(dyn tokio_postgres::types::ToSql + Sync),
+                &tenant_id as &(dyn tokio_postgres::types::ToSql + Sync),
+                &req.name as &(dyn tokio_postgres::types::ToSql + Sync),
+                &description as &(dyn tokio_postgres::types::ToSql + Sync),
+                &arguments as &(dyn tokio_postgres::types::ToSql + Sync),
+                &messages as &(dyn tokio_postgres::types::ToSql + Sync),
+                &now as &(dyn tokio_postgres::types::ToSql + Sync),
+            ],
+        )
+        .await
+        .to_store_error()?;
+
+    app_log!(info, tenant_id = %tenant_id, prompt = %req.name, "Upserted MCP prompt");
+
+    Ok(row_to_prompt(row))
+}
+
+pub async fn list_mcp_prompts(
+    store: &EndpointStore,
+    tenant_id: &str,
+) -> Result<Vec<McpPrompt>, StoreError> {
+    let client = store.get_conn(Some(tenant_id)).await?;
+
+    let rows = client
+        .query(
+            "SELECT id, tenant_id, name, description, arguments, messages,
+                    is_active, created_at, updated_at
+             FROM mcp_prompts
+             WHERE tenant_id = $1 AND is_active = true
+             ORDER BY name",
+            &[&tenant_id],
+        )
+        .await
+        .to_store_error()?;
+
+    Ok(rows.into_iter().map(row_to_prompt).collect())
+}
+
+pub async fn get_mcp_prompt(
+    store: &EndpointStore,
+    tenant_id: &str,
+    name: &str,
+) -> Result<Option<McpPrompt>, StoreError> {
+    let client = store.get_conn(Some(tenant_id)).await?;
+
+    let row = client
+        .query_opt(
+            "SELECT id, tenant_id, name, description, arguments, messages,
+                    is_active, created_at, updated_at
+             FROM mcp_prompts
+             WHERE tenant_id = $1 AND name = $2 AND is_active = true",
+            &[&tenant_id, &name],
+        )
+        .await
+        .to_store_error()?;
+
+    Ok(row.map(row_to_prompt))
+}
+
+pub async fn delete_mcp_prompt(
+    store: &EndpointStore,
+    tenant_id: &str,
+    name: &str,
+) -> Result<bool, StoreError> {
+    let client = store.get_conn(Some(tenant_id)).await?;
+
+    let n = client
+        .execute(
+            "UPDATE mcp_prompts SET is_active = false, updated_at = NOW()
+             WHERE tenant_id = $1 AND name = $2 AND is_active = true",
+            &[&tenant_id, &name],
+        )
+        .await
+        .to_store_error()?;
+
+    Ok(n > 0)
+}
+
+// ── helpers ───────────────────────────────────────────────────────────────────
+
+fn fill_template(template: &str, arguments: &serde_json::Map<String, serde_json::Value>) -> String {
+    let mut out = template.to_string();
+    for (name, value) in arguments {
+        let text = match value {
+            serde_json::Value::String(s) => s.clone(),
+            other => other.to_string(),
+        };
+        out = out.replace(&format!("{{{{{}}}}}", name), &text);
+    }
+    out
+}
+
+fn row_to_prompt(row: tokio_postgres::Row) -> McpPrompt {
+    McpPrompt {
+        id:          row.get(0),
+        tenant_id:   row.get(1),
+        name:        row.get(2),
+        description: row.get(3),
+        arguments:   serde_json::from_value(row.get(4)).unwrap_or_default(),
+        messages:    serde_json::from_value(row.get(5)).unwrap_or_default(),
+        is_active:   row.get(6),
+        created_at:  row.get::<_, chrono::DateTime<Utc>>(7).to_rfc3339(),
+        updated_at:  row.get::<_, chrono::DateTime<Utc>>(8).to_rfc3339(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn prompt() -> McpPrompt {
+        McpPrompt {
+            id: "p1".into(),
+            tenant_id: "t1".into(),
+            name: "review".into(),
+            description: "Review a CV".into(),
+            arguments: vec![
+                PromptArgument { name: "role".into(), description: String::new(), required: true },
+                PromptArgument { name: "years".into(), description: String::new(), required: false },
+            ],
+            messages: vec![PromptMessage {
+                role: "user".into(),
+                content: "Review this CV for a {{role}} with {{years}} years.".into(),
+            }],
+            is_active: true,
+            created_at: String::new(),
+            updated_at: String::new(),
+        }
+    }
+
+    #[test]
+    fn render_substitutes_arguments() {
+        let args = json!({ "role": "SRE", "years": 5 });
+        let out = prompt().render(args.as_object().unwrap()).unwrap();
+        assert_eq!(out["messages"][0]["content"]["text"], json!("Review this CV for a SRE with 5 years."));
+        assert_eq!(out["messages"][0]["role"], json!("user"));
+    }
+
+    #[test]
+    fn render_requires_required_arguments() {
+        let err = prompt().render(&serde_json::Map::new()).unwrap_err();
+        assert!(err.contains("role"));
+    }
+}
@@ -0,0 +1,254 @@
+// src/endpoint_store/mcp_resources_management.rs
+//
+// CRUD for the mcp_resources table — MCP resources a tenant exposes to AI
+// clients next to its tools (documentation, reference tables, …).
+//
+// A resource either carries its content inline or points at a backend URL the
+// gateway fetches on resources/read. Uploaded reference_data can be published
+// as an inline JSON resource.
+
+use crate::app_log;
+use crate::endpoint_store::db_helpers::ResultExt;
+use crate::endpoint_store::tenant_management::verify_tenant_access_with_conn;
+use crate::endpoint_store::{EndpointStore, StoreError};
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct McpResource {
+    pub id: String,
+    pub tenant_id: String,
+    pub uri: String,
+    pub name: String,
+    pub description: String,
+    pub mime_type: String,
+    /// Inline content. None when the resource is fetched from `fetch_url`.
+    pub content: Option<String>,
+    pub fetch_url: Option<String>,
+    /// Set when the resource was published from an uploaded reference_data row.
+    pub reference_data_id: Option<String>,
+    pub is_active: bool,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+impl McpResource {
+    /// Entry for an MCP `resources/list` result.
+    pub fn to_mcp_listing(&self) -> serde_json::Value {
+        serde_json::json!({
+            "uri": self.uri,
+            "name": self.name,
+            "description": self.description,
+            "mimeType": self.mime_type,
+        })
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct UpsertMcpResourceRequest {
+    pub uri: String,
+    pub name: String,
+    pub description: Option<String>,
+    pub mime_type: Option<String>,
+    pub content: Option<String>,
+    pub fetch_url: Option<String>,
+}
+
+impl UpsertMcpResourceRequest {
+    pub fn check(&self) -> Result<(), String> {
+        if self.uri.trim().is_empty() || !self.uri.contains(':') {
+            return Err("uri must be an absolute URI (e.g. docs://guide)".to_string());
+        }
+        match (&self.content, &self.fetch_url) {
+            (Some(_), None) | (None, Some(_)) => Ok(()),
+            _ => Err("exactly one of content or fetch_url is required".to_string()),
+        }
+    }
+}
+
+pub async fn upsert_mcp_resource(
+    store: &EndpointStore,
+    tenant_id: &str,
+    req: &UpsertMcpResourceRequest,
+) -> Result<McpResource, StoreError> {
+    upsert_resource_row(store, tenant_id, req, None).await
+}
+
+async fn upsert_resource_row(
+    store: &EndpointStore,
+    tenant_id: &str,
+    req: &UpsertMcpResourceRequest,
+    reference_data_id: Option<&str>,
+) -> Result<McpResource, StoreError> {
+    let client = store.get_conn(Some(tenant_id)).await?;
+    let id = Uuid::new_v4().to_string();
+    let now = Utc::now();
+    let description = req.description.as_deref().unwrap_or("");
+    let mime_type = req.mime_type.as_deref().unwrap_or("text/plain");
+
+    let row = client
+        .query_one(
+            "INSERT INTO mcp_resources
+                (id, tenant_id, uri, name, description, mime_type, content,
+                 fetch_url, reference_data_id, is_active, created_at, updated_at)
+             VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, true, $10, $10)
+             ON CONFLICT (tenant_id, uri) DO UPDATE SET
+                name              = EXCLUDED.name,
+                description       = EXCLUDED.description,
+                mime_type         = EXCLUDED.mime_type,
+                content           = EXCLUDED.content,
+                fetch_url         = EXCLUDED.fetch_url,
+                reference_data_id = EXCLUDED.reference_data_id,
+                is_active         = true,
+                updated_at        = EXCLUDED.updated_at
+             RETURNING id, tenant_id, uri, name, description, mime_type, content,
+                       fetch_url, reference_data_id, is_active, created_at, updated_at",
+            &[
+                &id as &(dyn tokio_postgres::types::ToSql + Sync),
+                &tenant_id as &(dyn tokio_postgres::types::ToSql + Sync),
+                &req.uri as &(dyn tokio_postgres::types::ToSql + Sync),
+                &req.name as &(dyn tokio_postgres::types::ToSql + Sync),
+                &description as &(dyn tokio_postgres::types::ToSql + Sync),
+                &mime_type as &(dyn tokio_postgres::types::ToSql + Sync),
+                &req.content as &(dyn tokio_postgres::types::ToSql + Sync),
+                &req.fetch_url as &(dyn tokio_postgres::types::ToSql + Sync),
+                &reference_data_id as &(dyn tokio_postgres::types::ToSql + Sync),
+                &now as &(dyn tokio_postgres::types::ToSql + Sync),
+            ],
+        )
+        .await
+        .to_store_error()?;
+
+    app_log!(info, tenant_id = %tenant_id, uri = %req.uri, "Upserted MCP resource");
+
+    Ok(row_to_resource(row))
+}
+
+pub async fn list_mcp_resources(
+    store: &EndpointStore,
+    tenant_id: &str,
+) -> Result<Vec<McpResource>, StoreError> {
+    let client = store.get_conn(Some(tenant_id)).await?;
+
+    let rows = client
+        .query(
+            "SELECT id, tenant_id, uri, name, description, mime_type, content,
+                    fetch_url, reference_data_id, is_active, created_at, updated_at
+             FROM mcp_resources
+             WHERE tenant_id = $1 AND is_active = true
+             ORDER BY uri",
+            &[&tenant_id],
+        )
+        .await
+        .to_store_error()?;
+
+    Ok(rows.into_iter().map(row_to_resource).collect())
+}
+
+pub async fn get_mcp_resource(
+    store: &EndpointStore,
+    tenant_id: &str,
+    uri: &str,
+) -> Result<Option<McpResource>, StoreError> {
+    let client = store.get_conn(Some(tenant_id)).await?;
+
+    let row = client
+        .query_opt(
+            "SELECT id, tenant_id, uri, name, description, mime_type, content,
+                    fetch_url, reference_data_id, is_active, created_at, updated_at
+             FROM mcp_resources
+             WHERE tenant_id = $1 AND uri = $2 AND is_active = true",
+            &[&tenant_id, &uri],
+        )
+        .await
+        .to_store_error()?;
+
+    Ok(row.map(row_to_resource))
+}
+
+pub async fn delete_mcp_resource(
+    store: &EndpointStore,
+    tenant_id: &str,
+    uri: &str,
+) -> Result<bool, StoreError> {
+    let client = store.get_conn(Some(tenant_id)).await?;
+
+    let n = client
+        .execute(
+            "UPDATE mcp_resources SET is_active = false, updated_at = NOW()
+             WHERE tenant_id = $1 AND uri = $2 AND is_active = true",
+            &[&tenant_id, &uri],
+        )
+        .await
+        .to_store_error()?;
+
+    Ok(n > 0)
+}
+
+/// Publish an uploaded reference_data row as an inline JSON resource.
+/// The uploader must be a member of the tenant. Re-publishing refreshes the
+/// content from the current reference_data row.
+/// Returns `None` when the row does not exist or belongs to someone outside the tenant.
+pub async fn publish_reference_data_as_resource(
+    store: &EndpointStore,
+    tenant_id: &str,
+    reference_data_id: &str,
+    uri: Option<&str>,
+    description: Option<&str>,
+) -> Result<Option<McpResource>, StoreError> {
+    let client = store.get_admin_conn().await?;
+
+    let row = client
+        .query_opt(
+            "SELECT email, name, data FROM reference_data WHERE id = $1",
+            &[&reference_data_id],
+        )
+        .await
+        .to_store_error()?;
+
+    let (email, name, data): (String, String, serde_json::Value) = match row {
+        Some(r) => (r.get(0), r.get(1), r.get(2)),
+        None => return Ok(None),
+    };
+
+    if !verify_tenant_access_with_conn(&client, &email, tenant_id).await? {
+        app_log!(warn, tenant_id = %tenant_id, reference_data_id = %reference_data_id,
+            "Refusing to publish reference data owned by a non-member");
+        return Ok(None);
+    }
+
+    let req = UpsertMcpResourceRequest {
+        uri: uri
+            .map(String::from)
+            .unwrap_or_else(|| format!("reference://{}", reference_data_id)),
+        name,
+        description: description.map(String::from),
+        mime_type: Some("application/json".to_string()),
+        content: Some(serde_json::to_string_pretty(&data).unwrap_or_else(|_| data.to_string())),
+        fetch_url: None,
+    };
+
+    upsert_resource_row(store, tenant_id, &req, Some(reference_data_id))
+        .await
+        .map(Some)
+}
+
+// ── helpers ───────────────────────────────────────────────────────────────────
+
+fn row_to_resource(row: tokio_postgres::Row) -> McpResource {
+    McpResource {
+        id:                row.get(0),
+        tenant_id:         row.get(1),
+        uri:               row.get(2),
+        name:              row.get(3),
+        description:       row.get(4),
+        mime_type:         row.get(5),
+        content:           row.get(6),
+        fetch_url:         row.get(7),
+        reference_data_id: row.get(8),
+        is_active:         row.get(9),
+        created_at:        row.get::<_, chrono::DateTime<Utc>>(10).to_rfc3339(),
+        updated_at:        row.get::<_, chrono::DateTime<Utc>>(11).to_rfc3339(),
+    }
+}
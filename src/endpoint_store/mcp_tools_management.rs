@@ -4,11 +4,17 @@
 // Called by mcp_tools_handler (HTTP) and the gateway via /api/mcp-tools/* endpoints.
 
 use crate::app_log;
+use crate::endpoint_store::argument_validation::{validate_arguments, ArgumentValidation};
+use crate::endpoint_store::request_mapping::{render_request, RenderedRequest, RequestMapping};
+use crate::endpoint_store::toolsets_management::{self as toolsets, get_key_toolsets};
+use crate::endpoint_store::tool_resilience::{load_circuits, policy_from_row, Circuit, CircuitState, ResiliencePolicy};
 use crate::endpoint_store::db_helpers::ResultExt;
+use crate::endpoint_store::secret_vault;
 use crate::endpoint_store::{ApiGroupWithEndpoints, EndpointStore, StoreError};
 use chrono::Utc;
 use serde::{Deserialize, Serialize};
 use slug::slugify;
+use std::collections::HashMap;
 use uuid::Uuid;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -28,6 +34,17 @@ pub struct McpTool {
     /// When Some("GET"|"POST"|…) the gateway does REST passthrough.
     /// When None the backend is expected to speak native MCP format.
     pub http_verb: Option<String>,
+    /// How arguments map onto the REST request when `http_verb` is set.
+    /// None = default placement (query for GET/HEAD/DELETE, JSON body otherwise).
+    pub request_mapping: Option<RequestMapping>,
+    /// Retry / concurrency / circuit-breaker policy applied by the gateway.
+    pub resilience: ResiliencePolicy,
+    /// Current circuit state derived from reported call outcomes.
+    pub circuit_state: CircuitState,
+    /// Free-form labels toolsets can select on.
+    pub tags: Vec<String>,
+    /// api_groups.id the tool was derived from (always set for virtual tools).
+    pub group_id: Option<String>,
     pub is_active: bool,
     pub created_at: String,
     pub updated_at: String,
@@ -43,6 +60,24 @@ pub struct UpsertMcpToolRequest {
     pub timeout_ms: Option<i32>,
     /// REST verb for endpoint-imported tools. None = native MCP backend.
     pub http_verb: Option<String>,
+    /// Absent = keep the stored mapping (config re-uploads carry none);
+    /// an explicit null clears it.
+    #[serde(default, deserialize_with = "present_or_null")]
+    pub request_mapping: Option<Option<RequestMapping>>,
+    /// None = keep the stored policy; new tools get the default.
+    pub resilience: Option<ResiliencePolicy>,
+    /// None = keep the stored tags; new tools get none.
+    pub tags: Option<Vec<String>>,
+    pub group_id: Option<String>,
+}
+
+/// Some(None) for an explicit null, so "absent" and "null" stay apart.
+fn present_or_null<'de, D, T>(deserializer: D) -> Result<Option<Option<T>>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+    T: Deserialize<'de>,
+{
+    Option::<T>::deserialize(deserializer).map(Some)
 }
 
 pub async fn upsert_mcp_tool(
@@ -64,14 +99,26 @@ pub async fn upsert_mcp_tool(
 
     // INSERT ... ON CONFLICT(tenant_id, tool_name) DO UPDATE
     let http_verb = req.http_verb.as_deref().map(|v| v.to_uppercase());
+    let keep_mapping = req.request_mapping.is_none();
+    let request_mapping = req
+        .request_mapping
+        .as_ref()
+        .and_then(|m| m.as_ref())
+        .and_then(|m| serde_json::to_value(m).ok());
+    let keep_resilience = req.resilience.is_none();
+    let resilience = req.resilience.clone().unwrap_or_default();
 
     let row = client
         .query_one(
             "INSERT INTO mcp_tools
                 (id, tenant_id, tool_name, backend_url, description,
                  input_schema, cost_credits, timeout_ms, http_verb, is_active,
-                 created_at, updated_at)
-             VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, true, $10, $10)
+                 created_at, updated_at, request_mapping,
+                 retry_max_attempts, retry_backoff_ms, retry_max_backoff_ms, retry_on_status,
+                 max_concurrency, circuit_failure_threshold, circuit_cooldown_ms,
+                 tags, group_id)
+             VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, true, $10, $10, $11,
+                     $12, $13, $14, $15, $16, $17, $18, COALESCE($19::TEXT[], '{}'), $20)
              ON CONFLICT (tenant_id, tool_name) DO UPDATE SET
                 backend_url  = EXCLUDED.backend_url,
                 description  = EXCLUDED.description,
@@ -79,11 +126,24 @@ pub async fn upsert_mcp_tool(
                 cost_credits = EXCLUDED.cost_credits,
                 timeout_ms   = EXCLUDED.timeout_ms,
                 http_verb    = EXCLUDED.http_verb,
+                request_mapping = CASE WHEN $22 THEN mcp_tools.request_mapping ELSE EXCLUDED.request_mapping END,
+                retry_max_attempts        = CASE WHEN $21 THEN mcp_tools.retry_max_attempts ELSE EXCLUDED.retry_max_attempts END,
+                retry_backoff_ms          = CASE WHEN $21 THEN mcp_tools.retry_backoff_ms ELSE EXCLUDED.retry_backoff_ms END,
+                retry_max_backoff_ms      = CASE WHEN $21 THEN mcp_tools.retry_max_backoff_ms ELSE EXCLUDED.retry_max_backoff_ms END,
+                retry_on_status           = CASE WHEN $21 THEN mcp_tools.retry_on_status ELSE EXCLUDED.retry_on_status END,
+                max_concurrency           = CASE WHEN $21 THEN mcp_tools.max_concurrency ELSE EXCLUDED.max_concurrency END,
+                circuit_failure_threshold = CASE WHEN $21 THEN mcp_tools.circuit_failure_threshold ELSE EXCLUDED.circuit_failure_threshold END,
+                circuit_cooldown_ms       = CASE WHEN $21 THEN mcp_tools.circuit_cooldown_ms ELSE EXCLUDED.circuit_cooldown_ms END,
+                tags                      = COALESCE($19::TEXT[], mcp_tools.tags),
+                group_id                  = EXCLUDED.group_id,
                 is_active    = true,
                 updated_at   = EXCLUDED.updated_at
              RETURNING id, tenant_id, tool_name, backend_url, description,
                        input_schema, cost_credits, timeout_ms, http_verb,
-                       is_active, created_at, updated_at",
+                       is_active, created_at, updated_at, request_mapping,
+                       retry_max_attempts, retry_backoff_ms, retry_max_backoff_ms, retry_on_status,
+                       max_concurrency, circuit_failure_threshold, circuit_cooldown_ms,
+                       tags, group_id",
             &[
                 &id as &(dyn tokio_postgres::types::ToSql + Sync),
                 &tenant_id as &(dyn tokio_postgres::types::ToSql + Sync),
@@ -95,6 +155,18 @@ pub async fn upsert_mcp_tool(
                 &timeout_ms as &(dyn tokio_postgres::types::ToSql + Sync),
                 &http_verb as &(dyn tokio_postgres::types::ToSql + Sync),
                 &now as &(dyn tokio_postgres::types::ToSql + Sync),
+                &request_mapping as &(dyn tokio_postgres::types::ToSql + Sync),
+                &resilience.retry_max_attempts as &(dyn tokio_postgres::types::ToSql + Sync),
+                &resilience.retry_backoff_ms as &(dyn tokio_postgres::types::ToSql + Sync),
+                &resilience.retry_max_backoff_ms as &(dyn tokio_postgres::types::ToSql + Sync),
+                &resilience.retry_on_status as &(dyn tokio_postgres::types::ToSql + Sync),
+                &resilience.max_concurrency as &(dyn tokio_postgres::types::ToSql + Sync),
+                &resilience.circuit_failure_threshold as &(dyn tokio_postgres::types::ToSql + Sync),
+                &resilience.circuit_cooldown_ms as &(dyn tokio_postgres::types::ToSql + Sync),
+                &req.tags as &(dyn tokio_postgres::types::ToSql + Sync),
+                &req.group_id as &(dyn tokio_postgres::types::ToSql + Sync),
+                &keep_resilience as &(dyn tokio_postgres::types::ToSql + Sync),
+                &keep_mapping as &(dyn tokio_postgres::types::ToSql + Sync),
             ],
         )
         .await
@@ -110,10 +182,12 @@ pub async fn upsert_mcp_tool(
     Ok(row_to_tool(row))
 }
 
+/// `key_id` restricts the listing to the key's toolsets (if it has any).
 pub async fn list_mcp_tools(
     store: &EndpointStore,
     tenant_id: &str,
     user_email: Option<&str>,
+    key_id: Option<&str>,
 ) -> Result<Vec<McpTool>, StoreError> {
     let client = store.get_conn(Some(tenant_id)).await?;
 
@@ -122,7 +196,10 @@ pub async fn list_mcp_tools(
         .query(
             "SELECT id, tenant_id, tool_name, backend_url, description,
                     input_schema, cost_credits, timeout_ms, http_verb,
-                    is_active, created_at, updated_at
+                    is_active, created_at, updated_at, request_mapping,
+                    retry_max_attempts, retry_backoff_ms, retry_max_backoff_ms, retry_on_status,
+                    max_concurrency, circuit_failure_threshold, circuit_cooldown_ms,
+                    tags, group_id
              FROM mcp_tools
              WHERE tenant_id = $1 AND is_active = true
              ORDER BY tool_name",
@@ -131,12 +208,16 @@ pub async fn list_mcp_tools(
         .await
         .to_store_error()?;
 
-    let mut all_tools: Vec<McpTool> = explicit_rows.into_iter().map(row_to_tool).collect();
+    let circuits = load_circuits(&client, tenant_id).await?;
+    let mut all_tools: Vec<McpTool> = explicit_rows
+        .into_iter()
+        .map(|r| with_circuit_state(row_to_tool(r), &circuits))
+        .collect();
 
     // 2. Fetch all endpoints for this tenant OR for this email
     let endpoint_rows = if let Some(email) = user_email {
         client.query(
-            "SELECT DISTINCT g.name, e.text, e.description, e.suggested_sentence, e.verb, e.base, g.base, e.path, e.id
+            "SELECT DISTINCT g.name, e.text, e.description, e.suggested_sentence, e.verb, e.base, g.base, e.path, e.id, g.id
              FROM api_groups g
              JOIN endpoints e ON g.id = e.group_id
              LEFT JOIN user_groups ug ON g.id = ug.group_id
@@ -145,7 +226,7 @@ pub async fn list_mcp_tools(
         ).await.to_store_error()?
     } else {
         client.query(
-            "SELECT g.name, e.text, e.description, e.suggested_sentence, e.verb, e.base, g.base, e.path, e.id
+            "SELECT g.name, e.text, e.description, e.suggested_sentence, e.verb, e.base, g.base, e.path, e.id, g.id
              FROM api_groups g
              JOIN endpoints e ON g.id = e.group_id
              WHERE g.tenant_id = $1",
@@ -165,6 +246,7 @@ pub async fn list_mcp_tools(
         let g_base: String = row.get(6);
         let path: String = row.get(7);
         let endpoint_id: String = row.get(8);
+        let group_id: String = row.get(9);
         
         let raw_name = format!("{} {}", group_name, endpoint_text);
         let tool_name = slugify(&raw_name);
@@ -203,6 +285,7 @@ pub async fn list_mcp_tools(
         
         let input_schema = build_input_schema(&params);
 
+        let circuit_state = circuit_state(&circuits, &tool_name, &ResiliencePolicy::default());
         all_tools.push(McpTool {
             id: format!("virtual-{}", endpoint_id),
             tenant_id: tenant_id.to_string(),
@@ -216,20 +299,54 @@ pub async fn list_mcp_tools(
             cost_credits: None,
             timeout_ms: 30000,
             http_verb: Some(verb.to_uppercase()),
+            request_mapping: None,
+            resilience: ResiliencePolicy::default(),
+            circuit_state,
+            tags: Vec::new(),
+            group_id: Some(group_id),
             is_active: true,
             created_at: Utc::now().to_rfc3339(),
             updated_at: Utc::now().to_rfc3339(),
         });
     }
 
+    if let Some(key_id) = key_id {
+        let toolsets = get_key_toolsets(store, tenant_id, key_id).await?;
+        all_tools.retain(|t| toolsets::permits(&toolsets, t));
+    }
+
     Ok(all_tools)
 }
 
+/// Look up one tool. With `key_id`, a tool outside the key's toolsets is
+/// reported as not found.
 pub async fn get_mcp_tool(
     store: &EndpointStore,
     tenant_id: &str,
     tool_name: &str,
     user_email: Option<&str>,
+    key_id: Option<&str>,
+) -> Result<Option<McpTool>, StoreError> {
+    let tool = match find_mcp_tool(store, tenant_id, tool_name, user_email).await? {
+        Some(t) => t,
+        None => return Ok(None),
+    };
+
+    if let Some(key_id) = key_id {
+        let toolsets = get_key_toolsets(store, tenant_id, key_id).await?;
+        if !toolsets::permits(&toolsets, &tool) {
+            return Ok(None);
+        }
+    }
+
+    Ok(Some(tool))
+}
+
+async fn find_mcp_tool(
+    store: &EndpointStore,
+    tenant_id: &str,
+    tool_name: &str,
+    user_email: Option<&str>,
 ) -> Result<Option<McpTool>, StoreError> {
     let client = store.get_conn(Some(tenant_id)).await?;
 
@@ -238,7 +355,10 @@ pub async fn get_mcp_tool(
         .query_opt(
             "SELECT id, tenant_id, tool_name, backend_url, description,
                     input_schema, cost_credits, timeout_ms, http_verb,
-                    is_active, created_at, updated_at
+                    is_active, created_at, updated_at, request_mapping,
+                    retry_max_attempts, retry_backoff_ms, retry_max_backoff_ms, retry_on_status,
+                    max_concurrency, circuit_failure_threshold, circuit_cooldown_ms,
+                    tags, group_id
              FROM mcp_tools
              WHERE tenant_id = $1 AND tool_name = $2 AND is_active = true",
             &[&tenant_id, &tool_name],
@@ -246,14 +366,15 @@ pub async fn get_mcp_tool(
         .await
         .to_store_error()?;
 
+    let circuits = load_circuits(&client, tenant_id).await?;
     if let Some(r) = row {
-        return Ok(Some(row_to_tool(r)));
+        return Ok(Some(with_circuit_state(row_to_tool(r), &circuits)));
     }
 
     // 2. Check virtual tools (endpoints)
     let endpoint_rows = if let Some(email) = user_email {
         client.query(
-            "SELECT DISTINCT g.name, e.text, e.description, e.suggested_sentence, e.verb, e.base, g.base, e.path, e.id
+            "SELECT DISTINCT g.name, e.text, e.description, e.suggested_sentence, e.verb, e.base, g.base, e.path, e.id, g.id
              FROM api_groups g
              JOIN endpoints e ON g.id = e.group_id
              LEFT JOIN user_groups ug ON g.id = ug.group_id
@@ -262,7 +383,7 @@ pub async fn get_mcp_tool(
         ).await.to_store_error()?
     } else {
         client.query(
-            "SELECT g.name, e.text, e.description, e.suggested_sentence, e.verb, e.base, g.base, e.path, e.id
+            "SELECT g.name, e.text, e.description, e.suggested_sentence, e.verb, e.base, g.base, e.path, e.id, g.id
              FROM api_groups g
              JOIN endpoints e ON g.id = e.group_id
              WHERE g.tenant_id = $1",
@@ -283,6 +404,7 @@ pub async fn get_mcp_tool(
             let g_base: String = row.get(6);
             let path: String = row.get(7);
             let endpoint_id: String = row.get(8);
+            let group_id: String = row.get(9);
 
             let base = if e_base.is_empty() { &g_base } else { &e_base };
             let backend_url = format!("{}{}", base.trim_end_matches('/'), path);
@@ -321,6 +443,11 @@ pub async fn get_mcp_tool(
                 cost_credits: None,
                 timeout_ms: 30000,
                 http_verb: Some(verb.to_uppercase()),
+                request_mapping: None,
+                resilience: ResiliencePolicy::default(),
+                circuit_state: circuit_state(&circuits, tool_name, &ResiliencePolicy::default()),
+                tags: Vec::new(),
+                group_id: Some(group_id),
                 is_active: true,
                 created_at: Utc::now().to_rfc3339(),
                 updated_at: Utc::now().to_rfc3339(),
@@ -331,6 +458,81 @@ pub async fn get_mcp_tool(
     Ok(None)
 }
 
+/// Validate `tools/call` arguments against the tool's input_schema.
+/// Works for explicit and virtual (endpoint-derived) tools alike.
+/// Returns `None` when the tool does not exist. A stored schema that is not
+/// valid JSON is treated as accepting anything.
+pub async fn validate_mcp_tool_arguments(
+    store: &EndpointStore,
+    tenant_id: &str,
+    tool_name: &str,
+    user_email: Option<&str>,
+    key_id: Option<&str>,
+    arguments: &serde_json::Value,
+) -> Result<Option<ArgumentValidation>, StoreError> {
+    let tool = match get_mcp_tool(store, tenant_id, tool_name, user_email, key_id).await? {
+        Some(t) => t,
+        None => return Ok(None),
+    };
+
+    let schema: serde_json::Value = serde_json::from_str(&tool.input_schema).unwrap_or_else(|e| {
+        app_log!(warn, tenant_id = %tenant_id, tool_name = %tool_name, error = %e,
+            "Stored input_schema is not valid JSON — skipping argument validation");
+        serde_json::Value::Bool(true)
+    });
+
+    Ok(Some(validate_arguments(&schema, arguments)))
+}
+
+/// Outcome of rendering a tools/call into a concrete backend request.
+#[derive(Debug, Serialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum RenderOutcome {
+    Rendered { request: RenderedRequest, arguments: serde_json::Value },
+    InvalidArguments { errors: Vec<crate::endpoint_store::argument_validation::ArgumentError> },
+    MappingError { error: String },
+}
+
+/// Validate the arguments, then apply the tool's request mapping.
+/// Returns `None` when the tool does not exist. With `resolve_secrets` the
+/// tool's {{secret:NAME}} references are substituted first (gateway only).
+pub async fn render_mcp_tool_request(
+    store: &EndpointStore,
+    tenant_id: &str,
+    tool_name: &str,
+    user_email: Option<&str>,
+    key_id: Option<&str>,
+    arguments: &serde_json::Value,
+    resolve_secrets: bool,
+) -> Result<Option<RenderOutcome>, StoreError> {
+    let mut tool = match get_mcp_tool(store, tenant_id, tool_name, user_email, key_id).await? {
+        Some(t) => t,
+        None => return Ok(None),
+    };
+    if resolve_secrets {
+        secret_vault::resolve_tool_secrets(store, tenant_id, &mut tool).await?;
+    }
+
+    let schema: serde_json::Value =
+        serde_json::from_str(&tool.input_schema).unwrap_or(serde_json::Value::Bool(true));
+    let validation = validate_arguments(&schema, arguments);
+    if !validation.valid {
+        return Ok(Some(RenderOutcome::InvalidArguments { errors: validation.errors }));
+    }
+
+    let outcome = match render_request(
+        &tool.backend_url,
+        tool.http_verb.as_deref(),
+        &tool.tool_name,
+        tool.request_mapping.as_ref(),
+        &validation.arguments,
+    ) {
+        Ok(request) => RenderOutcome::Rendered { request, arguments: validation.arguments },
+        Err(error) => RenderOutcome::MappingError { error },
+    };
+    Ok(Some(outcome))
+}
+
 pub async fn delete_mcp_tool(
     store: &EndpointStore,
     tenant_id: &str,
@@ -414,6 +616,10 @@ pub async fn sync_endpoints_as_mcp_tools(
                 cost_credits: None,
                 timeout_ms: Some(30_000),
                 http_verb: Some(endpoint.verb.to_uppercase()),
+                request_mapping: None,
+                resilience: None,
+                tags: None,
+                group_id: Some(group.group.id.clone()),
             };
 
             match upsert_mcp_tool(store, tenant_id, &req).await {
@@ -496,6 +702,18 @@ fn build_input_schema(params: &[crate::endpoint_store::models::Parameter]) -> St
 
 // ── helpers ───────────────────────────────────────────────────────────────────
 
+fn circuit_state(circuits: &HashMap<String, Circuit>, tool_name: &str, policy: &ResiliencePolicy) -> CircuitState {
+    circuits
+        .get(tool_name)
+        .map(|c| c.state(policy, Utc::now()))
+        .unwrap_or_default()
+}
+
+fn with_circuit_state(mut tool: McpTool, circuits: &HashMap<String, Circuit>) -> McpTool {
+    tool.circuit_state = circuit_state(circuits, &tool.tool_name, &tool.resilience);
+    tool
+}
+
 fn row_to_tool(row: tokio_postgres::Row) -> McpTool {
     // cost_credits is INTEGER NOT NULL in the DB (explicit tools always have a value).
     // Wrap in Some so the gateway can distinguish "has api0 billing" (Some) from
@@ -514,5 +732,102 @@ fn row_to_tool(row: tokio_postgres::Row) -> McpTool {
         is_active:    row.get(9),
         created_at:   row.get::<_, chrono::DateTime<Utc>>(10).to_rfc3339(),
         updated_at:   row.get::<_, chrono::DateTime<Utc>>(11).to_rfc3339(),
+        request_mapping: row
+            .get::<_, Option<serde_json::Value>>(12)
+            .and_then(|v| serde_json::from_value(v).ok()),
+        resilience: policy_from_row(&row, 13),
+        circuit_state: CircuitState::Closed,
+        tags:         row.get(20),
+        group_id:     row.get(21),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::endpoint_store::live_test_store;
+    use crate::endpoint_store::tenant_management::get_default_tenant;
+
+    fn endpoint_group(tenant_id: &str) -> ApiGroupWithEndpoints {
+        serde_json::from_value(serde_json::json!({
+            "id": Uuid::new_v4().to_string(),
+            "name": "Users",
+            "base": "https://users.example.com",
+            "tenant_id": tenant_id,
+            "endpoints": [{ "text": "get user", "verb": "GET", "path": "/users/{id}" }],
+        }))
+        .unwrap()
+    }
+
+    #[tokio::test]
+    #[ignore = "requires live PostgreSQL (DATABASE_URL)"]
+    async fn resync_keeps_tenant_settings() {
+        let store = live_test_store().await;
+        let email = format!("sync_{}@example.com", Uuid::new_v4());
+        let tenant_id = get_default_tenant(&store, &email).await.unwrap().id;
+        let groups = vec![endpoint_group(&tenant_id)];
+
+        sync_endpoints_as_mcp_tools(&store, &tenant_id, &groups).await.unwrap();
+        let tool = list_mcp_tools(&store, &tenant_id, None, None)
+            .await
+            .unwrap()
+            .into_iter()
+            .find(|t| t.group_id.as_deref() == Some(groups[0].group.id.as_str()))
+            .expect("synced tool");
+
+        let mapping = RequestMapping {
+            path: vec![crate::endpoint_store::request_mapping::ParamBinding {
+                name: "id".into(),
+                arg: Some("user_id".into()),
+                value: None,
+            }],
+            ..Default::default()
+        };
+        let resilience = ResiliencePolicy { retry_max_attempts: 3, max_concurrency: Some(4), ..Default::default() };
+        let req = UpsertMcpToolRequest {
+            tool_name: tool.tool_name.clone(),
+            backend_url: tool.backend_url.clone(),
+            description: None,
+            input_schema: None,
+            cost_credits: None,
+            timeout_ms: None,
+            http_verb: tool.http_verb.clone(),
+            request_mapping: Some(Some(mapping.clone())),
+            resilience: Some(resilience.clone()),
+            tags: Some(vec!["billing".into()]),
+            group_id: tool.group_id.clone(),
+        };
+        upsert_mcp_tool(&store, &tenant_id, &req).await.unwrap();
+
+        sync_endpoints_as_mcp_tools(&store, &tenant_id, &groups).await.unwrap();
+        let tool = get_mcp_tool(&store, &tenant_id, &tool.tool_name, None, None)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(tool.request_mapping, Some(mapping));
+        assert_eq!(tool.resilience, resilience);
+        assert_eq!(tool.tags, vec!["billing".to_string()]);
+
+        // An explicit null clears the mapping.
+        let clear = UpsertMcpToolRequest { request_mapping: Some(None), resilience: None, tags: None, ..req };
+        upsert_mcp_tool(&store, &tenant_id, &clear).await.unwrap();
+        let tool = get_mcp_tool(&store, &tenant_id, &tool.tool_name, None, None)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(tool.request_mapping, None);
+        assert_eq!(tool.resilience, resilience);
+    }
+
+    #[test]
+    fn null_request_mapping_is_not_absent() {
+        let parse = |body: serde_json::Value| {
+            let mut req = serde_json::json!({"tool_name": "t", "backend_url": "https://b.example.com"});
+            req.as_object_mut().unwrap().extend(body.as_object().unwrap().clone());
+            serde_json::from_value::<UpsertMcpToolRequest>(req).unwrap().request_mapping
+        };
+        assert_eq!(parse(serde_json::json!({})), None);
+        assert_eq!(parse(serde_json::json!({"request_mapping": null})), Some(None));
+        assert!(matches!(parse(serde_json::json!({"request_mapping": {}})), Some(Some(_))));
     }
 }
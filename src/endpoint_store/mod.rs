@@ -1,6 +1,18 @@
 mod add_user_api_group;
+pub mod argument_validation;
+pub mod auth_lockout;
 pub mod api_key_management;
+pub mod access_tokens;
+pub mod ip_allowlist;
+pub mod key_cache;
+pub mod notify_listener;
+pub mod key_expiry;
+pub mod key_quotas;
+pub mod key_scopes;
+pub mod key_usage;
 pub mod mcp_tools_management;
+pub mod mcp_resources_management;
+pub mod mcp_prompts_management;
 mod authorized_domains;
 mod cleanup;
 pub mod db_helpers;
@@ -13,21 +25,34 @@ use crate::endpoint_store::db_helpers::ResultExt;
 mod delete_user_endpoint;
 pub mod models;
 pub mod reference_data;
+pub mod request_mapping;
+pub mod request_signing;
 mod replace_user_api_groups;
 mod user_preferences;
 mod utils;
 pub mod tenant_management;
+pub mod tool_resilience;
+pub mod toolsets_management;
 pub mod downstream_auth_management;
+pub mod downstream_tokens;
+pub mod downstream_probe;
+pub mod consumer_oauth;
+pub mod secret_crypto;
+pub mod secret_vault;
+pub mod security_events;
+pub mod consumer_keys;
 use crate::app_log;
 pub use errors::*;
 pub use models::*;
 pub use utils::*;
 
 use crate::infra::db::{create_pg_pool, PgConnection, PgPool};
+use std::sync::Arc;
 
 #[derive(Clone)]
 pub struct EndpointStore {
     pool: PgPool,
+    secrets: Arc<secret_crypto::Keyring>,
 }
 
 impl EndpointStore {
@@ -69,7 +94,24 @@ impl EndpointStore {
         let pool = create_pg_pool(database_url)
             .map_err(|e| StoreError::Pool(format!("Failed to create connection pool: {:?}", e)))?;
 
-        let store = Self { pool };
+        let secrets = secret_crypto::Keyring::from_env().map_err(StoreError::Crypto)?;
+        match secrets.active_key_id() {
+            Some(key_id) => app_log!(info, key_id = %key_id, "Secret encryption enabled"),
+            None => app_log!(warn, "API0_MASTER_KEYS not set, secrets are stored unencrypted"),
+        }
+
+        if api_key_management::init_key_pepper().map_err(StoreError::Crypto)? {
+            app_log!(info, scanner_pattern = api_key_management::V2_KEY_PATTERN,
+                "API0_KEY_PEPPER set, new API keys use the v2 format");
+        } else {
+            app_log!(warn, "API0_KEY_PEPPER not set, new API keys use the legacy v1 format");
+        }
+        let proxies = ip_allowlist::init_trusted_proxies().map_err(StoreError::Invalid)?;
+        if proxies > 0 {
+            app_log!(info, networks = proxies, "X-Forwarded-For trusted from API0_TRUSTED_PROXIES");
+        }
+
+        let store = Self { pool, secrets: Arc::new(secrets) };
 
         let client = store.get_admin_conn().await?;
 
@@ -101,6 +143,17 @@ impl EndpointStore {
             app_log!(info, "RLS policies applied successfully");
         }
 
+        // Encrypt plaintext secrets and bind older envelopes to their row's
+        // table, column and tenant (see secret_crypto.rs).
+        if store.secrets().active_key_id().is_some() {
+            match secret_crypto::seal_unbound_secrets(&store).await {
+                Ok(report) if report.rotated > 0 || report.skipped > 0 => app_log!(info,
+                    sealed = report.rotated, skipped = report.skipped, "Sealed unbound secrets"),
+                Ok(_) => {}
+                Err(e) => app_log!(error, error = %e, "Failed to seal unbound secrets"),
+            }
+        }
+
         store.initialize_system_domains().await?;
         Ok(store)
     }
@@ -244,7 +297,7 @@ impl EndpointStore {
         email: &str,
         key_name: &str,
         tenant_id: Option<&str>,
-    ) -> Result<(String, String, String), StoreError> {
+    ) -> Result<(String, String, String, Option<chrono::DateTime<chrono::Utc>>), StoreError> {
         api_key_management::generate_api_key(self, email, key_name, tenant_id).await
     }
 
@@ -255,8 +308,10 @@ impl EndpointStore {
         key_name: &str,
         explicit_tenant_id: Option<&str>,
         provider_tenant_id: Option<&str>,
-    ) -> Result<(String, String, String), StoreError> {
-        api_key_management::generate_api_key_with_provider(self, email, key_name, explicit_tenant_id, provider_tenant_id).await
+        restrictions: &key_scopes::KeyScopes,
+        expiry: &key_expiry::KeyExpiry,
+    ) -> Result<(String, String, String, Option<chrono::DateTime<chrono::Utc>>), StoreError> {
+        api_key_management::generate_api_key_with_provider(self, email, key_name, explicit_tenant_id, provider_tenant_id, restrictions, expiry).await
     }
 
     pub async fn revoke_api_key(&self, tenant_id: &str, key_id: &str) -> Result<bool, StoreError> {
@@ -271,13 +326,28 @@ impl EndpointStore {
         &self,
         key: &str,
         expected_tenant_id: Option<&str>,
-    ) -> Result<Option<(String, String, String, Option<String>)>, StoreError> {
-        api_key_management::validate_api_key(self, key, expected_tenant_id).await
+        required_scope: Option<&str>,
+        client_ip: Option<&str>,
+    ) -> Result<api_key_management::KeyValidation, StoreError> {
+        api_key_management::validate_api_key(self, key, expected_tenant_id, required_scope, client_ip).await
     }
 
-    #[allow(dead_code)]
-    pub async fn record_api_key_usage(&self, key_id: &str) -> Result<(), StoreError> {
-        api_key_management::record_api_key_usage(self, key_id).await
+    pub async fn rotate_api_key(
+        &self,
+        tenant_id: &str,
+        key_id: &str,
+        grace: chrono::Duration,
+    ) -> Result<Option<api_key_management::RotatedKey>, StoreError> {
+        api_key_management::rotate_api_key(self, tenant_id, key_id, grace).await
+    }
+
+    pub async fn set_api_key_scopes(
+        &self,
+        tenant_id: &str,
+        key_id: &str,
+        restrictions: &key_scopes::KeyScopes,
+    ) -> Result<bool, StoreError> {
+        api_key_management::set_api_key_scopes(self, tenant_id, key_id, restrictions).await
     }
 
     pub async fn get_api_key_usage(&self, key_id: &str, tenant_id: &str) -> Result<Option<ApiKeyInfo>, StoreError> {
@@ -330,7 +400,7 @@ impl EndpointStore {
             response_status, response_time_ms, request_size, response_size,
             ip_address, user_agent, usage_estimated, input_tokens,
             output_tokens, total_tokens, model_used, metadata, tenant_id, consumer_id
-        ) VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15, $16, $17, $18::jsonb, $19, $20)",
+        ) VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15, $16, $17, $18::text::jsonb, $19, $20)",
             &[
                 &log_id as &(dyn tokio_postgres::types::ToSql + Sync),
                 &request.key_id as &(dyn tokio_postgres::types::ToSql + Sync),
@@ -357,6 +427,19 @@ impl EndpointStore {
         .await
         .to_store_error()?;
 
+        // api_keys.usage_count / last_used, written in batches (key_usage.rs).
+        key_usage::record(&request.key_id, now);
+
+        // Circuit tracking is best-effort: a failure here must not lose the log row.
+        if let (Some(tool_name), Some(tid)) = (request.tool_name.as_deref(), tenant_id.as_deref()) {
+            if let Err(e) =
+                tool_resilience::record_tool_outcome(self, tid, tool_name, request.status_code).await
+            {
+                app_log!(warn, tenant_id = %tid, tool_name = %tool_name, error = %e,
+                    "Failed to update MCP tool circuit");
+            }
+        }
+
         Ok(log_id)
     }
 
@@ -490,8 +573,9 @@ impl EndpointStore {
         &self,
         tenant_id: &str,
         user_email: Option<&str>,
+        key_id: Option<&str>,
     ) -> Result<Vec<mcp_tools_management::McpTool>, StoreError> {
-        mcp_tools_management::list_mcp_tools(self, tenant_id, user_email).await
+        mcp_tools_management::list_mcp_tools(self, tenant_id, user_email, key_id).await
     }
 
     pub async fn get_mcp_tool(
@@ -499,8 +583,38 @@ impl EndpointStore {
         tenant_id: &str,
         tool_name: &str,
         user_email: Option<&str>,
+        key_id: Option<&str>,
     ) -> Result<Option<mcp_tools_management::McpTool>, StoreError> {
-        mcp_tools_management::get_mcp_tool(self, tenant_id, tool_name, user_email).await
+        mcp_tools_management::get_mcp_tool(self, tenant_id, tool_name, user_email, key_id).await
+    }
+
+    pub async fn validate_mcp_tool_arguments(
+        &self,
+        tenant_id: &str,
+        tool_name: &str,
+        user_email: Option<&str>,
+        key_id: Option<&str>,
+        arguments: &serde_json::Value,
+    ) -> Result<Option<argument_validation::ArgumentValidation>, StoreError> {
+        mcp_tools_management::validate_mcp_tool_arguments(
+            self, tenant_id, tool_name, user_email, key_id, arguments,
+        )
+        .await
+    }
+
+    pub async fn render_mcp_tool_request(
+        &self,
+        tenant_id: &str,
+        tool_name: &str,
+        user_email: Option<&str>,
+        key_id: Option<&str>,
+        arguments: &serde_json::Value,
+        resolve_secrets: bool,
+    ) -> Result<Option<mcp_tools_management::RenderOutcome>, StoreError> {
+        mcp_tools_management::render_mcp_tool_request(
+            self, tenant_id, tool_name, user_email, key_id, arguments, resolve_secrets,
+        )
+        .await
     }
 
     pub async fn delete_mcp_tool(
@@ -511,6 +625,178 @@ impl EndpointStore {
         mcp_tools_management::delete_mcp_tool(self, tenant_id, tool_name).await
     }
 
+    pub async fn claim_tool_probe(&self, tenant_id: &str, tool_name: &str) -> Result<bool, StoreError> {
+        tool_resilience::claim_probe(self, tenant_id, tool_name).await
+    }
+
+    // ── MCP toolsets ──────────────────────────────────────────────────────────
+
+    pub async fn upsert_toolset(
+        &self,
+        tenant_id: &str,
+        req: &toolsets_management::UpsertToolsetRequest,
+    ) -> Result<toolsets_management::McpToolset, StoreError> {
+        toolsets_management::upsert_toolset(self, tenant_id, req).await
+    }
+
+    pub async fn list_toolsets(
+        &self,
+        tenant_id: &str,
+    ) -> Result<Vec<toolsets_management::McpToolset>, StoreError> {
+        toolsets_management::list_toolsets(self, tenant_id).await
+    }
+
+    pub async fn delete_toolset(&self, tenant_id: &str, name: &str) -> Result<bool, StoreError> {
+        toolsets_management::delete_toolset(self, tenant_id, name).await
+    }
+
+    pub async fn set_key_toolsets(
+        &self,
+        tenant_id: &str,
+        key_id: &str,
+        toolset_names: &[String],
+    ) -> Result<Vec<toolsets_management::McpToolset>, StoreError> {
+        toolsets_management::set_key_toolsets(self, tenant_id, key_id, toolset_names).await
+    }
+
+    pub async fn get_key_toolsets(
+        &self,
+        tenant_id: &str,
+        key_id: &str,
+    ) -> Result<toolsets_management::KeyToolsets, StoreError> {
+        toolsets_management::get_key_toolsets(self, tenant_id, key_id).await
+    }
+
+    // ── MCP resources ─────────────────────────────────────────────────────────
+
+    pub async fn upsert_mcp_resource(
+        &self,
+        tenant_id: &str,
+        req: &mcp_resources_management::UpsertMcpResourceRequest,
+    ) -> Result<mcp_resources_management::McpResource, StoreError> {
+        mcp_resources_management::upsert_mcp_resource(self, tenant_id, req).await
+    }
+
+    pub async fn list_mcp_resources(
+        &self,
+        tenant_id: &str,
+    ) -> Result<Vec<mcp_resources_management::McpResource>, StoreError> {
+        mcp_resources_management::list_mcp_resources(self, tenant_id).await
+    }
+
+    pub async fn get_mcp_resource(
+        &self,
+        tenant_id: &str,
+        uri: &str,
+    ) -> Result<Option<mcp_resources_management::McpResource>, StoreError> {
+        mcp_resources_management::get_mcp_resource(self, tenant_id, uri).await
+    }
+
+    pub async fn delete_mcp_resource(
+        &self,
+        tenant_id: &str,
+        uri: &str,
+    ) -> Result<bool, StoreError> {
+        mcp_resources_management::delete_mcp_resource(self, tenant_id, uri).await
+    }
+
+    pub async fn publish_reference_data_as_resource(
+        &self,
+        tenant_id: &str,
+        reference_data_id: &str,
+        uri: Option<&str>,
+        description: Option<&str>,
+    ) -> Result<Option<mcp_resources_management::McpResource>, StoreError> {
+        mcp_resources_management::publish_reference_data_as_resource(
+            self,
+            tenant_id,
+            reference_data_id,
+            uri,
+            description,
+        )
+        .await
+    }
+
+    // ── MCP prompts ───────────────────────────────────────────────────────────
+
+    pub async fn upsert_mcp_prompt(
+        &self,
+        tenant_id: &str,
+        req: &mcp_prompts_management::UpsertMcpPromptRequest,
+    ) -> Result<mcp_prompts_management::McpPrompt, StoreError> {
+        mcp_prompts_management::upsert_mcp_prompt(self, tenant_id, req).await
+    }
+
+    pub async fn list_mcp_prompts(
+        &self,
+        tenant_id: &str,
+    ) -> Result<Vec<mcp_prompts_management::McpPrompt>, StoreError> {
+        mcp_prompts_management::list_mcp_prompts(self, tenant_id).await
+    }
+
+    pub async fn get_mcp_prompt(
+        &self,
+        tenant_id: &str,
+        name: &str,
+    ) -> Result<Option<mcp_prompts_management::McpPrompt>, StoreError> {
+        mcp_prompts_management::get_mcp_prompt(self, tenant_id, name).await
+    }
+
+    pub async fn delete_mcp_prompt(
+        &self,
+        tenant_id: &str,
+        name: &str,
+    ) -> Result<bool, StoreError> {
+        mcp_prompts_management::delete_mcp_prompt(self, tenant_id, name).await
+    }
+
+    // ── Secret encryption ─────────────────────────────────────────────────────
+
+    /// Envelope encryption for secret columns, see secret_crypto.rs.
+    pub fn secrets(&self) -> &secret_crypto::Keyring {
+        &self.secrets
+    }
+
+    pub async fn rotate_secrets(&self) -> Result<secret_crypto::RotationReport, StoreError> {
+        secret_crypto::rotate_secrets(self).await
+    }
+
+    // ── Tenant secret vault ───────────────────────────────────────────────────
+
+    pub async fn put_tenant_secret(
+        &self,
+        tenant_id: &str,
+        name: &str,
+        value: &str,
+        description: Option<&str>,
+    ) -> Result<secret_vault::TenantSecret, StoreError> {
+        secret_vault::put_secret(self, tenant_id, name, value, description).await
+    }
+
+    pub async fn list_tenant_secrets(&self, tenant_id: &str) -> Result<Vec<secret_vault::TenantSecret>, StoreError> {
+        secret_vault::list_secrets(self, tenant_id).await
+    }
+
+    pub async fn list_tenant_secret_versions(
+        &self,
+        tenant_id: &str,
+        name: &str,
+    ) -> Result<Option<Vec<secret_vault::TenantSecretVersion>>, StoreError> {
+        secret_vault::list_secret_versions(self, tenant_id, name).await
+    }
+
+    pub async fn delete_tenant_secret(&self, tenant_id: &str, name: &str) -> Result<bool, StoreError> {
+        secret_vault::delete_secret(self, tenant_id, name).await
+    }
+
+    pub async fn resolve_tool_secrets(
+        &self,
+        tenant_id: &str,
+        tool: &mut mcp_tools_management::McpTool,
+    ) -> Result<(), StoreError> {
+        secret_vault::resolve_tool_secrets(self, tenant_id, tool).await
+    }
+
     // ── Downstream auth ───────────────────────────────────────────────────────
 
     #[allow(dead_code)]
@@ -530,6 +816,125 @@ impl EndpointStore {
         downstream_auth_management::save_downstream_auth(self, tenant_id, req).await
     }
 
+    pub async fn save_downstream_auth_override(
+        &self,
+        tenant_id: &str,
+        scope: &str,
+        target: &str,
+        req: &downstream_auth_management::SaveDownstreamAuthRequest,
+    ) -> Result<downstream_auth_management::DownstreamAuthOverride, StoreError> {
+        downstream_auth_management::save_downstream_auth_override(self, tenant_id, scope, target, req).await
+    }
+
+    pub async fn list_downstream_auth_overrides(
+        &self,
+        tenant_id: &str,
+    ) -> Result<Vec<downstream_auth_management::DownstreamAuthOverride>, StoreError> {
+        downstream_auth_management::list_downstream_auth_overrides(self, tenant_id).await
+    }
+
+    pub async fn get_downstream_auth_override(
+        &self,
+        tenant_id: &str,
+        scope: &str,
+        target: &str,
+    ) -> Result<Option<downstream_auth_management::DownstreamAuthOverride>, StoreError> {
+        downstream_auth_management::get_downstream_auth_override(self, tenant_id, scope, target).await
+    }
+
+    pub async fn delete_downstream_auth_override(
+        &self,
+        tenant_id: &str,
+        scope: &str,
+        target: &str,
+    ) -> Result<bool, StoreError> {
+        downstream_auth_management::delete_downstream_auth_override(self, tenant_id, scope, target).await
+    }
+
+    pub async fn resolve_downstream_auth(
+        &self,
+        tenant_id: &str,
+        tool_name: Option<&str>,
+    ) -> Result<downstream_auth_management::ResolvedDownstreamAuth, StoreError> {
+        downstream_auth_management::resolve_downstream_auth(self, tenant_id, tool_name).await
+    }
+
+    pub async fn downstream_access_token(
+        &self,
+        tenant_id: &str,
+        tool_name: Option<&str>,
+        consumer: Option<&consumer_oauth::ConsumerSubject>,
+        force_refresh: bool,
+    ) -> Result<downstream_tokens::DownstreamAccessToken, downstream_tokens::DownstreamTokenError> {
+        downstream_tokens::downstream_access_token(self, tenant_id, tool_name, consumer, force_refresh).await
+    }
+
+    pub async fn sign_downstream_request(
+        &self,
+        tenant_id: &str,
+        tool_name: Option<&str>,
+        req: &request_signing::SignRequest,
+    ) -> Result<request_signing::SignedHeaders, downstream_tokens::DownstreamTokenError> {
+        request_signing::sign_downstream_request(self, tenant_id, tool_name, req).await
+    }
+
+    pub async fn probe_downstream(
+        &self,
+        tenant_id: &str,
+        tool_name: Option<&str>,
+    ) -> Result<downstream_probe::ProbeReport, downstream_probe::ProbeError> {
+        downstream_probe::probe_downstream(self, tenant_id, tool_name).await
+    }
+
+    pub async fn mark_downstream_auth_verified(&self, tenant_id: &str) -> Result<bool, StoreError> {
+        downstream_auth_management::mark_downstream_auth_verified(self, tenant_id).await
+    }
+
+    // ── Consumer OAuth (delegated per-end-user tokens) ────────────────────────
+
+    pub async fn start_consumer_oauth(
+        &self,
+        provider_tenant_id: &str,
+        subject: &consumer_oauth::ConsumerSubject,
+        redirect_uri: &str,
+        tool_name: Option<&str>,
+    ) -> Result<consumer_oauth::ConsumerOAuthStart, downstream_tokens::DownstreamTokenError> {
+        consumer_oauth::start_consumer_oauth(self, provider_tenant_id, subject, redirect_uri, tool_name).await
+    }
+
+    pub async fn complete_consumer_oauth(
+        &self,
+        state: &str,
+        code: &str,
+    ) -> Result<consumer_oauth::ConsumerOAuthConnection, downstream_tokens::DownstreamTokenError> {
+        consumer_oauth::complete_consumer_oauth(self, state, code).await
+    }
+
+    pub async fn refresh_consumer_oauth(
+        &self,
+        provider_tenant_id: &str,
+        subject: &consumer_oauth::ConsumerSubject,
+        tool_name: Option<&str>,
+    ) -> Result<consumer_oauth::ConsumerOAuthConnection, downstream_tokens::DownstreamTokenError> {
+        consumer_oauth::refresh_consumer_oauth(self, provider_tenant_id, subject, tool_name).await
+    }
+
+    pub async fn get_consumer_oauth_connection(
+        &self,
+        provider_tenant_id: &str,
+        subject: &consumer_oauth::ConsumerSubject,
+    ) -> Result<Option<consumer_oauth::ConsumerOAuthConnection>, StoreError> {
+        consumer_oauth::get_consumer_oauth_connection(self, provider_tenant_id, subject).await
+    }
+
+    pub async fn delete_consumer_oauth(
+        &self,
+        provider_tenant_id: &str,
+        subject: &consumer_oauth::ConsumerSubject,
+    ) -> Result<bool, StoreError> {
+        consumer_oauth::delete_consumer_oauth(self, provider_tenant_id, subject).await
+    }
+
     // ── MCP client ID (per-provider OAuth) ────────────────────────────────────
 
     #[allow(dead_code)]
@@ -567,3 +972,11 @@ impl EndpointStore {
         tenant_management::list_user_tenants_with_conn(&client, email).await
     }
 }
+
+/// Store for the live-database tests. Skipped in CI; run locally with:
+///   DATABASE_URL=postgresql://... cargo test -- --ignored
+#[cfg(test)]
+pub(crate) async fn live_test_store() -> EndpointStore {
+    let url = std::env::var("DATABASE_URL").expect("DATABASE_URL must point at a test database");
+    EndpointStore::new(&url).await.expect("Failed to create store")
+}
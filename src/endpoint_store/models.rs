@@ -1,4 +1,7 @@
 use crate::endpoint_store::utils::generate_uuid;
+use crate::endpoint_store::key_expiry::KeyExpiry;
+use crate::endpoint_store::key_quotas::QuotaLimits;
+use crate::endpoint_store::key_scopes::KeyScopes;
 use serde::{Deserialize, Serialize};
 
 // Helper function to provide default verb value
@@ -140,6 +143,28 @@ pub struct ApiKeyInfo {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub last_used: Option<String>,
     pub usage_count: i64,
+    #[serde(flatten)]
+    pub restrictions: KeyScopes,
+    /// Bumped by every rotation.
+    #[serde(default)]
+    pub generation: i32,
+    /// Set while the pre-rotation secret is still inside its grace window.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub previous_key_prefix: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub previous_expires_at: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub expires_at: Option<String>,
+    /// Per-key overrides only; tenant defaults are not folded in.
+    #[serde(default)]
+    pub limits: QuotaLimits,
+    /// Caller IP allowlist; absent = any address.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub allowed_cidrs: Option<Vec<String>>,
+    /// Key format, "v1" or "v2"; rotating a v1 key issues a v2 secret once
+    /// API0_KEY_PEPPER is set.
+    #[serde(default)]
+    pub key_version: String,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -162,6 +187,12 @@ pub struct GenerateKeyRequest {
     /// When set, the generated key is a consumer key scoped to this provider tenant.
     #[serde(default)]
     pub provider_tenant_id: Option<String>,
+    /// Scopes and group/tool restrictions; omitted = full access.
+    #[serde(flatten)]
+    pub restrictions: KeyScopes,
+    /// `expires_at` or `ttl_days`; bounded by the tenant key policy.
+    #[serde(flatten)]
+    pub expiry: KeyExpiry,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -224,6 +255,10 @@ pub struct LogApiUsageRequest {
     pub tenant_id: Option<String>,
     // Add metadata for matched endpoint info (optional)
     pub metadata: Option<serde_json::Value>,
+    /// MCP tool that served the call. When set, the outcome (status_code,
+    /// None = timeout / connection error) feeds the tool's circuit breaker.
+    #[serde(default)]
+    pub tool_name: Option<String>,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -0,0 +1,72 @@
+// src/endpoint_store/notify_listener.rs
+//
+// One dedicated Postgres connection per process that LISTENs for the
+// in-process state other replicas change:
+//
+//   key_cache::CHANNEL     — API key validation cache invalidations
+//   auth_lockout::CHANNEL  — brute-force lockouts tripped or cleared
+//   access_tokens::CHANNEL — token signing keys created or rotated
+//
+// On every (re)connect the key cache and signing keys are dropped and active
+// lockouts are reloaded, since notifications sent while disconnected are lost.
+
+use crate::app_log;
+use crate::endpoint_store::{access_tokens, auth_lockout, key_cache};
+use futures::StreamExt;
+use std::time::Duration;
+use tokio_postgres::{AsyncMessage, NoTls};
+
+const RECONNECT_DELAY_SECS: u64 = 5;
+
+/// Keep the listener running for the life of the process, reconnecting on failure.
+pub fn spawn_listener(database_url: String) {
+    tokio::spawn(async move {
+        loop {
+            if let Err(e) = listen(&database_url).await {
+                app_log!(warn, error = %e, "Notification listener disconnected");
+            }
+            key_cache::clear();
+            tokio::time::sleep(Duration::from_secs(RECONNECT_DELAY_SECS)).await;
+        }
+    });
+}
+
+async fn listen(database_url: &str) -> Result<(), tokio_postgres::Error> {
+    let (client, mut connection) = tokio_postgres::connect(database_url, NoTls).await?;
+    let mut messages = futures::stream::poll_fn(move |cx| connection.poll_message(cx));
+
+    // The connection must be polled for LISTEN itself to complete.
+    let driver = tokio::spawn(async move {
+        while let Some(msg) = messages.next().await {
+            if let AsyncMessage::Notification(n) = msg? {
+                match n.channel() {
+                    key_cache::CHANNEL => key_cache::handle_notification(n.payload()),
+                    auth_lockout::CHANNEL => auth_lockout::handle_notification(n.payload()),
+                    access_tokens::CHANNEL => access_tokens::handle_notification(n.payload()),
+                    other => app_log!(warn, channel = %other, "Notification on unexpected channel"),
+                }
+            }
+        }
+        Ok(())
+    });
+
+    client
+        .batch_execute(&format!(
+            "LISTEN {}; LISTEN {}; LISTEN {};",
+            key_cache::CHANNEL,
+            auth_lockout::CHANNEL,
+            access_tokens::CHANNEL
+        ))
+        .await?;
+    key_cache::clear();
+    access_tokens::invalidate();
+    if let Err(e) = auth_lockout::reload(&client).await {
+        app_log!(error, error = %e, "Failed to load active auth lockouts");
+    }
+    app_log!(info, "Notification listener connected");
+
+    // Runs until the connection drops; `client` must stay alive until then.
+    let result = driver.await.unwrap_or(Ok(()));
+    drop(client);
+    result
+}
@@ -0,0 +1,340 @@
+// src/endpoint_store/request_mapping.rs
+//
+// Declarative mapping from MCP tool arguments onto a REST request, stored per
+// tool in mcp_tools.request_mapping and used by the "render request" API.
+//
+// A mapping lists bindings per request location:
+//
+//   {
+//     "path":    [{ "name": "user_id", "arg": "id" }],
+//     "query":   [{ "name": "q", "arg": "search" }, { "name": "format", "value": "json" }],
+//     "headers": [{ "name": "X-Tenant", "arg": "tenant" }],
+//     "body":    [{ "name": "displayName", "arg": "display_name" }],
+//     "unmapped": "body"
+//   }
+//
+// - `name`  — target name (path placeholder, query key, header, body field)
+// - `arg`   — source argument; defaults to `name`
+// - `value` — constant; when `arg` is also set it is used as the fallback
+//             if the argument is absent
+//
+// Path placeholders are `{name}` segments in the tool's backend_url. A
+// placeholder without an explicit binding is filled from the argument of the
+// same name. Arguments not consumed by any binding go to `unmapped`
+// ("query", "body" or "drop"); when omitted they go to the query string for
+// GET/HEAD/DELETE and to the JSON body otherwise — the same behaviour as
+// tools with no mapping at all.
+
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::collections::{BTreeMap, HashSet};
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct RequestMapping {
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub path: Vec<ParamBinding>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub query: Vec<ParamBinding>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub headers: Vec<ParamBinding>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub body: Vec<ParamBinding>,
+    /// "query" | "body" | "drop". None = decided by HTTP verb.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub unmapped: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ParamBinding {
+    pub name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub arg: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub value: Option<Value>,
+}
+
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct RenderedRequest {
+    pub method: String,
+    pub url: String,
+    pub headers: BTreeMap<String, String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub body: Option<Value>,
+}
+
+impl RequestMapping {
+    /// Reject mappings the renderer could never satisfy, at upsert time rather
+    /// than on the first call.
+    pub fn check(&self) -> Result<(), String> {
+        if let Some(u) = self.unmapped.as_deref() {
+            if !matches!(u, "query" | "body" | "drop") {
+                return Err(format!("unmapped must be \"query\", \"body\" or \"drop\", got \"{}\"", u));
+            }
+        }
+        let sections = [
+            ("path", &self.path),
+            ("query", &self.query),
+            ("headers", &self.headers),
+            ("body", &self.body),
+        ];
+        for (section, bindings) in sections {
+            for b in bindings {
+                if b.name.trim().is_empty() {
+                    return Err(format!("{}: binding name must not be empty", section));
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Turn (tool, arguments) into a concrete HTTP request.
+///
+/// `http_verb` None means the backend speaks MCP natively: the request is a
+/// POST of the `{ tool, arguments }` envelope to backend_url.
+pub fn render_request(
+    backend_url: &str,
+    http_verb: Option<&str>,
+    tool_name: &str,
+    mapping: Option<&RequestMapping>,
+    arguments: &Value,
+) -> Result<RenderedRequest, String> {
+    let empty = Map::new();
+    let args = arguments.as_object().unwrap_or(&empty);
+
+    let method = match http_verb {
+        Some(v) => v.to_uppercase(),
+        None => {
+            return Ok(RenderedRequest {
+                method: "POST".to_string(),
+                url: backend_url.to_string(),
+                headers: json_content_type(),
+                body: Some(serde_json::json!({ "tool": tool_name, "arguments": arguments })),
+            })
+        }
+    };
+
+    let default_mapping = RequestMapping::default();
+    let mapping = mapping.unwrap_or(&default_mapping);
+    let mut consumed: HashSet<&str> = HashSet::new();
+
+    // Path placeholders
+    let mut url = backend_url.to_string();
+    for b in &mapping.path {
+        let placeholder = format!("{{{}}}", b.name);
+        if !url.contains(&placeholder) {
+            return Err(format!("path binding \"{}\" has no {{{}}} placeholder in backend_url", b.name, b.name));
+        }
+        let value = resolve(b, args, &mut consumed)
+            .ok_or_else(|| format!("missing value for path parameter \"{}\"", b.name))?;
+        url = url.replace(&placeholder, &encode_path_segment(&scalar_to_string(&value)));
+    }
+    for name in placeholders(&url) {
+        let (key, value) = args
+            .get_key_value(&name)
+            .filter(|(_, v)| !v.is_null())
+            .ok_or_else(|| format!("missing value for path parameter \"{}\"", name))?;
+        consumed.insert(key.as_str());
+        url = url.replace(&format!("{{{}}}", name), &encode_path_segment(&scalar_to_string(value)));
+    }
+
+    // Query string
+    let mut query: Vec<(String, String)> = Vec::new();
+    for b in &mapping.query {
+        if let Some(value) = resolve(b, args, &mut consumed) {
+            push_query(&mut query, &b.name, &value);
+        }
+    }
+
+    // Headers
+    let mut headers = BTreeMap::new();
+    for b in &mapping.headers {
+        if let Some(value) = resolve(b, args, &mut consumed) {
+            headers.insert(b.name.clone(), scalar_to_string(&value));
+        }
+    }
+
+    // Body
+    let mut body = Map::new();
+    for b in &mapping.body {
+        if let Some(value) = resolve(b, args, &mut consumed) {
+            body.insert(b.name.clone(), value);
+        }
+    }
+
+    // Leftover arguments
+    let unmapped = mapping.unmapped.as_deref().unwrap_or(match method.as_str() {
+        "GET" | "HEAD" | "DELETE" => "query",
+        _ => "body",
+    });
+    for (name, value) in args {
+        if consumed.contains(name.as_str()) || value.is_null() {
+            continue;
+        }
+        match unmapped {
+            "query" => push_query(&mut query, name, value),
+            "body" => {
+                body.entry(name.clone()).or_insert_with(|| value.clone());
+            }
+            _ => {}
+        }
+    }
+
+    if !query.is_empty() {
+        let mut parsed = reqwest::Url::parse(&url).map_err(|e| format!("invalid backend_url: {}", e))?;
+        parsed.query_pairs_mut().extend_pairs(query.iter());
+        url = parsed.to_string();
+    }
+
+    let body = if body.is_empty() && matches!(method.as_str(), "GET" | "HEAD" | "DELETE") {
+        None
+    } else {
+        headers.entry("Content-Type".to_string()).or_insert_with(|| "application/json".to_string());
+        Some(Value::Object(body))
+    };
+
+    Ok(RenderedRequest { method, url, headers, body })
+}
+
+// ── helpers ───────────────────────────────────────────────────────────────────
+
+fn resolve<'a>(b: &'a ParamBinding, args: &'a Map<String, Value>, consumed: &mut HashSet<&'a str>) -> Option<Value> {
+    let source = match (&b.arg, &b.value) {
+        (Some(arg), _) => Some(arg.as_str()),
+        (None, Some(_)) => None,
+        (None, None) => Some(b.name.as_str()),
+    };
+    if let Some(source) = source {
+        consumed.insert(source);
+        if let Some(v) = args.get(source).filter(|v| !v.is_null()) {
+            return Some(v.clone());
+        }
+    }
+    b.value.clone()
+}
+
+fn placeholders(url: &str) -> Vec<String> {
+    let mut out = Vec::new();
+    let mut rest = url;
+    while let Some(start) = rest.find('{') {
+        // `{{...}}` is a vault reference (secret_vault.rs), not a path parameter.
+        if rest[start..].starts_with("{{") {
+            match rest[start..].find("}}") {
+                Some(end) => {
+                    rest = &rest[start + end + 2..];
+                    continue;
+                }
+                None => break,
+            }
+        }
+        match rest[start..].find('}') {
+            Some(end) => {
+                out.push(rest[start + 1..start + end].to_string());
+                rest = &rest[start + end + 1..];
+            }
+            None => break,
+        }
+    }
+    out
+}
+
+fn push_query(query: &mut Vec<(String, String)>, name: &str, value: &Value) {
+    match value {
+        Value::Null => {}
+        Value::Array(items) => {
+            for item in items {
+                push_query(query, name, item);
+            }
+        }
+        other => query.push((name.to_string(), scalar_to_string(other))),
+    }
+}
+
+fn scalar_to_string(value: &Value) -> String {
+    match value {
+        Value::String(s) => s.clone(),
+        other => other.to_string(),
+    }
+}
+
+fn encode_path_segment(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    for b in s.bytes() {
+        if b.is_ascii_alphanumeric() || matches!(b, b'-' | b'_' | b'.' | b'~') {
+            out.push(b as char);
+        } else {
+            out.push_str(&format!("%{:02X}", b));
+        }
+    }
+    out
+}
+
+fn json_content_type() -> BTreeMap<String, String> {
+    let mut headers = BTreeMap::new();
+    headers.insert("Content-Type".to_string(), "application/json".to_string());
+    headers
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn explicit_mapping_fills_every_location() {
+        let mapping: RequestMapping = serde_json::from_value(json!({
+            "path":    [{ "name": "user_id", "arg": "id" }],
+            "query":   [{ "name": "fmt", "value": "json" }, { "name": "tag", "arg": "tags" }],
+            "headers": [{ "name": "X-Trace", "arg": "trace" }],
+            "body":    [{ "name": "displayName", "arg": "display_name" }]
+        }))
+        .unwrap();
+
+        let rendered = render_request(
+            "https://api.example.com/users/{user_id}",
+            Some("patch"),
+            "update-user",
+            Some(&mapping),
+            &json!({ "id": "a b", "tags": ["x", "y"], "trace": 42, "display_name": "Ann", "age": 3 }),
+        )
+        .unwrap();
+
+        assert_eq!(rendered.method, "PATCH");
+        assert_eq!(rendered.url, "https://api.example.com/users/a%20b?fmt=json&tag=x&tag=y");
+        assert_eq!(rendered.headers.get("X-Trace").map(String::as_str), Some("42"));
+        assert_eq!(rendered.body, Some(json!({ "displayName": "Ann", "age": 3 })));
+    }
+
+    #[test]
+    fn unmapped_get_arguments_go_to_query() {
+        let rendered = render_request(
+            "https://api.example.com/items/{id}",
+            Some("GET"),
+            "get-item",
+            None,
+            &json!({ "id": 7, "expand": true }),
+        )
+        .unwrap();
+
+        assert_eq!(rendered.url, "https://api.example.com/items/7?expand=true");
+        assert_eq!(rendered.body, None);
+    }
+
+    #[test]
+    fn missing_path_parameter_is_an_error() {
+        let err = render_request("https://x/items/{id}", Some("GET"), "t", None, &json!({})).unwrap_err();
+        assert!(err.contains("\"id\""));
+    }
+
+    #[test]
+    fn vault_references_are_not_path_parameters() {
+        assert_eq!(placeholders("https://x/{{secret:API_KEY@2}}/items/{id}"), vec!["id".to_string()]);
+    }
+
+    #[test]
+    fn native_mcp_tools_get_the_envelope() {
+        let rendered = render_request("https://x/mcp", None, "search", None, &json!({ "q": "a" })).unwrap();
+        assert_eq!(rendered.method, "POST");
+        assert_eq!(rendered.body, Some(json!({ "tool": "search", "arguments": { "q": "a" } })));
+    }
+}
@@ -0,0 +1,254 @@
+// src/endpoint_store/request_signing.rs
+//
+// hmac_signature downstream auth: the store signs each outgoing request with
+// the tenant's shared secret and returns the headers to attach. The secret is
+// masked for tenants and left out of the gateway's config
+// (TenantDownstreamAuth::for_gateway).
+//
+// String to sign (lines joined by "\n"):
+//
+//   METHOD
+//   path (with query string)
+//   timestamp (unix seconds)
+//   hex SHA-256 of the body (of "" when there is none)
+//   lowercase-name:trimmed-value for each configured signed header, in order
+//
+// hmac_signature_headers maps header names to templates; placeholders:
+//   {signature}         hex HMAC
+//   {signature_base64}  base64 HMAC
+//   {timestamp}         unix seconds
+//   {algorithm}         sha256 / sha1 / sha512
+//   {body_sha256}       hex body hash
+//
+// e.g. {"X-Signature": "t={timestamp},v1={signature}"} or
+//      {"X-Signature": "{algorithm}={signature}", "X-Timestamp": "{timestamp}"}.
+//
+// The string to sign is fixed, so the downstream must verify this scheme:
+// schemes that MAC the raw body (GitHub's X-Hub-Signature-256) or
+// "timestamp.body" (Stripe webhooks) cannot be produced.
+
+use crate::endpoint_store::downstream_auth_management::TenantDownstreamAuth;
+use crate::endpoint_store::downstream_tokens::DownstreamTokenError;
+use crate::endpoint_store::EndpointStore;
+use base64::{engine::general_purpose::STANDARD, Engine as _};
+use hmac::{Hmac, Mac};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+
+pub const HMAC_MODE: &str = "hmac_signature";
+pub const HMAC_ALGORITHMS: [&str; 3] = ["sha256", "sha1", "sha512"];
+
+/// The outgoing request to sign. Give either the raw `body` or its hex
+/// `body_sha256`; neither means an empty body.
+#[derive(Debug, Deserialize)]
+pub struct SignRequest {
+    pub method: String,
+    pub path: String,
+    pub body: Option<String>,
+    pub body_sha256: Option<String>,
+    #[serde(default)]
+    pub headers: BTreeMap<String, String>,
+    /// Unix seconds; defaults to now.
+    pub timestamp: Option<i64>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SignedHeaders {
+    pub headers: BTreeMap<String, String>,
+    pub timestamp: i64,
+}
+
+/// Validate the algorithm / signature header templates of a config.
+pub fn check_config(algorithm: Option<&str>, signature_headers: Option<&Value>) -> Result<(), String> {
+    let algorithm = algorithm.unwrap_or("sha256");
+    if !HMAC_ALGORITHMS.contains(&algorithm) {
+        return Err(format!("hmac_algorithm must be one of {}", HMAC_ALGORITHMS.join(", ")));
+    }
+    let templates = signature_headers
+        .and_then(|v| v.as_object())
+        .filter(|m| !m.is_empty())
+        .ok_or("hmac_signature_headers must be a non-empty object of header templates")?;
+    if templates.values().any(|v| !v.is_string()) {
+        return Err("hmac_signature_headers values must be strings".to_string());
+    }
+    let carries_signature = templates.values().filter_map(|v| v.as_str()).any(|t| {
+        t.contains("{signature}") || t.contains("{signature_base64}")
+    });
+    if !carries_signature {
+        return Err("one of hmac_signature_headers must contain {signature} or {signature_base64}".to_string());
+    }
+    Ok(())
+}
+
+/// Sign `req` with the hmac_signature config in `auth`.
+pub fn sign(auth: &TenantDownstreamAuth, req: &SignRequest, timestamp: i64) -> Result<SignedHeaders, String> {
+    let secret = auth
+        .hmac_secret
+        .as_deref()
+        .filter(|s| !s.is_empty())
+        .ok_or("hmac_secret is not set")?;
+    check_config(auth.hmac_algorithm.as_deref(), auth.hmac_signature_headers.as_ref())?;
+    let algorithm = auth.hmac_algorithm.as_deref().unwrap_or("sha256");
+
+    let body_sha256 = match (&req.body, &req.body_sha256) {
+        (Some(body), _) => format!("{:x}", Sha256::digest(body.as_bytes())),
+        (None, Some(hash)) => hash.to_lowercase(),
+        (None, None) => format!("{:x}", Sha256::digest(b"")),
+    };
+
+    let mut lines = vec![
+        req.method.to_uppercase(),
+        req.path.clone(),
+        timestamp.to_string(),
+        body_sha256.clone(),
+    ];
+    for name in auth.hmac_signed_headers.iter().flatten() {
+        let value = req
+            .headers
+            .iter()
+            .find(|(k, _)| k.eq_ignore_ascii_case(name))
+            .map(|(_, v)| v.trim())
+            .unwrap_or("");
+        lines.push(format!("{}:{}", name.to_lowercase(), value));
+    }
+    let mac = hmac_bytes(algorithm, secret.as_bytes(), lines.join("\n").as_bytes())?;
+
+    let signature: String = mac.iter().map(|b| format!("{:02x}", b)).collect();
+    let signature_base64 = STANDARD.encode(&mac);
+    let timestamp_str = timestamp.to_string();
+
+    let mut headers = BTreeMap::new();
+    for (name, template) in auth.hmac_signature_headers.iter().flat_map(|v| v.as_object()).flatten() {
+        let value = template
+            .as_str()
+            .unwrap_or_default()
+            .replace("{signature_base64}", &signature_base64)
+            .replace("{signature}", &signature)
+            .replace("{timestamp}", &timestamp_str)
+            .replace("{algorithm}", algorithm)
+            .replace("{body_sha256}", &body_sha256);
+        headers.insert(name.clone(), value);
+    }
+
+    Ok(SignedHeaders { headers, timestamp })
+}
+
+/// Resolve the tenant's downstream auth for `tool_name` and sign `req`.
+pub async fn sign_downstream_request(
+    store: &EndpointStore,
+    tenant_id: &str,
+    tool_name: Option<&str>,
+    req: &SignRequest,
+) -> Result<SignedHeaders, DownstreamTokenError> {
+    let resolved = store.resolve_downstream_auth(tenant_id, tool_name).await?;
+    let auth = match resolved.auth {
+        Some(auth) if auth.auth_mode == HMAC_MODE => auth,
+        Some(auth) => {
+            return Err(DownstreamTokenError::NotConfigured(format!(
+                "auth_mode is {}, not {}",
+                auth.auth_mode, HMAC_MODE
+            )))
+        }
+        None => return Err(DownstreamTokenError::NotConfigured("auth_mode is none".to_string())),
+    };
+
+    let timestamp = req.timestamp.unwrap_or_else(|| chrono::Utc::now().timestamp());
+    sign(&auth, req, timestamp).map_err(DownstreamTokenError::NotConfigured)
+}
+
+fn hmac_bytes(algorithm: &str, key: &[u8], data: &[u8]) -> Result<Vec<u8>, String> {
+    fn run<M: Mac + hmac::digest::KeyInit>(key: &[u8], data: &[u8]) -> Vec<u8> {
+        let mut mac = <M as hmac::digest::KeyInit>::new_from_slice(key).expect("HMAC accepts any key length");
+        mac.update(data);
+        mac.finalize().into_bytes().to_vec()
+    }
+    match algorithm {
+        "sha256" => Ok(run::<Hmac<Sha256>>(key, data)),
+        "sha512" => Ok(run::<Hmac<sha2::Sha512>>(key, data)),
+        "sha1" => Ok(run::<Hmac<sha1::Sha1>>(key, data)),
+        other => Err(format!("unsupported hmac_algorithm: {}", other)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn config(algorithm: Option<&str>, templates: Value, signed: Option<Vec<String>>) -> TenantDownstreamAuth {
+        serde_json::from_value(json!({
+            "tenant_id": "t1",
+            "auth_mode": HMAC_MODE,
+            "service_account_json": null,
+            "target_audience": null,
+            "bearer_token": null,
+            "custom_headers": null,
+            "updated_at": "",
+            "oauth_token_url": null,
+            "oauth_client_id": null,
+            "oauth_client_secret": null,
+            "oauth_scopes": null,
+            "oauth_audience": null,
+            "oauth_authorize_url": null,
+            "hmac_secret": "whsec_test",
+            "hmac_algorithm": algorithm,
+            "hmac_signed_headers": signed,
+            "hmac_signature_headers": templates,
+        }))
+        .unwrap()
+    }
+
+    fn request(body: Option<&str>) -> SignRequest {
+        SignRequest {
+            method: "post".to_string(),
+            path: "/v1/charges?expand=true".to_string(),
+            body: body.map(str::to_string),
+            body_sha256: None,
+            headers: BTreeMap::from([("Content-Type".to_string(), " application/json ".to_string())]),
+            timestamp: None,
+        }
+    }
+
+    #[test]
+    fn signs_canonical_string_and_fills_templates() {
+        let auth = config(
+            None,
+            json!({"X-Signature": "t={timestamp},v1={signature}", "X-Body-Hash": "{body_sha256}"}),
+            Some(vec!["content-type".to_string()]),
+        );
+        let signed = sign(&auth, &request(Some("{\"amount\":1}")), 1_700_000_000).unwrap();
+
+        let body_hash = format!("{:x}", Sha256::digest(b"{\"amount\":1}"));
+        let canonical = format!(
+            "POST\n/v1/charges?expand=true\n1700000000\n{}\ncontent-type:application/json",
+            body_hash
+        );
+        let expected: String = hmac_bytes("sha256", b"whsec_test", canonical.as_bytes())
+            .unwrap()
+            .iter()
+            .map(|b| format!("{:02x}", b))
+            .collect();
+
+        assert_eq!(signed.headers["X-Signature"], format!("t=1700000000,v1={}", expected));
+        assert_eq!(signed.headers["X-Body-Hash"], body_hash);
+    }
+
+    #[test]
+    fn body_hash_and_raw_body_sign_the_same() {
+        let auth = config(Some("sha512"), json!({"X-Sig": "{signature_base64}"}), None);
+        let from_body = sign(&auth, &request(Some("payload")), 1).unwrap();
+        let mut hashed = request(None);
+        hashed.body_sha256 = Some(format!("{:X}", Sha256::digest(b"payload")));
+        assert_eq!(sign(&auth, &hashed, 1).unwrap().headers, from_body.headers);
+    }
+
+    #[test]
+    fn rejects_bad_configs() {
+        assert!(check_config(Some("md5"), Some(&json!({"X": "{signature}"}))).is_err());
+        assert!(check_config(None, Some(&json!({}))).is_err());
+        assert!(check_config(None, Some(&json!({"X": "{timestamp}"}))).is_err());
+        assert!(check_config(Some("sha1"), Some(&json!({"X": "sha1={signature}"}))).is_ok());
+    }
+}
@@ -0,0 +1,351 @@
+// src/endpoint_store/secret_crypto.rs
+//
+// Envelope encryption for secrets kept in TEXT columns:
+//
+//   tenant_downstream_auth / downstream_auth_overrides
+//       service_account_json, bearer_token, oauth_client_secret, hmac_secret
+//   whatsapp_channels.wa_token
+//   system_config 'email.smtp_password'
+//   tenant_secret_versions.value (secret_vault.rs)
+//   token_signing_keys.private_key (access_tokens.rs)
+//   consumer_oauth_tokens access_token, refresh_token and
+//   consumer_oauth_states.code_verifier (consumer_oauth.rs)
+//
+// Master keys come from API0_MASTER_KEYS, a comma-separated list of
+// `key_id:base64(32 bytes)`. The first key encrypts; every listed key can
+// decrypt, so rotating is: prepend the new key, run `store rotate-secrets`,
+// then drop the old one. Each value gets its own random data key, wrapped by
+// the master key (AES-256-GCM for both layers). Stored form:
+//
+//   enc:v2:<key_id>:<base64 nonce || wrapped data key>:<base64 nonce || ciphertext>
+//
+// Both layers take `table.column:tenant_id` of the value (a SecretSlot) as
+// associated data, so a sealed value copied to another column or tenant no
+// longer opens. `enc:v1:` values predate that binding and plain values
+// predate encryption; both are still read, and every start re-seals them
+// under the active key. Without API0_MASTER_KEYS new values are stored in
+// plaintext (local development only).
+
+use crate::app_log;
+use crate::endpoint_store::db_helpers::ResultExt;
+use crate::endpoint_store::{EndpointStore, StoreError};
+use aes_gcm::aead::{Aead, KeyInit, Payload};
+use aes_gcm::{Aes256Gcm, Nonce};
+use base64::{engine::general_purpose::STANDARD, Engine as _};
+use rand::{rng, RngCore};
+use serde::Serialize;
+use std::collections::HashMap;
+
+const PREFIX: &str = "enc:v2:";
+/// Envelopes without associated data.
+const UNBOUND_PREFIX: &str = "enc:v1:";
+const NONCE_LEN: usize = 12;
+
+/// (table, column, tenant expression, extra WHERE clause) of every encrypted
+/// value. Values that belong to no tenant are bound to the empty tenant.
+const ENCRYPTED_COLUMNS: [(&str, &str, &str, &str); 15] = [
+    ("tenant_downstream_auth", "service_account_json", "tenant_id", ""),
+    ("tenant_downstream_auth", "bearer_token", "tenant_id", ""),
+    ("tenant_downstream_auth", "oauth_client_secret", "tenant_id", ""),
+    ("tenant_downstream_auth", "hmac_secret", "tenant_id", ""),
+    ("downstream_auth_overrides", "service_account_json", "tenant_id", ""),
+    ("downstream_auth_overrides", "bearer_token", "tenant_id", ""),
+    ("downstream_auth_overrides", "oauth_client_secret", "tenant_id", ""),
+    ("downstream_auth_overrides", "hmac_secret", "tenant_id", ""),
+    ("whatsapp_channels", "wa_token", "tenant_id", ""),
+    ("system_config", "value", "''", "AND key = 'email.smtp_password'"),
+    ("tenant_secret_versions", "value", "tenant_id", ""),
+    ("token_signing_keys", "private_key", "''", ""),
+    ("consumer_oauth_tokens", "access_token", "provider_tenant_id", ""),
+    ("consumer_oauth_tokens", "refresh_token", "provider_tenant_id", ""),
+    ("consumer_oauth_states", "code_verifier", "provider_tenant_id", ""),
+];
+
+/// Where a sealed value is stored; see the associated data above.
+#[derive(Debug, Clone, Copy)]
+pub struct SecretSlot<'a> {
+    pub table: &'a str,
+    pub column: &'a str,
+    pub tenant_id: &'a str,
+}
+
+impl<'a> SecretSlot<'a> {
+    pub fn new(table: &'a str, column: &'a str, tenant_id: &'a str) -> Self {
+        SecretSlot { table, column, tenant_id }
+    }
+
+    fn aad(&self) -> String {
+        format!("{}.{}:{}", self.table, self.column, self.tenant_id)
+    }
+}
+
+pub struct Keyring {
+    active: Option<String>,
+    keys: HashMap<String, [u8; 32]>,
+}
+
+impl Keyring {
+    pub fn from_env() -> Result<Self, String> {
+        match std::env::var("API0_MASTER_KEYS") {
+            Ok(spec) if !spec.trim().is_empty() => Self::parse(&spec),
+            _ => Ok(Keyring { active: None, keys: HashMap::new() }),
+        }
+    }
+
+    pub fn parse(spec: &str) -> Result<Self, String> {
+        let mut active = None;
+        let mut keys = HashMap::new();
+        for entry in spec.split(',').map(str::trim).filter(|e| !e.is_empty()) {
+            let (id, b64) = entry
+                .split_once(':')
+                .ok_or_else(|| format!("API0_MASTER_KEYS entry must be key_id:base64, got {}", entry))?;
+            if id.is_empty() || id.contains(char::is_whitespace) {
+                return Err(format!("invalid master key id: {:?}", id));
+            }
+            let bytes = STANDARD
+                .decode(b64)
+                .map_err(|e| format!("master key {}: invalid base64: {}", id, e))?;
+            let key: [u8; 32] = bytes
+                .try_into()
+                .map_err(|_| format!("master key {} must be 32 bytes", id))?;
+            if keys.insert(id.to_string(), key).is_some() {
+                return Err(format!("duplicate master key id {}", id));
+            }
+            active.get_or_insert_with(|| id.to_string());
+        }
+        Ok(Keyring { active, keys })
+    }
+
+    pub fn active_key_id(&self) -> Option<&str> {
+        self.active.as_deref()
+    }
+
+    pub fn encrypt(&self, slot: SecretSlot, plaintext: &str) -> Result<String, StoreError> {
+        let Some(key_id) = self.active.as_deref() else {
+            return Ok(plaintext.to_string());
+        };
+        let mut data_key = [0u8; 32];
+        rng().fill_bytes(&mut data_key);
+
+        let aad = slot.aad();
+        let wrapped = seal(&self.keys[key_id], &data_key, aad.as_bytes())?;
+        let sealed = seal(&data_key, plaintext.as_bytes(), aad.as_bytes())?;
+        Ok(format!("{}{}:{}:{}", PREFIX, key_id, STANDARD.encode(wrapped), STANDARD.encode(sealed)))
+    }
+
+    pub fn decrypt(&self, slot: SecretSlot, stored: &str) -> Result<String, StoreError> {
+        let (rest, aad) = if let Some(rest) = stored.strip_prefix(PREFIX) {
+            (rest, slot.aad())
+        } else if let Some(rest) = stored.strip_prefix(UNBOUND_PREFIX) {
+            (rest, String::new())
+        } else {
+            return Ok(stored.to_string());
+        };
+        let mut parts = rest.splitn(3, ':');
+        let (Some(key_id), Some(wrapped), Some(sealed)) = (parts.next(), parts.next(), parts.next()) else {
+            return Err(StoreError::Crypto("malformed encrypted value".to_string()));
+        };
+        let master = self
+            .keys
+            .get(key_id)
+            .ok_or_else(|| StoreError::Crypto(format!("master key {} is not configured", key_id)))?;
+        let decode = |s: &str| STANDARD.decode(s).map_err(|e| StoreError::Crypto(e.to_string()));
+
+        let data_key = open(master, &decode(wrapped)?, aad.as_bytes())?;
+        let plaintext = open(&data_key, &decode(sealed)?, aad.as_bytes())?;
+        String::from_utf8(plaintext).map_err(|e| StoreError::Crypto(e.to_string()))
+    }
+
+    pub fn encrypt_opt(&self, slot: SecretSlot, plaintext: &Option<String>) -> Result<Option<String>, StoreError> {
+        plaintext.as_deref().map(|p| self.encrypt(slot, p)).transpose()
+    }
+
+    pub fn decrypt_opt(&self, slot: SecretSlot, stored: Option<String>) -> Result<Option<String>, StoreError> {
+        stored.as_deref().map(|s| self.decrypt(slot, s)).transpose()
+    }
+
+    /// Plaintext or unbound: sealed before associated data was added.
+    fn is_unbound(&self, stored: &str) -> bool {
+        self.active.is_some() && !stored.starts_with(PREFIX)
+    }
+
+    /// Unbound, or encrypted under a key other than the active one.
+    fn needs_rotation(&self, stored: &str) -> bool {
+        match (stored.strip_prefix(PREFIX), self.active.as_deref()) {
+            (_, None) => false,
+            (None, Some(_)) => true,
+            (Some(rest), Some(active)) => rest.split(':').next() != Some(active),
+        }
+    }
+}
+
+fn seal(key: &[u8], plaintext: &[u8], aad: &[u8]) -> Result<Vec<u8>, StoreError> {
+    let cipher = Aes256Gcm::new_from_slice(key).map_err(|e| StoreError::Crypto(e.to_string()))?;
+    let mut nonce = [0u8; NONCE_LEN];
+    rng().fill_bytes(&mut nonce);
+    let ciphertext = cipher
+        .encrypt(Nonce::from_slice(&nonce), Payload { msg: plaintext, aad })
+        .map_err(|_| StoreError::Crypto("encryption failed".to_string()))?;
+    Ok([nonce.as_slice(), &ciphertext].concat())
+}
+
+fn open(key: &[u8], sealed: &[u8], aad: &[u8]) -> Result<Vec<u8>, StoreError> {
+    if sealed.len() < NONCE_LEN {
+        return Err(StoreError::Crypto("encrypted value too short".to_string()));
+    }
+    let cipher = Aes256Gcm::new_from_slice(key).map_err(|e| StoreError::Crypto(e.to_string()))?;
+    let (nonce, ciphertext) = sealed.split_at(NONCE_LEN);
+    cipher
+        .decrypt(Nonce::from_slice(nonce), Payload { msg: ciphertext, aad })
+        .map_err(|_| StoreError::Crypto("decryption failed (wrong key or corrupted value)".to_string()))
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct RotationReport {
+    pub key_id: String,
+    pub scanned: u64,
+    pub rotated: u64,
+    /// Rows changed concurrently between read and write; rerun to pick them up.
+    pub skipped: u64,
+}
+
+/// Re-encrypt every stored secret under the active master key (unbound
+/// values included). Safe to rerun; values already under the active key
+/// are left alone.
+pub async fn rotate_secrets(store: &EndpointStore) -> Result<RotationReport, StoreError> {
+    reseal(store, Keyring::needs_rotation).await
+}
+
+/// Seal plaintext and unbound values under the active key; run at startup.
+/// Values under an older key are left to `store rotate-secrets`.
+pub async fn seal_unbound_secrets(store: &EndpointStore) -> Result<RotationReport, StoreError> {
+    reseal(store, Keyring::is_unbound).await
+}
+
+async fn reseal(store: &EndpointStore, stale: fn(&Keyring, &str) -> bool) -> Result<RotationReport, StoreError> {
+    let keyring = store.secrets();
+    let key_id = keyring
+        .active_key_id()
+        .ok_or_else(|| StoreError::Crypto("API0_MASTER_KEYS is not set".to_string()))?;
+    let mut report = RotationReport { key_id: key_id.to_string(), ..Default::default() };
+    let client = store.get_admin_conn().await?;
+
+    for (table, column, tenant, filter) in ENCRYPTED_COLUMNS {
+        let rows = client
+            .query(
+                &format!(
+                    "SELECT ctid::text, {tenant}, {column} FROM {table} WHERE {column} IS NOT NULL {filter}"
+                ),
+                &[],
+            )
+            .await
+            .to_store_error()?;
+
+        let mut rotated = 0;
+        for row in &rows {
+            report.scanned += 1;
+            let ctid: String = row.get(0);
+            let tenant_id: String = row.get(1);
+            let stored: String = row.get(2);
+            if !stale(keyring, &stored) {
+                continue;
+            }
+            let slot = SecretSlot::new(table, column, &tenant_id);
+            let resealed = keyring.encrypt(slot, &keyring.decrypt(slot, &stored)?)?;
+            let n = client
+                .execute(
+                    &format!("UPDATE {table} SET {column} = $1 WHERE ctid = $2::text::tid AND {column} = $3"),
+                    &[&resealed, &ctid, &stored],
+                )
+                .await
+                .to_store_error()?;
+            if n == 0 {
+                report.skipped += 1;
+            } else {
+                rotated += 1;
+            }
+        }
+        report.rotated += rotated;
+        if rotated > 0 {
+            app_log!(info, table = %table, column = %column, rotated = rotated, "Re-encrypted secrets");
+        }
+    }
+
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(byte: u8) -> String {
+        STANDARD.encode([byte; 32])
+    }
+
+    fn slot(tenant_id: &str) -> SecretSlot<'_> {
+        SecretSlot::new("whatsapp_channels", "wa_token", tenant_id)
+    }
+
+    #[test]
+    fn round_trips_with_per_value_data_keys() {
+        let ring = Keyring::parse(&format!("k1:{}", key(1))).unwrap();
+        let a = ring.encrypt(slot("t1"), "s3cret").unwrap();
+        let b = ring.encrypt(slot("t1"), "s3cret").unwrap();
+        assert!(a.starts_with("enc:v2:k1:"));
+        assert_ne!(a, b);
+        assert_eq!(ring.decrypt(slot("t1"), &a).unwrap(), "s3cret");
+        assert_eq!(ring.decrypt(slot("t1"), "legacy plaintext").unwrap(), "legacy plaintext");
+    }
+
+    #[test]
+    fn sealed_values_are_bound_to_their_slot() {
+        let ring = Keyring::parse(&format!("k1:{}", key(1))).unwrap();
+        let sealed = ring.encrypt(slot("t1"), "wa-token").unwrap();
+        assert!(ring.decrypt(slot("t2"), &sealed).is_err(), "other tenant");
+        let other_column = SecretSlot::new("whatsapp_channels", "verify_token", "t1");
+        assert!(ring.decrypt(other_column, &sealed).is_err(), "other column");
+        let other_table = SecretSlot::new("tenant_secret_versions", "wa_token", "t1");
+        assert!(ring.decrypt(other_table, &sealed).is_err(), "other table");
+    }
+
+    #[test]
+    fn unbound_values_still_open_and_are_resealed() {
+        let ring = Keyring::parse(&format!("k1:{}", key(1))).unwrap();
+        // An enc:v1 envelope: the same layers without associated data.
+        let data_key = [7u8; 32];
+        let unbound = format!(
+            "enc:v1:k1:{}:{}",
+            STANDARD.encode(seal(&[1u8; 32], &data_key, b"").unwrap()),
+            STANDARD.encode(seal(&data_key, b"wa-token", b"").unwrap()),
+        );
+        assert_eq!(ring.decrypt(slot("t1"), &unbound).unwrap(), "wa-token");
+        assert!(ring.is_unbound(&unbound));
+        assert!(ring.is_unbound("plaintext"));
+        assert!(!ring.is_unbound(&ring.encrypt(slot("t1"), "x").unwrap()));
+        assert!(ring.needs_rotation(&unbound));
+    }
+
+    #[test]
+    fn old_keys_decrypt_and_flag_rotation() {
+        let old = Keyring::parse(&format!("k1:{}", key(1))).unwrap();
+        let sealed = old.encrypt(slot("t1"), "wa-token").unwrap();
+
+        let rotated = Keyring::parse(&format!("k2:{},k1:{}", key(2), key(1))).unwrap();
+        assert_eq!(rotated.active_key_id(), Some("k2"));
+        assert_eq!(rotated.decrypt(slot("t1"), &sealed).unwrap(), "wa-token");
+        assert!(rotated.needs_rotation(&sealed));
+        assert!(!rotated.is_unbound(&sealed), "startup leaves key rotation alone");
+        assert!(rotated.needs_rotation("plaintext"));
+        assert!(!rotated.needs_rotation(&rotated.encrypt(slot("t1"), "x").unwrap()));
+
+        let without_old = Keyring::parse(&format!("k2:{}", key(2))).unwrap();
+        assert!(without_old.decrypt(slot("t1"), &sealed).is_err());
+    }
+
+    #[test]
+    fn rejects_bad_key_specs() {
+        assert!(Keyring::parse("nocolon").is_err());
+        assert!(Keyring::parse(&format!("k1:{}", STANDARD.encode([0u8; 16]))).is_err());
+        assert!(Keyring::parse(&format!("k1:{},k1:{}", key(1), key(2))).is_err());
+    }
+}
@@ -0,0 +1,397 @@
+// src/endpoint_store/secret_vault.rs
+//
+// Tenant secret vault. Named secrets are write-only (no API returns a value),
+// versioned (every put adds a version and makes it current) and record when
+// they were last used.
+//
+// Configs reference them as {{secret:NAME}} (current version) or
+// {{secret:NAME@3}} (pinned version) in:
+//
+//   tenant_downstream_auth / downstream_auth_overrides
+//       bearer_token, custom_headers values
+//   mcp_tools
+//       backend_url, request_mapping query / headers constant values
+//
+// References are substituted only on the gateway paths — resolve_downstream_auth
+// and the tool lookup / render routes called with X-Internal-Secret. Dashboard
+// reads keep the placeholders. A reference to a missing secret or version is
+// an error, never sent downstream as a literal.
+
+use crate::app_log;
+use crate::endpoint_store::db_helpers::ResultExt;
+use crate::endpoint_store::downstream_auth_management::TenantDownstreamAuth;
+use crate::endpoint_store::mcp_tools_management::McpTool;
+use crate::endpoint_store::secret_crypto::SecretSlot;
+use crate::endpoint_store::{EndpointStore, StoreError};
+use chrono::Utc;
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::{HashMap, HashSet};
+
+const OPEN: &str = "{{secret:";
+const CLOSE: &str = "}}";
+pub const NAME_MAX_LEN: usize = 64;
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct SecretRef {
+    pub name: String,
+    /// None = current version.
+    pub version: Option<i32>,
+}
+
+impl SecretRef {
+    fn pinned_key(&self) -> Option<String> {
+        self.version.map(|v| format!("{}@{}", self.name, v))
+    }
+}
+
+impl std::fmt::Display for SecretRef {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.version {
+            Some(v) => write!(f, "{}@{}", self.name, v),
+            None => write!(f, "{}", self.name),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct TenantSecret {
+    pub name: String,
+    pub description: Option<String>,
+    pub current_version: i32,
+    pub created_at: String,
+    pub updated_at: String,
+    pub last_used_at: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct TenantSecretVersion {
+    pub version: i32,
+    pub is_current: bool,
+    pub created_at: String,
+    pub last_used_at: Option<String>,
+}
+
+/// Secret names: letters, digits and underscores (e.g. STRIPE_KEY).
+pub fn check_name(name: &str) -> Result<(), String> {
+    if name.is_empty() || name.len() > NAME_MAX_LEN {
+        return Err(format!("name must be 1 to {} characters", NAME_MAX_LEN));
+    }
+    if !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') {
+        return Err("name may only contain letters, digits and underscores".to_string());
+    }
+    Ok(())
+}
+
+fn parse_ref(inner: &str) -> Option<SecretRef> {
+    let (name, version) = match inner.split_once('@') {
+        Some((name, v)) => (name, Some(v.parse::<i32>().ok().filter(|v| *v > 0)?)),
+        None => (inner, None),
+    };
+    check_name(name).ok()?;
+    Some(SecretRef { name: name.to_string(), version })
+}
+
+/// Walk `text`, calling `on_ref` for every well-formed reference and
+/// `on_text` for everything else (malformed placeholders included).
+fn scan<'a>(text: &'a str, mut on_text: impl FnMut(&'a str), mut on_ref: impl FnMut(SecretRef)) {
+    let mut rest = text;
+    while let Some(start) = rest.find(OPEN) {
+        let after = &rest[start + OPEN.len()..];
+        match after.find(CLOSE).and_then(|end| parse_ref(&after[..end]).map(|r| (end, r))) {
+            Some((end, secret_ref)) => {
+                on_text(&rest[..start]);
+                on_ref(secret_ref);
+                rest = &after[end + CLOSE.len()..];
+            }
+            None => {
+                on_text(&rest[..start + OPEN.len()]);
+                rest = after;
+            }
+        }
+    }
+    on_text(rest);
+}
+
+pub fn find_references(text: &str) -> Vec<SecretRef> {
+    let mut refs = Vec::new();
+    scan(text, |_| {}, |r| refs.push(r));
+    refs
+}
+
+pub fn has_references(text: &str) -> bool {
+    !find_references(text).is_empty()
+}
+
+fn substitute(text: &str, values: &HashMap<SecretRef, String>) -> String {
+    let out = std::cell::RefCell::new(String::with_capacity(text.len()));
+    scan(
+        text,
+        |t| out.borrow_mut().push_str(t),
+        |r| out.borrow_mut().push_str(values.get(&r).map(String::as_str).unwrap_or_default()),
+    );
+    out.into_inner()
+}
+
+// ── Vault CRUD ────────────────────────────────────────────────────────────────
+
+fn value_slot(tenant_id: &str) -> SecretSlot<'_> {
+    SecretSlot::new("tenant_secret_versions", "value", tenant_id)
+}
+
+/// Store `value` as the new current version of `name` (creating the secret).
+pub async fn put_secret(
+    store: &EndpointStore,
+    tenant_id: &str,
+    name: &str,
+    value: &str,
+    description: Option<&str>,
+) -> Result<TenantSecret, StoreError> {
+    let sealed = store.secrets().encrypt(value_slot(tenant_id), value)?;
+    let mut client = store.get_conn(Some(tenant_id)).await?;
+    let tx = client.transaction().await.to_store_error()?;
+
+    let row = tx
+        .query_one(
+            "INSERT INTO tenant_secrets (tenant_id, name, description)
+             VALUES ($1, $2, $3)
+             ON CONFLICT (tenant_id, name) DO UPDATE SET
+                current_version = tenant_secrets.current_version + 1,
+                description     = COALESCE(EXCLUDED.description, tenant_secrets.description),
+                updated_at      = NOW()
+             RETURNING name, description, current_version, created_at, updated_at, last_used_at",
+            &[&tenant_id, &name, &description],
+        )
+        .await
+        .to_store_error()?;
+    let secret = row_to_secret(&row);
+
+    tx.execute(
+        "INSERT INTO tenant_secret_versions (tenant_id, name, version, value)
+         VALUES ($1, $2, $3, $4)",
+        &[&tenant_id, &name, &secret.current_version, &sealed],
+    )
+    .await
+    .to_store_error()?;
+    tx.commit().await.to_store_error()?;
+
+    app_log!(info, tenant_id = %tenant_id, name = %name, version = secret.current_version,
+        "Stored tenant secret version");
+    Ok(secret)
+}
+
+pub async fn list_secrets(store: &EndpointStore, tenant_id: &str) -> Result<Vec<TenantSecret>, StoreError> {
+    let client = store.get_conn(Some(tenant_id)).await?;
+    let rows = client
+        .query(
+            "SELECT name, description, current_version, created_at, updated_at, last_used_at
+             FROM tenant_secrets WHERE tenant_id = $1 ORDER BY name",
+            &[&tenant_id],
+        )
+        .await
+        .to_store_error()?;
+    Ok(rows.iter().map(row_to_secret).collect())
+}
+
+/// Version history of `name`, newest first. None when the secret does not exist.
+pub async fn list_secret_versions(
+    store: &EndpointStore,
+    tenant_id: &str,
+    name: &str,
+) -> Result<Option<Vec<TenantSecretVersion>>, StoreError> {
+    let client = store.get_conn(Some(tenant_id)).await?;
+    let rows = client
+        .query(
+            "SELECT v.version, v.version = s.current_version, v.created_at, v.last_used_at
+             FROM tenant_secrets s
+             LEFT JOIN tenant_secret_versions v ON v.tenant_id = s.tenant_id AND v.name = s.name
+             WHERE s.tenant_id = $1 AND s.name = $2
+             ORDER BY v.version DESC",
+            &[&tenant_id, &name],
+        )
+        .await
+        .to_store_error()?;
+    if rows.is_empty() {
+        return Ok(None);
+    }
+    Ok(Some(
+        rows.iter()
+            .filter(|r| r.get::<_, Option<i32>>(0).is_some())
+            .map(|r| TenantSecretVersion {
+                version:      r.get(0),
+                is_current:   r.get(1),
+                created_at:   r.get::<_, chrono::DateTime<Utc>>(2).to_rfc3339(),
+                last_used_at: r.get::<_, Option<chrono::DateTime<Utc>>>(3).map(|t| t.to_rfc3339()),
+            })
+            .collect(),
+    ))
+}
+
+/// Delete a secret and all its versions. Configs still referencing it will
+/// fail to resolve.
+pub async fn delete_secret(store: &EndpointStore, tenant_id: &str, name: &str) -> Result<bool, StoreError> {
+    let client = store.get_conn(Some(tenant_id)).await?;
+    let n = client
+        .execute(
+            "DELETE FROM tenant_secrets WHERE tenant_id = $1 AND name = $2",
+            &[&tenant_id, &name],
+        )
+        .await
+        .to_store_error()?;
+    if n > 0 {
+        app_log!(info, tenant_id = %tenant_id, name = %name, "Deleted tenant secret");
+    }
+    Ok(n > 0)
+}
+
+fn row_to_secret(row: &tokio_postgres::Row) -> TenantSecret {
+    TenantSecret {
+        name:            row.get(0),
+        description:     row.get(1),
+        current_version: row.get(2),
+        created_at:      row.get::<_, chrono::DateTime<Utc>>(3).to_rfc3339(),
+        updated_at:      row.get::<_, chrono::DateTime<Utc>>(4).to_rfc3339(),
+        last_used_at:    row.get::<_, Option<chrono::DateTime<Utc>>>(5).map(|t| t.to_rfc3339()),
+    }
+}
+
+// ── Resolution ────────────────────────────────────────────────────────────────
+
+/// Decrypt the values behind `refs` and stamp last_used_at (at most once a
+/// minute per secret / version, to keep gateway lookups from writing on
+/// every call).
+async fn load_values(
+    store: &EndpointStore,
+    tenant_id: &str,
+    refs: &HashSet<SecretRef>,
+) -> Result<HashMap<SecretRef, String>, StoreError> {
+    let names: Vec<&str> = refs.iter().map(|r| r.name.as_str()).collect();
+    let pinned: Vec<String> = refs.iter().filter_map(SecretRef::pinned_key).collect();
+    let client = store.get_conn(Some(tenant_id)).await?;
+
+    let rows = client
+        .query(
+            "SELECT s.name, v.version, v.version = s.current_version, v.value
+             FROM tenant_secrets s
+             JOIN tenant_secret_versions v ON v.tenant_id = s.tenant_id AND v.name = s.name
+             WHERE s.tenant_id = $1 AND s.name = ANY($2)
+               AND (v.version = s.current_version OR s.name || '@' || v.version = ANY($3))",
+            &[&tenant_id, &names, &pinned],
+        )
+        .await
+        .to_store_error()?;
+
+    let mut values = HashMap::new();
+    let mut used_versions = Vec::new();
+    for secret_ref in refs {
+        let row = rows.iter().find(|r| {
+            r.get::<_, &str>(0) == secret_ref.name
+                && match secret_ref.version {
+                    Some(v) => r.get::<_, i32>(1) == v,
+                    None => r.get::<_, bool>(2),
+                }
+        });
+        let row = row.ok_or_else(|| StoreError::NotFound(format!("Secret {}", secret_ref)))?;
+        values.insert(secret_ref.clone(), store.secrets().decrypt(value_slot(tenant_id), row.get(3))?);
+        used_versions.push(format!("{}@{}", secret_ref.name, row.get::<_, i32>(1)));
+    }
+
+    let touched = client
+        .execute(
+            "UPDATE tenant_secrets SET last_used_at = NOW()
+             WHERE tenant_id = $1 AND name = ANY($2)
+               AND (last_used_at IS NULL OR last_used_at < NOW() - INTERVAL '1 minute')",
+            &[&tenant_id, &names],
+        )
+        .await;
+    let touched_versions = client
+        .execute(
+            "UPDATE tenant_secret_versions SET last_used_at = NOW()
+             WHERE tenant_id = $1 AND name || '@' || version = ANY($2)
+               AND (last_used_at IS NULL OR last_used_at < NOW() - INTERVAL '1 minute')",
+            &[&tenant_id, &used_versions],
+        )
+        .await;
+    if let Err(e) = touched.and(touched_versions) {
+        app_log!(warn, tenant_id = %tenant_id, error = %e, "Failed to record secret usage");
+    }
+
+    Ok(values)
+}
+
+/// Substitute every reference found in `slots`, in place.
+async fn resolve_slots(store: &EndpointStore, tenant_id: &str, slots: Vec<&mut String>) -> Result<(), StoreError> {
+    let refs: HashSet<SecretRef> = slots.iter().flat_map(|s| find_references(s)).collect();
+    if refs.is_empty() {
+        return Ok(());
+    }
+    let values = load_values(store, tenant_id, &refs).await?;
+    for slot in slots {
+        *slot = substitute(slot, &values);
+    }
+    Ok(())
+}
+
+pub async fn resolve_text(store: &EndpointStore, tenant_id: &str, text: &str) -> Result<String, StoreError> {
+    let mut out = text.to_string();
+    resolve_slots(store, tenant_id, vec![&mut out]).await?;
+    Ok(out)
+}
+
+pub async fn resolve_auth_secrets(
+    store: &EndpointStore,
+    tenant_id: &str,
+    auth: &mut TenantDownstreamAuth,
+) -> Result<(), StoreError> {
+    let mut slots: Vec<&mut String> = Vec::new();
+    slots.extend(auth.bearer_token.as_mut());
+    if let Some(Value::Object(headers)) = auth.custom_headers.as_mut() {
+        slots.extend(headers.values_mut().filter_map(|v| match v {
+            Value::String(s) => Some(s),
+            _ => None,
+        }));
+    }
+    resolve_slots(store, tenant_id, slots).await
+}
+
+pub async fn resolve_tool_secrets(store: &EndpointStore, tenant_id: &str, tool: &mut McpTool) -> Result<(), StoreError> {
+    let mut slots: Vec<&mut String> = vec![&mut tool.backend_url];
+    if let Some(mapping) = tool.request_mapping.as_mut() {
+        for binding in mapping.query.iter_mut().chain(mapping.headers.iter_mut()) {
+            if let Some(Value::String(s)) = binding.value.as_mut() {
+                slots.push(s);
+            }
+        }
+    }
+    resolve_slots(store, tenant_id, slots).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn finds_current_and_pinned_references() {
+        let refs = find_references("Bearer {{secret:STRIPE_KEY}} / {{secret:OLD@2}} {{secret:bad name}} {{secret:X@0}}");
+        assert_eq!(
+            refs,
+            vec![
+                SecretRef { name: "STRIPE_KEY".to_string(), version: None },
+                SecretRef { name: "OLD".to_string(), version: Some(2) },
+            ]
+        );
+        assert!(!has_references("{{secret:}} {{ secret:A }} {{secret:A"));
+    }
+
+    #[test]
+    fn substitutes_and_keeps_malformed_placeholders() {
+        let values = HashMap::from([
+            (SecretRef { name: "K".to_string(), version: None }, "v1".to_string()),
+            (SecretRef { name: "K".to_string(), version: Some(1) }, "v0".to_string()),
+        ]);
+        assert_eq!(
+            substitute("https://api/x?key={{secret:K}}&old={{secret:K@1}}&{{secret:}}", &values),
+            "https://api/x?key=v1&old=v0&{{secret:}}"
+        );
+    }
+}
@@ -0,0 +1,88 @@
+// src/endpoint_store/security_events.rs
+//
+// Append-only log of security-relevant events (denied callers, lockouts …).
+// Recording is best-effort: a failure to write the log is logged and never
+// changes the outcome of the request that triggered it.
+
+use crate::app_log;
+use crate::endpoint_store::db_helpers::ResultExt;
+use crate::endpoint_store::{EndpointStore, StoreError};
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+
+/// Key used from an address outside its allowlist.
+pub const IP_NOT_ALLOWED: &str = "ip_not_allowed";
+/// Too many failed validations; see auth_lockout.rs.
+pub const AUTH_LOCKOUT: &str = "auth_lockout";
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SecurityEvent {
+    pub id: i64,
+    pub tenant_id: Option<String>,
+    pub key_id: Option<String>,
+    pub event_type: String,
+    pub ip_address: Option<String>,
+    pub detail: serde_json::Value,
+    pub created_at: String,
+}
+
+pub async fn record_security_event(
+    store: &EndpointStore,
+    tenant_id: Option<&str>,
+    key_id: Option<&str>,
+    event_type: &str,
+    ip_address: Option<&str>,
+    detail: serde_json::Value,
+) {
+    let result = async {
+        let client = store.get_admin_conn().await?;
+        client
+            .execute(
+                // Unknown tenant ids (e.g. from a caller's expected_tenant_id)
+                // are stored as NULL rather than failing the foreign key.
+                "INSERT INTO security_events (tenant_id, key_id, event_type, ip_address, detail)
+                 VALUES ((SELECT id FROM tenants WHERE id = $1), $2, $3, $4, $5)",
+                &[&tenant_id, &key_id, &event_type, &ip_address, &detail],
+            )
+            .await
+            .to_store_error()
+    }
+    .await;
+    if let Err(e) = result {
+        app_log!(error, error = %e, event_type = %event_type, key_id = ?key_id, "Failed to record security event");
+    }
+}
+
+/// Most recent events of a tenant, newest first; optionally one type only.
+pub async fn list_security_events(
+    store: &EndpointStore,
+    tenant_id: &str,
+    event_type: Option<&str>,
+    limit: i64,
+) -> Result<Vec<SecurityEvent>, StoreError> {
+    let client = store.get_conn(Some(tenant_id)).await?;
+    let rows = client
+        .query(
+            "SELECT id, tenant_id, key_id, event_type, ip_address, detail, created_at
+             FROM security_events
+             WHERE tenant_id = $1 AND ($2::VARCHAR IS NULL OR event_type = $2)
+             ORDER BY created_at DESC, id DESC
+             LIMIT $3",
+            &[&tenant_id, &event_type, &limit],
+        )
+        .await
+        .to_store_error()?;
+
+    Ok(rows
+        .iter()
+        .map(|r| SecurityEvent {
+            id: r.get(0),
+            tenant_id: r.get(1),
+            key_id: r.get(2),
+            event_type: r.get(3),
+            ip_address: r.get(4),
+            detail: r.get(5),
+            created_at: r.get::<_, DateTime<Utc>>(6).to_rfc3339(),
+        })
+        .collect())
+}
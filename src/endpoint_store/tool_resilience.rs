@@ -0,0 +1,449 @@
+// src/endpoint_store/tool_resilience.rs
+//
+// Per-tool resilience policy (retries, backoff, concurrency cap, circuit
+// breaker thresholds) and the circuit state derived from call outcomes.
+//
+// The store does not make backend calls itself — the gateway applies the
+// policy. What the store owns is the circuit: every outcome reported through
+// log_api_usage with a tool_name updates mcp_tool_circuits, and the tool
+// listing exposes the resulting state so the gateway can fail fast.
+//
+//   closed    — calls flow normally
+//   open      — `circuit_failure_threshold` consecutive failures; reject calls
+//   half_open — cooldown elapsed; one probe call may go through. The gateway
+//               claims it with claim_probe (POST .../probe); until its outcome
+//               is reported the circuit reads as open again, so concurrent
+//               callers keep failing fast. A success closes the circuit, a
+//               failure re-opens it for another cooldown. A claim whose
+//               outcome never arrives lapses after one cooldown.
+//
+// Successes reported while the circuit is open and no probe is out come from
+// calls admitted before it opened; they do not close it.
+
+use crate::app_log;
+use crate::endpoint_store::db_helpers::ResultExt;
+use crate::endpoint_store::{EndpointStore, StoreError};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct ResiliencePolicy {
+    /// Extra attempts after the first call. 0 = no retries.
+    pub retry_max_attempts: i32,
+    /// Initial backoff; doubled on each retry up to `retry_max_backoff_ms`.
+    pub retry_backoff_ms: i32,
+    pub retry_max_backoff_ms: i32,
+    /// Backend status codes worth retrying. Connection errors and timeouts
+    /// are always retryable.
+    pub retry_on_status: Vec<i32>,
+    /// Maximum in-flight calls per gateway instance. None = unlimited.
+    pub max_concurrency: Option<i32>,
+    /// Consecutive failures that open the circuit. 0 = breaker disabled.
+    pub circuit_failure_threshold: i32,
+    /// How long the circuit stays open before a probe is allowed.
+    pub circuit_cooldown_ms: i32,
+}
+
+impl Default for ResiliencePolicy {
+    fn default() -> Self {
+        Self {
+            retry_max_attempts: 0,
+            retry_backoff_ms: 200,
+            retry_max_backoff_ms: 5_000,
+            retry_on_status: vec![502, 503, 504],
+            max_concurrency: None,
+            circuit_failure_threshold: 5,
+            circuit_cooldown_ms: 30_000,
+        }
+    }
+}
+
+impl ResiliencePolicy {
+    pub fn check(&self) -> Result<(), String> {
+        if self.retry_max_attempts < 0 || self.retry_max_attempts > 10 {
+            return Err("retry_max_attempts must be between 0 and 10".to_string());
+        }
+        if self.retry_backoff_ms < 0 || self.retry_max_backoff_ms < self.retry_backoff_ms {
+            return Err("retry_backoff_ms must be >= 0 and <= retry_max_backoff_ms".to_string());
+        }
+        if self.retry_on_status.iter().any(|s| !(100..=599).contains(s)) {
+            return Err("retry_on_status must contain HTTP status codes".to_string());
+        }
+        if matches!(self.max_concurrency, Some(n) if n < 1) {
+            return Err("max_concurrency must be >= 1 when set".to_string());
+        }
+        if self.circuit_failure_threshold < 0 || self.circuit_cooldown_ms < 0 {
+            return Err("circuit thresholds must be >= 0".to_string());
+        }
+        Ok(())
+    }
+
+    /// A failed call is one that never got a status (timeout, connection
+    /// error), got a 5xx, or got a status the policy marks as retryable.
+    pub fn is_failure(&self, status_code: Option<i32>) -> bool {
+        match status_code {
+            None => true,
+            Some(s) => s >= 500 || self.retry_on_status.contains(&s),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum CircuitState {
+    #[default]
+    Closed,
+    Open,
+    HalfOpen,
+}
+
+impl CircuitState {
+    fn as_str(&self) -> &'static str {
+        match self {
+            CircuitState::Closed => "closed",
+            // half_open is derived from opened_at + cooldown, never stored.
+            CircuitState::Open | CircuitState::HalfOpen => "open",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Circuit {
+    pub open: bool,
+    pub consecutive_failures: i32,
+    pub opened_at: Option<DateTime<Utc>>,
+    /// Set while the half-open probe is out.
+    pub probe_started_at: Option<DateTime<Utc>>,
+}
+
+impl Circuit {
+    pub fn state(&self, policy: &ResiliencePolicy, now: DateTime<Utc>) -> CircuitState {
+        match (self.open, self.opened_at) {
+            (false, _) => CircuitState::Closed,
+            (true, Some(at)) if now - at >= cooldown(policy) && !self.probe_in_flight(policy, now) => {
+                CircuitState::HalfOpen
+            }
+            (true, _) => CircuitState::Open,
+        }
+    }
+
+    fn probe_in_flight(&self, policy: &ResiliencePolicy, now: DateTime<Utc>) -> bool {
+        self.probe_started_at.is_some_and(|at| now - at < cooldown(policy))
+    }
+
+    /// Take the half-open probe slot. None unless the circuit is half-open.
+    pub fn claim_probe(&self, policy: &ResiliencePolicy, now: DateTime<Utc>) -> Option<Circuit> {
+        (self.state(policy, now) == CircuitState::HalfOpen)
+            .then(|| Circuit { probe_started_at: Some(now), ..self.clone() })
+    }
+
+    /// Apply one call outcome.
+    pub fn record(&self, policy: &ResiliencePolicy, failed: bool, now: DateTime<Utc>) -> Circuit {
+        let state = self.state(policy, now);
+        // The probe's outcome, or any call once the cooldown is over for
+        // gateways that do not claim probes.
+        let probe = state == CircuitState::HalfOpen || self.probe_in_flight(policy, now);
+
+        if !failed {
+            if state == CircuitState::Open && !probe {
+                return self.clone();
+            }
+            return Circuit { open: false, consecutive_failures: 0, opened_at: None, probe_started_at: None };
+        }
+
+        let failures = self.consecutive_failures.saturating_add(1);
+        let trips = policy.circuit_failure_threshold > 0 && failures >= policy.circuit_failure_threshold;
+
+        match state {
+            // Failed probe: back to open for a fresh cooldown.
+            _ if probe => Circuit { open: true, consecutive_failures: failures, opened_at: Some(now), probe_started_at: None },
+            // Calls that were already in flight when the circuit opened.
+            CircuitState::Open => Circuit { consecutive_failures: failures, ..self.clone() },
+            CircuitState::Closed if trips => {
+                Circuit { open: true, consecutive_failures: failures, opened_at: Some(now), probe_started_at: None }
+            }
+            _ => Circuit { open: false, consecutive_failures: failures, opened_at: None, probe_started_at: None },
+        }
+    }
+}
+
+fn cooldown(policy: &ResiliencePolicy) -> chrono::Duration {
+    chrono::Duration::milliseconds(policy.circuit_cooldown_ms as i64)
+}
+
+/// Policy for a tool: the stored one for active explicit tools, defaults
+/// otherwise (virtual endpoint-derived tools have no mcp_tools row, and a
+/// soft-deleted tool's policy no longer applies).
+async fn load_policy(
+    client: &tokio_postgres::Client,
+    tenant_id: &str,
+    tool_name: &str,
+) -> Result<ResiliencePolicy, StoreError> {
+    let row = client
+        .query_opt(
+            "SELECT retry_max_attempts, retry_backoff_ms, retry_max_backoff_ms, retry_on_status,
+                    max_concurrency, circuit_failure_threshold, circuit_cooldown_ms
+             FROM mcp_tools
+             WHERE tenant_id = $1 AND tool_name = $2 AND is_active = true",
+            &[&tenant_id, &tool_name],
+        )
+        .await
+        .to_store_error()?;
+
+    Ok(row.map(|r| policy_from_row(&r, 0)).unwrap_or_default())
+}
+
+/// Read a policy out of a row whose resilience columns start at `offset`
+/// in the order used by the mcp_tools SELECTs.
+pub fn policy_from_row(row: &tokio_postgres::Row, offset: usize) -> ResiliencePolicy {
+    ResiliencePolicy {
+        retry_max_attempts: row.get(offset),
+        retry_backoff_ms: row.get(offset + 1),
+        retry_max_backoff_ms: row.get(offset + 2),
+        retry_on_status: row.get(offset + 3),
+        max_concurrency: row.get(offset + 4),
+        circuit_failure_threshold: row.get(offset + 5),
+        circuit_cooldown_ms: row.get(offset + 6),
+    }
+}
+
+/// Update the circuit for (tenant, tool) with one call outcome.
+/// Called from log_api_usage; the row is locked so concurrent reports
+/// from several gateway instances serialise.
+pub async fn record_tool_outcome(
+    store: &EndpointStore,
+    tenant_id: &str,
+    tool_name: &str,
+    status_code: Option<i32>,
+) -> Result<CircuitState, StoreError> {
+    let mut client = store.get_conn(Some(tenant_id)).await?;
+    let policy = load_policy(&client, tenant_id, tool_name).await?;
+    let failed = policy.is_failure(status_code);
+    let now = Utc::now();
+
+    let tx = client.transaction().await.to_store_error()?;
+
+    tx.execute(
+        "INSERT INTO mcp_tool_circuits (tenant_id, tool_name)
+         VALUES ($1, $2)
+         ON CONFLICT (tenant_id, tool_name) DO NOTHING",
+        &[&tenant_id, &tool_name],
+    )
+    .await
+    .to_store_error()?;
+
+    let row = tx
+        .query_one(
+            "SELECT state, consecutive_failures, opened_at, probe_started_at
+             FROM mcp_tool_circuits
+             WHERE tenant_id = $1 AND tool_name = $2
+             FOR UPDATE",
+            &[&tenant_id, &tool_name],
+        )
+        .await
+        .to_store_error()?;
+
+    let current = circuit_from_row(&row, 0);
+    let before = current.state(&policy, now);
+    let next = current.record(&policy, failed, now);
+    let next_state = if next.open { CircuitState::Open } else { CircuitState::Closed };
+
+    tx.execute(
+        "UPDATE mcp_tool_circuits
+         SET state = $3, consecutive_failures = $4, opened_at = $5, probe_started_at = $7,
+             last_failure_at = CASE WHEN $6 THEN NOW() ELSE last_failure_at END,
+             last_success_at = CASE WHEN $6 THEN last_success_at ELSE NOW() END,
+             updated_at = NOW()
+         WHERE tenant_id = $1 AND tool_name = $2",
+        &[
+            &tenant_id as &(dyn tokio_postgres::types::ToSql + Sync),
+            &tool_name as &(dyn tokio_postgres::types::ToSql + Sync),
+            &next_state.as_str() as &(dyn tokio_postgres::types::ToSql + Sync),
+            &next.consecutive_failures as &(dyn tokio_postgres::types::ToSql + Sync),
+            &next.opened_at as &(dyn tokio_postgres::types::ToSql + Sync),
+            &failed as &(dyn tokio_postgres::types::ToSql + Sync),
+            &next.probe_started_at as &(dyn tokio_postgres::types::ToSql + Sync),
+        ],
+    )
+    .await
+    .to_store_error()?;
+
+    tx.commit().await.to_store_error()?;
+
+    if next.open && next.opened_at == Some(now) {
+        app_log!(warn, tenant_id = %tenant_id, tool_name = %tool_name,
+            failures = next.consecutive_failures, "Circuit opened for MCP tool");
+    } else if before != CircuitState::Closed && !next.open {
+        app_log!(info, tenant_id = %tenant_id, tool_name = %tool_name, "Circuit closed for MCP tool");
+    }
+
+    Ok(next.state(&policy, now))
+}
+
+/// Ask to send a call to (tenant, tool): true while the circuit is closed,
+/// and for the one caller that claims the probe once it is half-open.
+pub async fn claim_probe(store: &EndpointStore, tenant_id: &str, tool_name: &str) -> Result<bool, StoreError> {
+    let mut client = store.get_conn(Some(tenant_id)).await?;
+    let policy = load_policy(&client, tenant_id, tool_name).await?;
+    let now = Utc::now();
+
+    let tx = client.transaction().await.to_store_error()?;
+    let row = tx
+        .query_opt(
+            "SELECT state, consecutive_failures, opened_at, probe_started_at
+             FROM mcp_tool_circuits
+             WHERE tenant_id = $1 AND tool_name = $2
+             FOR UPDATE",
+            &[&tenant_id, &tool_name],
+        )
+        .await
+        .to_store_error()?;
+    let Some(row) = row else { return Ok(true) };
+
+    let current = circuit_from_row(&row, 0);
+    if current.state(&policy, now) == CircuitState::Closed {
+        return Ok(true);
+    }
+    let Some(claimed) = current.claim_probe(&policy, now) else { return Ok(false) };
+
+    tx.execute(
+        "UPDATE mcp_tool_circuits SET probe_started_at = $3, updated_at = NOW()
+         WHERE tenant_id = $1 AND tool_name = $2",
+        &[&tenant_id, &tool_name, &claimed.probe_started_at],
+    )
+    .await
+    .to_store_error()?;
+    tx.commit().await.to_store_error()?;
+
+    app_log!(info, tenant_id = %tenant_id, tool_name = %tool_name, "Circuit probe claimed for MCP tool");
+    Ok(true)
+}
+
+/// Read a circuit out of a row whose columns start at `offset` in the order
+/// state, consecutive_failures, opened_at, probe_started_at.
+fn circuit_from_row(row: &tokio_postgres::Row, offset: usize) -> Circuit {
+    Circuit {
+        open: row.get::<_, String>(offset) == "open",
+        consecutive_failures: row.get(offset + 1),
+        opened_at: row.get(offset + 2),
+        probe_started_at: row.get(offset + 3),
+    }
+}
+
+/// Raw circuit rows for a tenant, keyed by tool name.
+pub async fn load_circuits(
+    client: &tokio_postgres::Client,
+    tenant_id: &str,
+) -> Result<HashMap<String, Circuit>, StoreError> {
+    let rows = client
+        .query(
+            "SELECT tool_name, state, consecutive_failures, opened_at, probe_started_at
+             FROM mcp_tool_circuits
+             WHERE tenant_id = $1",
+            &[&tenant_id],
+        )
+        .await
+        .to_store_error()?;
+
+    Ok(rows
+        .into_iter()
+        .map(|r| (r.get::<_, String>(0), circuit_from_row(&r, 1)))
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn policy() -> ResiliencePolicy {
+        ResiliencePolicy { circuit_failure_threshold: 3, circuit_cooldown_ms: 1_000, ..Default::default() }
+    }
+
+    fn closed() -> Circuit {
+        Circuit { open: false, consecutive_failures: 0, opened_at: None, probe_started_at: None }
+    }
+
+    #[test]
+    fn opens_after_threshold_and_half_opens_after_cooldown() {
+        let p = policy();
+        let t0 = Utc::now();
+        let c = closed().record(&p, true, t0).record(&p, true, t0);
+        assert_eq!(c.state(&p, t0), CircuitState::Closed);
+
+        let c = c.record(&p, true, t0);
+        assert_eq!(c.state(&p, t0), CircuitState::Open);
+        assert_eq!(c.state(&p, t0 + chrono::Duration::milliseconds(1_000)), CircuitState::HalfOpen);
+    }
+
+    #[test]
+    fn probe_outcome_closes_or_reopens() {
+        let p = policy();
+        let t0 = Utc::now();
+        let open = Circuit { open: true, consecutive_failures: 3, opened_at: Some(t0), probe_started_at: None };
+        let later = t0 + chrono::Duration::seconds(2);
+
+        assert_eq!(open.record(&p, false, later), closed());
+
+        let reopened = open.record(&p, true, later);
+        assert_eq!(reopened.opened_at, Some(later));
+        assert_eq!(reopened.state(&p, later), CircuitState::Open);
+    }
+
+    #[test]
+    fn success_while_open_does_not_close() {
+        let p = policy();
+        let t0 = Utc::now();
+        let open = Circuit { open: true, consecutive_failures: 3, opened_at: Some(t0), probe_started_at: None };
+        let soon = t0 + chrono::Duration::milliseconds(100);
+        assert_eq!(open.record(&p, false, soon), open);
+        assert_eq!(open.claim_probe(&p, soon), None);
+    }
+
+    #[test]
+    fn half_open_admits_a_single_probe() {
+        let p = policy();
+        let t0 = Utc::now();
+        let open = Circuit { open: true, consecutive_failures: 3, opened_at: Some(t0), probe_started_at: None };
+        let later = t0 + chrono::Duration::seconds(2);
+
+        let probing = open.claim_probe(&p, later).unwrap();
+        assert_eq!(probing.state(&p, later), CircuitState::Open);
+        assert_eq!(probing.claim_probe(&p, later), None, "second caller must not get a probe");
+
+        let done = later + chrono::Duration::milliseconds(100);
+        assert_eq!(probing.record(&p, false, done), closed());
+        let reopened = probing.record(&p, true, done);
+        assert_eq!((reopened.opened_at, reopened.probe_started_at), (Some(done), None));
+
+        // A probe whose outcome never arrives lapses after one cooldown.
+        let lapsed = later + chrono::Duration::milliseconds(1_000);
+        assert_eq!(probing.state(&p, lapsed), CircuitState::HalfOpen);
+    }
+
+    #[test]
+    fn success_resets_failure_count_and_zero_threshold_never_trips() {
+        let t0 = Utc::now();
+        let p = policy();
+        let c = closed().record(&p, true, t0).record(&p, false, t0).record(&p, true, t0);
+        assert_eq!(c.consecutive_failures, 1);
+
+        let disabled = ResiliencePolicy { circuit_failure_threshold: 0, ..Default::default() };
+        let mut c = closed();
+        for _ in 0..20 {
+            c = c.record(&disabled, true, t0);
+        }
+        assert!(!c.open);
+    }
+
+    #[test]
+    fn failure_classification() {
+        let p = ResiliencePolicy { retry_on_status: vec![429], ..Default::default() };
+        assert!(p.is_failure(None));
+        assert!(p.is_failure(Some(503)));
+        assert!(p.is_failure(Some(429)));
+        assert!(!p.is_failure(Some(404)));
+        assert!(!p.is_failure(Some(200)));
+    }
+}
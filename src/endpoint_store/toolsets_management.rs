@@ -0,0 +1,355 @@
+// src/endpoint_store/toolsets_management.rs
+//
+// Named tool bundles ("toolsets") and their binding to API keys.
+//
+// A toolset selects tools of its tenant by explicit name, by tag or by
+// api_group — a tool is in the set if it matches any of the three lists.
+// Keys bound to one or more toolsets of a tenant only see the union of those
+// sets in list_mcp_tools / get_mcp_tool. Keys without bindings keep seeing the
+// tenant's whole catalog, so existing keys are unaffected. A toolset cannot be
+// deleted while active keys are bound to it: the keys would silently widen to
+// the whole catalog.
+
+use crate::app_log;
+use crate::endpoint_store::db_helpers::ResultExt;
+use crate::endpoint_store::mcp_tools_management::McpTool;
+use crate::endpoint_store::{EndpointStore, StoreError};
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct McpToolset {
+    pub id: String,
+    pub tenant_id: String,
+    pub name: String,
+    pub description: String,
+    pub tool_names: Vec<String>,
+    pub tags: Vec<String>,
+    pub group_ids: Vec<String>,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+impl McpToolset {
+    pub fn permits(&self, tool: &McpTool) -> bool {
+        self.tool_names.iter().any(|n| n == &tool.tool_name)
+            || tool.tags.iter().any(|t| self.tags.contains(t))
+            || tool
+                .group_id
+                .as_ref()
+                .map(|g| self.group_ids.contains(g))
+                .unwrap_or(false)
+    }
+}
+
+/// The toolsets a key is bound to within one tenant.
+/// `None` = no bindings, the key sees every tool.
+pub type KeyToolsets = Option<Vec<McpToolset>>;
+
+pub fn permits(toolsets: &KeyToolsets, tool: &McpTool) -> bool {
+    match toolsets {
+        None => true,
+        Some(sets) => sets.iter().any(|s| s.permits(tool)),
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct UpsertToolsetRequest {
+    pub name: String,
+    pub description: Option<String>,
+    #[serde(default)]
+    pub tool_names: Vec<String>,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    #[serde(default)]
+    pub group_ids: Vec<String>,
+}
+
+pub async fn upsert_toolset(
+    store: &EndpointStore,
+    tenant_id: &str,
+    req: &UpsertToolsetRequest,
+) -> Result<McpToolset, StoreError> {
+    let client = store.get_conn(Some(tenant_id)).await?;
+    let id = Uuid::new_v4().to_string();
+    let now = Utc::now();
+    let description = req.description.as_deref().unwrap_or("");
+
+    let row = client
+        .query_one(
+            "INSERT INTO mcp_toolsets
+                (id, tenant_id, name, description, tool_names, tags, group_ids, created_at, updated_at)
+             VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $8)
+             ON CONFLICT (tenant_id, name) DO UPDATE SET
+                description = EXCLUDED.description,
+                tool_names  = EXCLUDED.tool_names,
+                tags        = EXCLUDED.tags,
+                group_ids   = EXCLUDED.group_ids,
+                updated_at  = EXCLUDED.updated_at
+             RETURNING id, tenant_id, name, description, tool_names, tags, group_ids,
+                       created_at, updated_at",
+            &[
+                &id as &(dyn tokio_postgres::types::ToSql + Sync),
+                &tenant_id as &(dyn tokio_postgres::types::ToSql + Sync),
+                &req.name as &(dyn tokio_postgres::types::ToSql + Sync),
+                &description as &(dyn tokio_postgres::types::ToSql + Sync),
+                &req.tool_names as &(dyn tokio_postgres::types::ToSql + Sync),
+                &req.tags as &(dyn tokio_postgres::types::ToSql + Sync),
+                &req.group_ids as &(dyn tokio_postgres::types::ToSql + Sync),
+                &now as &(dyn tokio_postgres::types::ToSql + Sync),
+            ],
+        )
+        .await
+        .to_store_error()?;
+
+    app_log!(info, tenant_id = %tenant_id, toolset = %req.name, "Upserted MCP toolset");
+
+    Ok(row_to_toolset(row))
+}
+
+pub async fn list_toolsets(
+    store: &EndpointStore,
+    tenant_id: &str,
+) -> Result<Vec<McpToolset>, StoreError> {
+    let client = store.get_conn(Some(tenant_id)).await?;
+
+    let rows = client
+        .query(
+            "SELECT id, tenant_id, name, description, tool_names, tags, group_ids,
+                    created_at, updated_at
+             FROM mcp_toolsets
+             WHERE tenant_id = $1
+             ORDER BY name",
+            &[&tenant_id],
+        )
+        .await
+        .to_store_error()?;
+
+    Ok(rows.into_iter().map(row_to_toolset).collect())
+}
+
+/// Hard delete. Refused while active keys are bound to the toolset (rebind
+/// them first); bindings of revoked keys go with it (ON DELETE CASCADE).
+pub async fn delete_toolset(
+    store: &EndpointStore,
+    tenant_id: &str,
+    name: &str,
+) -> Result<bool, StoreError> {
+    // Admin connection: bound consumer keys live in the consumers' tenants.
+    let client = store.get_admin_conn().await?;
+
+    let row = client
+        .query_opt(
+            "SELECT COUNT(k.id)
+             FROM mcp_toolsets s
+             LEFT JOIN api_key_toolsets kt ON kt.toolset_id = s.id
+             LEFT JOIN api_keys k ON k.id = kt.key_id AND k.is_active
+             WHERE s.tenant_id = $1 AND s.name = $2
+             GROUP BY s.id",
+            &[&tenant_id, &name],
+        )
+        .await
+        .to_store_error()?;
+    let bound: i64 = match row {
+        Some(r) => r.get(0),
+        None => return Ok(false),
+    };
+    if bound > 0 {
+        return Err(StoreError::Invalid(format!(
+            "Toolset {} is bound to {} active API key(s); rebind them first",
+            name, bound
+        )));
+    }
+
+    // The NOT EXISTS closes the race with a binding added since the check.
+    let n = client
+        .execute(
+            "DELETE FROM mcp_toolsets s
+             WHERE s.tenant_id = $1 AND s.name = $2
+               AND NOT EXISTS (SELECT 1 FROM api_key_toolsets kt
+                               JOIN api_keys k ON k.id = kt.key_id AND k.is_active
+                               WHERE kt.toolset_id = s.id)",
+            &[&tenant_id, &name],
+        )
+        .await
+        .to_store_error()?;
+    if n == 0 {
+        return Err(StoreError::Invalid(format!(
+            "Toolset {} was bound to an API key while deleting; rebind it first",
+            name
+        )));
+    }
+
+    Ok(true)
+}
+
+/// Replace the key's toolset bindings within `tenant_id`. An empty list
+/// removes all bindings (full catalog again).
+///
+/// The key must belong to the tenant — either its own key or a consumer key
+/// issued for it (provider_tenant_id). Unknown toolset names are rejected.
+pub async fn set_key_toolsets(
+    store: &EndpointStore,
+    tenant_id: &str,
+    key_id: &str,
+    toolset_names: &[String],
+) -> Result<Vec<McpToolset>, StoreError> {
+    let mut client = store.get_admin_conn().await?;
+
+    let key = client
+        .query_opt(
+            "SELECT 1 FROM api_keys
+             WHERE id = $1 AND (tenant_id = $2 OR provider_tenant_id = $2)",
+            &[&key_id, &tenant_id],
+        )
+        .await
+        .to_store_error()?;
+    if key.is_none() {
+        return Err(StoreError::NotFound(format!("API key {} for tenant {}", key_id, tenant_id)));
+    }
+
+    let sets: Vec<McpToolset> = client
+        .query(
+            "SELECT id, tenant_id, name, description, tool_names, tags, group_ids,
+                    created_at, updated_at
+             FROM mcp_toolsets
+             WHERE tenant_id = $1 AND name = ANY($2)",
+            &[&tenant_id, &toolset_names],
+        )
+        .await
+        .to_store_error()?
+        .into_iter()
+        .map(row_to_toolset)
+        .collect();
+
+    if let Some(missing) = toolset_names.iter().find(|n| !sets.iter().any(|s| &s.name == *n)) {
+        return Err(StoreError::NotFound(format!("Toolset {}", missing)));
+    }
+
+    let tx = client.transaction().await.to_store_error()?;
+    tx.execute(
+        "DELETE FROM api_key_toolsets
+         WHERE key_id = $1
+           AND toolset_id IN (SELECT id FROM mcp_toolsets WHERE tenant_id = $2)",
+        &[&key_id, &tenant_id],
+    )
+    .await
+    .to_store_error()?;
+    for set in &sets {
+        tx.execute(
+            "INSERT INTO api_key_toolsets (key_id, toolset_id) VALUES ($1, $2)",
+            &[&key_id, &set.id],
+        )
+        .await
+        .to_store_error()?;
+    }
+    tx.commit().await.to_store_error()?;
+
+    app_log!(info, tenant_id = %tenant_id, key_id = %key_id, toolsets = ?toolset_names,
+        "Updated API key toolset bindings");
+
+    Ok(sets)
+}
+
+pub async fn get_key_toolsets(
+    store: &EndpointStore,
+    tenant_id: &str,
+    key_id: &str,
+) -> Result<KeyToolsets, StoreError> {
+    let client = store.get_conn(Some(tenant_id)).await?;
+
+    let rows = client
+        .query(
+            "SELECT s.id, s.tenant_id, s.name, s.description, s.tool_names, s.tags, s.group_ids,
+                    s.created_at, s.updated_at
+             FROM api_key_toolsets kt
+             JOIN mcp_toolsets s ON s.id = kt.toolset_id
+             WHERE kt.key_id = $1 AND s.tenant_id = $2
+             ORDER BY s.name",
+            &[&key_id, &tenant_id],
+        )
+        .await
+        .to_store_error()?;
+
+    if rows.is_empty() {
+        return Ok(None);
+    }
+    Ok(Some(rows.into_iter().map(row_to_toolset).collect()))
+}
+
+// ── helpers ───────────────────────────────────────────────────────────────────
+
+fn row_to_toolset(row: tokio_postgres::Row) -> McpToolset {
+    McpToolset {
+        id:          row.get(0),
+        tenant_id:   row.get(1),
+        name:        row.get(2),
+        description: row.get(3),
+        tool_names:  row.get(4),
+        tags:        row.get(5),
+        group_ids:   row.get(6),
+        created_at:  row.get::<_, chrono::DateTime<Utc>>(7).to_rfc3339(),
+        updated_at:  row.get::<_, chrono::DateTime<Utc>>(8).to_rfc3339(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::endpoint_store::tool_resilience::{CircuitState, ResiliencePolicy};
+
+    fn tool(name: &str, tags: &[&str], group_id: Option<&str>) -> McpTool {
+        McpTool {
+            id: name.into(),
+            tenant_id: "t1".into(),
+            tool_name: name.into(),
+            backend_url: "https://example.com".into(),
+            description: String::new(),
+            input_schema: "{}".into(),
+            cost_credits: None,
+            timeout_ms: 30_000,
+            http_verb: None,
+            request_mapping: None,
+            resilience: ResiliencePolicy::default(),
+            circuit_state: CircuitState::Closed,
+            tags: tags.iter().map(|t| t.to_string()).collect(),
+            group_id: group_id.map(str::to_string),
+            is_active: true,
+            created_at: String::new(),
+            updated_at: String::new(),
+        }
+    }
+
+    fn toolset(tool_names: &[&str], tags: &[&str], group_ids: &[&str]) -> McpToolset {
+        let strings = |v: &[&str]| v.iter().map(|s| s.to_string()).collect();
+        McpToolset {
+            id: "s1".into(),
+            tenant_id: "t1".into(),
+            name: "basic".into(),
+            description: String::new(),
+            tool_names: strings(tool_names),
+            tags: strings(tags),
+            group_ids: strings(group_ids),
+            created_at: String::new(),
+            updated_at: String::new(),
+        }
+    }
+
+    #[test]
+    fn selects_by_name_tag_or_group() {
+        let set = toolset(&["search"], &["billing"], &["g1"]);
+        assert!(set.permits(&tool("search", &[], None)));
+        assert!(set.permits(&tool("invoice", &["billing", "pdf"], None)));
+        assert!(set.permits(&tool("list_users", &[], Some("g1"))));
+        assert!(!set.permits(&tool("delete_user", &["admin"], Some("g2"))));
+    }
+
+    #[test]
+    fn unbound_key_sees_everything_bound_key_only_its_sets() {
+        let t = tool("delete_user", &["admin"], Some("g2"));
+        assert!(permits(&None, &t));
+        assert!(!permits(&Some(vec![toolset(&["search"], &[], &[])]), &t));
+        assert!(permits(&Some(vec![toolset(&["search"], &[], &[]), toolset(&[], &["admin"], &[])]), &t));
+    }
+}
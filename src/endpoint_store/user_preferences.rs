@@ -54,8 +54,8 @@ pub async fn update_user_preferences(
     action: &str,
     endpoint_id: &str,
 ) -> Result<(), StoreError> {
-    let mut client = store.get_admin_conn().await?;
-    update_user_preferences_with_conn(&mut client, email, action, endpoint_id).await
+    let client = store.get_admin_conn().await?;
+    update_user_preferences_with_conn(&client, email, action, endpoint_id).await
 }
 
 pub async fn update_user_preferences_with_conn(
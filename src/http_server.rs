@@ -1,10 +1,19 @@
 use crate::api::tenant_usage::get_tenant_stats;
 use crate::api::group_add::add_api_group;
 use crate::mcp::downstream_auth::{
-    get_downstream_auth_handler, get_downstream_auth_by_id_handler, save_downstream_auth_handler,
+    delete_downstream_auth_override_handler, get_downstream_auth_by_id_handler,
+    get_downstream_auth_handler, get_downstream_token_handler, list_downstream_auth_overrides_handler,
+    save_downstream_auth_handler, save_downstream_auth_override_handler,
+    sign_downstream_request_handler, test_downstream_auth_handler,
 };
 use crate::mcp::client_id::{get_by_client_id_handler, set_client_id_handler};
+use crate::mcp::consumer_oauth::{
+    complete_consumer_oauth_handler, delete_consumer_oauth_handler, get_consumer_oauth_handler,
+    refresh_consumer_oauth_handler, start_consumer_oauth_handler,
+};
+use crate::admin::auth_lockouts::{clear_auth_lockout, list_auth_lockouts};
 use crate::admin::model_config::{get_ai_config_public, get_model_config, update_model_config};
+use crate::admin::token_keys::{list_token_keys, rotate_token_key};
 use crate::admin::user_roles::{delete_user_role, get_user_role, list_user_roles, set_user_role};
 use crate::whatsapp::channel::{
     delete_channel, get_channel, lookup_channel_by_tenant_internal, lookup_channel_internal,
@@ -17,11 +26,33 @@ use crate::payment::admin::admin_credit_handler;
 use crate::api::key_consumer::generate_consumer_key_handler;
 use crate::api::providers::list_providers_handler;
 use crate::api::key_consumer_self_service::{
-    generate_self_service_key, list_self_service_keys,
+    generate_self_service_key, list_self_service_keys, revoke_self_service_key,
+};
+use crate::api::provider_consumer_keys::{
+    consumer_usage_handler, list_consumer_keys_handler, resume_consumer_handler,
+    resume_consumer_key_handler, revoke_consumer_handler, revoke_consumer_key_handler,
+    set_consumer_key_limits_handler, set_consumer_limits_handler, suspend_consumer_handler,
+    suspend_consumer_key_handler,
+};
+use crate::mcp::prompts::{
+    delete_mcp_prompt_handler, get_mcp_prompt_handler, list_mcp_prompts_handler,
+    upsert_mcp_prompt_handler,
+};
+use crate::mcp::resources::{
+    delete_mcp_resource_handler, list_mcp_resources_handler, publish_reference_data_handler,
+    read_mcp_resource_handler, upsert_mcp_resource_handler,
+};
+use crate::mcp::tenant_secrets::{
+    delete_secret_handler, list_secret_versions_handler, list_secrets_handler, put_secret_handler,
+};
+use crate::mcp::toolsets::{
+    delete_toolset_handler, get_key_toolsets_handler, list_toolsets_handler,
+    set_key_toolsets_handler, upsert_toolset_handler,
 };
 use crate::mcp::tools::{
-    delete_mcp_tool_handler, get_mcp_tool_handler, list_mcp_tools_handler,
-    upsert_mcp_tool_handler,
+    claim_tool_probe_handler, delete_mcp_tool_handler, get_mcp_tool_handler, list_mcp_tools_handler,
+    render_mcp_tool_request_handler, upsert_mcp_tool_handler,
+    validate_mcp_tool_arguments_handler,
 };
 use crate::app_log;
 use crate::api::group_delete::delete_api_group;
@@ -53,7 +84,16 @@ use crate::user::update::update_user_preferences;
 use crate::api::tenant_name::update_tenant_name_handler;
 use crate::api::config_upload::upload_api_config;
 use crate::api::reference_upload;
+use crate::api::key_ip_allowlist::{list_security_events_handler, set_allowed_ips_handler};
+use crate::api::key_expiry::{get_key_policy_handler, list_expiring_keys_handler, set_key_policy_handler};
+use crate::api::key_quota::{
+    consume_quota_handler, get_tenant_limits_handler, set_key_limits_handler, set_tenant_limits_handler,
+};
+use crate::api::key_rotate::rotate_api_key_handler;
+use crate::api::key_usage_series::{get_key_usage_series_handler, list_stale_keys_handler};
+use crate::api::key_scopes::set_api_key_scopes_handler;
 use crate::api::key_validate::validate_api_key;
+use crate::api::token_exchange::{jwks_handler, token_exchange_handler};
 use crate::api::tenant_management::{verify_tenant_access, list_user_tenants};
 use crate::middleware::error_handler::handle_internal_server_error;
 use actix_cors::Cors;
@@ -150,6 +190,42 @@ pub async fn start_http_server(
                                 "/user/keys/{tenant_id}",
                                 web::delete().to(revoke_all_api_keys_handler),
                             )
+                            .route(
+                                "/user/keys/{tenant_id}/{key_id}/scopes",
+                                web::put().to(set_api_key_scopes_handler),
+                            )
+                            .route(
+                                "/user/keys/{tenant_id}/{key_id}/rotate",
+                                web::post().to(rotate_api_key_handler),
+                            )
+                            .route(
+                                "/user/keys/{tenant_id}/expiring",
+                                web::get().to(list_expiring_keys_handler),
+                            )
+                            .route("/user/key-policy/{tenant_id}", web::get().to(get_key_policy_handler))
+                            .route("/user/key-policy/{tenant_id}", web::put().to(set_key_policy_handler))
+                            .route(
+                                "/user/keys/{tenant_id}/{key_id}/limits",
+                                web::put().to(set_key_limits_handler),
+                            )
+                            .route("/user/key-limits/{tenant_id}", web::get().to(get_tenant_limits_handler))
+                            .route("/user/key-limits/{tenant_id}", web::put().to(set_tenant_limits_handler))
+                            .route(
+                                "/user/keys/{tenant_id}/{key_id}/allowed-ips",
+                                web::put().to(set_allowed_ips_handler),
+                            )
+                            .route(
+                                "/user/security-events/{tenant_id}",
+                                web::get().to(list_security_events_handler),
+                            )
+                            .route(
+                                "/user/keys/{tenant_id}/{key_id}/usage-series",
+                                web::get().to(get_key_usage_series_handler),
+                            )
+                            .route(
+                                "/user/keys/{tenant_id}/stale",
+                                web::get().to(list_stale_keys_handler),
+                            )
                             // Credit balance endpoints
                             .route(
                                 "/user/credits/{tenant_id}",
@@ -165,7 +241,12 @@ pub async fn start_http_server(
                             )
                             // Key validation and usage
                             .route("/key/validate", web::post().to(validate_api_key))
-                            // Alias used by the gateway's OAuth token-exchange flow
+                            .route("/key/quota/consume", web::post().to(consume_quota_handler))
+                            // Short-lived JWTs for API keys, verified offline against the JWKS
+                            .route("/oauth/token", web::post().to(token_exchange_handler))
+                            .route("/oauth/jwks", web::get().to(jwks_handler))
+                            // Legacy alias from before /oauth/token: creates a new API key,
+                            // it does not exchange one. Kept for existing gateway deployments.
                             .route("/key/generate", web::post().to(generate_api_key))
                             .route(
                                 "/endpoints/{email}/{endpoint_id}",
@@ -185,6 +266,11 @@ pub async fn start_http_server(
                             // Admin model config (X-Internal-Secret, gateway-facing)
                             .route("/admin/config/models", web::get().to(get_model_config))
                             .route("/admin/config/models", web::put().to(update_model_config))
+                            // Key validation lockouts (X-Internal-Secret)
+                            .route("/admin/auth-lockouts", web::get().to(list_auth_lockouts))
+                            .route("/admin/auth-lockouts/clear", web::post().to(clear_auth_lockout))
+                            .route("/admin/token-keys", web::get().to(list_token_keys))
+                            .route("/admin/token-keys/rotate", web::post().to(rotate_token_key))
                             // Email: SMTP config admin + internal send endpoint + broadcast
                             .route("/admin/smtp-config", web::get().to(get_smtp_config_handler))
                             .route("/admin/smtp-config", web::put().to(update_smtp_config_handler))
@@ -206,19 +292,67 @@ pub async fn start_http_server(
                             .route("/mcp-tools/{tenant_id}", web::get().to(list_mcp_tools_handler))
                             .route("/mcp-tools/{tenant_id}/{tool_name}", web::get().to(get_mcp_tool_handler))
                             .route("/mcp-tools/{tenant_id}/{tool_name}", web::delete().to(delete_mcp_tool_handler))
+                            .route("/mcp-tools/{tenant_id}/{tool_name}/validate", web::post().to(validate_mcp_tool_arguments_handler))
+                            .route("/mcp-tools/{tenant_id}/{tool_name}/render", web::post().to(render_mcp_tool_request_handler))
+                            .route("/mcp-tools/{tenant_id}/{tool_name}/probe", web::post().to(claim_tool_probe_handler))
+                            // MCP toolsets
+                            .route("/mcp-toolsets", web::post().to(upsert_toolset_handler))
+                            .route("/mcp-toolsets/{tenant_id}", web::get().to(list_toolsets_handler))
+                            .route("/mcp-toolsets/{tenant_id}/{name}", web::delete().to(delete_toolset_handler))
+                            .route("/mcp-toolsets/{tenant_id}/keys/{key_id}", web::get().to(get_key_toolsets_handler))
+                            .route("/mcp-toolsets/{tenant_id}/keys/{key_id}", web::put().to(set_key_toolsets_handler))
+                            // MCP resources & prompts
+                            .route("/mcp-resources", web::post().to(upsert_mcp_resource_handler))
+                            .route("/mcp-resources/from-reference-data", web::post().to(publish_reference_data_handler))
+                            .route("/mcp-resources/{tenant_id}", web::get().to(list_mcp_resources_handler))
+                            .route("/mcp-resources/{tenant_id}", web::delete().to(delete_mcp_resource_handler))
+                            .route("/mcp-resources/{tenant_id}/read", web::get().to(read_mcp_resource_handler))
+                            .route("/mcp-prompts", web::post().to(upsert_mcp_prompt_handler))
+                            .route("/mcp-prompts/{tenant_id}", web::get().to(list_mcp_prompts_handler))
+                            .route("/mcp-prompts/{tenant_id}/{name}/get", web::post().to(get_mcp_prompt_handler))
+                            .route("/mcp-prompts/{tenant_id}/{name}", web::delete().to(delete_mcp_prompt_handler))
                             // Consumer key generation (B2B2C — internal, requires X-Internal-Secret)
                             .route("/consumer-keys", web::post().to(generate_consumer_key_handler))
                             // Self-service consumer keys (end-users, Firebase JWT auth)
                             .route("/consumer-keys/me", web::post().to(generate_self_service_key))
                             .route("/consumer-keys/me", web::get().to(list_self_service_keys))
+                            .route("/consumer-keys/me/{key_id}", web::delete().to(revoke_self_service_key))
+                            // Provider-side consumer key management (X-Internal-Secret)
+                            .route("/provider/{provider_tenant_id}/consumer-keys", web::get().to(list_consumer_keys_handler))
+                            .route("/provider/{provider_tenant_id}/consumer-usage", web::get().to(consumer_usage_handler))
+                            .route("/provider/{provider_tenant_id}/consumer-keys/{key_id}/suspend", web::post().to(suspend_consumer_key_handler))
+                            .route("/provider/{provider_tenant_id}/consumer-keys/{key_id}/resume", web::post().to(resume_consumer_key_handler))
+                            .route("/provider/{provider_tenant_id}/consumer-keys/{key_id}/limits", web::put().to(set_consumer_key_limits_handler))
+                            .route("/provider/{provider_tenant_id}/consumer-keys/{key_id}", web::delete().to(revoke_consumer_key_handler))
+                            .route("/provider/{provider_tenant_id}/consumers/suspend", web::post().to(suspend_consumer_handler))
+                            .route("/provider/{provider_tenant_id}/consumers/resume", web::post().to(resume_consumer_handler))
+                            .route("/provider/{provider_tenant_id}/consumers/limits", web::put().to(set_consumer_limits_handler))
+                            .route("/provider/{provider_tenant_id}/consumers/revoke", web::post().to(revoke_consumer_handler))
                             // Provider discovery (public)
                             .route("/providers", web::get().to(list_providers_handler))
                             // Downstream auth (tenant-level)
                             .route("/user/downstream-auth", web::get().to(get_downstream_auth_handler))
                             .route("/user/downstream-auth", web::put().to(save_downstream_auth_handler))
+                            .route("/user/downstream-auth/test", web::post().to(test_downstream_auth_handler))
+                            .route("/user/downstream-auth/overrides", web::get().to(list_downstream_auth_overrides_handler))
+                            .route("/user/downstream-auth/overrides", web::put().to(save_downstream_auth_override_handler))
+                            .route("/user/downstream-auth/overrides", web::delete().to(delete_downstream_auth_override_handler))
+                            // Tenant secret vault (write-only values)
+                            .route("/user/secrets", web::get().to(list_secrets_handler))
+                            .route("/user/secrets", web::put().to(put_secret_handler))
+                            .route("/user/secrets/{name}/versions", web::get().to(list_secret_versions_handler))
+                            .route("/user/secrets/{name}", web::delete().to(delete_secret_handler))
                             .route("/user/tenant/name", web::put().to(update_tenant_name_handler))
-                            // Internal: gateway uses tenant_id directly
+                            // Internal: gateway uses tenant_id directly (X-Internal-Secret)
                             .route("/tenant/downstream-auth/{tenant_id}", web::get().to(get_downstream_auth_by_id_handler))
+                            .route("/tenant/downstream-token/{tenant_id}", web::get().to(get_downstream_token_handler))
+                            .route("/tenant/downstream-sign/{tenant_id}", web::post().to(sign_downstream_request_handler))
+                            // Delegated per-end-user OAuth for consumer keys (internal, X-Internal-Secret)
+                            .route("/consumer-oauth/complete", web::post().to(complete_consumer_oauth_handler))
+                            .route("/consumer-oauth/{provider_tenant_id}/start", web::post().to(start_consumer_oauth_handler))
+                            .route("/consumer-oauth/{provider_tenant_id}/refresh", web::post().to(refresh_consumer_oauth_handler))
+                            .route("/consumer-oauth/{provider_tenant_id}", web::get().to(get_consumer_oauth_handler))
+                            .route("/consumer-oauth/{provider_tenant_id}", web::delete().to(delete_consumer_oauth_handler))
                             // Per-provider OAuth client ID resolution
                             .route("/tenant/by-client-id/{client_id}", web::get().to(get_by_client_id_handler))
                             .route("/user/mcp-client-id", web::put().to(set_client_id_handler))
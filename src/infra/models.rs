@@ -7,6 +7,14 @@ use serde::{Deserialize, Serialize};
 pub struct ValidateKeyRequest {
     pub api_key: String,
     pub expected_tenant_id: Option<String>,
+    /// Scope the operation needs (e.g. "tools:call"); the key must grant it.
+    #[serde(default)]
+    pub required_scope: Option<String>,
+    /// Address of the end caller as seen by the gateway. Only honoured with
+    /// X-Internal-Secret; otherwise the TCP peer is the caller, for IP
+    /// allowlists and lockouts alike.
+    #[serde(default)]
+    pub client_ip: Option<String>,
 }
 
 // #[derive(Debug, Clone, Deserialize)]
@@ -26,9 +34,47 @@ pub struct ValidateKeyResponse {
     /// If set, this is a consumer key: tools come from this provider tenant,
     /// but credits are deducted from the consumer's tenant (tenant_id above).
     pub provider_tenant_id: Option<String>,
+    /// Scopes granted to the key (all scopes for legacy unscoped keys).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub scopes: Option<Vec<String>>,
+    /// When set, only these API groups / tool names may be exposed to the key.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub allowed_group_ids: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub allowed_tools: Option<Vec<String>>,
+    /// Generation of the secret that matched (bumped by every rotation).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub key_generation: Option<i32>,
+    /// Set when a rotated-out secret matched: the end of its grace window.
+    /// Clients seeing this should switch to the new secret.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub secret_expires_at: Option<String>,
+    /// Machine-readable rejection reason: "missing_scope", "ip_not_allowed",
+    /// "malformed_key" or "locked_out".
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
     pub message: String,
 }
 
+impl ValidateKeyResponse {
+    pub fn rejected(message: &str) -> Self {
+        ValidateKeyResponse {
+            valid: false,
+            email: None,
+            key_id: None,
+            tenant_id: None,
+            provider_tenant_id: None,
+            scopes: None,
+            allowed_group_ids: None,
+            allowed_tools: None,
+            key_generation: None,
+            secret_expires_at: None,
+            reason: None,
+            message: message.to_string(),
+        }
+    }
+}
+
 // Response model for API key usage
 // #[derive(Debug, Serialize)]
 // pub struct RecordUsageResponse {
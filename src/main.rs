@@ -15,7 +15,6 @@ mod grpc_server;
 
 use infra::config::Config;
 use infra::formatter::YamlFormatter;
-use chrono;
 use graflog::app_log;
 use graflog::init_logging;
 
@@ -91,6 +90,19 @@ async fn main() -> Result<(), Box<dyn Error + Send + Sync>> {
         e
     })?;
 
+    // `store rotate-secrets`: re-encrypt stored secrets under the first key
+    // of API0_MASTER_KEYS, then exit (see endpoint_store/secret_crypto.rs).
+    if env::args().nth(1).as_deref() == Some("rotate-secrets") {
+        let report = store.rotate_secrets().await.map_err(|e| {
+            app_log!(error, "Secret rotation failed: {}", e);
+            e
+        })?;
+        app_log!(info, key_id = %report.key_id, scanned = report.scanned, rotated = report.rotated,
+            skipped = report.skipped, "Secret rotation finished");
+        println!("{}", serde_json::to_string_pretty(&report)?);
+        return Ok(());
+    }
+
     // Load default API groups from YAML if available
     if let Some(endpoints_config_path) = resolve_endpoints_config_path() {
         app_log!(
@@ -158,7 +170,7 @@ async fn main() -> Result<(), Box<dyn Error + Send + Sync>> {
                     for endpoint in endpoints {
                         groups_map
                             .entry(endpoint.base.clone())
-                            .or_insert_with(Vec::new)
+                            .or_default()
                             .push(endpoint);
                     }
 
@@ -238,6 +250,65 @@ async fn main() -> Result<(), Box<dyn Error + Send + Sync>> {
         });
     }
 
+    // ── API key expiry notices (14 / 3 / 1 days ahead, then on expiry) ────────
+    {
+        let sched_store = Arc::clone(&store_arc);
+        tokio::spawn(async move {
+            tokio::time::sleep(std::time::Duration::from_secs(60)).await;
+            let mut interval = tokio::time::interval(std::time::Duration::from_secs(3600));
+            loop {
+                interval.tick().await;
+                run_key_expiry_notices(&sched_store).await;
+            }
+        });
+    }
+
+    // ── Cross-instance notifications (key cache, auth lockouts) ──────────────
+    crate::endpoint_store::notify_listener::spawn_listener(database_url.clone());
+
+    // ── Brute-force bookkeeping: prune old failures and decayed lockouts ──────
+    {
+        let sched_store = Arc::clone(&store_arc);
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(std::time::Duration::from_secs(3600));
+            loop {
+                interval.tick().await;
+                match crate::endpoint_store::auth_lockout::prune(&sched_store).await {
+                    Ok((failures, lockouts)) => app_log!(debug, failures = failures, lockouts = lockouts,
+                        "Pruned auth failure records"),
+                    Err(e) => app_log!(error, error = %e, "Failed to prune auth failure records"),
+                }
+            }
+        });
+    }
+
+    // ── Access token signing keys: first key, scheduled rotation, pruning ─────
+    {
+        let sched_store = Arc::clone(&store_arc);
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(std::time::Duration::from_secs(3600));
+            loop {
+                interval.tick().await;
+                if let Err(e) = crate::endpoint_store::access_tokens::maintain_signing_keys(&sched_store).await {
+                    app_log!(error, error = %e, "Failed to maintain token signing keys");
+                }
+            }
+        });
+    }
+
+    // ── API key usage counters: batched writes to api_keys ───────────────────
+    {
+        let sched_store = Arc::clone(&store_arc);
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(crate::endpoint_store::key_usage::flush_interval());
+            loop {
+                interval.tick().await;
+                crate::endpoint_store::key_usage::flush_logged(&sched_store).await;
+            }
+        });
+    }
+    let usage_store = Arc::clone(&store_arc);
+
     // Get HTTP configuration
     let http_host = config.http_host().to_string();
     let http_port = config.http_port();
@@ -332,6 +403,9 @@ async fn main() -> Result<(), Box<dyn Error + Send + Sync>> {
         _ = shutdown => app_log!(info, "Shutdown signal received"),
     }
 
+    // Counts from the last interval would otherwise be lost.
+    crate::endpoint_store::key_usage::flush_logged(&usage_store).await;
+
     app_log!(info, "Application shutting down");
     Ok(())
 }
@@ -443,8 +517,39 @@ async fn run_engagement_schedulers(store: &Arc<EndpointStore>) {
     }
 }
 
+async fn run_key_expiry_notices(store: &Arc<EndpointStore>) {
+    use crate::email::{send, EmailKind};
+    use crate::endpoint_store::key_expiry::{due_expiry_notices, release_expiry_notice};
+
+    let notices = match due_expiry_notices(store).await {
+        Ok(n) => n,
+        Err(e) => { app_log!(error, "[scheduler] Key expiry scan failed: {}", e); return; }
+    };
+    if !notices.is_empty() {
+        app_log!(info, "[scheduler] Key expiry notices: {}", notices.len());
+    }
+    for n in notices {
+        let kind = if n.stage == 0 {
+            EmailKind::KeyExpired { key_name: n.key_name, key_prefix: n.key_prefix }
+        } else {
+            EmailKind::KeyExpiring {
+                key_name: n.key_name,
+                key_prefix: n.key_prefix,
+                expires_at: n.expires_at.format("%Y-%m-%d %H:%M UTC").to_string(),
+                days_left: n.days_left,
+            }
+        };
+        // A notice that did not go out is released for the next run.
+        if send(store, &n.email, &kind).await.is_err() {
+            if let Err(e) = release_expiry_notice(store, &n.key_id, n.stage).await {
+                app_log!(error, "[scheduler] Failed to release key expiry notice: {}", e);
+            }
+        }
+    }
+}
+
 fn ensure_database_url() {
-    if let Err(_) = dotenvy::dotenv() {
+    if dotenvy::dotenv().is_err() {
         // .env file not found, that's okay
     }
 
@@ -0,0 +1,213 @@
+// src/mcp/consumer_oauth.rs
+//
+// HTTP handlers for delegated per-end-user OAuth (provider tenants in
+// oauth2_authorization_code mode). The gateway / provider backend drives the
+// dance on behalf of the end-user, so every route requires X-Internal-Secret.
+// The end-user is identified by `key_id` (their consumer key) or by the
+// provider's `consumer_id` — exactly one of the two.
+//
+// Routes (all under /api):
+//   POST   /consumer-oauth/{provider_tenant_id}/start    — authorization URL + state
+//   POST   /consumer-oauth/complete                      — exchange code, store tokens
+//   POST   /consumer-oauth/{provider_tenant_id}/refresh  — refresh_token grant
+//   GET    /consumer-oauth/{provider_tenant_id}          — connection status (no tokens)
+//   DELETE /consumer-oauth/{provider_tenant_id}          — disconnect
+//
+// The access token itself is served by GET /api/tenant/downstream-token/{tenant_id}.
+
+use crate::app_log;
+use crate::endpoint_store::consumer_oauth::ConsumerSubject;
+use crate::endpoint_store::EndpointStore;
+use crate::mcp::downstream_auth::token_error_response;
+use actix_web::{web, HttpRequest, HttpResponse, Responder};
+use serde::Deserialize;
+use std::sync::Arc;
+
+fn check_internal_secret(req: &HttpRequest) -> bool {
+    let expected = match std::env::var("API0_INTERNAL_SECRET") {
+        Ok(s) if !s.is_empty() => s,
+        _ => return false,
+    };
+    req.headers()
+        .get("X-Internal-Secret")
+        .and_then(|v| v.to_str().ok())
+        .map(|v| v == expected)
+        .unwrap_or(false)
+}
+
+#[derive(Deserialize)]
+pub struct SubjectQuery {
+    pub key_id: Option<String>,
+    pub consumer_id: Option<String>,
+}
+
+// ── POST /api/consumer-oauth/{provider_tenant_id}/start ─────────────────────
+// Body: { key_id? | consumer_id?, redirect_uri, tool_name? }
+
+#[derive(Deserialize)]
+pub struct StartConsumerOAuthRequest {
+    pub key_id: Option<String>,
+    pub consumer_id: Option<String>,
+    pub redirect_uri: String,
+    pub tool_name: Option<String>,
+}
+
+pub async fn start_consumer_oauth_handler(
+    req: HttpRequest,
+    store: web::Data<Arc<EndpointStore>>,
+    path: web::Path<String>,
+    body: web::Json<StartConsumerOAuthRequest>,
+) -> impl Responder {
+    if !check_internal_secret(&req) {
+        return HttpResponse::Unauthorized()
+            .json(serde_json::json!({"success":false,"error":"Unauthorized"}));
+    }
+
+    let subject = match ConsumerSubject::from_parts(body.key_id.as_deref(), body.consumer_id.as_deref()) {
+        Ok(s) => s,
+        Err(e) => return HttpResponse::BadRequest().json(serde_json::json!({"success":false,"error":e})),
+    };
+    if !body.redirect_uri.starts_with("https://") && !body.redirect_uri.starts_with("http://") {
+        return HttpResponse::BadRequest()
+            .json(serde_json::json!({"success":false,"error":"redirect_uri must be an http(s) URL"}));
+    }
+
+    let provider_tenant_id = path.into_inner();
+    match store
+        .start_consumer_oauth(&provider_tenant_id, &subject, &body.redirect_uri, body.tool_name.as_deref())
+        .await
+    {
+        Ok(start) => HttpResponse::Ok().json(serde_json::json!({
+            "success": true,
+            "authorization_url": start.authorization_url,
+            "state": start.state,
+            "expires_at": start.expires_at,
+        })),
+        Err(e) => token_error_response(e, "start_consumer_oauth"),
+    }
+}
+
+// ── POST /api/consumer-oauth/complete ───────────────────────────────────────
+// Body: { state, code } — as received on the redirect_uri.
+
+#[derive(Deserialize)]
+pub struct CompleteConsumerOAuthRequest {
+    pub state: String,
+    pub code: String,
+}
+
+pub async fn complete_consumer_oauth_handler(
+    req: HttpRequest,
+    store: web::Data<Arc<EndpointStore>>,
+    body: web::Json<CompleteConsumerOAuthRequest>,
+) -> impl Responder {
+    if !check_internal_secret(&req) {
+        return HttpResponse::Unauthorized()
+            .json(serde_json::json!({"success":false,"error":"Unauthorized"}));
+    }
+
+    match store.complete_consumer_oauth(&body.state, &body.code).await {
+        Ok(connection) => HttpResponse::Ok().json(serde_json::json!({ "success": true, "connection": connection })),
+        Err(e) => token_error_response(e, "complete_consumer_oauth"),
+    }
+}
+
+// ── POST /api/consumer-oauth/{provider_tenant_id}/refresh ───────────────────
+// Body: { key_id? | consumer_id?, tool_name? }
+
+#[derive(Deserialize)]
+pub struct RefreshConsumerOAuthRequest {
+    pub key_id: Option<String>,
+    pub consumer_id: Option<String>,
+    pub tool_name: Option<String>,
+}
+
+pub async fn refresh_consumer_oauth_handler(
+    req: HttpRequest,
+    store: web::Data<Arc<EndpointStore>>,
+    path: web::Path<String>,
+    body: web::Json<RefreshConsumerOAuthRequest>,
+) -> impl Responder {
+    if !check_internal_secret(&req) {
+        return HttpResponse::Unauthorized()
+            .json(serde_json::json!({"success":false,"error":"Unauthorized"}));
+    }
+
+    let subject = match ConsumerSubject::from_parts(body.key_id.as_deref(), body.consumer_id.as_deref()) {
+        Ok(s) => s,
+        Err(e) => return HttpResponse::BadRequest().json(serde_json::json!({"success":false,"error":e})),
+    };
+
+    let provider_tenant_id = path.into_inner();
+    match store
+        .refresh_consumer_oauth(&provider_tenant_id, &subject, body.tool_name.as_deref())
+        .await
+    {
+        Ok(connection) => HttpResponse::Ok().json(serde_json::json!({ "success": true, "connection": connection })),
+        Err(e) => token_error_response(e, "refresh_consumer_oauth"),
+    }
+}
+
+// ── GET /api/consumer-oauth/{provider_tenant_id}?key_id=|consumer_id= ───────
+
+pub async fn get_consumer_oauth_handler(
+    req: HttpRequest,
+    store: web::Data<Arc<EndpointStore>>,
+    path: web::Path<String>,
+    query: web::Query<SubjectQuery>,
+) -> impl Responder {
+    if !check_internal_secret(&req) {
+        return HttpResponse::Unauthorized()
+            .json(serde_json::json!({"success":false,"error":"Unauthorized"}));
+    }
+
+    let subject = match ConsumerSubject::from_parts(query.key_id.as_deref(), query.consumer_id.as_deref()) {
+        Ok(s) => s,
+        Err(e) => return HttpResponse::BadRequest().json(serde_json::json!({"success":false,"error":e})),
+    };
+
+    let provider_tenant_id = path.into_inner();
+    match store.get_consumer_oauth_connection(&provider_tenant_id, &subject).await {
+        Ok(connection) => HttpResponse::Ok().json(serde_json::json!({
+            "success": true,
+            "connected": connection.is_some(),
+            "connection": connection,
+        })),
+        Err(e) => {
+            app_log!(error, error = %e, "Failed to get consumer OAuth connection");
+            HttpResponse::InternalServerError()
+                .json(serde_json::json!({"success":false,"error":e.to_string()}))
+        }
+    }
+}
+
+// ── DELETE /api/consumer-oauth/{provider_tenant_id}?key_id=|consumer_id= ────
+
+pub async fn delete_consumer_oauth_handler(
+    req: HttpRequest,
+    store: web::Data<Arc<EndpointStore>>,
+    path: web::Path<String>,
+    query: web::Query<SubjectQuery>,
+) -> impl Responder {
+    if !check_internal_secret(&req) {
+        return HttpResponse::Unauthorized()
+            .json(serde_json::json!({"success":false,"error":"Unauthorized"}));
+    }
+
+    let subject = match ConsumerSubject::from_parts(query.key_id.as_deref(), query.consumer_id.as_deref()) {
+        Ok(s) => s,
+        Err(e) => return HttpResponse::BadRequest().json(serde_json::json!({"success":false,"error":e})),
+    };
+
+    let provider_tenant_id = path.into_inner();
+    match store.delete_consumer_oauth(&provider_tenant_id, &subject).await {
+        Ok(true) => HttpResponse::Ok().json(serde_json::json!({"success":true})),
+        Ok(false) => HttpResponse::NotFound()
+            .json(serde_json::json!({"success":false,"error":"Connection not found"})),
+        Err(e) => {
+            app_log!(error, error = %e, "Failed to delete consumer OAuth connection");
+            HttpResponse::InternalServerError()
+                .json(serde_json::json!({"success":false,"error":e.to_string()}))
+        }
+    }
+}
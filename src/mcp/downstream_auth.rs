@@ -1,15 +1,34 @@
 // src/downstream_auth_handler.rs
-// HTTP handlers for GET/PUT /api/user/downstream-auth
-// and GET /api/tenant/downstream-auth/{tenant_id} (internal, used by gateway)
+// HTTP handlers for downstream auth configs.
+//
+// Dashboard routes (identified by ?email= / body email):
+//   GET/PUT         /api/user/downstream-auth
+//   POST            /api/user/downstream-auth/test
+//   GET/PUT/DELETE  /api/user/downstream-auth/overrides   (per-group / per-tool configs)
+//
+// Internal routes (X-Internal-Secret, used by the gateway):
+//   GET  /api/tenant/downstream-auth/{tenant_id}?tool_name=
+//   GET  /api/tenant/downstream-token/{tenant_id}?tool_name=&key_id=|consumer_id=&refresh=
+//   POST /api/tenant/downstream-sign/{tenant_id}
+//
+// The dashboard routes never return secrets: configs go out as
+// DownstreamAuthView (masked to the last 4 characters plus has_* flags), and
+// secret fields are write-only on save (omitted = keep stored). Only the
+// internal /api/tenant/downstream-auth route returns the full config.
 
 use crate::app_log;
 use crate::email::{send_async, EmailKind};
 use crate::endpoint_store::downstream_auth_management::{
-    get_downstream_auth, save_downstream_auth, SaveDownstreamAuthRequest,
+    get_downstream_auth, save_downstream_auth, SaveDownstreamAuthRequest, TenantDownstreamAuth,
+    OVERRIDE_SCOPES,
 };
+use crate::endpoint_store::consumer_oauth::ConsumerSubject;
+use crate::endpoint_store::downstream_probe::ProbeError;
+use crate::endpoint_store::downstream_tokens::DownstreamTokenError;
+use crate::endpoint_store::request_signing::SignRequest;
 use crate::endpoint_store::tenant_management::get_default_tenant;
-use crate::endpoint_store::EndpointStore;
-use actix_web::{web, HttpResponse, Responder};
+use crate::endpoint_store::{EndpointStore, StoreError};
+use actix_web::{web, HttpRequest, HttpResponse, Responder};
 use serde::Deserialize;
 use std::sync::Arc;
 
@@ -26,6 +45,17 @@ pub struct SaveAuthBody {
     pub target_audience: Option<String>,
     pub bearer_token: Option<String>,
     pub custom_headers: Option<serde_json::Value>,
+    pub oauth_token_url: Option<String>,
+    pub oauth_client_id: Option<String>,
+    pub oauth_client_secret: Option<String>,
+    pub oauth_scopes: Option<String>,
+    pub oauth_audience: Option<String>,
+    pub oauth_authorize_url: Option<String>,
+    pub hmac_secret: Option<String>,
+    pub hmac_algorithm: Option<String>,
+    pub hmac_signed_headers: Option<Vec<String>>,
+    pub hmac_signature_headers: Option<serde_json::Value>,
+    pub health_check_url: Option<String>,
 }
 
 pub async fn get_downstream_auth_handler(
@@ -60,7 +90,7 @@ pub async fn get_downstream_auth_handler(
     match get_downstream_auth(&store, &tenant.id).await {
         Ok(Some(auth)) => HttpResponse::Ok().json(serde_json::json!({
             "success": true,
-            "auth": auth,
+            "auth": auth.redacted(),
             "tenant_name": tenant.name,
             "mcp_client_id": mcp_client_id,
             "google_client_id": google_client_id
@@ -70,11 +100,28 @@ pub async fn get_downstream_auth_handler(
             "auth": {
                 "tenant_id": tenant.id,
                 "auth_mode": "none",
-                "service_account_json": null,
+                "service_account_email": null,
+                "has_service_account_json": false,
                 "target_audience": null,
                 "bearer_token": null,
+                "has_bearer_token": false,
                 "custom_headers": null,
-                "updated_at": null
+                "has_custom_headers": false,
+                "updated_at": null,
+                "oauth_token_url": null,
+                "oauth_client_id": null,
+                "oauth_client_secret": null,
+                "has_oauth_client_secret": false,
+                "oauth_scopes": null,
+                "oauth_audience": null,
+                "oauth_authorize_url": null,
+                "hmac_secret": null,
+                "has_hmac_secret": false,
+                "hmac_algorithm": null,
+                "hmac_signed_headers": null,
+                "hmac_signature_headers": null,
+                "health_check_url": null,
+                "verified_at": null
             },
             "tenant_name": tenant.name,
             "mcp_client_id": mcp_client_id,
@@ -101,26 +148,42 @@ pub async fn save_downstream_auth_handler(
         }
     };
 
-    let req = SaveDownstreamAuthRequest {
+    let mut req = SaveDownstreamAuthRequest {
         auth_mode:            body.auth_mode.clone(),
         service_account_json: body.service_account_json.clone(),
         target_audience:      body.target_audience.clone(),
         bearer_token:         body.bearer_token.clone(),
         custom_headers:       body.custom_headers.clone(),
+        oauth_token_url:      body.oauth_token_url.clone(),
+        oauth_client_id:      body.oauth_client_id.clone(),
+        oauth_client_secret:  body.oauth_client_secret.clone(),
+        oauth_scopes:         body.oauth_scopes.clone(),
+        oauth_audience:       body.oauth_audience.clone(),
+        oauth_authorize_url:  body.oauth_authorize_url.clone(),
+        hmac_secret:            body.hmac_secret.clone(),
+        hmac_algorithm:         body.hmac_algorithm.clone(),
+        hmac_signed_headers:    body.hmac_signed_headers.clone(),
+        hmac_signature_headers: body.hmac_signature_headers.clone(),
+        health_check_url:       body.health_check_url.clone(),
     };
 
+    match get_downstream_auth(&store, &tenant.id).await {
+        Ok(stored) => req.keep_stored_secrets(stored.as_ref()),
+        Err(e) => {
+            app_log!(error, error = %e, "save_downstream_auth: DB error");
+            return HttpResponse::InternalServerError()
+                .json(serde_json::json!({"success": false, "error": "DB error"}));
+        }
+    }
+
+    if let Err(e) = req.check() {
+        return HttpResponse::BadRequest().json(serde_json::json!({"success": false, "error": e}));
+    }
+
+    // ProviderConnected is sent by the connectivity test once the saved
+    // config has actually reached the backend, not here.
     match save_downstream_auth(&store, &tenant.id, &req).await {
-        Ok(auth) => {
-            // Notify user which provider they just connected (derive from target_audience or auth_mode).
-            let provider_label = body.target_audience
-                .as_deref()
-                .unwrap_or(&body.auth_mode)
-                .to_string();
-            send_async(store.as_ref().clone(), body.email.clone(), EmailKind::ProviderConnected {
-                provider: provider_label,
-            });
-            HttpResponse::Ok().json(serde_json::json!({ "success": true, "auth": auth }))
-        },
+        Ok(auth) => HttpResponse::Ok().json(serde_json::json!({ "success": true, "auth": auth.redacted() })),
         Err(e) => {
             app_log!(error, error = %e, "save_downstream_auth: DB error");
             HttpResponse::InternalServerError()
@@ -129,26 +192,431 @@ pub async fn save_downstream_auth_handler(
     }
 }
 
+/// POST /api/user/downstream-auth/test
+/// Body: { email, tool_name? }
+/// Probes the backend with the effective config (DNS, TLS, credentials,
+/// status, latency). The target is always the configured health_check_url
+/// or the tool's backend_url, never a caller-supplied URL: the probe carries
+/// the real credentials. The first successful probe of the tenant config after a
+/// save marks it verified and sends the ProviderConnected email.
+#[derive(Debug, Deserialize)]
+pub struct TestAuthBody {
+    pub email: String,
+    pub tool_name: Option<String>,
+}
+
+pub async fn test_downstream_auth_handler(
+    store: web::Data<Arc<EndpointStore>>,
+    body: web::Json<TestAuthBody>,
+) -> impl Responder {
+    let tenant = match get_default_tenant(&store, &body.email).await {
+        Ok(t) => t,
+        Err(e) => {
+            app_log!(error, error = %e, "test_downstream_auth: tenant lookup failed");
+            return HttpResponse::InternalServerError()
+                .json(serde_json::json!({"success": false, "error": "Tenant not found"}));
+        }
+    };
+
+    let report = match store
+        .probe_downstream(&tenant.id, body.tool_name.as_deref())
+        .await
+    {
+        Ok(r) => r,
+        Err(ProbeError::NoTarget(e)) => {
+            return HttpResponse::BadRequest().json(serde_json::json!({"success": false, "error": e}))
+        }
+        Err(ProbeError::Store(StoreError::NotFound(what))) => {
+            return HttpResponse::NotFound()
+                .json(serde_json::json!({"success": false, "error": format!("{} not found", what)}))
+        }
+        Err(ProbeError::Store(e)) => {
+            app_log!(error, error = %e, "test_downstream_auth: DB error");
+            return HttpResponse::InternalServerError()
+                .json(serde_json::json!({"success": false, "error": "DB error"}));
+        }
+    };
+
+    app_log!(info, tenant_id = %tenant.id, ok = report.ok, status = ?report.status,
+        latency_ms = report.latency_ms, "Downstream auth connectivity test");
+
+    if report.ok && report.source == "tenant" {
+        match store.mark_downstream_auth_verified(&tenant.id).await {
+            Ok(true) => {
+                let provider_label = get_downstream_auth(&store, &tenant.id)
+                    .await
+                    .ok()
+                    .flatten()
+                    .and_then(|a| a.target_audience)
+                    .unwrap_or_else(|| report.auth_mode.clone());
+                send_async(store.as_ref().clone(), body.email.clone(), EmailKind::ProviderConnected {
+                    provider: provider_label,
+                });
+            }
+            Ok(false) => {}
+            Err(e) => app_log!(warn, error = %e, "test_downstream_auth: could not mark verified"),
+        }
+    }
+
+    HttpResponse::Ok().json(serde_json::json!({ "success": true, "report": report }))
+}
+
 /// Internal handler — called by the gateway with a direct tenant_id
-/// (avoids going through the email→tenant lookup).
+/// (avoids going through the email→tenant lookup). Returns the unredacted
+/// config, so it requires X-Internal-Secret.
+/// With `?tool_name=` the per-tool / per-group overrides are applied
+/// (tool → group → tenant); `source` tells which level matched.
+/// Secrets the store uses itself are never returned, whatever the mode (one
+/// left over from an earlier config included): for google_sa the store mints
+/// the ID token and sends it as `token` instead, client-credentials tokens
+/// come from the token endpoint and hmac_signature requests from the signing
+/// endpoint.
+#[derive(Debug, Deserialize)]
+pub struct ResolveAuthQuery {
+    pub tool_name: Option<String>,
+}
+
 pub async fn get_downstream_auth_by_id_handler(
+    req: HttpRequest,
     store: web::Data<Arc<EndpointStore>>,
     path: web::Path<String>,
+    query: web::Query<ResolveAuthQuery>,
 ) -> impl Responder {
+    if !check_internal_secret(&req) {
+        return HttpResponse::Unauthorized()
+            .json(serde_json::json!({"success":false,"error":"Unauthorized"}));
+    }
+
     let tenant_id = path.into_inner();
-    match get_downstream_auth(&store, &tenant_id).await {
-        Ok(Some(auth)) => HttpResponse::Ok().json(serde_json::json!({
+    match store.resolve_downstream_auth(&tenant_id, query.tool_name.as_deref()).await {
+        Ok(resolved) => match resolved.auth.map(TenantDownstreamAuth::for_gateway) {
+            Some(auth) if auth.auth_mode == "google_sa" => {
+                match store
+                    .downstream_access_token(&tenant_id, query.tool_name.as_deref(), None, false)
+                    .await
+                {
+                    Ok(token) => HttpResponse::Ok().json(serde_json::json!({
+                        "success": true,
+                        "auth": auth,
+                        "token": token.token,
+                        "source": resolved.source
+                    })),
+                    Err(e) => token_error_response(e, "get_downstream_auth_by_id"),
+                }
+            }
+            Some(auth) => HttpResponse::Ok().json(serde_json::json!({
+                "success": true,
+                "auth": auth,
+                "source": resolved.source
+            })),
+            None => HttpResponse::Ok().json(serde_json::json!({
+                "success": true,
+                "auth": { "auth_mode": "none" },
+                "source": resolved.source
+            })),
+        },
+        Err(StoreError::NotFound(what)) => HttpResponse::UnprocessableEntity()
+            .json(serde_json::json!({"success": false, "error": format!("{} not found", what)})),
+        Err(e) => {
+            app_log!(error, error = %e, "get_downstream_auth_by_id: DB error");
+            HttpResponse::InternalServerError()
+                .json(serde_json::json!({"success": false}))
+        }
+    }
+}
+
+fn check_internal_secret(req: &HttpRequest) -> bool {
+    let expected = match std::env::var("API0_INTERNAL_SECRET") {
+        Ok(s) if !s.is_empty() => s,
+        _ => return false,
+    };
+    req.headers()
+        .get("X-Internal-Secret")
+        .and_then(|v| v.to_str().ok())
+        .map(|v| v == expected)
+        .unwrap_or(false)
+}
+
+/// Internal handler — a ready bearer token for the gateway.
+/// oauth2_client_credentials tokens are cached by the store and refreshed
+/// before expiry; `?refresh=true` forces a new one (e.g. after a backend 401).
+/// oauth2_authorization_code needs the end-user: `key_id` (consumer key) or
+/// `consumer_id`; 409 with consent_required when they have not connected yet.
+#[derive(Debug, Deserialize)]
+pub struct DownstreamTokenQuery {
+    pub tool_name: Option<String>,
+    pub key_id: Option<String>,
+    pub consumer_id: Option<String>,
+    #[serde(default)]
+    pub refresh: bool,
+}
+
+pub async fn get_downstream_token_handler(
+    req: HttpRequest,
+    store: web::Data<Arc<EndpointStore>>,
+    path: web::Path<String>,
+    query: web::Query<DownstreamTokenQuery>,
+) -> impl Responder {
+    if !check_internal_secret(&req) {
+        return HttpResponse::Unauthorized()
+            .json(serde_json::json!({"success": false, "error": "Unauthorized"}));
+    }
+
+    let tenant_id = path.into_inner();
+    let consumer = if query.key_id.is_some() || query.consumer_id.is_some() {
+        match ConsumerSubject::from_parts(query.key_id.as_deref(), query.consumer_id.as_deref()) {
+            Ok(s) => Some(s),
+            Err(e) => {
+                return HttpResponse::BadRequest().json(serde_json::json!({"success": false, "error": e}))
+            }
+        }
+    } else {
+        None
+    };
+
+    match store
+        .downstream_access_token(&tenant_id, query.tool_name.as_deref(), consumer.as_ref(), query.refresh)
+        .await
+    {
+        Ok(token) => HttpResponse::Ok().json(serde_json::json!({ "success": true, "token": token })),
+        Err(e) => token_error_response(e, "get_downstream_token"),
+    }
+}
+
+/// Internal handler — signature headers for an outgoing request in
+/// hmac_signature mode. The shared secret stays in the store.
+/// Body: { tool_name?, method, path, body? | body_sha256?, headers?, timestamp? }
+#[derive(Debug, Deserialize)]
+pub struct SignDownstreamBody {
+    pub tool_name: Option<String>,
+    #[serde(flatten)]
+    pub request: SignRequest,
+}
+
+pub async fn sign_downstream_request_handler(
+    req: HttpRequest,
+    store: web::Data<Arc<EndpointStore>>,
+    path: web::Path<String>,
+    body: web::Json<SignDownstreamBody>,
+) -> impl Responder {
+    if !check_internal_secret(&req) {
+        return HttpResponse::Unauthorized()
+            .json(serde_json::json!({"success": false, "error": "Unauthorized"}));
+    }
+    if body.request.method.trim().is_empty() || !body.request.path.starts_with('/') {
+        return HttpResponse::BadRequest().json(serde_json::json!({
+            "success": false,
+            "error": "method and an absolute path are required"
+        }));
+    }
+
+    let tenant_id = path.into_inner();
+    match store
+        .sign_downstream_request(&tenant_id, body.tool_name.as_deref(), &body.request)
+        .await
+    {
+        Ok(signed) => HttpResponse::Ok().json(serde_json::json!({
             "success": true,
-            "auth": auth
+            "headers": signed.headers,
+            "timestamp": signed.timestamp
         })),
-        Ok(None) => HttpResponse::Ok().json(serde_json::json!({
+        Err(e) => token_error_response(e, "sign_downstream_request"),
+    }
+}
+
+/// Map token errors to HTTP: config problems 400, missing consent 409,
+/// token endpoint failures 502.
+pub(crate) fn token_error_response(e: DownstreamTokenError, context: &str) -> HttpResponse {
+    match e {
+        DownstreamTokenError::NotConfigured(msg) => HttpResponse::BadRequest()
+            .json(serde_json::json!({"success": false, "error": msg})),
+        DownstreamTokenError::ConsentRequired(msg) => HttpResponse::Conflict().json(serde_json::json!({
+            "success": false,
+            "consent_required": true,
+            "error": msg
+        })),
+        DownstreamTokenError::InvalidState => HttpResponse::BadRequest()
+            .json(serde_json::json!({"success": false, "error": e.to_string()})),
+        DownstreamTokenError::Store(StoreError::NotFound(what)) => HttpResponse::NotFound()
+            .json(serde_json::json!({"success": false, "error": format!("{} not found", what)})),
+        DownstreamTokenError::Store(e) => {
+            app_log!(error, error = %e, context = %context, "Downstream token: DB error");
+            HttpResponse::InternalServerError()
+                .json(serde_json::json!({"success": false, "error": "DB error"}))
+        }
+        e => {
+            app_log!(warn, error = %e, context = %context, "Downstream token: token endpoint failed");
+            HttpResponse::BadGateway()
+                .json(serde_json::json!({"success": false, "error": e.to_string()}))
+        }
+    }
+}
+
+// ── Overrides ─────────────────────────────────────────────────────────────────
+
+const AUTH_MODES: [&str; 7] = [
+    "none",
+    "google_sa",
+    "static_bearer",
+    "header_injection",
+    "oauth2_client_credentials",
+    "oauth2_authorization_code",
+    "hmac_signature",
+];
+
+#[derive(Debug, Deserialize)]
+pub struct SaveOverrideBody {
+    pub email: String,
+    /// "tool" or "group"
+    pub scope: String,
+    /// tool_name for scope "tool", api group id for scope "group"
+    pub target: String,
+    pub auth_mode: String,
+    pub service_account_json: Option<String>,
+    pub target_audience: Option<String>,
+    pub bearer_token: Option<String>,
+    pub custom_headers: Option<serde_json::Value>,
+    pub oauth_token_url: Option<String>,
+    pub oauth_client_id: Option<String>,
+    pub oauth_client_secret: Option<String>,
+    pub oauth_scopes: Option<String>,
+    pub oauth_audience: Option<String>,
+    pub oauth_authorize_url: Option<String>,
+    pub hmac_secret: Option<String>,
+    pub hmac_algorithm: Option<String>,
+    pub hmac_signed_headers: Option<Vec<String>>,
+    pub hmac_signature_headers: Option<serde_json::Value>,
+    pub health_check_url: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct OverrideQuery {
+    pub email: String,
+    pub scope: String,
+    pub target: String,
+}
+
+pub async fn list_downstream_auth_overrides_handler(
+    store: web::Data<Arc<EndpointStore>>,
+    query: web::Query<EmailQuery>,
+) -> impl Responder {
+    let tenant = match get_default_tenant(&store, &query.email).await {
+        Ok(t) => t,
+        Err(e) => {
+            app_log!(error, error = %e, "list_downstream_auth_overrides: tenant lookup failed");
+            return HttpResponse::InternalServerError()
+                .json(serde_json::json!({"success": false, "error": "Tenant not found"}));
+        }
+    };
+
+    match store.list_downstream_auth_overrides(&tenant.id).await {
+        Ok(overrides) => HttpResponse::Ok().json(serde_json::json!({
             "success": true,
-            "auth": { "auth_mode": "none" }
+            "overrides": overrides.iter().map(|o| o.redacted()).collect::<Vec<_>>()
         })),
         Err(e) => {
-            app_log!(error, error = %e, "get_downstream_auth_by_id: DB error");
+            app_log!(error, error = %e, "list_downstream_auth_overrides: DB error");
             HttpResponse::InternalServerError()
-                .json(serde_json::json!({"success": false}))
+                .json(serde_json::json!({"success": false, "error": "DB error"}))
+        }
+    }
+}
+
+pub async fn save_downstream_auth_override_handler(
+    store: web::Data<Arc<EndpointStore>>,
+    body: web::Json<SaveOverrideBody>,
+) -> impl Responder {
+    if !OVERRIDE_SCOPES.contains(&body.scope.as_str()) {
+        return HttpResponse::BadRequest()
+            .json(serde_json::json!({"success": false, "error": "scope must be \"tool\" or \"group\""}));
+    }
+    if !AUTH_MODES.contains(&body.auth_mode.as_str()) {
+        return HttpResponse::BadRequest()
+            .json(serde_json::json!({"success": false, "error": format!("Unknown auth_mode: {}", body.auth_mode)}));
+    }
+    if body.target.trim().is_empty() {
+        return HttpResponse::BadRequest()
+            .json(serde_json::json!({"success": false, "error": "target is required"}));
+    }
+
+    let tenant = match get_default_tenant(&store, &body.email).await {
+        Ok(t) => t,
+        Err(e) => {
+            app_log!(error, error = %e, "save_downstream_auth_override: tenant lookup failed");
+            return HttpResponse::InternalServerError()
+                .json(serde_json::json!({"success": false, "error": "Tenant not found"}));
+        }
+    };
+
+    let mut req = SaveDownstreamAuthRequest {
+        auth_mode:            body.auth_mode.clone(),
+        service_account_json: body.service_account_json.clone(),
+        target_audience:      body.target_audience.clone(),
+        bearer_token:         body.bearer_token.clone(),
+        custom_headers:       body.custom_headers.clone(),
+        oauth_token_url:      body.oauth_token_url.clone(),
+        oauth_client_id:      body.oauth_client_id.clone(),
+        oauth_client_secret:  body.oauth_client_secret.clone(),
+        oauth_scopes:         body.oauth_scopes.clone(),
+        oauth_audience:       body.oauth_audience.clone(),
+        oauth_authorize_url:  body.oauth_authorize_url.clone(),
+        hmac_secret:            body.hmac_secret.clone(),
+        hmac_algorithm:         body.hmac_algorithm.clone(),
+        hmac_signed_headers:    body.hmac_signed_headers.clone(),
+        hmac_signature_headers: body.hmac_signature_headers.clone(),
+        health_check_url:       body.health_check_url.clone(),
+    };
+
+    match store.get_downstream_auth_override(&tenant.id, &body.scope, &body.target).await {
+        Ok(stored) => req.keep_stored_secrets(stored.map(|o| o.into_auth()).as_ref()),
+        Err(e) => {
+            app_log!(error, error = %e, "save_downstream_auth_override: DB error");
+            return HttpResponse::InternalServerError()
+                .json(serde_json::json!({"success": false, "error": "DB error"}));
+        }
+    }
+
+    if let Err(e) = req.check() {
+        return HttpResponse::BadRequest().json(serde_json::json!({"success": false, "error": e}));
+    }
+
+    match store
+        .save_downstream_auth_override(&tenant.id, &body.scope, &body.target, &req)
+        .await
+    {
+        Ok(o) => HttpResponse::Ok().json(serde_json::json!({ "success": true, "override": o.redacted() })),
+        Err(e) => {
+            app_log!(error, error = %e, "save_downstream_auth_override: DB error");
+            HttpResponse::InternalServerError()
+                .json(serde_json::json!({"success": false, "error": "DB error"}))
+        }
+    }
+}
+
+pub async fn delete_downstream_auth_override_handler(
+    store: web::Data<Arc<EndpointStore>>,
+    query: web::Query<OverrideQuery>,
+) -> impl Responder {
+    let tenant = match get_default_tenant(&store, &query.email).await {
+        Ok(t) => t,
+        Err(e) => {
+            app_log!(error, error = %e, "delete_downstream_auth_override: tenant lookup failed");
+            return HttpResponse::InternalServerError()
+                .json(serde_json::json!({"success": false, "error": "Tenant not found"}));
+        }
+    };
+
+    match store
+        .delete_downstream_auth_override(&tenant.id, &query.scope, &query.target)
+        .await
+    {
+        Ok(true) => HttpResponse::Ok().json(serde_json::json!({"success": true})),
+        Ok(false) => HttpResponse::NotFound()
+            .json(serde_json::json!({"success": false, "error": "Override not found"})),
+        Err(e) => {
+            app_log!(error, error = %e, "delete_downstream_auth_override: DB error");
+            HttpResponse::InternalServerError()
+                .json(serde_json::json!({"success": false, "error": "DB error"}))
         }
     }
 }
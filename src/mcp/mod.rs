@@ -1,3 +1,8 @@
 pub mod downstream_auth;
 pub mod client_id;
 pub mod tools;
+pub mod resources;
+pub mod prompts;
+pub mod toolsets;
+pub mod consumer_oauth;
+pub mod tenant_secrets;
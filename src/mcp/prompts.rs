@@ -0,0 +1,144 @@
+// src/mcp/prompts.rs
+//
+// HTTP handlers for the MCP prompt registry.
+//
+// Writes require X-Internal-Secret; reads are open (gateway-facing).
+//
+// Routes (all under /api):
+//   POST   /mcp-prompts                         — upsert a prompt
+//   GET    /mcp-prompts/{tenant_id}             — list (MCP prompts/list shape)
+//   POST   /mcp-prompts/{tenant_id}/{name}/get  — render (MCP prompts/get shape)
+//   DELETE /mcp-prompts/{tenant_id}/{name}      — soft-delete a prompt
+
+use crate::app_log;
+use crate::endpoint_store::mcp_prompts_management::UpsertMcpPromptRequest;
+use crate::endpoint_store::EndpointStore;
+use actix_web::{web, HttpRequest, HttpResponse, Responder};
+use serde::Deserialize;
+use std::sync::Arc;
+
+fn check_internal_secret(req: &HttpRequest) -> bool {
+    let expected = match std::env::var("API0_INTERNAL_SECRET") {
+        Ok(s) if !s.is_empty() => s,
+        _ => return false,
+    };
+    req.headers()
+        .get("X-Internal-Secret")
+        .and_then(|v| v.to_str().ok())
+        .map(|v| v == expected)
+        .unwrap_or(false)
+}
+
+// ── POST /api/mcp-prompts ────────────────────────────────────────────────────
+// Body: { tenant_id, name, description?, arguments?: [{ name, description?, required? }],
+//          messages: [{ role, content }] }
+
+#[derive(Deserialize)]
+pub struct UpsertPromptWithTenant {
+    pub tenant_id: String,
+    #[serde(flatten)]
+    pub prompt: UpsertMcpPromptRequest,
+}
+
+pub async fn upsert_mcp_prompt_handler(
+    req: HttpRequest,
+    store: web::Data<Arc<EndpointStore>>,
+    body: web::Json<UpsertPromptWithTenant>,
+) -> impl Responder {
+    if !check_internal_secret(&req) {
+        return HttpResponse::Unauthorized()
+            .json(serde_json::json!({"success":false,"error":"Unauthorized"}));
+    }
+
+    if let Err(e) = body.prompt.check() {
+        return HttpResponse::BadRequest().json(serde_json::json!({"success":false,"error":e}));
+    }
+
+    match store.upsert_mcp_prompt(&body.tenant_id, &body.prompt).await {
+        Ok(prompt) => HttpResponse::Ok().json(serde_json::json!({ "success": true, "prompt": prompt })),
+        Err(e) => {
+            app_log!(error, error = %e, "Failed to upsert MCP prompt");
+            HttpResponse::InternalServerError()
+                .json(serde_json::json!({"success":false,"error":e.to_string()}))
+        }
+    }
+}
+
+// ── GET /api/mcp-prompts/{tenant_id} ────────────────────────────────────────
+
+pub async fn list_mcp_prompts_handler(
+    store: web::Data<Arc<EndpointStore>>,
+    path: web::Path<String>,
+) -> impl Responder {
+    let tenant_id = path.into_inner();
+
+    match store.list_mcp_prompts(&tenant_id).await {
+        Ok(prompts) => {
+            let listing: Vec<serde_json::Value> = prompts.iter().map(|p| p.to_mcp_listing()).collect();
+            HttpResponse::Ok().json(serde_json::json!({ "prompts": listing }))
+        }
+        Err(e) => {
+            app_log!(error, tenant_id = %tenant_id, error = %e, "Failed to list MCP prompts");
+            HttpResponse::InternalServerError()
+                .json(serde_json::json!({"success":false,"error":e.to_string()}))
+        }
+    }
+}
+
+// ── POST /api/mcp-prompts/{tenant_id}/{name}/get ────────────────────────────
+// Body: { arguments?: { name: value } }
+
+#[derive(Deserialize)]
+pub struct GetPromptRequest {
+    #[serde(default)]
+    pub arguments: serde_json::Map<String, serde_json::Value>,
+}
+
+pub async fn get_mcp_prompt_handler(
+    store: web::Data<Arc<EndpointStore>>,
+    path: web::Path<(String, String)>,
+    body: web::Json<GetPromptRequest>,
+) -> impl Responder {
+    let (tenant_id, name) = path.into_inner();
+
+    match store.get_mcp_prompt(&tenant_id, &name).await {
+        Ok(Some(prompt)) => match prompt.render(&body.arguments) {
+            Ok(result) => HttpResponse::Ok().json(result),
+            Err(e) => HttpResponse::UnprocessableEntity()
+                .json(serde_json::json!({"success":false,"error":e})),
+        },
+        Ok(None) => HttpResponse::NotFound()
+            .json(serde_json::json!({"success":false,"error":"Prompt not found"})),
+        Err(e) => {
+            app_log!(error, error = %e, "Failed to get MCP prompt");
+            HttpResponse::InternalServerError()
+                .json(serde_json::json!({"success":false,"error":e.to_string()}))
+        }
+    }
+}
+
+// ── DELETE /api/mcp-prompts/{tenant_id}/{name} ──────────────────────────────
+
+pub async fn delete_mcp_prompt_handler(
+    req: HttpRequest,
+    store: web::Data<Arc<EndpointStore>>,
+    path: web::Path<(String, String)>,
+) -> impl Responder {
+    if !check_internal_secret(&req) {
+        return HttpResponse::Unauthorized()
+            .json(serde_json::json!({"success":false,"error":"Unauthorized"}));
+    }
+
+    let (tenant_id, name) = path.into_inner();
+
+    match store.delete_mcp_prompt(&tenant_id, &name).await {
+        Ok(true) => HttpResponse::Ok().json(serde_json::json!({"success":true})),
+        Ok(false) => HttpResponse::NotFound()
+            .json(serde_json::json!({"success":false,"error":"Prompt not found"})),
+        Err(e) => {
+            app_log!(error, error = %e, "Failed to delete MCP prompt");
+            HttpResponse::InternalServerError()
+                .json(serde_json::json!({"success":false,"error":e.to_string()}))
+        }
+    }
+}
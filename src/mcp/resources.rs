@@ -0,0 +1,198 @@
+// src/mcp/resources.rs
+//
+// HTTP handlers for the MCP resource registry.
+//
+// Same access model as the tool registry: writes require X-Internal-Secret,
+// reads are open (the gateway calls them after verifying the caller's key).
+// Resource URIs contain slashes, so single-resource routes take `?uri=`.
+//
+// Routes (all under /api):
+//   POST   /mcp-resources                          — upsert a resource
+//   POST   /mcp-resources/from-reference-data      — publish uploaded reference data
+//   GET    /mcp-resources/{tenant_id}              — list (MCP resources/list shape)
+//   GET    /mcp-resources/{tenant_id}/read?uri=    — read (MCP resources/read shape)
+//   DELETE /mcp-resources/{tenant_id}?uri=         — soft-delete a resource
+
+use crate::app_log;
+use crate::endpoint_store::mcp_resources_management::UpsertMcpResourceRequest;
+use crate::endpoint_store::EndpointStore;
+use actix_web::{web, HttpRequest, HttpResponse, Responder};
+use serde::Deserialize;
+use std::sync::Arc;
+
+fn check_internal_secret(req: &HttpRequest) -> bool {
+    let expected = match std::env::var("API0_INTERNAL_SECRET") {
+        Ok(s) if !s.is_empty() => s,
+        _ => return false,
+    };
+    req.headers()
+        .get("X-Internal-Secret")
+        .and_then(|v| v.to_str().ok())
+        .map(|v| v == expected)
+        .unwrap_or(false)
+}
+
+#[derive(Deserialize)]
+pub struct UriQuery {
+    pub uri: String,
+}
+
+// ── POST /api/mcp-resources ──────────────────────────────────────────────────
+// Body: { tenant_id, uri, name, description?, mime_type?, content? | fetch_url? }
+
+#[derive(Deserialize)]
+pub struct UpsertResourceWithTenant {
+    pub tenant_id: String,
+    #[serde(flatten)]
+    pub resource: UpsertMcpResourceRequest,
+}
+
+pub async fn upsert_mcp_resource_handler(
+    req: HttpRequest,
+    store: web::Data<Arc<EndpointStore>>,
+    body: web::Json<UpsertResourceWithTenant>,
+) -> impl Responder {
+    if !check_internal_secret(&req) {
+        return HttpResponse::Unauthorized()
+            .json(serde_json::json!({"success":false,"error":"Unauthorized"}));
+    }
+
+    if let Err(e) = body.resource.check() {
+        return HttpResponse::BadRequest().json(serde_json::json!({"success":false,"error":e}));
+    }
+
+    match store.upsert_mcp_resource(&body.tenant_id, &body.resource).await {
+        Ok(resource) => HttpResponse::Ok().json(serde_json::json!({ "success": true, "resource": resource })),
+        Err(e) => {
+            app_log!(error, error = %e, "Failed to upsert MCP resource");
+            HttpResponse::InternalServerError()
+                .json(serde_json::json!({"success":false,"error":e.to_string()}))
+        }
+    }
+}
+
+// ── POST /api/mcp-resources/from-reference-data ─────────────────────────────
+// Body: { tenant_id, reference_data_id, uri?, description? }
+// uri defaults to reference://{reference_data_id}.
+
+#[derive(Deserialize)]
+pub struct PublishReferenceDataRequest {
+    pub tenant_id: String,
+    pub reference_data_id: String,
+    pub uri: Option<String>,
+    pub description: Option<String>,
+}
+
+pub async fn publish_reference_data_handler(
+    req: HttpRequest,
+    store: web::Data<Arc<EndpointStore>>,
+    body: web::Json<PublishReferenceDataRequest>,
+) -> impl Responder {
+    if !check_internal_secret(&req) {
+        return HttpResponse::Unauthorized()
+            .json(serde_json::json!({"success":false,"error":"Unauthorized"}));
+    }
+
+    match store
+        .publish_reference_data_as_resource(
+            &body.tenant_id,
+            &body.reference_data_id,
+            body.uri.as_deref(),
+            body.description.as_deref(),
+        )
+        .await
+    {
+        Ok(Some(resource)) => {
+            app_log!(info, tenant_id = %body.tenant_id, reference_data_id = %body.reference_data_id,
+                uri = %resource.uri, "Published reference data as MCP resource");
+            HttpResponse::Ok().json(serde_json::json!({ "success": true, "resource": resource }))
+        }
+        Ok(None) => HttpResponse::NotFound()
+            .json(serde_json::json!({"success":false,"error":"Reference data not found"})),
+        Err(e) => {
+            app_log!(error, error = %e, "Failed to publish reference data");
+            HttpResponse::InternalServerError()
+                .json(serde_json::json!({"success":false,"error":e.to_string()}))
+        }
+    }
+}
+
+// ── GET /api/mcp-resources/{tenant_id} ──────────────────────────────────────
+
+pub async fn list_mcp_resources_handler(
+    store: web::Data<Arc<EndpointStore>>,
+    path: web::Path<String>,
+) -> impl Responder {
+    let tenant_id = path.into_inner();
+
+    match store.list_mcp_resources(&tenant_id).await {
+        Ok(resources) => {
+            let listing: Vec<serde_json::Value> = resources.iter().map(|r| r.to_mcp_listing()).collect();
+            HttpResponse::Ok().json(serde_json::json!({ "resources": listing }))
+        }
+        Err(e) => {
+            app_log!(error, tenant_id = %tenant_id, error = %e, "Failed to list MCP resources");
+            HttpResponse::InternalServerError()
+                .json(serde_json::json!({"success":false,"error":e.to_string()}))
+        }
+    }
+}
+
+// ── GET /api/mcp-resources/{tenant_id}/read?uri= ────────────────────────────
+// Inline resources return the MCP resources/read result. Backend-fetched ones
+// return { uri, mimeType, fetch_url } — the gateway fetches and wraps the body.
+
+pub async fn read_mcp_resource_handler(
+    store: web::Data<Arc<EndpointStore>>,
+    path: web::Path<String>,
+    query: web::Query<UriQuery>,
+) -> impl Responder {
+    let tenant_id = path.into_inner();
+
+    match store.get_mcp_resource(&tenant_id, &query.uri).await {
+        Ok(Some(resource)) => match (&resource.content, &resource.fetch_url) {
+            (Some(text), _) => HttpResponse::Ok().json(serde_json::json!({
+                "contents": [{ "uri": resource.uri, "mimeType": resource.mime_type, "text": text }]
+            })),
+            (None, fetch_url) => HttpResponse::Ok().json(serde_json::json!({
+                "uri": resource.uri,
+                "mimeType": resource.mime_type,
+                "fetch_url": fetch_url,
+            })),
+        },
+        Ok(None) => HttpResponse::NotFound()
+            .json(serde_json::json!({"success":false,"error":"Resource not found"})),
+        Err(e) => {
+            app_log!(error, error = %e, "Failed to read MCP resource");
+            HttpResponse::InternalServerError()
+                .json(serde_json::json!({"success":false,"error":e.to_string()}))
+        }
+    }
+}
+
+// ── DELETE /api/mcp-resources/{tenant_id}?uri= ──────────────────────────────
+
+pub async fn delete_mcp_resource_handler(
+    req: HttpRequest,
+    store: web::Data<Arc<EndpointStore>>,
+    path: web::Path<String>,
+    query: web::Query<UriQuery>,
+) -> impl Responder {
+    if !check_internal_secret(&req) {
+        return HttpResponse::Unauthorized()
+            .json(serde_json::json!({"success":false,"error":"Unauthorized"}));
+    }
+
+    let tenant_id = path.into_inner();
+
+    match store.delete_mcp_resource(&tenant_id, &query.uri).await {
+        Ok(true) => HttpResponse::Ok().json(serde_json::json!({"success":true})),
+        Ok(false) => HttpResponse::NotFound()
+            .json(serde_json::json!({"success":false,"error":"Resource not found"})),
+        Err(e) => {
+            app_log!(error, error = %e, "Failed to delete MCP resource");
+            HttpResponse::InternalServerError()
+                .json(serde_json::json!({"success":false,"error":e.to_string()}))
+        }
+    }
+}
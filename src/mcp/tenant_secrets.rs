@@ -0,0 +1,144 @@
+// src/mcp/tenant_secrets.rs
+// HTTP handlers for the tenant secret vault (dashboard, gateway-proxied):
+//   GET    /api/user/secrets?email=                     — names + metadata, never values
+//   PUT    /api/user/secrets                            — create / add a new version
+//   GET    /api/user/secrets/{name}/versions?email=     — version history
+//   DELETE /api/user/secrets/{name}?email=
+//
+// Values are write-only. Auth configs and tools reference them as
+// {{secret:NAME}} or {{secret:NAME@VERSION}}; see endpoint_store/secret_vault.rs.
+
+use crate::app_log;
+use crate::endpoint_store::secret_vault::check_name;
+use crate::endpoint_store::tenant_management::get_default_tenant;
+use crate::endpoint_store::EndpointStore;
+use actix_web::{web, HttpResponse, Responder};
+use serde::Deserialize;
+use std::sync::Arc;
+
+#[derive(Debug, Deserialize)]
+pub struct EmailQuery {
+    pub email: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct PutSecretBody {
+    pub email: String,
+    pub name: String,
+    pub value: String,
+    pub description: Option<String>,
+}
+
+pub async fn list_secrets_handler(
+    store: web::Data<Arc<EndpointStore>>,
+    query: web::Query<EmailQuery>,
+) -> impl Responder {
+    let tenant = match get_default_tenant(&store, &query.email).await {
+        Ok(t) => t,
+        Err(e) => {
+            app_log!(error, error = %e, "list_secrets: tenant lookup failed");
+            return HttpResponse::InternalServerError()
+                .json(serde_json::json!({"success": false, "error": "Tenant not found"}));
+        }
+    };
+
+    match store.list_tenant_secrets(&tenant.id).await {
+        Ok(secrets) => HttpResponse::Ok().json(serde_json::json!({ "success": true, "secrets": secrets })),
+        Err(e) => {
+            app_log!(error, error = %e, "list_secrets: DB error");
+            HttpResponse::InternalServerError()
+                .json(serde_json::json!({"success": false, "error": "DB error"}))
+        }
+    }
+}
+
+pub async fn put_secret_handler(
+    store: web::Data<Arc<EndpointStore>>,
+    body: web::Json<PutSecretBody>,
+) -> impl Responder {
+    if let Err(e) = check_name(&body.name) {
+        return HttpResponse::BadRequest().json(serde_json::json!({"success": false, "error": e}));
+    }
+    if body.value.is_empty() {
+        return HttpResponse::BadRequest()
+            .json(serde_json::json!({"success": false, "error": "value is required"}));
+    }
+
+    let tenant = match get_default_tenant(&store, &body.email).await {
+        Ok(t) => t,
+        Err(e) => {
+            app_log!(error, error = %e, "put_secret: tenant lookup failed");
+            return HttpResponse::InternalServerError()
+                .json(serde_json::json!({"success": false, "error": "Tenant not found"}));
+        }
+    };
+
+    match store
+        .put_tenant_secret(&tenant.id, &body.name, &body.value, body.description.as_deref())
+        .await
+    {
+        Ok(secret) => HttpResponse::Ok().json(serde_json::json!({ "success": true, "secret": secret })),
+        Err(e) => {
+            app_log!(error, error = %e, "put_secret: DB error");
+            HttpResponse::InternalServerError()
+                .json(serde_json::json!({"success": false, "error": "DB error"}))
+        }
+    }
+}
+
+pub async fn list_secret_versions_handler(
+    store: web::Data<Arc<EndpointStore>>,
+    path: web::Path<String>,
+    query: web::Query<EmailQuery>,
+) -> impl Responder {
+    let tenant = match get_default_tenant(&store, &query.email).await {
+        Ok(t) => t,
+        Err(e) => {
+            app_log!(error, error = %e, "list_secret_versions: tenant lookup failed");
+            return HttpResponse::InternalServerError()
+                .json(serde_json::json!({"success": false, "error": "Tenant not found"}));
+        }
+    };
+
+    let name = path.into_inner();
+    match store.list_tenant_secret_versions(&tenant.id, &name).await {
+        Ok(Some(versions)) => HttpResponse::Ok().json(serde_json::json!({
+            "success": true,
+            "name": name,
+            "versions": versions
+        })),
+        Ok(None) => HttpResponse::NotFound()
+            .json(serde_json::json!({"success": false, "error": "Secret not found"})),
+        Err(e) => {
+            app_log!(error, error = %e, "list_secret_versions: DB error");
+            HttpResponse::InternalServerError()
+                .json(serde_json::json!({"success": false, "error": "DB error"}))
+        }
+    }
+}
+
+pub async fn delete_secret_handler(
+    store: web::Data<Arc<EndpointStore>>,
+    path: web::Path<String>,
+    query: web::Query<EmailQuery>,
+) -> impl Responder {
+    let tenant = match get_default_tenant(&store, &query.email).await {
+        Ok(t) => t,
+        Err(e) => {
+            app_log!(error, error = %e, "delete_secret: tenant lookup failed");
+            return HttpResponse::InternalServerError()
+                .json(serde_json::json!({"success": false, "error": "Tenant not found"}));
+        }
+    };
+
+    match store.delete_tenant_secret(&tenant.id, &path.into_inner()).await {
+        Ok(true) => HttpResponse::Ok().json(serde_json::json!({"success": true})),
+        Ok(false) => HttpResponse::NotFound()
+            .json(serde_json::json!({"success": false, "error": "Secret not found"})),
+        Err(e) => {
+            app_log!(error, error = %e, "delete_secret: DB error");
+            HttpResponse::InternalServerError()
+                .json(serde_json::json!({"success": false, "error": "DB error"}))
+        }
+    }
+}
@@ -10,11 +10,22 @@
 //   POST   /mcp-tools                            — upsert a tool
 //   GET    /mcp-tools/{tenant_id}                — list tools for a tenant
 //   GET    /mcp-tools/{tenant_id}/{tool_name}    — lookup single tool (used by gateway)
+//   POST   /mcp-tools/{tenant_id}/{tool_name}/validate — validate tools/call arguments
+//   POST   /mcp-tools/{tenant_id}/{tool_name}/render   — render the concrete backend request
+//   POST   /mcp-tools/{tenant_id}/{tool_name}/probe    — may a call go out? (claims the half-open probe)
 //   DELETE /mcp-tools/{tenant_id}/{tool_name}    — soft-delete a tool
+//
+// Read routes accept ?email= and ?key_id= (filters to the key's toolsets).
+// When the request carries the caller's API key in Authorization (the gateway
+// forwards it), the filter follows that key instead of ?key_id=, so a key
+// bound to toolsets cannot see more by leaving the parameter out.
+// Vault references ({{secret:NAME}}) in backend_url and request_mapping are
+// substituted only when the caller sends X-Internal-Secret (the gateway).
 
 use crate::app_log;
-use crate::endpoint_store::mcp_tools_management::UpsertMcpToolRequest;
-use crate::endpoint_store::EndpointStore;
+use crate::endpoint_store::api_key_management::KeyValidation;
+use crate::endpoint_store::mcp_tools_management::{RenderOutcome, UpsertMcpToolRequest};
+use crate::endpoint_store::{EndpointStore, StoreError};
 use actix_web::{web, HttpRequest, HttpResponse, Responder};
 use serde::Deserialize;
 use std::sync::Arc;
@@ -33,7 +44,8 @@ fn check_internal_secret(req: &HttpRequest) -> bool {
 
 // ── POST /api/mcp-tools ───────────────────────────────────────────────────────
 // Body: { tenant_id, tool_name, backend_url, description?, input_schema?,
-//          cost_credits?, timeout_ms? }
+//          cost_credits?, timeout_ms?, http_verb?, request_mapping? (null clears), resilience?,
+//          tags?, group_id? }
 
 #[derive(Deserialize)]
 pub struct UpsertWithTenantRequest {
@@ -52,6 +64,24 @@ pub async fn upsert_mcp_tool_handler(
             .json(serde_json::json!({"success":false,"error":"Unauthorized"}));
     }
 
+    if let Some(Some(mapping)) = &body.tool.request_mapping {
+        if let Err(e) = mapping.check() {
+            return HttpResponse::BadRequest().json(serde_json::json!({
+                "success": false,
+                "error": format!("Invalid request_mapping: {}", e)
+            }));
+        }
+    }
+
+    if let Some(policy) = &body.tool.resilience {
+        if let Err(e) = policy.check() {
+            return HttpResponse::BadRequest().json(serde_json::json!({
+                "success": false,
+                "error": format!("Invalid resilience policy: {}", e)
+            }));
+        }
+    }
+
     match store.upsert_mcp_tool(&body.tenant_id, &body.tool).await {
         Ok(tool) => {
             app_log!(info, tenant_id = %body.tenant_id, tool_name = %tool.tool_name, "MCP tool upserted");
@@ -68,18 +98,61 @@ pub async fn upsert_mcp_tool_handler(
 #[derive(Deserialize)]
 pub struct McpQuery {
     pub email: Option<String>,
+    /// Calling key (as verified by the gateway). Restricts results to the
+    /// key's toolsets when it is bound to any.
+    pub key_id: Option<String>,
+}
+
+/// API key presented in Authorization ("Bearer <key>" or the bare key).
+fn presented_api_key(req: &HttpRequest) -> Option<&str> {
+    let value = req.headers().get("Authorization")?.to_str().ok()?;
+    let key = value.strip_prefix("Bearer ").unwrap_or(value).trim();
+    (!key.is_empty()).then_some(key)
+}
+
+/// Key whose toolsets apply: the presented key when there is one, else
+/// ?key_id=. A presented key that is unknown, revoked, suspended or not a
+/// key of (or consumer key for) this tenant is refused, never read as "no
+/// key". Scope and IP checks are the gateway's, done at /key/validate.
+async fn calling_key_id(
+    store: &EndpointStore,
+    req: &HttpRequest,
+    tenant_id: &str,
+    query: &McpQuery,
+) -> Result<Option<String>, HttpResponse> {
+    let Some(api_key) = presented_api_key(req) else {
+        return Ok(query.key_id.clone());
+    };
+    let validation = store.validate_api_key(api_key, None, None, None).await.map_err(|e| {
+        app_log!(error, error = %e, "Failed to resolve calling API key");
+        HttpResponse::InternalServerError().json(serde_json::json!({"success":false,"error":e.to_string()}))
+    })?;
+    match validation {
+        KeyValidation::Valid(key) | KeyValidation::MissingScope(key) | KeyValidation::IpNotAllowed(key)
+            if key.provider_tenant_id.as_deref().unwrap_or(&key.tenant_id) == tenant_id =>
+        {
+            Ok(Some(key.key_id))
+        }
+        _ => Err(HttpResponse::Unauthorized()
+            .json(serde_json::json!({"success":false,"error":"Invalid API key"}))),
+    }
 }
 
 // ── GET /api/mcp-tools/{tenant_id} ───────────────────────────────────────────
 
 pub async fn list_mcp_tools_handler(
+    req: HttpRequest,
     store: web::Data<Arc<EndpointStore>>,
     path: web::Path<String>,
     query: web::Query<McpQuery>,
 ) -> impl Responder {
     let tenant_id = path.into_inner();
+    let key_id = match calling_key_id(&store, &req, &tenant_id, &query).await {
+        Ok(key_id) => key_id,
+        Err(resp) => return resp,
+    };
 
-    match store.list_mcp_tools(&tenant_id, query.email.as_deref()).await {
+    match store.list_mcp_tools(&tenant_id, query.email.as_deref(), key_id.as_deref()).await {
         Ok(tools) => HttpResponse::Ok().json(serde_json::json!({ "tools": tools })),
         Err(e) => {
             app_log!(error, tenant_id = %tenant_id, error = %e, "Failed to list MCP tools");
@@ -92,14 +165,28 @@ pub async fn list_mcp_tools_handler(
 // ── GET /api/mcp-tools/{tenant_id}/{tool_name} ───────────────────────────────
 
 pub async fn get_mcp_tool_handler(
+    req: HttpRequest,
     store: web::Data<Arc<EndpointStore>>,
     path: web::Path<(String, String)>,
     query: web::Query<McpQuery>,
 ) -> impl Responder {
     let (tenant_id, tool_name) = path.into_inner();
+    let key_id = match calling_key_id(&store, &req, &tenant_id, &query).await {
+        Ok(key_id) => key_id,
+        Err(resp) => return resp,
+    };
 
-    match store.get_mcp_tool(&tenant_id, &tool_name, query.email.as_deref()).await {
-        Ok(Some(tool)) => HttpResponse::Ok().json(tool),
+    match store.get_mcp_tool(&tenant_id, &tool_name, query.email.as_deref(), key_id.as_deref()).await {
+        Ok(Some(mut tool)) => {
+            if check_internal_secret(&req) {
+                if let Err(e) = store.resolve_tool_secrets(&tenant_id, &mut tool).await {
+                    app_log!(warn, tenant_id = %tenant_id, tool_name = %tool_name, error = %e,
+                        "Failed to resolve tool secrets");
+                    return secret_error_response(e);
+                }
+            }
+            HttpResponse::Ok().json(tool)
+        }
         Ok(None) => HttpResponse::NotFound()
             .json(serde_json::json!({"success":false,"error":"Tool not found"})),
         Err(e) => {
@@ -110,6 +197,140 @@ pub async fn get_mcp_tool_handler(
     }
 }
 
+/// A {{secret:NAME}} reference that cannot be resolved is a configuration
+/// error of the tenant, not a server fault.
+fn secret_error_response(e: StoreError) -> HttpResponse {
+    match e {
+        StoreError::NotFound(what) => HttpResponse::UnprocessableEntity()
+            .json(serde_json::json!({"success":false,"error":format!("{} not found", what)})),
+        e => HttpResponse::InternalServerError()
+            .json(serde_json::json!({"success":false,"error":e.to_string()})),
+    }
+}
+
+// ── POST /api/mcp-tools/{tenant_id}/{tool_name}/validate ─────────────────────
+// Body: { arguments }  — the `arguments` object of a tools/call request.
+// Returns { valid, arguments, errors: [{ path, message }] }. The returned
+// arguments have string scalars coerced and schema defaults applied; the
+// gateway should forward those instead of the raw ones.
+
+#[derive(Deserialize)]
+pub struct ValidateArgumentsRequest {
+    #[serde(default)]
+    pub arguments: serde_json::Value,
+}
+
+pub async fn validate_mcp_tool_arguments_handler(
+    req: HttpRequest,
+    store: web::Data<Arc<EndpointStore>>,
+    path: web::Path<(String, String)>,
+    query: web::Query<McpQuery>,
+    body: web::Json<ValidateArgumentsRequest>,
+) -> impl Responder {
+    let (tenant_id, tool_name) = path.into_inner();
+    let key_id = match calling_key_id(&store, &req, &tenant_id, &query).await {
+        Ok(key_id) => key_id,
+        Err(resp) => return resp,
+    };
+
+    match store
+        .validate_mcp_tool_arguments(&tenant_id, &tool_name, query.email.as_deref(), key_id.as_deref(), &body.arguments)
+        .await
+    {
+        Ok(Some(result)) => {
+            if !result.valid {
+                app_log!(info, tenant_id = %tenant_id, tool_name = %tool_name,
+                    errors = result.errors.len(), "Tool arguments rejected by input_schema");
+            }
+            HttpResponse::Ok().json(result)
+        }
+        Ok(None) => HttpResponse::NotFound()
+            .json(serde_json::json!({"success":false,"error":"Tool not found"})),
+        Err(e) => {
+            app_log!(error, error = %e, "Failed to validate MCP tool arguments");
+            HttpResponse::InternalServerError()
+                .json(serde_json::json!({"success":false,"error":e.to_string()}))
+        }
+    }
+}
+
+// ── POST /api/mcp-tools/{tenant_id}/{tool_name}/render ───────────────────────
+// Body: { arguments }
+// 200 → { method, url, headers, body? } ready to send to the backend.
+// 422 → arguments failed input_schema validation ({ errors }) or the mapping
+//       could not be applied (e.g. a path parameter has no value).
+
+pub async fn render_mcp_tool_request_handler(
+    req: HttpRequest,
+    store: web::Data<Arc<EndpointStore>>,
+    path: web::Path<(String, String)>,
+    query: web::Query<McpQuery>,
+    body: web::Json<ValidateArgumentsRequest>,
+) -> impl Responder {
+    let (tenant_id, tool_name) = path.into_inner();
+    let key_id = match calling_key_id(&store, &req, &tenant_id, &query).await {
+        Ok(key_id) => key_id,
+        Err(resp) => return resp,
+    };
+
+    match store
+        .render_mcp_tool_request(
+            &tenant_id,
+            &tool_name,
+            query.email.as_deref(),
+            key_id.as_deref(),
+            &body.arguments,
+            check_internal_secret(&req),
+        )
+        .await
+    {
+        Ok(Some(RenderOutcome::Rendered { request, .. })) => HttpResponse::Ok().json(request),
+        Ok(Some(RenderOutcome::InvalidArguments { errors })) => HttpResponse::UnprocessableEntity()
+            .json(serde_json::json!({"success":false,"error":"Invalid arguments","errors":errors})),
+        Ok(Some(RenderOutcome::MappingError { error })) => {
+            app_log!(warn, tenant_id = %tenant_id, tool_name = %tool_name, error = %error,
+                "Failed to apply request mapping");
+            HttpResponse::UnprocessableEntity()
+                .json(serde_json::json!({"success":false,"error":error}))
+        }
+        Ok(None) => HttpResponse::NotFound()
+            .json(serde_json::json!({"success":false,"error":"Tool not found"})),
+        Err(e @ StoreError::NotFound(_)) | Err(e @ StoreError::Crypto(_)) => secret_error_response(e),
+        Err(e) => {
+            app_log!(error, error = %e, "Failed to render MCP tool request");
+            HttpResponse::InternalServerError()
+                .json(serde_json::json!({"success":false,"error":e.to_string()}))
+        }
+    }
+}
+
+// ── POST /api/mcp-tools/{tenant_id}/{tool_name}/probe ────────────────────────
+
+/// Called by the gateway before a call to a tool whose circuit is not
+/// closed. `admitted` is true for the one caller that gets the half-open
+/// probe; everyone else fails fast until its outcome is reported.
+pub async fn claim_tool_probe_handler(
+    req: HttpRequest,
+    store: web::Data<Arc<EndpointStore>>,
+    path: web::Path<(String, String)>,
+) -> impl Responder {
+    if !check_internal_secret(&req) {
+        return HttpResponse::Unauthorized()
+            .json(serde_json::json!({"success":false,"error":"Unauthorized"}));
+    }
+
+    let (tenant_id, tool_name) = path.into_inner();
+
+    match store.claim_tool_probe(&tenant_id, &tool_name).await {
+        Ok(admitted) => HttpResponse::Ok().json(serde_json::json!({"success":true,"admitted":admitted})),
+        Err(e) => {
+            app_log!(error, error = %e, "Failed to claim circuit probe");
+            HttpResponse::InternalServerError()
+                .json(serde_json::json!({"success":false,"error":e.to_string()}))
+        }
+    }
+}
+
 // ── DELETE /api/mcp-tools/{tenant_id}/{tool_name} ────────────────────────────
 
 pub async fn delete_mcp_tool_handler(
@@ -135,3 +356,77 @@ pub async fn delete_mcp_tool_handler(
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::endpoint_store::api_key_management::{generate_api_key, generate_api_key_with_provider};
+    use crate::endpoint_store::key_expiry::KeyExpiry;
+    use crate::endpoint_store::key_scopes::KeyScopes;
+    use crate::endpoint_store::tenant_management::get_default_tenant;
+    use actix_web::test::TestRequest;
+
+    fn query(key_id: Option<&str>) -> McpQuery {
+        McpQuery { email: None, key_id: key_id.map(str::to_string) }
+    }
+
+    #[test]
+    fn null_request_mapping_survives_flatten() {
+        let body: UpsertWithTenantRequest = serde_json::from_value(serde_json::json!({
+            "tenant_id": "t1",
+            "tool_name": "t",
+            "backend_url": "https://b.example.com",
+            "request_mapping": null,
+        }))
+        .unwrap();
+        assert!(matches!(body.tool.request_mapping, Some(None)));
+    }
+
+    #[test]
+    fn reads_the_presented_key() {
+        let req = TestRequest::default().insert_header(("Authorization", "Bearer sk_abc")).to_http_request();
+        assert_eq!(presented_api_key(&req), Some("sk_abc"));
+        let req = TestRequest::default().insert_header(("Authorization", "Bearer ")).to_http_request();
+        assert_eq!(presented_api_key(&req), None);
+    }
+
+    #[actix_web::test]
+    #[ignore = "requires live PostgreSQL (DATABASE_URL)"]
+    async fn presented_key_decides_the_toolset_filter() {
+        let store = crate::endpoint_store::live_test_store().await;
+        let provider = format!("provider_{}@example.com", uuid::Uuid::new_v4());
+        let provider_tenant_id = get_default_tenant(&store, &provider).await.unwrap().id;
+        let consumer = format!("consumer_{}@example.com", uuid::Uuid::new_v4());
+        let (secret, _, key_id, _) = generate_api_key_with_provider(
+            &store,
+            &consumer,
+            "basic",
+            None,
+            Some(&provider_tenant_id),
+            &KeyScopes::default(),
+            &KeyExpiry::default(),
+        )
+        .await
+        .unwrap();
+        let with_key = |key: &str| {
+            TestRequest::default().insert_header(("Authorization", format!("Bearer {}", key))).to_http_request()
+        };
+
+        // The presented key wins over a ?key_id= naming some other key.
+        let resolved = calling_key_id(&store, &with_key(&secret), &provider_tenant_id, &query(Some("other"))).await;
+        assert_eq!(resolved.ok().flatten(), Some(key_id));
+
+        // A key for another tenant, or an unknown one, is refused.
+        let stranger = format!("stranger_{}@example.com", uuid::Uuid::new_v4());
+        let (foreign, _, _, _) = generate_api_key(&store, &stranger, "k", None).await.unwrap();
+        for key in [foreign.as_str(), "sk_not_a_key"] {
+            let refused = calling_key_id(&store, &with_key(key), &provider_tenant_id, &query(None)).await;
+            assert_eq!(refused.err().map(|r| r.status()), Some(actix_web::http::StatusCode::UNAUTHORIZED));
+        }
+
+        // Without a presented key ?key_id= still applies.
+        let anonymous = TestRequest::default().to_http_request();
+        let plain = calling_key_id(&store, &anonymous, &provider_tenant_id, &query(Some("k1"))).await;
+        assert_eq!(plain.ok().flatten().as_deref(), Some("k1"));
+    }
+}
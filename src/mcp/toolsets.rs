@@ -0,0 +1,170 @@
+// src/mcp/toolsets.rs
+//
+// HTTP handlers for MCP toolsets (named tool bundles) and their binding to
+// API keys / consumer keys.
+//
+// Writes require X-Internal-Secret (provider backends like cvenom define
+// "basic" / "pro" packs and assign them to their consumers' keys).
+//
+// Routes (all under /api):
+//   POST   /mcp-toolsets                              — upsert a toolset
+//   GET    /mcp-toolsets/{tenant_id}                  — list toolsets
+//   DELETE /mcp-toolsets/{tenant_id}/{name}           — delete a toolset
+//                                                       (409 while active keys are bound)
+//   GET    /mcp-toolsets/{tenant_id}/keys/{key_id}    — toolsets bound to a key
+//   PUT    /mcp-toolsets/{tenant_id}/keys/{key_id}    — replace a key's bindings
+
+use crate::app_log;
+use crate::endpoint_store::toolsets_management::UpsertToolsetRequest;
+use crate::endpoint_store::{EndpointStore, StoreError};
+use actix_web::{web, HttpRequest, HttpResponse, Responder};
+use serde::Deserialize;
+use std::sync::Arc;
+
+fn check_internal_secret(req: &HttpRequest) -> bool {
+    let expected = match std::env::var("API0_INTERNAL_SECRET") {
+        Ok(s) if !s.is_empty() => s,
+        _ => return false,
+    };
+    req.headers()
+        .get("X-Internal-Secret")
+        .and_then(|v| v.to_str().ok())
+        .map(|v| v == expected)
+        .unwrap_or(false)
+}
+
+// ── POST /api/mcp-toolsets ───────────────────────────────────────────────────
+// Body: { tenant_id, name, description?, tool_names?, tags?, group_ids? }
+
+#[derive(Deserialize)]
+pub struct UpsertToolsetWithTenant {
+    pub tenant_id: String,
+    #[serde(flatten)]
+    pub toolset: UpsertToolsetRequest,
+}
+
+pub async fn upsert_toolset_handler(
+    req: HttpRequest,
+    store: web::Data<Arc<EndpointStore>>,
+    body: web::Json<UpsertToolsetWithTenant>,
+) -> impl Responder {
+    if !check_internal_secret(&req) {
+        return HttpResponse::Unauthorized()
+            .json(serde_json::json!({"success":false,"error":"Unauthorized"}));
+    }
+
+    if body.toolset.name.trim().is_empty() {
+        return HttpResponse::BadRequest()
+            .json(serde_json::json!({"success":false,"error":"name is required"}));
+    }
+
+    match store.upsert_toolset(&body.tenant_id, &body.toolset).await {
+        Ok(toolset) => HttpResponse::Ok().json(serde_json::json!({ "success": true, "toolset": toolset })),
+        Err(e) => {
+            app_log!(error, error = %e, "Failed to upsert MCP toolset");
+            HttpResponse::InternalServerError()
+                .json(serde_json::json!({"success":false,"error":e.to_string()}))
+        }
+    }
+}
+
+// ── GET /api/mcp-toolsets/{tenant_id} ───────────────────────────────────────
+
+pub async fn list_toolsets_handler(
+    store: web::Data<Arc<EndpointStore>>,
+    path: web::Path<String>,
+) -> impl Responder {
+    let tenant_id = path.into_inner();
+
+    match store.list_toolsets(&tenant_id).await {
+        Ok(toolsets) => HttpResponse::Ok().json(serde_json::json!({ "toolsets": toolsets })),
+        Err(e) => {
+            app_log!(error, tenant_id = %tenant_id, error = %e, "Failed to list MCP toolsets");
+            HttpResponse::InternalServerError()
+                .json(serde_json::json!({"success":false,"error":e.to_string()}))
+        }
+    }
+}
+
+// ── DELETE /api/mcp-toolsets/{tenant_id}/{name} ─────────────────────────────
+
+pub async fn delete_toolset_handler(
+    req: HttpRequest,
+    store: web::Data<Arc<EndpointStore>>,
+    path: web::Path<(String, String)>,
+) -> impl Responder {
+    if !check_internal_secret(&req) {
+        return HttpResponse::Unauthorized()
+            .json(serde_json::json!({"success":false,"error":"Unauthorized"}));
+    }
+
+    let (tenant_id, name) = path.into_inner();
+
+    match store.delete_toolset(&tenant_id, &name).await {
+        Ok(true) => HttpResponse::Ok().json(serde_json::json!({"success":true})),
+        Ok(false) => HttpResponse::NotFound()
+            .json(serde_json::json!({"success":false,"error":"Toolset not found"})),
+        Err(StoreError::Invalid(msg)) => HttpResponse::Conflict()
+            .json(serde_json::json!({"success":false,"error":msg})),
+        Err(e) => {
+            app_log!(error, error = %e, "Failed to delete MCP toolset");
+            HttpResponse::InternalServerError()
+                .json(serde_json::json!({"success":false,"error":e.to_string()}))
+        }
+    }
+}
+
+// ── GET /api/mcp-toolsets/{tenant_id}/keys/{key_id} ─────────────────────────
+// { restricted: false } when the key has no bindings (sees the full catalog).
+
+pub async fn get_key_toolsets_handler(
+    store: web::Data<Arc<EndpointStore>>,
+    path: web::Path<(String, String)>,
+) -> impl Responder {
+    let (tenant_id, key_id) = path.into_inner();
+
+    match store.get_key_toolsets(&tenant_id, &key_id).await {
+        Ok(toolsets) => HttpResponse::Ok().json(serde_json::json!({
+            "restricted": toolsets.is_some(),
+            "toolsets": toolsets.unwrap_or_default(),
+        })),
+        Err(e) => {
+            app_log!(error, error = %e, "Failed to get key toolsets");
+            HttpResponse::InternalServerError()
+                .json(serde_json::json!({"success":false,"error":e.to_string()}))
+        }
+    }
+}
+
+// ── PUT /api/mcp-toolsets/{tenant_id}/keys/{key_id} ─────────────────────────
+// Body: { toolsets: ["basic", "pro"] } — an empty list removes all bindings.
+
+#[derive(Deserialize)]
+pub struct SetKeyToolsetsRequest {
+    pub toolsets: Vec<String>,
+}
+
+pub async fn set_key_toolsets_handler(
+    req: HttpRequest,
+    store: web::Data<Arc<EndpointStore>>,
+    path: web::Path<(String, String)>,
+    body: web::Json<SetKeyToolsetsRequest>,
+) -> impl Responder {
+    if !check_internal_secret(&req) {
+        return HttpResponse::Unauthorized()
+            .json(serde_json::json!({"success":false,"error":"Unauthorized"}));
+    }
+
+    let (tenant_id, key_id) = path.into_inner();
+
+    match store.set_key_toolsets(&tenant_id, &key_id, &body.toolsets).await {
+        Ok(toolsets) => HttpResponse::Ok().json(serde_json::json!({ "success": true, "toolsets": toolsets })),
+        Err(StoreError::NotFound(what)) => HttpResponse::NotFound()
+            .json(serde_json::json!({"success":false,"error":format!("{} not found", what)})),
+        Err(e) => {
+            app_log!(error, error = %e, "Failed to set key toolsets");
+            HttpResponse::InternalServerError()
+                .json(serde_json::json!({"success":false,"error":e.to_string()}))
+        }
+    }
+}
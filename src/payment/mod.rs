@@ -1,6 +1,7 @@
 pub mod admin;
 pub mod balance;
 pub mod transactions;
+#[allow(clippy::module_inception)]
 pub mod payment;
 pub mod service;
 pub mod update_balance;
@@ -7,11 +7,14 @@
 //   GET    /api/whatsapp/channel/{email}
 //   DELETE /api/whatsapp/channel/{email}
 //
+// wa_token is envelope-encrypted at rest (endpoint_store/secret_crypto.rs).
+//
 // Bridge-internal (X-Internal-Secret):
 //   GET    /api/internal/whatsapp/channel/{phone_number_id}
 //   GET    /api/internal/whatsapp/channel/by-tenant/{tenant_id}
 
 use crate::app_log;
+use crate::endpoint_store::secret_crypto::SecretSlot;
 use crate::endpoint_store::EndpointStore;
 use crate::endpoint_store::tenant_management::get_default_tenant;
 use actix_web::{web, HttpRequest, HttpResponse, Responder};
@@ -30,6 +33,10 @@ fn check_internal_secret(req: &HttpRequest) -> bool {
         .unwrap_or(false)
 }
 
+fn wa_token_slot(tenant_id: &str) -> SecretSlot<'_> {
+    SecretSlot::new("whatsapp_channels", "wa_token", tenant_id)
+}
+
 #[derive(Deserialize)]
 pub struct RegisterChannelRequest {
     pub email: String,
@@ -60,6 +67,14 @@ pub async fn register_channel(
     };
 
     let system_prompt = body.system_prompt.as_deref().unwrap_or("");
+    let wa_token = match store.secrets().encrypt(wa_token_slot(&tenant.id), &body.wa_token) {
+        Ok(t) => t,
+        Err(e) => {
+            app_log!(error, tenant_id = %tenant.id, error = %e, "WA token encryption failed");
+            return HttpResponse::InternalServerError()
+                .json(serde_json::json!({"success": false, "error": "Encryption error"}));
+        }
+    };
 
     match client.execute(
         "INSERT INTO whatsapp_channels (phone_number_id, tenant_id, wa_token, verify_token, system_prompt)
@@ -69,7 +84,7 @@ pub async fn register_channel(
                wa_token        = EXCLUDED.wa_token,
                verify_token    = EXCLUDED.verify_token,
                system_prompt   = EXCLUDED.system_prompt",
-        &[&body.phone_number_id, &tenant.id, &body.wa_token, &body.verify_token, &system_prompt],
+        &[&body.phone_number_id, &tenant.id, &wa_token, &body.verify_token, &system_prompt],
     ).await {
         Ok(_) => {
             app_log!(info, tenant_id = %tenant.id, "WA channel registered");
@@ -169,13 +184,17 @@ pub async fn lookup_channel_internal(
          FROM whatsapp_channels WHERE phone_number_id = $1",
         &[&phone_number_id],
     ).await {
-        Ok(Some(row)) => HttpResponse::Ok().json(serde_json::json!({
-            "success":       true,
-            "tenant_id":     row.get::<_, &str>(0),
-            "wa_token":      row.get::<_, &str>(1),
-            "verify_token":  row.get::<_, &str>(2),
-            "system_prompt": row.get::<_, &str>(3),
-        })),
+        Ok(Some(row)) => match store.secrets().decrypt(wa_token_slot(row.get(0)), row.get::<_, &str>(1)) {
+            Ok(wa_token) => HttpResponse::Ok().json(serde_json::json!({
+                "success":       true,
+                "tenant_id":     row.get::<_, &str>(0),
+                "wa_token":      wa_token,
+                "verify_token":  row.get::<_, &str>(2),
+                "system_prompt": row.get::<_, &str>(3),
+            })),
+            Err(e) => HttpResponse::InternalServerError()
+                .json(serde_json::json!({"success": false, "error": e.to_string()})),
+        },
         Ok(None) => HttpResponse::NotFound()
             .json(serde_json::json!({"success": false, "error": "Channel not found"})),
         Err(e) => HttpResponse::InternalServerError()
@@ -204,13 +223,17 @@ pub async fn lookup_channel_by_tenant_internal(
          FROM whatsapp_channels WHERE tenant_id = $1",
         &[&tenant_id],
     ).await {
-        Ok(Some(row)) => HttpResponse::Ok().json(serde_json::json!({
-            "success":        true,
-            "phone_number_id": row.get::<_, &str>(0),
-            "wa_token":       row.get::<_, &str>(1),
-            "verify_token":   row.get::<_, &str>(2),
-            "system_prompt":  row.get::<_, &str>(3),
-        })),
+        Ok(Some(row)) => match store.secrets().decrypt(wa_token_slot(&tenant_id), row.get::<_, &str>(1)) {
+            Ok(wa_token) => HttpResponse::Ok().json(serde_json::json!({
+                "success":        true,
+                "phone_number_id": row.get::<_, &str>(0),
+                "wa_token":       wa_token,
+                "verify_token":   row.get::<_, &str>(2),
+                "system_prompt":  row.get::<_, &str>(3),
+            })),
+            Err(e) => HttpResponse::InternalServerError()
+                .json(serde_json::json!({"success": false, "error": e.to_string()})),
+        },
         Ok(None) => HttpResponse::NotFound()
             .json(serde_json::json!({"success": false, "error": "Channel not found"})),
         Err(e) => HttpResponse::InternalServerError()